@@ -0,0 +1,145 @@
+pub mod track;
+
+use std::ops::RangeInclusive;
+
+use glam::{Affine3A, Quat, Vec3};
+
+use crate::{math::Interval, rendering::camera::CameraConfig};
+
+pub use track::Track;
+
+/// Where the open shutter sits relative to a frame's time.
+#[derive(Clone, Copy, Default)]
+pub enum ShutterPosition {
+    /// Opens at the frame's time.
+    #[default]
+    Start,
+    /// Open around the frame's time.
+    Center,
+    /// Closes at the frame's time.
+    End,
+}
+
+/// Playback settings mapping frame numbers to scene time.
+#[derive(Clone)]
+pub struct Animation {
+    pub frames: RangeInclusive<u32>,
+    pub fps: f32,
+    /// Portion of a frame the shutter stays open, in degrees (360 = whole frame).
+    pub shutter_angle: f32,
+    pub shutter_position: ShutterPosition,
+}
+
+impl Default for Animation {
+    fn default() -> Self {
+        Self {
+            frames: 1..=1,
+            fps: 24.0,
+            shutter_angle: 180.0,
+            shutter_position: ShutterPosition::Start,
+        }
+    }
+}
+
+impl Animation {
+    pub fn frame_time(&self, frame: u32) -> f32 {
+        frame as f32 / self.fps
+    }
+
+    /// Scene time interval during which the shutter is open for `frame`.
+    pub fn shutter(&self, frame: u32) -> Interval {
+        let time = self.frame_time(frame);
+        let length = (self.shutter_angle / 360.0) / self.fps;
+        let open = match self.shutter_position {
+            ShutterPosition::Start => time,
+            ShutterPosition::Center => time - length / 2.0,
+            ShutterPosition::End => time - length,
+        };
+        open..open + length
+    }
+}
+
+/// Keyframed camera parameters. Parameters without a track, or with an empty
+/// one, keep the value of the scene's camera configuration.
+#[derive(Clone, Default)]
+pub struct CameraAnimation {
+    pub look_from: Option<Track<Vec3>>,
+    pub look_at: Option<Track<Vec3>>,
+    pub vfov: Option<Track<f32>>,
+}
+
+impl CameraAnimation {
+    pub fn config_at(&self, base: &CameraConfig, time: f32) -> CameraConfig {
+        let mut config = base.clone();
+        if let Some(look_from) = self.look_from.as_ref().and_then(|t| t.sample(time)) {
+            config.look_from = look_from;
+        }
+        if let Some(look_at) = self.look_at.as_ref().and_then(|t| t.sample(time)) {
+            config.look_at = look_at;
+        }
+        if let Some(vfov) = self.vfov.as_ref().and_then(|t| t.sample(time)) {
+            config.vfov = vfov;
+        }
+        config
+    }
+}
+
+/// Keyframed translation, rotation and scale of an object. Empty tracks
+/// leave their part of the transform at the identity.
+#[derive(Clone)]
+pub struct TransformTrack {
+    pub translation: Track<Vec3>,
+    pub rotation: Track<Quat>,
+    pub scale: Track<Vec3>,
+}
+
+impl Default for TransformTrack {
+    fn default() -> Self {
+        Self {
+            translation: Track::constant(Vec3::ZERO),
+            rotation: Track::constant(Quat::IDENTITY),
+            scale: Track::constant(Vec3::ONE),
+        }
+    }
+}
+
+impl TransformTrack {
+    pub fn matrix(&self, time: f32) -> Affine3A {
+        Affine3A::from_scale_rotation_translation(
+            self.scale.sample(time).unwrap_or(Vec3::ONE),
+            self.rotation
+                .sample(time)
+                .unwrap_or(Quat::IDENTITY)
+                .normalize(),
+            self.translation.sample(time).unwrap_or(Vec3::ZERO),
+        )
+    }
+
+    fn key_times(&self) -> impl Iterator<Item = f32> + '_ {
+        self.translation
+            .keys()
+            .iter()
+            .chain(self.scale.keys())
+            .map(|k| k.time)
+            .chain(self.rotation.keys().iter().map(|k| k.time))
+    }
+
+    /// Interval between the first and last key, `None` if nothing moves.
+    pub fn key_range(&self) -> Option<Interval> {
+        let first = self.key_times().fold(f32::MAX, f32::min);
+        let last = self.key_times().fold(f32::MIN, f32::max);
+        (first < last).then_some(first..last)
+    }
+
+    /// Times worth evaluating to bound the motion during `times`: the ends,
+    /// every key in between and evenly spaced points to catch curved segments.
+    pub fn sample_times(&self, times: &Interval) -> Vec<f32> {
+        const STEPS: usize = 16;
+        let size = times.end - times.start;
+
+        self.key_times()
+            .filter(|time| times.contains(time))
+            .chain((0..=STEPS).map(|i| times.start + size * i as f32 / STEPS as f32))
+            .collect()
+    }
+}
@@ -0,0 +1,181 @@
+use glam::{Quat, Vec3};
+
+/// A value that can be blended between keyframes.
+pub trait Animatable: Copy {
+    fn lerp(a: Self, b: Self, t: f32) -> Self;
+}
+
+impl Animatable for f32 {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        a + (b - a) * t
+    }
+}
+
+impl Animatable for Vec3 {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        a.lerp(b, t)
+    }
+}
+
+impl Animatable for Quat {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        a.slerp(b, t)
+    }
+}
+
+/// How a key blends into the one after it.
+#[derive(Clone, Copy)]
+pub enum Interpolation<T> {
+    Step,
+    Linear,
+    /// Cubic Bezier segment with the two inner control points given as values.
+    Bezier {
+        control_out: T,
+        control_in: T,
+    },
+}
+
+#[derive(Clone, Copy)]
+pub struct Keyframe<T> {
+    pub time: f32,
+    pub value: T,
+    pub interpolation: Interpolation<T>,
+}
+
+#[derive(Clone)]
+pub struct Track<T> {
+    keys: Vec<Keyframe<T>>,
+}
+
+impl<T: Animatable> Default for Track<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Animatable> Track<T> {
+    pub fn constant(value: T) -> Self {
+        Self {
+            keys: vec![Keyframe {
+                time: 0.0,
+                value,
+                interpolation: Interpolation::Step,
+            }],
+        }
+    }
+
+    pub fn new() -> Self {
+        Self { keys: Vec::new() }
+    }
+
+    pub fn key(mut self, time: f32, value: T, interpolation: Interpolation<T>) -> Self {
+        let index = self.keys.partition_point(|k| k.time <= time);
+        self.keys.insert(
+            index,
+            Keyframe {
+                time,
+                value,
+                interpolation,
+            },
+        );
+        self
+    }
+
+    pub fn linear(self, time: f32, value: T) -> Self {
+        self.key(time, value, Interpolation::Linear)
+    }
+
+    /// Adds a key whose segment towards the next key is a cubic Bezier curve.
+    pub fn bezier(self, time: f32, value: T, control_out: T, control_in: T) -> Self {
+        self.key(
+            time,
+            value,
+            Interpolation::Bezier {
+                control_out,
+                control_in,
+            },
+        )
+    }
+
+    pub fn keys(&self) -> &[Keyframe<T>] {
+        &self.keys
+    }
+
+    /// Value at `time`, holding the first and last keys outside the track.
+    /// `None` if the track has no keys.
+    pub fn sample(&self, time: f32) -> Option<T> {
+        let next = self.keys.partition_point(|k| k.time <= time);
+        if next == 0 {
+            return self.keys.first().map(|k| k.value);
+        }
+        if next == self.keys.len() {
+            return Some(self.keys[next - 1].value);
+        }
+
+        let a = &self.keys[next - 1];
+        let b = &self.keys[next];
+        let t = (time - a.time) / (b.time - a.time);
+
+        let value = match a.interpolation {
+            Interpolation::Step => a.value,
+            Interpolation::Linear => T::lerp(a.value, b.value, t),
+            Interpolation::Bezier {
+                control_out,
+                control_in,
+            } => {
+                // de Casteljau, which also works for rotations.
+                let p01 = T::lerp(a.value, control_out, t);
+                let p12 = T::lerp(control_out, control_in, t);
+                let p23 = T::lerp(control_in, b.value, t);
+                let p012 = T::lerp(p01, p12, t);
+                let p123 = T::lerp(p12, p23, t);
+                T::lerp(p012, p123, t)
+            }
+        };
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_track_has_no_value() {
+        assert!(Track::<f32>::new().sample(0.5).is_none());
+        assert!(Track::<Vec3>::default().sample(0.5).is_none());
+    }
+
+    #[test]
+    fn holds_the_ends_outside_the_keys() {
+        let track = Track::new().linear(1.0, 2.0).linear(3.0, 6.0);
+        assert_eq!(track.sample(0.0), Some(2.0));
+        assert_eq!(track.sample(5.0), Some(6.0));
+    }
+
+    #[test]
+    fn interpolates_between_keys() {
+        let track = Track::new()
+            .linear(0.0, 0.0)
+            .key(1.0, 10.0, Interpolation::Step)
+            .linear(2.0, 20.0);
+        assert_eq!(track.sample(0.25), Some(2.5));
+        assert_eq!(track.sample(1.5), Some(10.0));
+        assert_eq!(track.sample(2.0), Some(20.0));
+    }
+
+    #[test]
+    fn keys_are_sorted_by_time() {
+        let track = Track::new().linear(2.0, 20.0).linear(0.0, 0.0);
+        assert_eq!(track.sample(1.0), Some(10.0));
+    }
+
+    #[test]
+    fn bezier_passes_through_its_ends_and_controls_the_middle() {
+        let track = Track::new().bezier(0.0, 0.0, 1.0, 1.0).linear(1.0, 0.0);
+        assert_eq!(track.sample(0.0), Some(0.0));
+        assert_eq!(track.sample(1.0), Some(0.0));
+        // Halfway along, a cubic Bezier is (p0 + 3 p1 + 3 p2 + p3) / 8.
+        assert!((track.sample(0.5).unwrap() - 0.75).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,243 @@
+use std::{
+    collections::VecDeque,
+    io::{self, BufReader, BufWriter},
+    net::{TcpListener, TcpStream},
+    sync::{Condvar, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    rendering::{
+        camera::Camera,
+        film::{Film, FilmPixel},
+    },
+    scene::SceneSource,
+};
+
+use super::protocol::{read_message, write_message, Job, Message};
+
+pub struct CoordinatorConfig {
+    pub listen: String,
+    /// Sent to every worker when it joins.
+    pub scene: SceneSource,
+    pub seed: u64,
+    pub samples: u32,
+    /// Height of the row bands handed to workers.
+    pub tile_rows: u32,
+    /// Samples per job, so one band can be shared between several workers.
+    pub tile_samples: u32,
+    /// How long a worker may take on a job before it is given to someone else.
+    pub timeout: Duration,
+}
+
+struct WorkQueue {
+    pending: VecDeque<Job>,
+    remaining: usize,
+}
+
+struct State<'a> {
+    config: &'a CoordinatorConfig,
+    camera: &'a Camera,
+    queue: Mutex<WorkQueue>,
+    changed: Condvar,
+    film: Mutex<Film>,
+    total_jobs: usize,
+}
+
+impl State<'_> {
+    /// Blocks until a job is available, returning `None` once every job has
+    /// been completed.
+    fn next_job(&self) -> Option<Job> {
+        let mut queue = self.queue.lock().unwrap();
+        loop {
+            if queue.remaining == 0 {
+                return None;
+            }
+            if let Some(job) = queue.pending.pop_front() {
+                return Some(job);
+            }
+            queue = self.changed.wait(queue).unwrap();
+        }
+    }
+
+    fn requeue(&self, job: Job) {
+        self.queue.lock().unwrap().pending.push_front(job);
+        self.changed.notify_all();
+    }
+
+    /// Merges the tile a worker returned for `job`, unless it doesn't cover
+    /// the rows the job's tile should.
+    fn complete(&self, job: &Job, first_row: u32, pixels: &[FilmPixel]) -> io::Result<()> {
+        let rows = self.camera.tile_rows(&job.rows);
+        let expected = rows.len() * self.camera.image_width() as usize;
+        if first_row != rows.start || pixels.len() != expected {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "tile of {} pixels from row {first_row} for rows {rows:?}",
+                    pixels.len()
+                ),
+            ));
+        }
+        self.film.lock().unwrap().merge_rows(first_row, pixels);
+
+        let mut queue = self.queue.lock().unwrap();
+        queue.remaining -= 1;
+        print!(
+            "\rRendering... {:.0}%",
+            (1.0 - queue.remaining as f32 / self.total_jobs as f32) * 100.0
+        );
+        self.changed.notify_all();
+        Ok(())
+    }
+
+    fn finished(&self) -> bool {
+        self.queue.lock().unwrap().remaining == 0
+    }
+}
+
+/// Splits a frame `image_height` rows tall into jobs of at most
+/// `tile_rows` rows and `tile_samples` samples.
+fn split_jobs(config: &CoordinatorConfig, image_height: u32) -> io::Result<VecDeque<Job>> {
+    if config.tile_rows == 0 || config.tile_samples == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "tiles need at least one row and one sample",
+        ));
+    }
+
+    let scene = config.scene.hash();
+    let mut pending = VecDeque::new();
+    let mut id = 0;
+    for first_row in (0..image_height).step_by(config.tile_rows as usize) {
+        let rows = first_row..(first_row + config.tile_rows).min(image_height);
+        for first_sample in (0..config.samples).step_by(config.tile_samples as usize) {
+            let samples = first_sample..(first_sample + config.tile_samples).min(config.samples);
+            pending.push_back(Job {
+                id,
+                scene,
+                seed: config.seed,
+                rows: rows.clone(),
+                samples,
+            });
+            id += 1;
+        }
+    }
+    Ok(pending)
+}
+
+/// Splits the frame into jobs, serves them to every worker that connects and
+/// merges the results. Jobs held by workers that disconnect or exceed the
+/// timeout are handed out again.
+pub fn run(config: &CoordinatorConfig, camera: &Camera) -> io::Result<Film> {
+    let pending = split_jobs(config, camera.image_height())?;
+
+    let state = State {
+        config,
+        camera,
+        total_jobs: pending.len(),
+        queue: Mutex::new(WorkQueue {
+            remaining: pending.len(),
+            pending,
+        }),
+        changed: Condvar::new(),
+        film: Mutex::new(camera.new_film(config.seed)),
+    };
+
+    let listener = TcpListener::bind(&config.listen)?;
+    listener.set_nonblocking(true)?;
+    println!("Waiting for workers on {}", listener.local_addr()?);
+
+    thread::scope(|scope| -> io::Result<()> {
+        while !state.finished() {
+            match listener.accept() {
+                Ok((stream, address)) => {
+                    stream.set_nonblocking(false)?;
+                    let state = &state;
+                    scope.spawn(move || {
+                        if let Err(error) = serve_worker(state, stream) {
+                            println!("\nWorker {address} left: {error}");
+                        }
+                    });
+                }
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(50));
+                }
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(())
+    })?;
+
+    println!();
+    Ok(state.film.into_inner().unwrap())
+}
+
+fn serve_worker(state: &State, stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(state.config.timeout))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+
+    match read_message(&mut reader)? {
+        Message::Hello { name } => println!("\nWorker {name} joined"),
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "expected hello")),
+    }
+    write_message(&mut writer, &Message::Scene(state.config.scene.clone()))?;
+
+    while let Some(job) = state.next_job() {
+        let result = write_message(&mut writer, &Message::Job(job.clone()))
+            .and_then(|_| read_message(&mut reader));
+
+        let result = match result {
+            Ok(Message::Tile {
+                id,
+                first_row,
+                pixels,
+            }) if id == job.id => state.complete(&job, first_row, &pixels),
+            Ok(_) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unexpected message",
+            )),
+            Err(error) => Err(error),
+        };
+        if let Err(error) = result {
+            state.requeue(job);
+            return Err(error);
+        }
+    }
+
+    write_message(&mut writer, &Message::Shutdown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(tile_rows: u32, tile_samples: u32) -> CoordinatorConfig {
+        CoordinatorConfig {
+            listen: String::new(),
+            scene: SceneSource::Preset("spheres".into()),
+            seed: 0,
+            samples: 10,
+            tile_rows,
+            tile_samples,
+            timeout: Duration::from_secs(1),
+        }
+    }
+
+    #[test]
+    fn jobs_cover_the_frame() {
+        let jobs = split_jobs(&config(16, 4), 40).unwrap();
+        assert_eq!(jobs.len(), 3 * 3);
+        let last = jobs.back().unwrap();
+        assert_eq!(last.rows, 32..40);
+        assert_eq!(last.samples, 8..10);
+    }
+
+    #[test]
+    fn rejects_empty_tiles() {
+        assert!(split_jobs(&config(0, 4), 40).is_err());
+        assert!(split_jobs(&config(16, 0), 40).is_err());
+    }
+}
@@ -0,0 +1,7 @@
+//! Rendering a frame across several processes. A coordinator splits the frame
+//! into bands of rows and ranges of samples, hands them to workers over TCP
+//! and merges the returned float tiles into one film.
+
+pub mod coordinator;
+pub mod protocol;
+pub mod worker;
@@ -0,0 +1,338 @@
+use std::{
+    io::{self, Read, Write},
+    ops::Range,
+};
+
+use glam::Vec3;
+
+use crate::{rendering::film::FilmPixel, scene::SceneSource};
+
+const HELLO: u8 = 0;
+const JOB: u8 = 1;
+const TILE: u8 = 2;
+const SHUTDOWN: u8 = 3;
+const SCENE: u8 = 4;
+
+const PRESET: u8 = 0;
+const TEXT: u8 = 1;
+
+/// Largest payload either side sends or accepts, so that a bad length
+/// prefix can't make the reader allocate gigabytes.
+pub const MAX_MESSAGE_SIZE: usize = 256 << 20;
+
+/// Bytes a pixel takes in a tile.
+const PIXEL_SIZE: usize = 20;
+
+/// A band of rows to be rendered with a range of sample indices.
+#[derive(Clone)]
+pub struct Job {
+    pub id: u32,
+    /// `SceneSource::hash` of a scene sent to the worker before.
+    pub scene: u64,
+    pub seed: u64,
+    pub rows: Range<u32>,
+    pub samples: Range<u32>,
+}
+
+pub enum Message {
+    Hello {
+        name: String,
+    },
+    /// A scene later jobs refer to by its hash.
+    Scene(SceneSource),
+    Job(Job),
+    /// Rendered rows starting at `first_row`, which reach past the job's
+    /// rows by the reconstruction filter's margin.
+    Tile {
+        id: u32,
+        first_row: u32,
+        pixels: Vec<FilmPixel>,
+    },
+    Shutdown,
+}
+
+/// Every message is a tag byte followed by a length prefixed payload, all
+/// integers little endian.
+pub fn write_message(stream: &mut impl Write, message: &Message) -> io::Result<()> {
+    let mut payload = Vec::new();
+    let tag = match message {
+        Message::Hello { name } => {
+            put_str(&mut payload, name);
+            HELLO
+        }
+        Message::Job(job) => {
+            payload.extend(job.id.to_le_bytes());
+            payload.extend(job.scene.to_le_bytes());
+            payload.extend(job.seed.to_le_bytes());
+            payload.extend(job.rows.start.to_le_bytes());
+            payload.extend(job.rows.end.to_le_bytes());
+            payload.extend(job.samples.start.to_le_bytes());
+            payload.extend(job.samples.end.to_le_bytes());
+            JOB
+        }
+        Message::Scene(source) => {
+            let (kind, text) = match source {
+                SceneSource::Preset(name) => (PRESET, name),
+                SceneSource::Text(text) => (TEXT, text),
+            };
+            payload.push(kind);
+            put_str(&mut payload, text);
+            SCENE
+        }
+        Message::Tile {
+            id,
+            first_row,
+            pixels,
+        } => {
+            payload.extend(id.to_le_bytes());
+            payload.extend(first_row.to_le_bytes());
+            payload.extend((pixels.len() as u32).to_le_bytes());
+            for pixel in pixels {
+                for channel in pixel.sum.to_array() {
+                    payload.extend(channel.to_le_bytes());
+                }
+                payload.extend(pixel.weight.to_le_bytes());
+                payload.extend(pixel.samples.to_le_bytes());
+            }
+            TILE
+        }
+        Message::Shutdown => SHUTDOWN,
+    };
+    if payload.len() > MAX_MESSAGE_SIZE {
+        return Err(too_large(payload.len()));
+    }
+
+    stream.write_all(&[tag])?;
+    stream.write_all(&(payload.len() as u32).to_le_bytes())?;
+    stream.write_all(&payload)?;
+    stream.flush()
+}
+
+pub fn read_message(stream: &mut impl Read) -> io::Result<Message> {
+    let mut tag = [0u8; 1];
+    stream.read_exact(&mut tag)?;
+    let mut length = [0u8; 4];
+    stream.read_exact(&mut length)?;
+    let length = u32::from_le_bytes(length) as usize;
+    if length > MAX_MESSAGE_SIZE {
+        return Err(too_large(length));
+    }
+    let mut payload = vec![0u8; length];
+    stream.read_exact(&mut payload)?;
+
+    let mut reader = Reader {
+        bytes: &payload,
+        position: 0,
+    };
+    let message = match tag[0] {
+        HELLO => Message::Hello {
+            name: reader.string()?,
+        },
+        JOB => Message::Job(Job {
+            id: reader.u32()?,
+            scene: reader.u64()?,
+            seed: reader.u64()?,
+            rows: reader.u32()?..reader.u32()?,
+            samples: reader.u32()?..reader.u32()?,
+        }),
+        SCENE => {
+            let kind = reader.take::<1>()?[0];
+            let text = reader.string()?;
+            Message::Scene(match kind {
+                PRESET => SceneSource::Preset(text),
+                TEXT => SceneSource::Text(text),
+                other => return Err(invalid(format!("unknown scene kind {other}"))),
+            })
+        }
+        TILE => {
+            let id = reader.u32()?;
+            let first_row = reader.u32()?;
+            let count = reader.u32()? as usize;
+            if count * PIXEL_SIZE != reader.remaining() {
+                return Err(invalid(format!(
+                    "tile of {count} pixels in {} bytes",
+                    reader.remaining()
+                )));
+            }
+            let mut pixels = Vec::with_capacity(count);
+            for _ in 0..count {
+                let sum = Vec3::new(reader.f32()?, reader.f32()?, reader.f32()?);
+                let weight = reader.f32()?;
+                let samples = reader.u32()?;
+                pixels.push(FilmPixel {
+                    sum,
+                    weight,
+                    samples,
+                });
+            }
+            Message::Tile {
+                id,
+                first_row,
+                pixels,
+            }
+        }
+        SHUTDOWN => Message::Shutdown,
+        other => return Err(invalid(format!("unknown message tag {other}"))),
+    };
+
+    Ok(message)
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn too_large(length: usize) -> io::Error {
+    invalid(format!(
+        "message of {length} bytes is over the limit of {MAX_MESSAGE_SIZE}"
+    ))
+}
+
+fn put_str(payload: &mut Vec<u8>, value: &str) {
+    payload.extend((value.len() as u32).to_le_bytes());
+    payload.extend(value.as_bytes());
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let end = self.position + N;
+        let bytes = self
+            .bytes
+            .get(self.position..end)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+        self.position = end;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.position
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        self.take().map(u64::from_le_bytes)
+    }
+
+    fn f32(&mut self) -> io::Result<f32> {
+        self.take().map(f32::from_le_bytes)
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        let length = self.u32()? as usize;
+        let end = self.position + length;
+        let bytes = self
+            .bytes
+            .get(self.position..end)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+        self.position = end;
+        String::from_utf8(bytes.to_vec()).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(message: &Message) -> Message {
+        let mut bytes = Vec::new();
+        write_message(&mut bytes, message).unwrap();
+        read_message(&mut &bytes[..]).unwrap()
+    }
+
+    #[test]
+    fn job_round_trip() {
+        let job = Job {
+            id: 7,
+            scene: 0x0123_4567_89ab_cdef,
+            seed: 42,
+            rows: 16..32,
+            samples: 4..8,
+        };
+        let Message::Job(read) = round_trip(&Message::Job(job.clone())) else {
+            panic!("expected a job");
+        };
+        assert_eq!(read.id, job.id);
+        assert_eq!(read.scene, job.scene);
+        assert_eq!(read.seed, job.seed);
+        assert_eq!(read.rows, job.rows);
+        assert_eq!(read.samples, job.samples);
+    }
+
+    #[test]
+    fn scene_round_trip() {
+        for source in [
+            SceneSource::Preset("balls".to_string()),
+            SceneSource::Text("camera\n  size 4 3\n".to_string()),
+        ] {
+            let Message::Scene(read) = round_trip(&Message::Scene(source.clone())) else {
+                panic!("expected a scene");
+            };
+            assert_eq!(read, source);
+        }
+    }
+
+    #[test]
+    fn tile_round_trip() {
+        let pixels = vec![
+            FilmPixel {
+                sum: Vec3::new(1.0, 2.0, 3.0),
+                weight: 0.5,
+                samples: 4,
+            },
+            FilmPixel {
+                sum: Vec3::new(-1.0, 0.25, 1e9),
+                weight: 2.0,
+                samples: 1,
+            },
+        ];
+        let message = Message::Tile {
+            id: 3,
+            first_row: 10,
+            pixels: pixels.clone(),
+        };
+        let Message::Tile {
+            id,
+            first_row,
+            pixels: read,
+        } = round_trip(&message)
+        else {
+            panic!("expected a tile");
+        };
+        assert_eq!((id, first_row), (3, 10));
+        assert_eq!(read.len(), pixels.len());
+        for (read, pixel) in read.iter().zip(&pixels) {
+            assert_eq!(read.sum, pixel.sum);
+            assert_eq!(read.weight, pixel.weight);
+            assert_eq!(read.samples, pixel.samples);
+        }
+    }
+
+    #[test]
+    fn rejects_oversized_messages() {
+        let mut bytes = vec![TILE];
+        bytes.extend(u32::MAX.to_le_bytes());
+        let error = read_message(&mut &bytes[..]).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_tiles_with_the_wrong_pixel_count() {
+        let mut payload = Vec::new();
+        payload.extend(0u32.to_le_bytes());
+        payload.extend(0u32.to_le_bytes());
+        payload.extend(1_000_000u32.to_le_bytes());
+        let mut bytes = vec![TILE];
+        bytes.extend((payload.len() as u32).to_le_bytes());
+        bytes.extend(payload);
+        let error = read_message(&mut &bytes[..]).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,70 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufReader, BufWriter},
+    net::TcpStream,
+};
+
+use crate::{
+    math::reseed,
+    scene::{Scene, SceneSource},
+};
+
+use super::protocol::{read_message, write_message, Message};
+
+/// Connects to a coordinator and renders the jobs it hands out until told to
+/// shut down. Scenes arrive ahead of the jobs that refer to them, and the
+/// scene of the last job is kept built for the next one.
+pub fn run(address: &str) -> io::Result<()> {
+    let stream = TcpStream::connect(address)?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+
+    let name = format!("{}@{}", std::process::id(), writer.get_ref().local_addr()?);
+    write_message(&mut writer, &Message::Hello { name })?;
+
+    let mut sources: HashMap<u64, SceneSource> = HashMap::new();
+    let mut loaded: Option<(u64, u64, Scene)> = None;
+
+    loop {
+        let job = match read_message(&mut reader)? {
+            Message::Scene(source) => {
+                sources.insert(source.hash(), source);
+                continue;
+            }
+            Message::Job(job) => job,
+            Message::Shutdown => return Ok(()),
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "expected a job")),
+        };
+
+        let is_loaded =
+            matches!(&loaded, Some((scene, seed, _)) if *scene == job.scene && *seed == job.seed);
+        if !is_loaded {
+            let source = sources.get(&job.scene).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "job for a scene never sent")
+            })?;
+            reseed(job.seed);
+            let scene = source
+                .build()
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+            loaded = Some((job.scene, job.seed, scene));
+        }
+        let scene = &loaded.as_ref().unwrap().2;
+
+        let (first_row, pixels) = scene.camera().render_tile(
+            &scene.world,
+            job.seed,
+            job.rows.clone(),
+            job.samples.clone(),
+        );
+        println!("Rendered rows {:?}, samples {:?}", job.rows, job.samples);
+
+        write_message(
+            &mut writer,
+            &Message::Tile {
+                id: job.id,
+                first_row,
+                pixels,
+            },
+        )?;
+    }
+}
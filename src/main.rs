@@ -1,189 +1,532 @@
-use std::env;
-
-use glam::vec3;
-use math::{random, random_range};
-use object::{
-    bvh::{BVHCollection, BVHNode},
-    collection::ObjectCollection,
-    types::{Quad, Sphere},
+use std::{
+    env, fs,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
 };
+
+use animation::ShutterPosition;
+use distributed::coordinator::{self, CoordinatorConfig};
+use math::{hash_bytes, hash_seed, random_seed, reseed};
 use rendering::{
-    camera::{self, Camera, CameraConfig},
-    material::{Dielectric, Lambertian, Light, Metal},
-    ray::Color,
-    texture::{CheckerTexture, ImageTexture},
+    aperture::{Aperture, ApertureImage},
+    camera::{Camera, CameraConfig, Focus, PhysicalLens},
+    colorspace::{set_working_space, ColorSpace, OutputTransform, ViewTransform},
+    debug::DebugView,
+    denoise::DenoiseSettings,
+    film::{colors_to_float_image, colors_to_image, Film},
+    filter::{Filter, FilterKind},
+    integrator::IntegratorKind,
+    lens::LensPrescription,
+    material::BounceKind,
+    projection::{FisheyeMapping, Projection},
 };
+use scene::{description::SceneDescription, file::camera_settings, Scene, SceneSource};
 use show_image::{create_window, event};
 
+mod animation;
+mod distributed;
 mod math;
 mod object;
 mod rendering;
+mod scene;
+#[cfg(feature = "viewer")]
+mod viewer;
+
+#[derive(PartialEq)]
+enum Mode {
+    Local,
+    Coordinator,
+    Worker,
+    Viewer,
+}
 
-fn random_balls() -> (Camera, BVHCollection) {
-    let mut world = ObjectCollection::new();
-
-    let checkers =
-        CheckerTexture::with_colors(0.32, Color::new(0.2, 0.3, 0.1), Color::new(0.9, 0.9, 0.9));
-    let ground_material = Lambertian::new(checkers);
+struct Options {
+    mode: Mode,
+    /// Scene name, or the coordinator address in worker mode.
+    scene: String,
+    seed: Option<u64>,
+    samples: Option<u32>,
+    checkpoint: Option<PathBuf>,
+    checkpoint_interval: Duration,
+    resume: bool,
+    listen: String,
+    tile_rows: u32,
+    tile_samples: Option<u32>,
+    timeout: Duration,
+    frames: Option<RangeInclusive<u32>>,
+    fps: Option<f32>,
+    shutter_angle: Option<f32>,
+    shutter_position: Option<ShutterPosition>,
+    shutter_open: Option<f32>,
+    shutter_close: Option<f32>,
+    /// Directory the image, its render passes or the frames are written to.
+    output: PathBuf,
+    projection: Option<String>,
+    lens: Option<PathBuf>,
+    /// The prescription at `lens`, read by `load_files`.
+    lens_prescription: Option<Arc<LensPrescription>>,
+    aperture: Option<f32>,
+    focus: Option<Focus>,
+    f_stop: Option<f32>,
+    focal_length: Option<f32>,
+    sensor_width: Option<f32>,
+    blades: Option<u32>,
+    aperture_image: Option<PathBuf>,
+    /// The mask at `aperture_image`, read by `load_files`.
+    aperture_mask: Option<Arc<ApertureImage>>,
+    aovs: bool,
+    denoise: bool,
+    filter: Option<FilterKind>,
+    filter_radius: Option<f32>,
+    spectral: bool,
+    debug: Option<DebugView>,
+    integrator: Option<IntegratorKind>,
+    bounce_limits: Vec<(BounceKind, u32)>,
+    working_space: Option<ColorSpace>,
+    output_transform: OutputTransform,
+    export: Option<PathBuf>,
+    /// Export even if some objects can't be described, leaving them out.
+    partial_export: bool,
+}
 
-    world.add(Sphere::new(
-        vec3(0.0, -1000.0, 0.0),
-        1000.0,
-        ground_material,
-    ));
+impl Options {
+    fn parse() -> Self {
+        let mut args = env::args().skip(1);
+        let mut options = Self {
+            mode: Mode::Local,
+            scene: String::new(),
+            seed: None,
+            samples: None,
+            checkpoint: None,
+            checkpoint_interval: Duration::from_secs(60),
+            resume: false,
+            listen: String::from("0.0.0.0:7878"),
+            tile_rows: 16,
+            tile_samples: None,
+            timeout: Duration::from_secs(300),
+            frames: None,
+            fps: None,
+            shutter_angle: None,
+            shutter_position: None,
+            shutter_open: None,
+            shutter_close: None,
+            output: PathBuf::from("."),
+            projection: None,
+            lens: None,
+            lens_prescription: None,
+            aperture: None,
+            focus: None,
+            f_stop: None,
+            focal_length: None,
+            sensor_width: None,
+            blades: None,
+            aperture_image: None,
+            aperture_mask: None,
+            aovs: false,
+            denoise: false,
+            filter: None,
+            filter_radius: None,
+            spectral: false,
+            debug: None,
+            integrator: None,
+            bounce_limits: Vec::new(),
+            working_space: None,
+            output_transform: OutputTransform::default(),
+            export: None,
+            partial_export: false,
+        };
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .unwrap_or_else(|| panic!("Missing value for {arg}"))
+            };
+            match arg.as_str() {
+                "--seed" => options.seed = Some(value().parse().expect("Invalid seed")),
+                "--samples" => {
+                    options.samples = Some(value().parse().expect("Invalid sample count"))
+                }
+                "--checkpoint" => options.checkpoint = Some(value().into()),
+                "--checkpoint-interval" => {
+                    options.checkpoint_interval =
+                        Duration::from_secs(value().parse().expect("Invalid checkpoint interval"))
+                }
+                "--resume" => options.resume = true,
+                "--listen" => options.listen = value(),
+                "--tile-rows" => options.tile_rows = value().parse().expect("Invalid tile height"),
+                "--tile-samples" => {
+                    options.tile_samples = Some(value().parse().expect("Invalid tile samples"))
+                }
+                "--timeout" => {
+                    options.timeout = Duration::from_secs(value().parse().expect("Invalid timeout"))
+                }
+                "--frames" => options.frames = Some(parse_frames(&value())),
+                "--fps" => options.fps = Some(value().parse().expect("Invalid frame rate")),
+                "--shutter-angle" => {
+                    options.shutter_angle = Some(value().parse().expect("Invalid shutter angle"))
+                }
+                "--shutter-position" => {
+                    options.shutter_position = Some(match value().as_str() {
+                        "start" => ShutterPosition::Start,
+                        "center" => ShutterPosition::Center,
+                        "end" => ShutterPosition::End,
+                        _ => panic!("Shutter position must be start, center or end"),
+                    })
+                }
+                "--shutter-open" => {
+                    options.shutter_open = Some(value().parse().expect("Invalid shutter time"))
+                }
+                "--shutter-close" => {
+                    options.shutter_close = Some(value().parse().expect("Invalid shutter time"))
+                }
+                "--output" => options.output = value().into(),
+                "--projection" => options.projection = Some(value()),
+                "--lens" => options.lens = Some(value().into()),
+                "--aperture" => {
+                    options.aperture = Some(value().parse().expect("Invalid aperture diameter"))
+                }
+                "--focus" => options.focus = Some(parse_focus(&value())),
+                "--f-stop" => options.f_stop = Some(value().parse().expect("Invalid f-stop")),
+                "--focal-length" => {
+                    options.focal_length = Some(value().parse().expect("Invalid focal length"))
+                }
+                "--sensor-width" => {
+                    options.sensor_width = Some(value().parse().expect("Invalid sensor width"))
+                }
+                "--blades" => {
+                    options.blades = Some(value().parse().expect("Invalid aperture blade count"))
+                }
+                "--aperture-image" => options.aperture_image = Some(value().into()),
+                "--aovs" => options.aovs = true,
+                "--denoise" => options.denoise = true,
+                "--filter" => {
+                    options.filter = Some(
+                        FilterKind::from_name(&value())
+                            .expect("Filter must be box, tent, gaussian, mitchell or lanczos"),
+                    )
+                }
+                "--filter-radius" => {
+                    options.filter_radius = Some(value().parse().expect("Invalid filter radius"))
+                }
+                "--spectral" => options.spectral = true,
+                "--debug" => {
+                    options.debug = Some(DebugView::from_name(&value()).expect(
+                        "Debug view must be normals, uv, depth, material-id, object-id, albedo, \
+                         node-visits, primitive-tests, wireframe or furnace",
+                    ))
+                }
+                "--integrator" => {
+                    options.integrator = Some(
+                        IntegratorKind::from_name(&value())
+                            .expect("Integrator must be naive, whitted, ao, direct, path or bdpt"),
+                    )
+                }
+                "--diffuse-bounces" | "--specular-bounces" | "--transmission-bounces" => {
+                    let kind = arg.trim_start_matches("--").trim_end_matches("-bounces");
+                    let kind = BounceKind::from_name(kind).unwrap();
+                    let limit = value().parse().expect("Invalid bounce count");
+                    options.bounce_limits.push((kind, limit));
+                }
+                "--working-space" => options.working_space = Some(parse_color_space(&value())),
+                "--output-space" => options.output_transform.space = parse_color_space(&value()),
+                "--view" => {
+                    options.output_transform.view = ViewTransform::from_name(&value())
+                        .expect("View transform must be standard, reinhard or filmic")
+                }
+                "--exposure" => {
+                    options.output_transform.exposure = value().parse().expect("Invalid exposure")
+                }
+                "--export" => options.export = Some(value().into()),
+                "--partial-export" => options.partial_export = true,
+                "coordinator" if options.scene.is_empty() => options.mode = Mode::Coordinator,
+                "worker" if options.scene.is_empty() => options.mode = Mode::Worker,
+                "viewer" if options.scene.is_empty() => options.mode = Mode::Viewer,
+                _ => options.scene = arg,
+            }
+        }
 
-    for a in -11..11 {
-        for b in -11..11 {
-            let material_choice = random();
-            let center = vec3(a as f32 + 0.9 * random(), 0.2, b as f32 + 0.9 * random());
+        if options.resume && options.checkpoint.is_none() {
+            options.checkpoint = Some(PathBuf::from("render.ckpt"));
+        }
 
-            if (center - vec3(4.0, 0.2, 0.0)).length() <= 0.9 {
-                continue;
-            }
+        options
+    }
 
-            if material_choice < 0.8 {
-                let albedo = Color::random();
-                world.add(Sphere::new(center, 0.2, Lambertian::solid_color(albedo)))
-            } else if material_choice < 0.95 {
-                let albedo = Color::random();
-                let fuzz = random_range(0.0..0.5);
-                let material = Metal::solid_color(albedo, fuzz);
-                world.add(Sphere::new(center, 0.2, material))
-            } else {
-                let material = Dielectric::new(1.5);
-                world.add(Sphere::new(center, 0.2, material))
-            }
+    /// Reads the lens prescription and aperture image given with `--lens`
+    /// and `--aperture-image`, so bad ones are reported before the scene is
+    /// built.
+    fn load_files(&mut self) -> Result<(), String> {
+        if let Some(path) = &self.lens {
+            let lens = LensPrescription::from_file(path)
+                .map_err(|error| format!("Cannot load lens {}: {error}", path.display()))?;
+            self.lens_prescription = Some(Arc::new(lens));
+        }
+        if let Some(path) = &self.aperture_image {
+            let mask = ApertureImage::from_file(path).map_err(|error| {
+                format!("Cannot load aperture image {}: {error}", path.display())
+            })?;
+            self.aperture_mask = Some(Arc::new(mask));
         }
+        Ok(())
     }
 
-    world.add(Sphere::new(vec3(0.0, 1.0, 0.0), 1.0, Dielectric::new(1.5)));
+    /// Whether films need render passes, which the denoiser is guided by.
+    fn needs_aovs(&self) -> bool {
+        self.aovs || self.denoise
+    }
 
-    world.add(Sphere::new(
-        vec3(-4.0, 1.0, 0.0),
-        1.0,
-        Lambertian::solid_color(Color::new(0.4, 0.2, 0.1)),
-    ));
+    /// Applies the lens, projection, filter, spectral, debug, integrator and
+    /// bounce options to `camera`, returning whether anything was changed.
+    fn override_camera(&self, camera: &mut CameraConfig) -> bool {
+        let mut changed = false;
 
-    world.add_light(Sphere::new(
-        vec3(4.0, 1.0, 0.0),
-        0.2,
-        Light::solid_color(Color::new(2.0, 2.0, 2.0)), //Metal::solid_color(Color::new(0.7, 0.6, 0.5), 0.1),
-    ));
+        if let Some(projection) = self.projection(camera) {
+            if let Projection::Equirectangular = projection {
+                camera.aspect_ratio = 2.0;
+            }
+            camera.projection = projection;
+            changed = true;
+        }
+        if let Some(focus) = self.focus {
+            camera.focus = focus;
+            changed = true;
+        }
+        // A sensor width alone only changes a lens the scene already has.
+        let lens_given = self.f_stop.is_some() || self.focal_length.is_some();
+        if lens_given || (self.sensor_width.is_some() && camera.lens.is_some()) {
+            let lens = camera.lens.unwrap_or_default();
+            camera.lens = Some(PhysicalLens {
+                focal_length: self.focal_length.unwrap_or(lens.focal_length),
+                f_stop: self.f_stop.unwrap_or(lens.f_stop),
+                sensor_width: self.sensor_width.unwrap_or(lens.sensor_width),
+            });
+            changed = true;
+        }
+        if let Some(mask) = &self.aperture_mask {
+            camera.aperture = Aperture::Image(mask.clone());
+            changed = true;
+        } else if let Some(blades) = self.blades {
+            camera.aperture = Aperture::Polygon {
+                blades,
+                rotation: 0.0,
+            };
+            changed = true;
+        }
+        if self.filter.is_some() || self.filter_radius.is_some() {
+            let filter = self.filter.map_or(camera.filter, Filter::new);
+            camera.filter = filter.with_radius(self.filter_radius.unwrap_or(filter.radius));
+            changed = true;
+        }
+        if self.spectral {
+            camera.spectral = true;
+            changed = true;
+        }
+        if let Some(view) = self.debug {
+            camera.debug = Some(view);
+            changed = true;
+        }
+        if let Some(integrator) = self.integrator {
+            camera.integrator = integrator;
+            changed = true;
+        }
+        for &(kind, limit) in &self.bounce_limits {
+            camera.bounce_limits.set(kind, limit);
+            changed = true;
+        }
 
-    let vfov = 20.0;
-    let look_from = vec3(13.0, 2.0, 3.0);
-    let look_at = vec3(0.0, 0.0, 0.0);
+        changed
+    }
 
-    let camera = Camera::new(CameraConfig {
-        image_width: 800,
-        samples_per_pixel: 1000,
-        defocus_angle: 0.0,
-        vfov,
-        look_from,
-        look_at,
-        skybox: Color::new(0., 0., 0.),
-        ..Default::default()
-    });
+    /// The projection picked on the command line, framed like `camera`.
+    fn projection(&self, camera: &CameraConfig) -> Option<Projection> {
+        if let Some(lens) = &self.lens_prescription {
+            return Some(Projection::Realistic {
+                lens: lens.clone(),
+                sensor_width: self.sensor_width.unwrap_or(PhysicalLens::FULL_FRAME_WIDTH),
+                aperture_diameter: self.aperture.unwrap_or(f32::MAX),
+            });
+        }
 
-    (camera, world.as_bvh())
+        Some(match self.projection.as_deref()? {
+            "perspective" => Projection::Perspective,
+            "orthographic" => Projection::Orthographic {
+                height: 2.0
+                    * (camera.vfov.to_radians() / 2.0).tan()
+                    * (camera.look_from - camera.look_at).length(),
+            },
+            "equirectangular" => Projection::Equirectangular,
+            "fisheye" => Projection::Fisheye {
+                fov: 180.0,
+                mapping: FisheyeMapping::Equidistant,
+            },
+            "fisheye-equisolid" => Projection::Fisheye {
+                fov: 180.0,
+                mapping: FisheyeMapping::Equisolid,
+            },
+            "cylindrical" => Projection::Cylindrical {
+                horizontal_fov: 360.0,
+            },
+            _ => panic!(
+                "Projection must be perspective, orthographic, equirectangular, fisheye, \
+                 fisheye-equisolid or cylindrical"
+            ),
+        })
+    }
 }
 
-fn earth() -> (Camera, BVHCollection) {
-    let mut world = ObjectCollection::new();
-    world.add(Sphere::new(
-        vec3(0., 1.0, 0.0),
-        1.0,
-        Lambertian::new(ImageTexture::from_file("earthmap.jpg")),
-    ));
-
-    let vfov = 45.0;
-    let look_from = vec3(3., 2.0, -1.0);
-    let look_at = vec3(0.0, 1.0, 0.0);
-
-    let camera = Camera::new(CameraConfig {
-        image_width: 5120,
-        samples_per_pixel: 8192,
-        defocus_angle: 0.0,
-        vfov,
-        look_from,
-        look_at,
-        ..Default::default()
-    });
-
-    (camera, world.as_bvh())
+/// Parses `manual`, `lookat` or a pixel to focus on written as `x,y`.
+fn parse_focus(value: &str) -> Focus {
+    match value {
+        "manual" => Focus::Manual,
+        "lookat" => Focus::LookAt,
+        _ => {
+            let (x, y) = value
+                .split_once(',')
+                .expect("Focus must be manual, lookat or a pixel such as 200,120");
+            Focus::Pixel(
+                x.parse().expect("Invalid focus pixel"),
+                y.parse().expect("Invalid focus pixel"),
+            )
+        }
+    }
 }
 
-fn quads() -> (Camera, BVHCollection) {
-    let mut world = ObjectCollection::new();
-
-    let red = Lambertian::solid_color(Color::new(1.0, 0.2, 0.2));
-    let green = Lambertian::solid_color(Color::new(0.2, 1.0, 0.2));
-    let blue = Lambertian::solid_color(Color::new(0.2, 0.2, 1.0));
-    let orange = Lambertian::solid_color(Color::new(1.0, 0.5, 0.0));
-    let teal = Lambertian::solid_color(Color::new(0.2, 0.8, 0.8));
-
-    world.add(Quad::new(
-        vec3(-3., -2., 5.),
-        vec3(0., 0., -4.),
-        vec3(0., 4., 0.),
-        red,
-    ));
-
-    world.add(Quad::new(
-        vec3(-2., -2., 0.),
-        vec3(4., 0., 0.),
-        vec3(0., 4., 0.),
-        green,
-    ));
-
-    world.add(Quad::new(
-        vec3(3., -2., 1.),
-        vec3(0., 0., 4.),
-        vec3(0., 4., 0.),
-        blue,
-    ));
-
-    world.add(Quad::new(
-        vec3(-2., 3., 1.),
-        vec3(4., 0., 0.),
-        vec3(0., 0., 4.),
-        orange,
-    ));
-
-    world.add(Quad::new(
-        vec3(-2., -3., 5.),
-        vec3(4., 0., 0.),
-        vec3(0., 0., -4.),
-        teal,
-    ));
-
-    let camera = Camera::new(CameraConfig {
-        aspect_ratio: 1.0,
-        image_width: 400,
-        samples_per_pixel: 100,
-        max_bounces: 50,
-        vfov: 80.0,
-        look_from: vec3(0., 0., 9.),
-        look_at: vec3(0., 0., 0.),
-        defocus_angle: 0.0,
-        ..Default::default()
-    });
+fn parse_color_space(value: &str) -> ColorSpace {
+    ColorSpace::from_name(value).expect("Color space must be srgb, rec709, acescg or p3")
+}
 
-    (camera, world.as_bvh())
+/// Parses a frame range such as `1-48`, or a single frame number.
+fn parse_frames(value: &str) -> RangeInclusive<u32> {
+    let (start, end) = value.split_once('-').unwrap_or((value, value));
+    let start = start.parse().expect("Invalid frame range");
+    let end = end.parse().expect("Invalid frame range");
+    start..=end
 }
 
 #[show_image::main]
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let scene = env::args().nth(1).unwrap();
-    let (camera, world) = match scene.as_str() {
-        "balls" => random_balls(),
-        "earth" => earth(),
-        "quads" => quads(),
-        _ => panic!("Nonexistent scene"),
+    let mut options = Options::parse();
+    options.load_files()?;
+
+    if options.mode == Mode::Worker {
+        distributed::worker::run(&options.scene)?;
+        return Ok(());
+    }
+
+    let resumed = if options.resume {
+        let path = options.checkpoint.as_ref().unwrap();
+        let film = Film::load_checkpoint(path)?;
+        println!(
+            "Resuming from {} ({} samples per pixel)",
+            path.display(),
+            film.samples_taken()
+        );
+        Some(film)
+    } else {
+        None
     };
-    let image = camera.render(&world);
 
-    image.save("image.png").unwrap();
+    // The scene is built from the render seed so a resumed render sees the
+    // exact same randomly generated world.
+    let seed = resumed
+        .as_ref()
+        .map(Film::seed)
+        .or(options.seed)
+        .unwrap_or_else(random_seed);
+    reseed(seed);
+
+    // Colors in the scene are read in the working space, so it has to be
+    // in place before the scene is built.
+    if let Some(space) = options.working_space {
+        if options.mode == Mode::Coordinator {
+            return Err("The working space is not forwarded to workers".into());
+        }
+        set_working_space(space.primaries());
+    }
+
+    let source = SceneSource::open(&options.scene)?;
+    let scene_id = source.hash();
+    let mut scene = source.build()?;
+
+    if options.override_camera(&mut scene.camera) && options.mode == Mode::Coordinator {
+        return Err("Camera overrides are not forwarded to workers".into());
+    }
+
+    if let Some(path) = &options.export {
+        return export(&scene, path, options.partial_export);
+    }
+
+    if options.mode == Mode::Viewer {
+        #[cfg(feature = "viewer")]
+        return viewer::run(scene, options.output_transform, seed);
+        #[cfg(not(feature = "viewer"))]
+        return Err("The viewer needs rtx built with `--features viewer`".into());
+    }
+
+    if options.frames.is_some() || scene.animation.is_some() {
+        return render_sequence(&options, &scene, seed);
+    }
+
+    if options.shutter_open.is_some() || options.shutter_close.is_some() {
+        if options.mode == Mode::Coordinator {
+            return Err("Shutter overrides are not forwarded to workers".into());
+        }
+        let open = options.shutter_open.unwrap_or(scene.camera.shutter_open);
+        scene.camera.shutter_open = open;
+        scene.camera.shutter_close = options.shutter_close.unwrap_or(open);
+    }
+
+    let camera = scene.camera();
+    let target_samples = options.samples.unwrap_or(camera.samples_per_pixel());
+
+    if options.needs_aovs() && options.mode == Mode::Coordinator {
+        return Err("Render passes are only written by local renders".into());
+    }
+
+    let film = if options.mode == Mode::Coordinator {
+        coordinator::run(
+            &CoordinatorConfig {
+                listen: options.listen.clone(),
+                scene: source,
+                seed,
+                samples: target_samples,
+                tile_rows: options.tile_rows,
+                tile_samples: options.tile_samples.unwrap_or(target_samples),
+                timeout: options.timeout,
+            },
+            &camera,
+        )?
+    } else {
+        render_local(
+            &options,
+            &scene,
+            &camera,
+            resumed,
+            seed,
+            scene_id,
+            target_samples,
+        )?
+    };
+
+    let image = if options.denoise {
+        let colors = film.denoised(&DenoiseSettings::default()).unwrap();
+        colors_to_image(
+            film.width(),
+            film.height(),
+            &colors,
+            &options.output_transform,
+        )
+    } else {
+        film.to_image(&options.output_transform)
+    };
+    fs::create_dir_all(&options.output)?;
+    let path = options.output.join("image.png");
+    image.save(&path)?;
+    if options.aovs {
+        film.save_aovs(&path, &options.output_transform)?;
+    }
 
     // Create a window with default options and display the image.
     let window = create_window("balls", Default::default()).unwrap();
@@ -203,3 +546,141 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// Writes the scene to `path`, as geometry only if it is an OBJ file.
+fn export(scene: &Scene, path: &Path, partial: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let description = SceneDescription::of(scene);
+    if description.skipped > 0 && !partial {
+        return Err(format!(
+            "{} objects of the scene can't be exported; pass --partial-export to leave them out",
+            description.skipped
+        )
+        .into());
+    }
+    if path.extension().is_some_and(|extension| extension == "obj") {
+        description.write_obj(path)?;
+    } else {
+        description.save(path)?;
+    }
+    println!(
+        "Exported {} objects to {} ({} skipped)",
+        description.objects.len(),
+        path.display(),
+        description.skipped
+    );
+    Ok(())
+}
+
+/// Renders every frame of an animation to numbered OpenEXR files.
+fn render_sequence(
+    options: &Options,
+    scene: &Scene,
+    seed: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut animation = scene.animation.clone().unwrap_or_default();
+    if let Some(frames) = &options.frames {
+        animation.frames = frames.clone();
+    }
+    if let Some(fps) = options.fps {
+        animation.fps = fps;
+    }
+    if let Some(shutter_angle) = options.shutter_angle {
+        animation.shutter_angle = shutter_angle;
+    }
+    if let Some(shutter_position) = options.shutter_position {
+        animation.shutter_position = shutter_position;
+    }
+
+    fs::create_dir_all(&options.output)?;
+    for frame in animation.frames.clone() {
+        let camera = scene.frame_camera(&animation, frame);
+        let target_samples = options.samples.unwrap_or(camera.samples_per_pixel());
+
+        let mut film = camera.new_film(hash_seed(&[seed, frame as u64]));
+        if options.needs_aovs() {
+            film.enable_aovs(scene.world.lights().len());
+        }
+        camera.render_progressive(&scene.world, &mut film, target_samples, |_| {});
+
+        let path = options.output.join(format!("frame_{frame:04}.exr"));
+        if options.denoise {
+            let colors = film.denoised(&DenoiseSettings::default()).unwrap();
+            colors_to_float_image(
+                film.width(),
+                film.height(),
+                &colors,
+                &options.output_transform,
+            )
+            .save(&path)?;
+        } else {
+            film.to_float_image(&options.output_transform).save(&path)?;
+        }
+        if options.aovs {
+            film.save_aovs(&path, &options.output_transform)?;
+        }
+        println!("\rSaved {}", path.display());
+    }
+
+    Ok(())
+}
+
+fn render_local(
+    options: &Options,
+    scene: &Scene,
+    camera: &Camera,
+    resumed: Option<Film>,
+    seed: u64,
+    scene_id: u64,
+    target_samples: u32,
+) -> Result<Film, Box<dyn std::error::Error>> {
+    // Options can change the camera from the scene's, and a checkpoint is
+    // only resumed with the same one.
+    let camera_id = hash_bytes(camera_settings(&scene.camera).as_bytes());
+    let mut film = match resumed {
+        Some(film) => {
+            if film.scene() != scene_id {
+                return Err("Checkpoint was rendered from a different scene".into());
+            }
+            if film.camera() != camera_id {
+                return Err("Checkpoint was rendered with different camera settings".into());
+            }
+            if film.width() != camera.image_width() || film.height() != camera.image_height() {
+                return Err("Checkpoint resolution does not match the scene".into());
+            }
+            film
+        }
+        None => {
+            let mut film = camera
+                .new_film(seed)
+                .with_scene(scene_id)
+                .with_camera(camera_id);
+            if options.needs_aovs() {
+                film.enable_aovs(scene.world.lights().len());
+            }
+            film
+        }
+    };
+    if options.needs_aovs() && film.aovs().is_none() {
+        return Err("Checkpoint was rendered without render passes".into());
+    }
+
+    let mut last_checkpoint = Instant::now();
+    camera.render_progressive(&scene.world, &mut film, target_samples, |film| {
+        if let Some(path) = &options.checkpoint {
+            if last_checkpoint.elapsed() >= options.checkpoint_interval {
+                // A failed write leaves the previous checkpoint in place, so
+                // keep rendering and try again at the next interval.
+                if let Err(error) = film.save_checkpoint(path) {
+                    eprintln!("\nCould not write {}: {error}", path.display());
+                }
+                last_checkpoint = Instant::now();
+            }
+        }
+    });
+
+    if let Some(path) = &options.checkpoint {
+        film.save_checkpoint(path)?;
+    }
+
+    Ok(film)
+}
@@ -1,7 +1,9 @@
 pub mod interval;
+pub mod polynomial;
 pub mod random;
 pub mod vector;
 
 pub use interval::{Interval, IntervalExt};
-pub use random::{random, random_int, random_range};
+pub use polynomial::{polynomial_roots, solve_quadratic};
+pub use random::{hash_bytes, hash_seed, random, random_int, random_range, random_seed, reseed};
 pub use vector::VecExt;
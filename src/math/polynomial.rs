@@ -0,0 +1,188 @@
+use super::Interval;
+
+/// Real roots of a t² + b t + c, smaller first.
+pub fn solve_quadratic(a: f32, b: f32, c: f32) -> Option<(f32, f32)> {
+    if a == 0.0 {
+        if b == 0.0 {
+            return None;
+        }
+        let t = -c / b;
+        return Some((t, t));
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    // Avoids cancellation between -b and the square root.
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    let (t0, t1) = if q == 0.0 { (0.0, 0.0) } else { (q / a, c / q) };
+    Some((t0.min(t1), t0.max(t1)))
+}
+
+/// Real roots within `range` of the polynomial with `coefficients`, highest
+/// power first, in increasing order.
+///
+/// Between neighbouring roots of its derivative a polynomial only rises or
+/// falls, so it crosses zero at most once there, and bisection finds the
+/// crossing. The derivative's roots are found the same way, down to a line.
+/// A root the polynomial touches without crossing, such as at a tangent hit,
+/// is found where it is zero up to rounding at a root of the derivative.
+pub fn polynomial_roots(coefficients: &[f64], range: &Interval) -> Vec<f32> {
+    real_roots(coefficients, range.start as f64, range.end as f64)
+        .into_iter()
+        .map(|t| t as f32)
+        .collect()
+}
+
+fn real_roots(coefficients: &[f64], start: f64, end: f64) -> Vec<f64> {
+    // Leading zeros lower the degree.
+    let Some(first) = coefficients.iter().position(|&c| c != 0.0) else {
+        return Vec::new();
+    };
+    let coefficients = &coefficients[first..];
+    let degree = coefficients.len() - 1;
+    match degree {
+        0 => return Vec::new(),
+        1 => {
+            let t = -coefficients[1] / coefficients[0];
+            return if (start..=end).contains(&t) {
+                vec![t]
+            } else {
+                Vec::new()
+            };
+        }
+        _ => {}
+    }
+
+    let evaluate = |t: f64| coefficients.iter().fold(0.0, |sum, &c| sum * t + c);
+    // Bound on the rounding error of `evaluate`, with some margin for that
+    // of the coefficients.
+    let rounding = |t: f64| {
+        let magnitude = coefficients
+            .iter()
+            .fold(0.0, |sum, &c| sum * t.abs() + c.abs());
+        magnitude * 1e-12
+    };
+
+    let derivative: Vec<f64> = coefficients[..degree]
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| c * (degree - i) as f64)
+        .collect();
+    let mut bounds = vec![start];
+    bounds.extend(real_roots(&derivative, start, end));
+    bounds.push(end);
+
+    let last = bounds.len() - 1;
+    let values: Vec<f64> = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, &t)| match evaluate(t) {
+            value if i > 0 && i < last && value.abs() <= rounding(t) => 0.0,
+            value => value,
+        })
+        .collect();
+
+    let mut roots = Vec::new();
+    for i in 0..bounds.len() {
+        if values[i] == 0.0 && roots.last() != Some(&bounds[i]) {
+            roots.push(bounds[i]);
+        }
+        if i < last && values[i] * values[i + 1] < 0.0 {
+            roots.push(bisect(evaluate, bounds[i], bounds[i + 1], values[i]));
+        }
+    }
+    roots
+}
+
+/// The crossing of zero between `low` and `high`, where `f` has the value
+/// `f_low` at `low` and the opposite sign at `high`.
+fn bisect(f: impl Fn(f64) -> f64, mut low: f64, mut high: f64, mut f_low: f64) -> f64 {
+    loop {
+        let middle = 0.5 * (low + high);
+        if middle <= low || middle >= high {
+            return middle;
+        }
+        let f_middle = f(middle);
+        if f_middle == 0.0 {
+            return middle;
+        }
+        if f_low * f_middle < 0.0 {
+            high = middle;
+        } else {
+            (low, f_low) = (middle, f_middle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Coefficients of the product of `(t - root)` for every root, and of
+    /// `t² + 1` for every complex pair.
+    fn with_roots(roots: &[f64], complex_pairs: usize) -> Vec<f64> {
+        let mut coefficients = vec![1.0];
+        let mut multiply = |factor: &[f64]| {
+            let mut product = vec![0.0; coefficients.len() + factor.len() - 1];
+            for (i, a) in coefficients.iter().enumerate() {
+                for (j, b) in factor.iter().enumerate() {
+                    product[i + j] += a * b;
+                }
+            }
+            coefficients = product;
+        };
+        for root in roots {
+            multiply(&[1.0, -root]);
+        }
+        for _ in 0..complex_pairs {
+            multiply(&[1.0, 0.0, 1.0]);
+        }
+        coefficients
+    }
+
+    fn assert_roots(found: Vec<f32>, expected: &[f32]) {
+        assert_eq!(found.len(), expected.len(), "{found:?}");
+        for (found, expected) in found.iter().zip(expected) {
+            assert!((found - expected).abs() < 1e-5, "{found} for {expected}");
+        }
+    }
+
+    #[test]
+    fn finds_the_four_roots_of_a_quartic() {
+        let quartic = with_roots(&[1.0, 2.0, 3.0, 4.0], 0);
+        assert_eq!(quartic, [1.0, -10.0, 35.0, -50.0, 24.0]);
+        assert_roots(
+            polynomial_roots(&quartic, &(0.0..10.0)),
+            &[1.0, 2.0, 3.0, 4.0],
+        );
+        assert_roots(polynomial_roots(&quartic, &(2.5..10.0)), &[3.0, 4.0]);
+        let negated: Vec<f64> = quartic.iter().map(|c| -c).collect();
+        assert_roots(
+            polynomial_roots(&negated, &(0.0..10.0)),
+            &[1.0, 2.0, 3.0, 4.0],
+        );
+    }
+
+    #[test]
+    fn finds_roots_close_together() {
+        let quartic = with_roots(&[1.0, 1.0001], 1);
+        assert_roots(polynomial_roots(&quartic, &(0.0..100.0)), &[1.0, 1.0001]);
+    }
+
+    #[test]
+    fn finds_roots_the_polynomial_only_touches() {
+        let quartic = with_roots(&[-0.5, 2.0, 2.0], 0);
+        assert_roots(polynomial_roots(&quartic, &(0.0..10.0)), &[2.0]);
+        let quartic = with_roots(&[3.0, 3.0], 1);
+        assert_roots(polynomial_roots(&quartic, &(0.0..10.0)), &[3.0]);
+    }
+
+    #[test]
+    fn finds_no_roots_of_complex_pairs() {
+        let quartic = with_roots(&[], 2);
+        assert!(polynomial_roots(&quartic, &(-100.0..100.0)).is_empty());
+    }
+}
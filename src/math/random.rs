@@ -1,13 +1,59 @@
-use std::ops::Range;
+use std::{cell::RefCell, ops::Range};
 
-use rand::distributions::{Distribution, Uniform};
+use rand::{
+    distributions::{Distribution, Uniform},
+    rngs::StdRng,
+    Rng, SeedableRng,
+};
 
 use super::Interval;
 
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+/// Runs `f` with this thread's random number generator.
+pub fn with_rng<T>(f: impl FnOnce(&mut StdRng) -> T) -> T {
+    RNG.with(|rng| f(&mut rng.borrow_mut()))
+}
+
+/// Reseeds this thread's generator, making every following draw reproducible.
+pub fn reseed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+/// Mixes several values into one well distributed seed (SplitMix64 finalizer).
+pub fn hash_seed(values: &[u64]) -> u64 {
+    values.iter().fold(0x9E37_79B9_7F4A_7C15, |acc, &value| {
+        let mut z = (acc ^ value).wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    })
+}
+
+/// Hashes `bytes` like `hash_seed` does numbers, e.g. to tell scenes apart
+/// by their text.
+pub fn hash_bytes(bytes: &[u8]) -> u64 {
+    let words = bytes.chunks(8).map(|chunk| {
+        let mut word = [0u8; 8];
+        word[..chunk.len()].copy_from_slice(chunk);
+        u64::from_le_bytes(word)
+    });
+    hash_seed(
+        &std::iter::once(bytes.len() as u64)
+            .chain(words)
+            .collect::<Vec<_>>(),
+    )
+}
+
+pub fn random_seed() -> u64 {
+    with_rng(|rng| rng.gen())
+}
+
 pub fn random_range(range: Interval) -> f32 {
-    let mut rng = rand::thread_rng();
     let uniform = Uniform::from(range);
-    uniform.sample(&mut rng)
+    with_rng(|rng| uniform.sample(rng))
 }
 
 pub fn random() -> f32 {
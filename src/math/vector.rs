@@ -1,7 +1,7 @@
 use glam::{vec3, Vec3};
 use rand::distributions::{Distribution, Uniform};
 
-use super::{random_range, Interval};
+use super::{random::with_rng, random_range, Interval};
 
 pub trait VecExt {
     fn random() -> Self;
@@ -20,12 +20,13 @@ impl VecExt for Vec3 {
     }
 
     fn random_range(range: Interval) -> Vec3 {
-        let mut rng = rand::thread_rng();
         let uniform = Uniform::from(range);
-        let x = uniform.sample(&mut rng);
-        let y = uniform.sample(&mut rng);
-        let z = uniform.sample(&mut rng);
-        vec3(x, y, z)
+        with_rng(|rng| {
+            let x = uniform.sample(rng);
+            let y = uniform.sample(rng);
+            let z = uniform.sample(rng);
+            vec3(x, y, z)
+        })
     }
 
     fn random_in_unit_sphere() -> Vec3 {
@@ -43,7 +44,7 @@ impl VecExt for Vec3 {
 
     fn random_in_unit_disk() -> Self {
         loop {
-            let v = vec3(random_range(-1.0..0.0), random_range(-1.0..0.0), 0.0);
+            let v = vec3(random_range(-1.0..1.0), random_range(-1.0..1.0), 0.0);
             if v.length_squared() < 1.0 {
                 return v;
             }
@@ -1,4 +1,4 @@
-use glam::Vec3;
+use glam::{Affine3A, Vec3};
 
 use crate::{
     math::{Interval, IntervalExt},
@@ -41,6 +41,44 @@ impl Aabb {
         }
     }
 
+    pub fn min(&self) -> Vec3 {
+        Vec3::new(self.x.start, self.y.start, self.z.start)
+    }
+
+    pub fn max(&self) -> Vec3 {
+        Vec3::new(self.x.end, self.y.end, self.z.end)
+    }
+
+    pub fn corners(&self) -> [Vec3; 8] {
+        let (min, max) = (self.min(), self.max());
+        [0, 1, 2, 3, 4, 5, 6, 7].map(|i| {
+            Vec3::new(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            )
+        })
+    }
+
+    /// Box enclosing this one after it is moved by `transform`.
+    pub fn transformed(&self, transform: &Affine3A) -> Self {
+        self.corners()
+            .into_iter()
+            .map(|corner| transform.transform_point3(corner))
+            .fold(Self::empty(), |bbox, point| {
+                Self::from_boxes(&bbox, &Self::from_points(point, point))
+            })
+    }
+
+    /// Box enlarged by `by` on every side.
+    pub fn grow(&self, by: f32) -> Self {
+        Self::new(
+            self.x.start - by..self.x.end + by,
+            self.y.start - by..self.y.end + by,
+            self.z.start - by..self.z.end + by,
+        )
+    }
+
     pub fn axis(&self, a: usize) -> &Interval {
         match a {
             1 => &self.y,
@@ -71,6 +109,11 @@ impl Aabb {
     }
 
     pub fn hit(&self, ray: Ray, range: &Interval) -> bool {
+        self.hit_range(ray, range).is_some()
+    }
+
+    /// Part of `range` along which `ray` is inside the box.
+    pub fn hit_range(&self, ray: Ray, range: &Interval) -> Option<Interval> {
         let mut range = range.to_owned();
         for a in 0..3 {
             let inverse_direction = (1.0 / ray.direction)[a];
@@ -91,10 +134,10 @@ impl Aabb {
             }
 
             if range.end < range.start {
-                return false;
+                return None;
             }
         }
 
-        true
+        Some(range)
     }
 }
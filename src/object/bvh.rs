@@ -1,28 +1,108 @@
-use std::{cmp::Ordering, sync::Arc};
+use std::{cell::Cell, cmp::Ordering, collections::HashMap, sync::Arc};
 
 use glam::vec3;
 
 use crate::{
-    math::{random_int, Interval},
+    math::{random_int, Interval, IntervalExt},
     object::Object,
-    rendering::ray::{HitRecord, Ray},
+    rendering::{
+        material::Material,
+        ray::{HitRecord, Ray},
+    },
 };
 
 use super::{aabb::Aabb, collection::ObjectCollection};
 
+/// Number of time segments nodes containing moving objects are bounded over.
+const MOTION_SEGMENTS: usize = 32;
+
+thread_local! {
+    static TRAVERSAL: Cell<TraversalCounts> = const {
+        Cell::new(TraversalCounts {
+            node_visits: 0,
+            primitive_tests: 0,
+        })
+    };
+}
+
+/// Work done looking for hits: nodes of a hierarchy whose bounds were
+/// tested, and tests against the objects, triangles or other primitives in
+/// its leaves. Objects with a hierarchy of their own count theirs too.
+#[derive(Clone, Copy, Default)]
+pub struct TraversalCounts {
+    pub node_visits: u32,
+    pub primitive_tests: u32,
+}
+
+impl TraversalCounts {
+    /// Work done on this thread since the last call.
+    pub fn take() -> Self {
+        TRAVERSAL.take()
+    }
+}
+
+/// Adds to the work done on this thread.
+pub fn count_traversal(node_visits: u32, primitive_tests: u32) {
+    TRAVERSAL.set(TraversalCounts {
+        node_visits: TRAVERSAL.get().node_visits + node_visits,
+        primitive_tests: TRAVERSAL.get().primitive_tests + primitive_tests,
+    });
+}
+
+/// Bounds of a node for consecutive, equally long slices of `range`, so a ray
+/// is only tested against where the node's contents are around its time.
+struct MotionBounds {
+    range: Interval,
+    boxes: Vec<Aabb>,
+}
+
+impl MotionBounds {
+    fn new(range: Interval, bounds_over: impl Fn(&Interval) -> Aabb) -> Self {
+        let step = range.size() / MOTION_SEGMENTS as f32;
+        let boxes = (0..MOTION_SEGMENTS)
+            .map(|i| {
+                let start = range.start + step * i as f32;
+                bounds_over(&(start..start + step))
+            })
+            .collect();
+
+        Self { range, boxes }
+    }
+
+    fn segment(&self, time: f32) -> usize {
+        let position = (time - self.range.start) / self.range.size() * self.boxes.len() as f32;
+        (position.max(0.0) as usize).min(self.boxes.len() - 1)
+    }
+
+    /// Bounds over `times`, from every segment it overlaps.
+    fn over(&self, times: &Interval) -> Aabb {
+        let first = self.segment(times.start);
+        let last = self.segment(times.end);
+        self.boxes[first..=last]
+            .iter()
+            .fold(Aabb::empty(), |a, b| Aabb::from_boxes(&a, b))
+    }
+}
+
 pub struct BVHNode {
     left: Arc<dyn Object>,
     right: Arc<dyn Object>,
     bbox: Aabb,
+    motion: Option<MotionBounds>,
+    /// Whether the children are the objects themselves rather than nodes.
+    leaf: bool,
 }
 
 impl BVHNode {
-    pub fn new(list: &Vec<Arc<dyn Object>>) -> Self {
-        let len = list.len();
-        Self::from_object_range(list, 0, len)
+    pub fn new(list: &[Arc<dyn Object>]) -> Self {
+        let motion_range = list
+            .iter()
+            .filter_map(|o| o.motion_range())
+            .reduce(|a, b| Interval::from_intervals(&a, &b));
+        Self::from_objects(&mut list.to_vec(), &motion_range)
     }
 
-    pub fn from_object_range(source: &Vec<Arc<dyn Object>>, start: usize, end: usize) -> Self {
+    fn from_objects(objects: &mut [Arc<dyn Object>], motion_range: &Option<Interval>) -> Self {
         let axis = random_int(0..3);
         let comparator = match axis {
             0 => Self::compare_boxes_x,
@@ -31,38 +111,50 @@ impl BVHNode {
             _ => unreachable!(),
         };
 
-        let span = end - start;
-        let (left, right) = match span {
-            1 => {
-                let obj = source.get(start).cloned().unwrap();
-                (obj.clone(), obj)
-            }
+        let (left, right) = match objects.len() {
+            1 => (objects[0].clone(), objects[0].clone()),
             2 => {
-                let one = source.get(start).cloned().unwrap();
-                let two = source.get(start + 1).cloned().unwrap();
+                let one = objects[0].clone();
+                let two = objects[1].clone();
                 if comparator(&one, &two) == Ordering::Less {
                     (one, two)
                 } else {
                     (two, one)
                 }
             }
-            _ => {
-                let mut needed_part = source[start..end].to_vec();
-                needed_part.sort_by(comparator);
+            span => {
+                objects.sort_by(comparator);
 
-                let mid = (start + end) / 2;
-                let left = Self::from_object_range(source, start, mid);
-                let right = Self::from_object_range(source, mid, end);
+                let (left, right) = objects.split_at_mut(span / 2);
                 (
-                    Arc::new(left) as Arc<dyn Object>,
-                    Arc::new(right) as Arc<dyn Object>,
+                    Arc::new(Self::from_objects(left, motion_range)) as Arc<dyn Object>,
+                    Arc::new(Self::from_objects(right, motion_range)) as Arc<dyn Object>,
                 )
             }
         };
 
         let bbox = Aabb::from_boxes(left.bounding_box(), right.bounding_box());
 
-        Self { left, right, bbox }
+        let moving = left.motion_range().is_some() || right.motion_range().is_some();
+        let motion = match motion_range {
+            Some(range) if moving && range.size() > 0.0 => {
+                Some(MotionBounds::new(range.clone(), |times| {
+                    Aabb::from_boxes(
+                        &left.bounding_box_over(times),
+                        &right.bounding_box_over(times),
+                    )
+                }))
+            }
+            _ => None,
+        };
+
+        Self {
+            left,
+            right,
+            bbox,
+            motion,
+            leaf: objects.len() <= 2,
+        }
     }
 
     fn compare_boxes(a: &Arc<dyn Object>, b: &Arc<dyn Object>, axis: usize) -> Ordering {
@@ -86,10 +178,16 @@ impl BVHNode {
 }
 
 impl Object for BVHNode {
-    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord> {
-        if !self.bbox.hit(ray, range) {
+    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord<'_>> {
+        let bbox = match &self.motion {
+            Some(motion) => &motion.boxes[motion.segment(ray.time)],
+            None => &self.bbox,
+        };
+        if !bbox.hit(ray, range) {
+            count_traversal(1, 0);
             return None;
         }
+        count_traversal(1, if self.leaf { 2 } else { 0 });
 
         let left_hit = self.left.hit(ray, range);
 
@@ -110,28 +208,97 @@ impl Object for BVHNode {
     fn position(&self) -> glam::Vec3 {
         vec3(0., 0., 0.)
     }
+
+    fn motion_range(&self) -> Option<Interval> {
+        self.motion.as_ref().map(|motion| motion.range.clone())
+    }
+
+    fn bounding_box_over(&self, times: &Interval) -> Aabb {
+        match &self.motion {
+            Some(motion) => motion.over(times),
+            None => self.bbox.clone(),
+        }
+    }
+}
+
+/// Address identifying an object or material behind a trait object.
+fn address<T: ?Sized>(value: &T) -> usize {
+    value as *const T as *const () as usize
 }
 
 pub struct BVHCollection {
     root: BVHNode,
+    objects: Vec<Arc<dyn Object>>,
     lights: Vec<Arc<dyn Object>>,
+    /// Index of every object in the collection it was built from.
+    object_ids: HashMap<usize, u32>,
+    /// Materials that aren't registry handles, numbered in order of first
+    /// use after the registered ones.
+    material_ids: HashMap<usize, u32>,
 }
 
 impl BVHCollection {
     pub fn from_simple_collection(collection: &ObjectCollection) -> Self {
+        let objects = collection.objects();
+        let object_ids = objects
+            .iter()
+            .enumerate()
+            .map(|(i, object)| (address(object.as_ref()), i as u32))
+            .collect();
+
+        let materials: Vec<_> = objects
+            .iter()
+            .filter_map(|object| object.material())
+            .collect();
+        let registered = materials
+            .iter()
+            .filter_map(|material| material.handle_id())
+            .max()
+            .map_or(0, |id| id + 1);
+        let mut material_ids = HashMap::new();
+        for material in materials.into_iter().filter(|m| m.handle_id().is_none()) {
+            let next = registered + material_ids.len() as u32;
+            material_ids.entry(address(material)).or_insert(next);
+        }
+
         Self {
-            root: BVHNode::new(collection.objects()),
+            root: BVHNode::new(objects),
+            objects: objects.clone(),
             lights: collection.lights().clone(),
+            object_ids,
+            material_ids,
         }
     }
 
+    /// The objects the collection was built from, in their original order.
+    pub fn objects(&self) -> &[Arc<dyn Object>] {
+        &self.objects
+    }
+
     pub fn lights(&self) -> &[Arc<dyn Object>] {
         &self.lights
     }
+
+    pub fn object_id(&self, object: &dyn Object) -> Option<u32> {
+        self.object_ids.get(&address(object)).copied()
+    }
+
+    /// Number of a material that isn't a registry handle, which come after
+    /// the registry's ids.
+    pub fn material_id(&self, material: &dyn Material) -> Option<u32> {
+        self.material_ids.get(&address(material)).copied()
+    }
+
+    /// Position of `object` in the light list, which is also its light group.
+    pub fn light_group(&self, object: &dyn Object) -> Option<usize> {
+        self.lights
+            .iter()
+            .position(|light| address(light.as_ref()) == address(object))
+    }
 }
 
 impl Object for BVHCollection {
-    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord> {
+    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord<'_>> {
         self.root.hit(ray, range)
     }
 
@@ -142,4 +309,12 @@ impl Object for BVHCollection {
     fn position(&self) -> glam::Vec3 {
         vec3(0., 0., 0.)
     }
+
+    fn motion_range(&self) -> Option<Interval> {
+        self.root.motion_range()
+    }
+
+    fn bounding_box_over(&self, times: &Interval) -> Aabb {
+        self.root.bounding_box_over(times)
+    }
 }
@@ -7,11 +7,7 @@ use crate::{
     rendering::ray::{HitRecord, Ray},
 };
 
-use super::{
-    aabb::Aabb,
-    bvh::{BVHCollection, BVHNode},
-    Object,
-};
+use super::{aabb::Aabb, bvh::BVHCollection, Object};
 
 pub struct ObjectCollection {
     objects: Vec<Arc<dyn Object>>,
@@ -54,13 +50,11 @@ impl ObjectCollection {
 }
 
 impl Object for ObjectCollection {
-    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord> {
-        use ord_subset::OrdSubsetIterExt;
-
+    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord<'_>> {
         self.objects
             .iter()
             .filter_map(|o| o.hit(ray, range))
-            .ord_subset_min_by_key(|o| o.t)
+            .min_by(|a, b| a.t.total_cmp(&b.t))
     }
 
     fn bounding_box(&self) -> &Aabb {
@@ -73,7 +67,7 @@ impl Object for ObjectCollection {
 }
 
 impl Object for &ObjectCollection {
-    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord> {
+    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord<'_>> {
         (*self).hit(ray, range)
     }
 
@@ -0,0 +1,105 @@
+pub mod strands;
+
+use glam::Vec3;
+
+use super::aabb::Aabb;
+
+pub use strands::load_strands;
+
+/// Cubic Bézier curve whose width changes linearly from one end to the
+/// other.
+#[derive(Clone, Copy, Debug)]
+pub struct Curve {
+    pub points: [Vec3; 4],
+    pub widths: [f32; 2],
+}
+
+impl Curve {
+    pub fn bezier(points: [Vec3; 4], widths: [f32; 2]) -> Self {
+        Self { points, widths }
+    }
+
+    /// Segment of a uniform cubic B-spline with control points `points`,
+    /// running between the second and third of them.
+    pub fn b_spline(points: [Vec3; 4], widths: [f32; 2]) -> Self {
+        let [p0, p1, p2, p3] = points;
+        Self::bezier(
+            [
+                (p0 + 4.0 * p1 + p2) / 6.0,
+                (2.0 * p1 + p2) / 3.0,
+                (p1 + 2.0 * p2) / 3.0,
+                (p1 + 4.0 * p2 + p3) / 6.0,
+            ],
+            widths,
+        )
+    }
+
+    /// Smooth curves passing through every one of `points`, as Catmull-Rom
+    /// segments, with the width at every point given by `widths`.
+    pub fn through_points(points: &[Vec3], widths: &[f32]) -> Vec<Self> {
+        assert_eq!(points.len(), widths.len());
+        let point = |i: isize| points[i.clamp(0, points.len() as isize - 1) as usize];
+
+        (0..points.len().saturating_sub(1) as isize)
+            .map(|i| {
+                let (p0, p1, p2, p3) = (point(i - 1), point(i), point(i + 1), point(i + 2));
+                Self::bezier(
+                    [p1, p1 + (p2 - p0) / 6.0, p2 - (p3 - p1) / 6.0, p2],
+                    [widths[i as usize], widths[i as usize + 1]],
+                )
+            })
+            .collect()
+    }
+
+    /// The part of the curve between `u0` and `u1`, found by blossoming.
+    pub fn segment(&self, u0: f32, u1: f32) -> Self {
+        Self {
+            points: [
+                self.blossom(u0, u0, u0),
+                self.blossom(u0, u0, u1),
+                self.blossom(u0, u1, u1),
+                self.blossom(u1, u1, u1),
+            ],
+            widths: [self.width(u0), self.width(u1)],
+        }
+    }
+
+    fn blossom(&self, u0: f32, u1: f32, u2: f32) -> Vec3 {
+        let [p0, p1, p2, p3] = self.points;
+        let a = [p0.lerp(p1, u0), p1.lerp(p2, u0), p2.lerp(p3, u0)];
+        let b = [a[0].lerp(a[1], u1), a[1].lerp(a[2], u1)];
+        b[0].lerp(b[1], u2)
+    }
+
+    pub fn width(&self, u: f32) -> f32 {
+        self.widths[0] + (self.widths[1] - self.widths[0]) * u
+    }
+
+    /// Point at `u` and the derivative there.
+    pub fn evaluate(&self, u: f32) -> (Vec3, Vec3) {
+        evaluate_bezier(&self.points, u)
+    }
+
+    /// Bounds of the control points grown by half the width, which hold the
+    /// whole curve.
+    pub fn bounds(&self) -> Aabb {
+        let [p0, p1, p2, p3] = self.points;
+        let half_width = 0.5 * self.widths[0].max(self.widths[1]);
+        Aabb::from_points(p0.min(p1).min(p2.min(p3)), p0.max(p1).max(p2.max(p3))).grow(half_width)
+    }
+}
+
+/// Point at `u` of the Bézier curve with control points `points`, and the
+/// derivative there.
+pub fn evaluate_bezier(points: &[Vec3; 4], u: f32) -> (Vec3, Vec3) {
+    let [p0, p1, p2, p3] = *points;
+    let a = [p0.lerp(p1, u), p1.lerp(p2, u), p2.lerp(p3, u)];
+    let b = [a[0].lerp(a[1], u), a[1].lerp(a[2], u)];
+    let derivative = if b[1] != b[0] {
+        3.0 * (b[1] - b[0])
+    } else {
+        // Coinciding control points at the ends.
+        p3 - p0
+    };
+    (b[0].lerp(b[1], u), derivative)
+}
@@ -0,0 +1,57 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+};
+
+use glam::Vec3;
+
+use super::Curve;
+
+/// Reads hair strands from a text file, returning smooth curves through
+/// their points.
+///
+/// Every line holds one point of a strand as `x y z width`, and strands
+/// are separated by blank lines. Lines starting with `#` are comments.
+pub fn load_strands<P: AsRef<Path>>(path: P) -> io::Result<Vec<Curve>> {
+    let reader = BufReader::new(File::open(path)?);
+
+    let mut curves = Vec::new();
+    let mut points = Vec::new();
+    let mut widths = Vec::new();
+    let mut end_strand = |points: &mut Vec<Vec3>, widths: &mut Vec<f32>| {
+        curves.extend(Curve::through_points(points, widths));
+        points.clear();
+        widths.clear();
+    };
+
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        if line.is_empty() {
+            end_strand(&mut points, &mut widths);
+            continue;
+        }
+
+        let values = line
+            .split_whitespace()
+            .map(str::parse::<f32>)
+            .collect::<Result<Vec<_>, _>>()
+            .ok()
+            .filter(|values| values.len() == 4)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: expected `x y z width`", number + 1),
+                )
+            })?;
+        points.push(Vec3::new(values[0], values[1], values[2]));
+        widths.push(values[3]);
+    }
+    end_strand(&mut points, &mut widths);
+
+    Ok(curves)
+}
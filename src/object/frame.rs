@@ -0,0 +1,97 @@
+use glam::{Affine3A, Quat, Vec2, Vec3};
+
+use crate::{
+    math::Interval,
+    rendering::{
+        material::Material,
+        ray::{HitRecord, Ray},
+    },
+};
+
+use super::{aabb::Aabb, Object};
+
+/// Surface crossing found in a frame's local space.
+pub struct LocalHit {
+    pub t: f32,
+    pub outward_normal: Vec3,
+    pub uv: Vec2,
+}
+
+/// Rigid placement of a shape that is defined around the origin of its own
+/// space. Distances along rays are the same in both spaces.
+#[derive(Clone)]
+pub struct Frame {
+    to_world: Affine3A,
+    to_local: Affine3A,
+}
+
+impl Frame {
+    pub fn new(origin: Vec3, rotation: Quat) -> Self {
+        let to_world = Affine3A::from_rotation_translation(rotation, origin);
+        Self {
+            to_world,
+            to_local: to_world.inverse(),
+        }
+    }
+
+    /// Frame at `origin` whose local z axis points along `axis`.
+    pub fn with_z_axis(origin: Vec3, axis: Vec3) -> Self {
+        Self::new(origin, Quat::from_rotation_arc(Vec3::Z, axis.normalize()))
+    }
+
+    pub fn origin(&self) -> Vec3 {
+        self.to_world.translation.into()
+    }
+
+    pub fn rotation(&self) -> Quat {
+        Quat::from_mat3a(&self.to_world.matrix3)
+    }
+
+    pub fn ray_to_local(&self, ray: Ray) -> Ray {
+        Ray {
+            origin: self.to_local.transform_point3(ray.origin),
+            direction: self.to_local.transform_vector3(ray.direction),
+            ..ray
+        }
+    }
+
+    pub fn point_to_world(&self, point: Vec3) -> Vec3 {
+        self.to_world.transform_point3(point)
+    }
+
+    pub fn vector_to_world(&self, vector: Vec3) -> Vec3 {
+        self.to_world.transform_vector3(vector)
+    }
+
+    /// World space bounds of a local space box.
+    pub fn bounds(&self, local: &Aabb) -> Aabb {
+        local.transformed(&self.to_world).pad()
+    }
+
+    /// World space record of a local `hit` along the world space `ray`.
+    pub fn hit_record<'a>(
+        &self,
+        object: &'a dyn Object,
+        ray: Ray,
+        hit: &LocalHit,
+        material: &'a dyn Material,
+    ) -> HitRecord<'a> {
+        HitRecord::new(
+            object,
+            ray,
+            ray.at(hit.t),
+            self.vector_to_world(hit.outward_normal),
+            hit.t,
+            material,
+            hit.uv.x,
+            hit.uv.y,
+        )
+    }
+}
+
+/// The crossings within `range`, nearest first.
+pub fn sorted_hits(mut hits: Vec<LocalHit>, range: &Interval) -> Vec<LocalHit> {
+    hits.retain(|hit| range.contains(&hit.t));
+    hits.sort_by(|a, b| a.t.total_cmp(&b.t));
+    hits
+}
@@ -0,0 +1,39 @@
+use std::{error::Error, fmt, io};
+
+/// Why geometry or a scene couldn't be read from a file.
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    /// The file's header is malformed or describes something unsupported.
+    Header(String),
+    /// The data after the header doesn't match it or makes no sense.
+    Data(String),
+    /// The file is of a kind there is no loader for.
+    Unsupported(String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{error}"),
+            Self::Header(message) => write!(f, "bad header: {message}"),
+            Self::Data(message) => write!(f, "bad data: {message}"),
+            Self::Unsupported(message) => write!(f, "unsupported file: {message}"),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
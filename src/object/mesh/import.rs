@@ -0,0 +1,304 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::Path,
+};
+
+use glam::{Vec2, Vec3};
+
+use crate::object::{load::LoadError, ply::Ply};
+
+use super::MeshData;
+
+impl MeshData {
+    /// Reads a `.ply` or `.stl` file by its extension.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, LoadError> {
+        let path = path.as_ref();
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("ply") => Self::from_ply(path),
+            Some(extension) if extension.eq_ignore_ascii_case("stl") => Self::from_stl(path),
+            _ => Err(LoadError::Unsupported(format!(
+                "{}: not a .ply or .stl file",
+                path.display()
+            ))),
+        }
+    }
+
+    pub fn from_ply<P: AsRef<Path>>(path: P) -> Result<Self, LoadError> {
+        Self::read_ply(BufReader::new(File::open(path)?))
+    }
+
+    pub fn from_stl<P: AsRef<Path>>(path: P) -> Result<Self, LoadError> {
+        Self::read_stl(File::open(path)?)
+    }
+
+    /// Reads a PLY mesh from its `vertex` and `face` elements. Vertices need
+    /// `x`, `y` and `z`, and can have normals in `nx`, `ny` and `nz`, UVs in
+    /// `u` and `v` or `s` and `t`, and colors in `red`, `green` and `blue`.
+    /// Faces with more than three corners are split into fans, so they have
+    /// to be convex.
+    pub fn read_ply(reader: impl BufRead) -> Result<Self, LoadError> {
+        let ply = Ply::read(reader)?;
+        let vertices = ply
+            .element("vertex")
+            .ok_or_else(|| LoadError::Header("no `vertex` element".to_string()))?;
+        let faces = ply
+            .element("face")
+            .ok_or_else(|| LoadError::Header("no `face` element".to_string()))?;
+        let indices = ["vertex_indices", "vertex_index"]
+            .into_iter()
+            .find_map(|name| faces.property(name).filter(|property| property.is_list()))
+            .ok_or_else(|| LoadError::Header("faces have no vertex indices".to_string()))?;
+
+        let positions = vertices
+            .vectors(["x", "y", "z"])
+            .ok_or_else(|| LoadError::Header("vertices have no position".to_string()))?;
+        let vertex_count = positions.len();
+
+        let mut triangles = Vec::with_capacity(faces.count);
+        for (face, corners) in indices.lists().enumerate() {
+            if let Some(&corner) = corners
+                .iter()
+                .find(|&&i| i < 0.0 || i >= vertex_count as f64)
+            {
+                return Err(LoadError::Data(format!(
+                    "face {face} uses vertex {corner} of {vertex_count}"
+                )));
+            }
+            let corners: Vec<u32> = corners.iter().map(|&i| i as u32).collect();
+            triangles.extend(
+                (1..corners.len().saturating_sub(1))
+                    .map(|i| [corners[0], corners[i], corners[i + 1]]),
+            );
+        }
+
+        let mut data = MeshData::new(positions, triangles);
+        if let Some(normals) = vertices.vectors(["nx", "ny", "nz"]) {
+            data = data.with_normals(normals.iter().map(|n| n.normalize_or_zero()).collect());
+        }
+        let uvs = [["u", "v"], ["s", "t"]]
+            .into_iter()
+            .find_map(|[u, v]| Some((vertices.scalar(u)?, vertices.scalar(v)?)));
+        if let Some((u, v)) = uvs {
+            data = data.with_uvs(
+                u.iter()
+                    .zip(v)
+                    .map(|(&u, &v)| Vec2::new(u as f32, v as f32))
+                    .collect(),
+            );
+        }
+        if let Some(colors) = vertices.colors() {
+            data = data.with_colors(colors);
+        }
+
+        Ok(data)
+    }
+
+    /// Reads an STL file in its binary or ASCII form. STL repeats the
+    /// corners of every triangle, so corners at the same position are joined
+    /// into shared vertices.
+    pub fn read_stl(mut reader: impl Read) -> Result<Self, LoadError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        // Binary files may also start with `solid`, so their size decides.
+        let binary_count = bytes
+            .get(80..84)
+            .map(|count| u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize);
+        let corners = match binary_count {
+            Some(count) if bytes.len() == 84 + 50 * count => read_binary_stl(&bytes[84..]),
+            _ if bytes.starts_with(b"solid") => read_ascii_stl(&bytes)?,
+            Some(count) => {
+                return Err(LoadError::Header(format!(
+                    "binary STL of {} bytes can't hold the {count} triangles it promises",
+                    bytes.len()
+                )))
+            }
+            None => return Err(LoadError::Header("file too short for STL".to_string())),
+        };
+
+        let mut indices = HashMap::new();
+        let mut positions = Vec::new();
+        let mut index_of = |corner: Vec3| {
+            *indices
+                .entry(corner.to_array().map(f32::to_bits))
+                .or_insert_with(|| {
+                    positions.push(corner);
+                    positions.len() as u32 - 1
+                })
+        };
+        let triangles: Vec<[u32; 3]> = corners
+            .chunks_exact(3)
+            .map(|triangle| [0, 1, 2].map(|i| index_of(triangle[i])))
+            .filter(|[a, b, c]| a != b && b != c && c != a)
+            .collect();
+
+        Ok(MeshData::new(positions, triangles))
+    }
+}
+
+/// Triangle corners of binary STL records: a normal, three corners and
+/// two unused bytes each.
+fn read_binary_stl(records: &[u8]) -> Vec<Vec3> {
+    let float = |bytes: &[u8]| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    records
+        .chunks_exact(50)
+        .flat_map(|record| {
+            (1..4).map(move |corner| {
+                let start = 12 * corner;
+                Vec3::new(
+                    float(&record[start..]),
+                    float(&record[start + 4..]),
+                    float(&record[start + 8..]),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Triangle corners of an ASCII STL file, ignoring its facet normals.
+fn read_ascii_stl(bytes: &[u8]) -> Result<Vec<Vec3>, LoadError> {
+    let text = std::str::from_utf8(bytes)
+        .map_err(|_| LoadError::Header("ASCII STL that isn't text".to_string()))?;
+
+    let mut tokens = text.split_whitespace();
+    let mut corners = Vec::new();
+    while let Some(token) = tokens.next() {
+        if token != "vertex" {
+            continue;
+        }
+        let mut coordinate = || {
+            let token = tokens
+                .next()
+                .ok_or_else(|| LoadError::Data("file ends inside a vertex".to_string()))?;
+            token
+                .parse::<f32>()
+                .map_err(|_| LoadError::Data(format!("bad number `{token}`")))
+        };
+        corners.push(Vec3::new(coordinate()?, coordinate()?, coordinate()?));
+    }
+
+    if corners.len() % 3 != 0 {
+        return Err(LoadError::Data(
+            "facets need exactly three vertices".to_string(),
+        ));
+    }
+    Ok(corners)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQUARE_PLY: &str = "ply
+format ascii 1.0
+element vertex 4
+property float x
+property float y
+property float z
+property float u
+property float v
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 0 0
+1 0 0 1 0
+1 1 0 1 1
+0 1 0 0 1
+4 0 1 2 3
+";
+
+    #[test]
+    fn reads_ascii_ply_and_splits_polygons() {
+        let mesh = MeshData::read_ply(SQUARE_PLY.as_bytes()).unwrap();
+        assert_eq!(mesh.vertex_count(), 4);
+        assert_eq!(mesh.triangles, vec![[0, 1, 2], [0, 2, 3]]);
+        assert_eq!(mesh.positions[0][2], Vec3::new(1.0, 1.0, 0.0));
+        assert_eq!(mesh.uvs.as_ref().unwrap()[3], Vec2::new(0.0, 1.0));
+        assert!(mesh.normals.is_none());
+    }
+
+    #[test]
+    fn rejects_ply_faces_past_the_vertices() {
+        let ply = SQUARE_PLY.replace("4 0 1 2 3", "3 0 1 4");
+        let error = MeshData::read_ply(ply.as_bytes()).err().unwrap();
+        assert!(matches!(error, LoadError::Data(_)), "{error}");
+    }
+
+    #[test]
+    fn rejects_ply_without_faces() {
+        let ply = SQUARE_PLY
+            .replace(
+                "element face 1\nproperty list uchar int vertex_indices\n",
+                "",
+            )
+            .replace("4 0 1 2 3\n", "");
+        let error = MeshData::read_ply(ply.as_bytes()).err().unwrap();
+        assert!(matches!(error, LoadError::Header(_)), "{error}");
+    }
+
+    /// A binary STL of the two triangles of a square, sharing an edge.
+    fn square_stl() -> Vec<u8> {
+        let triangles = [
+            [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]],
+            [[0.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0f32]],
+        ];
+        let mut bytes = vec![0; 80];
+        bytes.extend((triangles.len() as u32).to_le_bytes());
+        for triangle in triangles {
+            bytes.extend([0.0, 0.0, 1.0f32].iter().flat_map(|x| x.to_le_bytes()));
+            for corner in triangle {
+                bytes.extend(corner.iter().flat_map(|x| x.to_le_bytes()));
+            }
+            bytes.extend([0, 0]);
+        }
+        bytes
+    }
+
+    #[test]
+    fn reads_binary_stl_and_joins_corners() {
+        let mesh = MeshData::read_stl(&square_stl()[..]).unwrap();
+        assert_eq!(mesh.vertex_count(), 4);
+        assert_eq!(mesh.triangles, vec![[0, 1, 2], [0, 2, 3]]);
+        assert_eq!(mesh.positions[0][3], Vec3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn rejects_truncated_binary_stl() {
+        let mut stl = square_stl();
+        stl.truncate(stl.len() - 10);
+        let error = MeshData::read_stl(&stl[..]).err().unwrap();
+        assert!(matches!(error, LoadError::Header(_)), "{error}");
+    }
+
+    #[test]
+    fn reads_ascii_stl() {
+        let stl = "solid square
+facet normal 0 0 1
+ outer loop
+  vertex 0 0 0
+  vertex 1 0 0
+  vertex 1 1 0
+ endloop
+endfacet
+facet normal 0 0 1
+ outer loop
+  vertex 0 0 0
+  vertex 1 1 0
+  vertex 0 1 0
+ endloop
+endfacet
+endsolid square
+";
+        let mesh = MeshData::read_stl(stl.as_bytes()).unwrap();
+        assert_eq!(mesh.vertex_count(), 4);
+        assert_eq!(mesh.triangle_count(), 2);
+    }
+
+    #[test]
+    fn refuses_other_extensions() {
+        let error = MeshData::load("square.obj").err().unwrap();
+        assert!(matches!(error, LoadError::Unsupported(_)), "{error}");
+    }
+}
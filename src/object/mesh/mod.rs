@@ -0,0 +1,182 @@
+pub mod import;
+pub mod subdivision;
+
+use std::fmt;
+
+use glam::{Vec2, Vec3};
+
+use crate::rendering::{ray::Color, texture::Texture};
+
+use super::aabb::Aabb;
+
+pub use subdivision::{PolygonMesh, Subdivision};
+
+/// Triangle geometry before it is turned into a renderable mesh.
+///
+/// A deforming mesh has several motion keys: complete sets of vertex
+/// positions spread evenly over the mesh's time interval, sharing one topology.
+#[derive(Clone, Default)]
+pub struct MeshData {
+    pub positions: Vec<Vec<Vec3>>,
+    pub normals: Option<Vec<Vec3>>,
+    pub uvs: Option<Vec<Vec2>>,
+    pub colors: Option<Vec<Color>>,
+    pub triangles: Vec<[u32; 3]>,
+}
+
+/// Size of a mesh, for reporting what a file held.
+pub struct MeshStats {
+    pub vertices: usize,
+    pub triangles: usize,
+    pub bounds: Aabb,
+}
+
+impl MeshData {
+    pub fn new(positions: Vec<Vec3>, triangles: Vec<[u32; 3]>) -> Self {
+        Self {
+            positions: vec![positions],
+            normals: None,
+            uvs: None,
+            colors: None,
+            triangles,
+        }
+    }
+
+    /// Flat polygon through `points`, split into a fan of triangles around
+    /// the first one, so it has to be convex.
+    pub fn polygon(points: Vec<Vec3>) -> Self {
+        let triangles = (1..points.len().saturating_sub(1) as u32)
+            .map(|i| [0, i, i + 1])
+            .collect();
+        Self::new(points, triangles)
+    }
+
+    /// Adds a later set of vertex positions for deformation blur.
+    pub fn with_motion_key(mut self, positions: Vec<Vec3>) -> Self {
+        assert_eq!(positions.len(), self.vertex_count());
+        self.positions.push(positions);
+        self
+    }
+
+    pub fn with_normals(mut self, normals: Vec<Vec3>) -> Self {
+        assert_eq!(normals.len(), self.vertex_count());
+        self.normals = Some(normals);
+        self
+    }
+
+    pub fn with_uvs(mut self, uvs: Vec<Vec2>) -> Self {
+        assert_eq!(uvs.len(), self.vertex_count());
+        self.uvs = Some(uvs);
+        self
+    }
+
+    /// Gives every vertex a color, which textures such as `VertexColor`
+    /// can read.
+    pub fn with_colors(mut self, colors: Vec<Color>) -> Self {
+        assert_eq!(colors.len(), self.vertex_count());
+        self.colors = Some(colors);
+        self
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.positions[0].len()
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    pub fn motion_keys(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Bounds of one triangle between two motion keys, which also contain it
+    /// at every time in between.
+    pub fn triangle_bounds(&self, triangle: usize, keys: [usize; 2]) -> Aabb {
+        self.triangles[triangle]
+            .iter()
+            .flat_map(|&i| keys.map(|key| self.positions[key][i as usize]))
+            .fold(Aabb::empty(), |bbox, p| {
+                Aabb::from_boxes(&bbox, &Aabb::from_points(p, p))
+            })
+    }
+
+    /// Bounds of the mesh over all motion keys.
+    pub fn bounds(&self) -> Aabb {
+        self.positions
+            .iter()
+            .flatten()
+            .fold(Aabb::empty(), |bbox, &p| {
+                Aabb::from_boxes(&bbox, &Aabb::from_points(p, p))
+            })
+    }
+
+    pub fn stats(&self) -> MeshStats {
+        MeshStats {
+            vertices: self.vertex_count(),
+            triangles: self.triangle_count(),
+            bounds: self.bounds(),
+        }
+    }
+
+    /// Vertex normals of motion key `key`, averaging the normals of the
+    /// triangles around every vertex weighted by their area.
+    pub fn smooth_normals(&self, key: usize) -> Vec<Vec3> {
+        let positions = &self.positions[key];
+        let mut normals = vec![Vec3::ZERO; positions.len()];
+        for triangle in &self.triangles {
+            let [p0, p1, p2] = triangle.map(|i| positions[i as usize]);
+            let normal = (p1 - p0).cross(p2 - p0);
+            for &i in triangle {
+                normals[i as usize] += normal;
+            }
+        }
+        normals.iter().map(|n| n.normalize_or_zero()).collect()
+    }
+
+    /// Mesh with every vertex pushed out along its normal by `scale` times
+    /// the brightness of `height` at its UV and position. Detail finer than
+    /// the spacing of the vertices is lost, so meshes are usually subdivided
+    /// first.
+    pub fn displaced(&self, height: &dyn Texture, scale: f32) -> MeshData {
+        let positions = (0..self.motion_keys())
+            .map(|key| {
+                let normals = match &self.normals {
+                    Some(normals) if key == 0 => normals.clone(),
+                    _ => self.smooth_normals(key),
+                };
+                self.positions[key]
+                    .iter()
+                    .zip(normals)
+                    .enumerate()
+                    .map(|(i, (&point, normal))| {
+                        let uv = self.uvs.as_ref().map_or(Vec2::ZERO, |uvs| uvs[i]);
+                        let value = height.value(uv.x, uv.y, point).0;
+                        point + normal * scale * (value.x + value.y + value.z) / 3.0
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut data = MeshData {
+            positions,
+            normals: None,
+            ..self.clone()
+        };
+        data.normals = Some(data.smooth_normals(0));
+        data
+    }
+}
+
+impl fmt::Display for MeshStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} vertices, {} triangles, bounds {} to {}",
+            self.vertices,
+            self.triangles,
+            self.bounds.min(),
+            self.bounds.max()
+        )
+    }
+}
@@ -0,0 +1,443 @@
+use std::collections::HashMap;
+
+use glam::{Vec2, Vec3};
+
+use crate::rendering::ray::Color;
+
+use super::MeshData;
+
+/// Scheme used to refine a mesh into a smooth surface.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Subdivision {
+    /// Loop's scheme for triangle meshes: every triangle becomes four.
+    Loop,
+    /// Catmull-Clark for any polygons: every n-gon becomes n quads.
+    CatmullClark,
+}
+
+/// Mesh of arbitrary polygons, the input and output of subdivision. Mesh
+/// boundaries, including UV seams where vertices are split, are kept as
+/// creases that follow a cubic curve.
+#[derive(Clone)]
+pub struct PolygonMesh {
+    positions: Vec<Vec<Vec3>>,
+    uvs: Option<Vec<Vec2>>,
+    colors: Option<Vec<Color>>,
+    faces: Vec<Vec<u32>>,
+}
+
+/// New vertex as a weighted sum of old ones.
+type Stencil = Vec<(u32, f32)>;
+
+struct Edge {
+    /// Index of the vertex added on the edge.
+    vertex: u32,
+    faces: Vec<usize>,
+}
+
+impl Edge {
+    fn is_boundary(&self) -> bool {
+        self.faces.len() != 2
+    }
+}
+
+impl From<MeshData> for PolygonMesh {
+    fn from(data: MeshData) -> Self {
+        Self {
+            positions: data.positions,
+            uvs: data.uvs,
+            colors: data.colors,
+            faces: data.triangles.iter().map(|t| t.to_vec()).collect(),
+        }
+    }
+}
+
+impl PolygonMesh {
+    /// Polygons wound counterclockwise seen from outside. Every face needs
+    /// at least three corners, all of them existing vertices.
+    pub fn new(positions: Vec<Vec3>, faces: Vec<Vec<u32>>) -> Result<Self, String> {
+        for (f, face) in faces.iter().enumerate() {
+            if face.len() < 3 {
+                return Err(format!("face {f} has {} corners", face.len()));
+            }
+            if let Some(&v) = face.iter().find(|&&v| v as usize >= positions.len()) {
+                return Err(format!("face {f} uses vertex {v} of {}", positions.len()));
+            }
+        }
+        Ok(Self {
+            positions: vec![positions],
+            uvs: None,
+            colors: None,
+            faces,
+        })
+    }
+
+    /// Applies `levels` rounds of `scheme`. Loop subdivision splits other
+    /// polygons into triangles first.
+    pub fn subdivided(mut self, scheme: Subdivision, levels: u32) -> Self {
+        for _ in 0..levels {
+            self = match scheme {
+                Subdivision::Loop => self.triangulated().loop_step(),
+                Subdivision::CatmullClark => self.catmull_clark_step(),
+            };
+        }
+        self
+    }
+
+    /// Triangle mesh with smooth normals, splitting polygons into fans.
+    pub fn to_mesh_data(&self) -> MeshData {
+        let triangulated = self.triangulated();
+        let mut data = MeshData {
+            positions: triangulated.positions,
+            normals: None,
+            uvs: triangulated.uvs,
+            colors: triangulated.colors,
+            triangles: triangulated
+                .faces
+                .iter()
+                .map(|face| [face[0], face[1], face[2]])
+                .collect(),
+        };
+        data.normals = Some(data.smooth_normals(0));
+        data
+    }
+
+    fn triangulated(&self) -> Self {
+        let faces = self
+            .faces
+            .iter()
+            .flat_map(|face| (1..face.len() - 1).map(|i| vec![face[0], face[i], face[i + 1]]))
+            .collect();
+        Self {
+            faces,
+            ..self.clone()
+        }
+    }
+
+    fn vertex_count(&self) -> usize {
+        self.positions[0].len()
+    }
+
+    /// Edges keyed by their sorted end points, numbering the vertices added
+    /// on them from `first_vertex`.
+    fn edges(&self, first_vertex: u32) -> HashMap<(u32, u32), Edge> {
+        let mut edges: HashMap<(u32, u32), Edge> = HashMap::new();
+        for (f, face) in self.faces.iter().enumerate() {
+            for i in 0..face.len() {
+                let key = edge_key(face[i], face[(i + 1) % face.len()]);
+                let next = first_vertex + edges.len() as u32;
+                edges
+                    .entry(key)
+                    .or_insert(Edge {
+                        vertex: next,
+                        faces: Vec::new(),
+                    })
+                    .faces
+                    .push(f);
+            }
+        }
+        edges
+    }
+
+    /// Neighbors of every vertex, and for vertices on a boundary the
+    /// neighbors along it.
+    fn neighbors(&self, edges: &HashMap<(u32, u32), Edge>) -> (Vec<Vec<u32>>, Vec<Vec<u32>>) {
+        let mut neighbors = vec![Vec::new(); self.vertex_count()];
+        let mut boundary = vec![Vec::new(); self.vertex_count()];
+        // Sorted so that the result doesn't depend on hash map order.
+        let mut keys: Vec<_> = edges.keys().collect();
+        keys.sort();
+        for &(a, b) in keys {
+            neighbors[a as usize].push(b);
+            neighbors[b as usize].push(a);
+            if edges[&(a, b)].is_boundary() {
+                boundary[a as usize].push(b);
+                boundary[b as usize].push(a);
+            }
+        }
+        (neighbors, boundary)
+    }
+
+    fn loop_step(&self) -> Self {
+        let n = self.vertex_count() as u32;
+        let edges = self.edges(n);
+        let (neighbors, boundary) = self.neighbors(&edges);
+
+        let mut smooth: Vec<Stencil> = (0..n)
+            .map(|v| {
+                let ring = &neighbors[v as usize];
+                if !boundary[v as usize].is_empty() {
+                    return crease_stencil(v, &boundary[v as usize]);
+                }
+                let k = ring.len() as f32;
+                let beta = if ring.len() == 3 {
+                    3.0 / 16.0
+                } else {
+                    3.0 / (8.0 * k)
+                };
+                let mut stencil = vec![(v, 1.0 - k * beta)];
+                stencil.extend(ring.iter().map(|&w| (w, beta)));
+                stencil
+            })
+            .collect();
+        let mut linear: Vec<Stencil> = (0..n).map(|v| vec![(v, 1.0)]).collect();
+
+        let mut ordered: Vec<_> = edges.iter().collect();
+        ordered.sort_by_key(|(_, edge)| edge.vertex);
+        for (&(a, b), edge) in ordered {
+            let midpoint = vec![(a, 0.5), (b, 0.5)];
+            if edge.is_boundary() {
+                smooth.push(midpoint.clone());
+            } else {
+                let opposite = |face: usize| {
+                    *self.faces[face]
+                        .iter()
+                        .find(|&&v| v != a && v != b)
+                        .unwrap()
+                };
+                smooth.push(vec![
+                    (a, 0.375),
+                    (b, 0.375),
+                    (opposite(edge.faces[0]), 0.125),
+                    (opposite(edge.faces[1]), 0.125),
+                ]);
+            }
+            linear.push(midpoint);
+        }
+
+        let mid = |a: u32, b: u32| edges[&edge_key(a, b)].vertex;
+        let faces = self
+            .faces
+            .iter()
+            .flat_map(|face| {
+                let [a, b, c] = [face[0], face[1], face[2]];
+                let (ab, bc, ca) = (mid(a, b), mid(b, c), mid(c, a));
+                [
+                    vec![a, ab, ca],
+                    vec![ab, b, bc],
+                    vec![ca, bc, c],
+                    vec![ab, bc, ca],
+                ]
+            })
+            .collect();
+
+        self.apply(&smooth, &linear, faces)
+    }
+
+    fn catmull_clark_step(&self) -> Self {
+        let n = self.vertex_count() as u32;
+        let face_count = self.faces.len() as u32;
+        let edges = self.edges(n + face_count);
+        let (neighbors, boundary) = self.neighbors(&edges);
+
+        let face_points: Vec<Stencil> = self
+            .faces
+            .iter()
+            .map(|face| {
+                let weight = 1.0 / face.len() as f32;
+                face.iter().map(|&v| (v, weight)).collect()
+            })
+            .collect();
+
+        let mut vertex_faces = vec![Vec::new(); n as usize];
+        for (f, face) in self.faces.iter().enumerate() {
+            for &v in face {
+                vertex_faces[v as usize].push(f);
+            }
+        }
+
+        let mut smooth: Vec<Stencil> = (0..n)
+            .map(|v| {
+                if !boundary[v as usize].is_empty() {
+                    return crease_stencil(v, &boundary[v as usize]);
+                }
+                // (F + 2R + (k - 3) v) / k, with F the average of the
+                // surrounding face points and R of the edge midpoints.
+                let ring = &neighbors[v as usize];
+                let k = ring.len() as f32;
+                let mut stencil = vec![(v, (k - 3.0) / k + 1.0 / k)];
+                stencil.extend(ring.iter().map(|&w| (w, 1.0 / (k * k))));
+                let adjacent = &vertex_faces[v as usize];
+                for &f in adjacent {
+                    let share = 1.0 / (k * adjacent.len() as f32);
+                    stencil.extend(
+                        face_points[f]
+                            .iter()
+                            .map(|&(w, weight)| (w, weight * share)),
+                    );
+                }
+                stencil
+            })
+            .collect();
+        let mut linear: Vec<Stencil> = (0..n).map(|v| vec![(v, 1.0)]).collect();
+
+        smooth.extend(face_points.iter().cloned());
+        linear.extend(face_points.iter().cloned());
+
+        let mut ordered: Vec<_> = edges.iter().collect();
+        ordered.sort_by_key(|(_, edge)| edge.vertex);
+        for (&(a, b), edge) in ordered {
+            let midpoint = vec![(a, 0.5), (b, 0.5)];
+            if edge.is_boundary() {
+                smooth.push(midpoint.clone());
+            } else {
+                let mut stencil = vec![(a, 0.25), (b, 0.25)];
+                for &f in &edge.faces {
+                    stencil.extend(face_points[f].iter().map(|&(w, weight)| (w, weight * 0.25)));
+                }
+                smooth.push(stencil);
+            }
+            linear.push(midpoint);
+        }
+
+        let mid = |a: u32, b: u32| edges[&edge_key(a, b)].vertex;
+        let faces = self
+            .faces
+            .iter()
+            .enumerate()
+            .flat_map(|(f, face)| {
+                let center = n + f as u32;
+                let len = face.len();
+                (0..len).map(move |i| {
+                    let (previous, current, next) =
+                        (face[(i + len - 1) % len], face[i], face[(i + 1) % len]);
+                    vec![current, mid(current, next), center, mid(previous, current)]
+                })
+            })
+            .collect();
+
+        self.apply(&smooth, &linear, faces)
+    }
+
+    /// Mesh with positions from the `smooth` stencils and UVs and colors from
+    /// the `linear` ones, which keep textures from sliding over the surface.
+    fn apply(&self, smooth: &[Stencil], linear: &[Stencil], faces: Vec<Vec<u32>>) -> Self {
+        let positions = self
+            .positions
+            .iter()
+            .map(|key| {
+                smooth
+                    .iter()
+                    .map(|stencil| stencil.iter().map(|&(v, w)| key[v as usize] * w).sum())
+                    .collect()
+            })
+            .collect();
+        let uvs = self.uvs.as_ref().map(|uvs| {
+            linear
+                .iter()
+                .map(|stencil| stencil.iter().map(|&(v, w)| uvs[v as usize] * w).sum())
+                .collect()
+        });
+        let colors = self.colors.as_ref().map(|colors| {
+            linear
+                .iter()
+                .map(|stencil| Color(stencil.iter().map(|&(v, w)| colors[v as usize].0 * w).sum()))
+                .collect()
+        });
+
+        Self {
+            positions,
+            uvs,
+            colors,
+            faces,
+        }
+    }
+}
+
+fn edge_key(a: u32, b: u32) -> (u32, u32) {
+    (a.min(b), a.max(b))
+}
+
+/// Boundary vertices follow the cubic B-spline through their two boundary
+/// neighbors; corners where more boundaries meet stay where they are.
+fn crease_stencil(v: u32, boundary: &[u32]) -> Stencil {
+    match boundary {
+        &[a, b] => vec![(v, 0.75), (a, 0.125), (b, 0.125)],
+        _ => vec![(v, 1.0)],
+    }
+}
+
+impl MeshData {
+    /// Smooth version of the mesh after `levels` rounds of `scheme`.
+    pub fn subdivided(&self, scheme: Subdivision, levels: u32) -> MeshData {
+        PolygonMesh::from(self.clone())
+            .subdivided(scheme, levels)
+            .to_mesh_data()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cube() -> PolygonMesh {
+        let corners = (0..8)
+            .map(|i| Vec3::new((i & 1) as f32, (i >> 1 & 1) as f32, (i >> 2 & 1) as f32))
+            .collect();
+        let faces = vec![
+            vec![0, 4, 6, 2],
+            vec![1, 3, 7, 5],
+            vec![0, 1, 5, 4],
+            vec![2, 6, 7, 3],
+            vec![0, 2, 3, 1],
+            vec![4, 5, 7, 6],
+        ];
+        PolygonMesh::new(corners, faces).unwrap()
+    }
+
+    #[test]
+    fn rejects_bad_faces() {
+        let corners = vec![Vec3::ZERO, Vec3::X, Vec3::Y];
+        assert!(PolygonMesh::new(corners.clone(), vec![vec![]]).is_err());
+        assert!(PolygonMesh::new(corners.clone(), vec![vec![0, 1]]).is_err());
+        assert!(PolygonMesh::new(corners.clone(), vec![vec![0, 1, 3]]).is_err());
+        assert!(PolygonMesh::new(corners, vec![vec![0, 1, 2]]).is_ok());
+    }
+
+    #[test]
+    fn catmull_clark_splits_polygons_into_quads() {
+        let mesh = cube().subdivided(Subdivision::CatmullClark, 1);
+        // 8 corners, 6 face points and 12 edge points.
+        assert_eq!(mesh.vertex_count(), 26);
+        assert_eq!(mesh.faces.len(), 24);
+        assert!(mesh.faces.iter().all(|face| face.len() == 4));
+    }
+
+    #[test]
+    fn catmull_clark_moves_cube_corners_inward() {
+        let mesh = cube().subdivided(Subdivision::CatmullClark, 1);
+        // (F + 2R + (k - 3) v) / k with k = 3, F = 1/3 and R = 1/6 along
+        // every axis for the corner at the origin.
+        let corner = mesh.positions[0][0];
+        assert!((corner - Vec3::splat(2.0 / 9.0)).abs().max_element() < 1e-6);
+    }
+
+    #[test]
+    fn loop_splits_triangles_into_four() {
+        let mesh = cube().subdivided(Subdivision::Loop, 1);
+        // The cube's quads are split into 12 triangles with 18 edges first.
+        assert_eq!(mesh.vertex_count(), 8 + 18);
+        assert_eq!(mesh.faces.len(), 48);
+        assert!(mesh.faces.iter().all(|face| face.len() == 3));
+    }
+
+    #[test]
+    fn colors_are_interpolated_linearly() {
+        let mut data = MeshData::new(vec![Vec3::ZERO, Vec3::X, Vec3::Y], vec![[0, 1, 2]]);
+        data.colors = Some(vec![Color(Vec3::X), Color(Vec3::Y), Color(Vec3::Z)]);
+        let subdivided = data.subdivided(Subdivision::Loop, 1);
+        let colors = subdivided.colors.unwrap();
+        assert_eq!(colors.len(), subdivided.positions[0].len());
+        // Corners keep their colors and edge points get the average of
+        // their ends, even though boundary positions are smoothed.
+        assert_eq!(colors[0].0, Vec3::X);
+        let midpoints: Vec<Vec3> = colors[3..].iter().map(|color| color.0).collect();
+        for expected in [
+            Vec3::new(0.5, 0.5, 0.0),
+            Vec3::new(0.0, 0.5, 0.5),
+            Vec3::new(0.5, 0.0, 0.5),
+        ] {
+            assert!(midpoints.contains(&expected));
+        }
+    }
+}
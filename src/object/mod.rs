@@ -1,19 +1,137 @@
 pub mod aabb;
 pub mod bvh;
 pub mod collection;
+pub mod curves;
+pub mod frame;
+pub mod load;
+pub mod mesh;
+pub mod ply;
+pub mod points;
+pub mod sdf;
 pub mod types;
 
-use glam::Vec3;
+use glam::{Quat, Vec3};
 
 use crate::{
     math::Interval,
-    rendering::ray::{HitRecord, Ray},
+    rendering::{
+        material::Material,
+        ray::{HitRecord, Ray},
+    },
 };
 
-use self::aabb::Aabb;
+use self::{aabb::Aabb, mesh::MeshData};
+
+/// A point on an object's surface with the outward normal there.
+pub struct SurfaceSample {
+    pub point: Vec3,
+    pub normal: Vec3,
+}
+
+/// Relative distance past a hit at which `Object::hit_all` looks for the
+/// next one.
+const NEXT_HIT_EPSILON: f32 = 1e-4;
 
 pub trait Object: Send + Sync {
-    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord>;
+    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord<'_>>;
+
+    /// Every surface crossing within `range`, nearest first. For closed
+    /// objects, `front_face` tells entries from exits.
+    fn hit_all(&self, ray: Ray, range: &Interval) -> Vec<HitRecord<'_>> {
+        let mut hits = Vec::new();
+        let mut start = range.start;
+        while let Some(hit) = self.hit(ray, &(start..range.end)) {
+            start = hit.t + NEXT_HIT_EPSILON * hit.t.abs().max(1.0);
+            hits.push(hit);
+        }
+        hits
+    }
+
     fn bounding_box(&self) -> &Aabb;
+    // Like `type_name`, only the viewer's inspector asks for it.
+    #[cfg_attr(not(feature = "viewer"), allow(dead_code))]
     fn position(&self) -> Vec3;
+
+    /// The material the whole object is made of, if it has a single one.
+    fn material(&self) -> Option<&dyn Material> {
+        None
+    }
+
+    /// Full name of the object's type, for showing what something is.
+    #[cfg_attr(not(feature = "viewer"), allow(dead_code))]
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Surface area, zero for objects that can't be sampled.
+    fn area(&self) -> f32 {
+        0.0
+    }
+
+    /// Random point spread uniformly over the surface, or `None` if the
+    /// object can't be sampled. Moving objects are sampled where they are at
+    /// time zero.
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        None
+    }
+
+    /// Direction from `origin` to a random point on the surface, for aiming
+    /// rays at an object such as a light.
+    fn random_direction(&self, origin: Vec3) -> Option<Vec3> {
+        self.sample_surface().map(|sample| sample.point - origin)
+    }
+
+    /// Density per unit solid angle, seen from `origin`, with which
+    /// `random_direction` picks `direction`. Every crossing of the surface
+    /// along the direction adds to it.
+    fn pdf_value(&self, origin: Vec3, direction: Vec3) -> f32 {
+        let area = self.area();
+        if area <= 0.0 {
+            return 0.0;
+        }
+
+        let ray = Ray::new(origin, direction, 0.0);
+        let length = direction.length();
+        self.hit_all(ray, &(0.001..f32::MAX))
+            .iter()
+            .map(|hit| {
+                let distance = hit.t * length;
+                let cosine = direction.dot(hit.normal).abs() / length;
+                if cosine < 1e-6 {
+                    0.0
+                } else {
+                    distance * distance / (cosine * area)
+                }
+            })
+            .sum()
+    }
+
+    /// Time interval over which the object moves, `None` for static objects.
+    fn motion_range(&self) -> Option<Interval> {
+        None
+    }
+
+    /// Bounds of everywhere the object is during `times`.
+    fn bounding_box_over(&self, _times: &Interval) -> Aabb {
+        self.bounding_box().clone()
+    }
+
+    /// Hands the object's geometry to `visitor` if it is one of the shapes
+    /// the visitor has a method for, and does nothing otherwise.
+    fn visit_shape(&self, _visitor: &mut dyn ShapeVisitor) {}
+}
+
+/// Receives the geometry of a primitive shape in the terms of its
+/// constructor, for code that needs objects as plain data, such as saving
+/// scenes.
+pub trait ShapeVisitor {
+    /// `movement` is how far the sphere moves over the shutter interval 0..1.
+    fn sphere(&mut self, center: Vec3, radius: f32, movement: Vec3);
+    fn quad(&mut self, origin: Vec3, u: Vec3, v: Vec3);
+    fn disk(&mut self, center: Vec3, u: Vec3, v: Vec3);
+    fn cuboid(&mut self, center: Vec3, size: Vec3, rotation: Quat);
+    fn cylinder(&mut self, base: Vec3, top: Vec3, radius: f32, capped: bool);
+    fn cone(&mut self, base: Vec3, apex: Vec3, radius: f32);
+    fn torus(&mut self, center: Vec3, axis: Vec3, major_radius: f32, minor_radius: f32);
+    fn mesh(&mut self, data: &MeshData);
 }
@@ -0,0 +1,337 @@
+use std::io::{self, BufRead, Read};
+
+use glam::Vec3;
+
+use crate::rendering::{colorspace::ColorSpace, ray::Color};
+
+use super::load::LoadError;
+
+/// Contents of a PLY file, in its ASCII or either binary form: a list of
+/// elements such as vertices and faces, each with a table of properties.
+pub struct Ply {
+    pub elements: Vec<Element>,
+}
+
+pub struct Element {
+    pub name: String,
+    pub count: usize,
+    pub properties: Vec<Property>,
+}
+
+/// One column of an element. Values of every type are widened to `f64`,
+/// which holds them all exactly.
+pub struct Property {
+    pub name: String,
+    /// Type of the values, or of the items for a list.
+    pub ty: ScalarType,
+    /// Type of the item count, for a list.
+    count_type: Option<ScalarType>,
+    values: Vec<f64>,
+    /// Number of items per element, for a list.
+    lengths: Vec<u32>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    Ascii,
+    Binary { big_endian: bool },
+}
+
+impl Ply {
+    pub fn read(mut reader: impl BufRead) -> Result<Self, LoadError> {
+        let (format, mut elements) = read_header(&mut reader)?;
+        match format {
+            Format::Ascii => read_ascii(&mut reader, &mut elements)?,
+            Format::Binary { big_endian } => read_binary(&mut reader, &mut elements, big_endian)?,
+        }
+        Ok(Self { elements })
+    }
+
+    pub fn element(&self, name: &str) -> Option<&Element> {
+        self.elements.iter().find(|element| element.name == name)
+    }
+}
+
+impl Element {
+    pub fn property(&self, name: &str) -> Option<&Property> {
+        self.properties
+            .iter()
+            .find(|property| property.name == name)
+    }
+
+    /// Values of scalar property `name`, ignoring a list of that name.
+    pub fn scalar(&self, name: &str) -> Option<&[f64]> {
+        self.property(name)
+            .filter(|property| !property.is_list())
+            .map(Property::values)
+    }
+
+    /// Vectors made of three scalar properties, if all are there.
+    pub fn vectors(&self, [x, y, z]: [&str; 3]) -> Option<Vec<Vec3>> {
+        let (x, y, z) = (self.scalar(x)?, self.scalar(y)?, self.scalar(z)?);
+        Some(
+            (0..self.count)
+                .map(|i| Vec3::new(x[i] as f32, y[i] as f32, z[i] as f32))
+                .collect(),
+        )
+    }
+
+    /// Colors from the `red`, `green` and `blue` properties. Integer colors
+    /// are taken to be sRGB and floating point ones linear Rec. 709.
+    pub fn colors(&self) -> Option<Vec<Color>> {
+        let channels = ["red", "green", "blue"]
+            .map(|name| self.property(name).filter(|property| !property.is_list()));
+        let [Some(red), Some(green), Some(blue)] = channels else {
+            return None;
+        };
+
+        let color_space = if red.ty.is_integer() {
+            ColorSpace::Srgb
+        } else {
+            ColorSpace::LinearRec709
+        };
+        Some(
+            red.unit_values()
+                .zip(green.unit_values())
+                .zip(blue.unit_values())
+                .map(|((r, g), b)| Color(color_space.to_working(Vec3::new(r, g, b))))
+                .collect(),
+        )
+    }
+}
+
+impl Property {
+    pub fn is_list(&self) -> bool {
+        self.count_type.is_some()
+    }
+
+    /// Values of a scalar property, one per element.
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    /// Values of a list property, one slice per element.
+    pub fn lists(&self) -> impl Iterator<Item = &[f64]> {
+        self.lengths.iter().scan(0, |start, &length| {
+            let first = *start;
+            *start += length as usize;
+            Some(&self.values[first..*start])
+        })
+    }
+
+    /// Values scaled into [0, 1] when stored as unsigned integers, the way
+    /// colors usually are, and as they are otherwise.
+    fn unit_values(&self) -> impl Iterator<Item = f32> + '_ {
+        let scale = 1.0 / self.ty.max().unwrap_or(1.0);
+        self.values.iter().map(move |&value| (value * scale) as f32)
+    }
+}
+
+impl ScalarType {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "char" | "int8" => Some(Self::I8),
+            "uchar" | "uint8" => Some(Self::U8),
+            "short" | "int16" => Some(Self::I16),
+            "ushort" | "uint16" => Some(Self::U16),
+            "int" | "int32" => Some(Self::I32),
+            "uint" | "uint32" => Some(Self::U32),
+            "float" | "float32" => Some(Self::F32),
+            "double" | "float64" => Some(Self::F64),
+            _ => None,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+
+    /// Largest value of an unsigned integer type.
+    fn max(self) -> Option<f64> {
+        match self {
+            Self::U8 => Some(u8::MAX as f64),
+            Self::U16 => Some(u16::MAX as f64),
+            Self::U32 => Some(u32::MAX as f64),
+            _ => None,
+        }
+    }
+
+    fn is_integer(self) -> bool {
+        !matches!(self, Self::F32 | Self::F64)
+    }
+}
+
+fn read_header<R: BufRead>(reader: &mut R) -> Result<(Format, Vec<Element>), LoadError> {
+    let mut lines = reader.lines();
+    if lines.next().transpose()?.as_deref().map(str::trim) != Some("ply") {
+        return Err(LoadError::Header("not a PLY file".to_string()));
+    }
+
+    let scalar_type = |name: &str| {
+        ScalarType::from_name(name)
+            .ok_or_else(|| LoadError::Header(format!("unknown type `{name}`")))
+    };
+
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    for line in lines {
+        let line = line?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let property = match words.as_slice() {
+            [] | ["comment", ..] | ["obj_info", ..] => None,
+            ["format", name, "1.0"] => {
+                format = Some(match *name {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::Binary { big_endian: false },
+                    "binary_big_endian" => Format::Binary { big_endian: true },
+                    _ => return Err(LoadError::Header(format!("unknown format `{name}`"))),
+                });
+                None
+            }
+            ["element", name, count] => {
+                let count = count
+                    .parse()
+                    .map_err(|_| LoadError::Header(format!("bad count for element `{name}`")))?;
+                elements.push(Element {
+                    name: name.to_string(),
+                    count,
+                    properties: Vec::new(),
+                });
+                None
+            }
+            ["property", "list", count_type, item_type, name] => Some((
+                name.to_string(),
+                scalar_type(item_type)?,
+                Some(scalar_type(count_type)?),
+            )),
+            ["property", ty, name] => Some((name.to_string(), scalar_type(ty)?, None)),
+            ["end_header"] => {
+                let format =
+                    format.ok_or_else(|| LoadError::Header("missing format line".to_string()))?;
+                return Ok((format, elements));
+            }
+            _ => {
+                return Err(LoadError::Header(format!(
+                    "unexpected header line `{}`",
+                    line.trim()
+                )))
+            }
+        };
+
+        if let Some((name, ty, count_type)) = property {
+            let element = elements.last_mut().ok_or_else(|| {
+                LoadError::Header(format!("property `{name}` outside an element"))
+            })?;
+            element.properties.push(Property {
+                name,
+                ty,
+                count_type,
+                values: Vec::new(),
+                lengths: Vec::new(),
+            });
+        }
+    }
+
+    Err(LoadError::Header("header has no end".to_string()))
+}
+
+fn read_ascii<R: Read>(reader: &mut R, elements: &mut [Element]) -> Result<(), LoadError> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    let mut tokens = text.split_whitespace();
+    let mut next = || {
+        let token = tokens
+            .next()
+            .ok_or_else(|| LoadError::Data("file ends before its last element".to_string()))?;
+        token
+            .parse::<f64>()
+            .map_err(|_| LoadError::Data(format!("bad number `{token}`")))
+    };
+
+    for element in elements {
+        for _ in 0..element.count {
+            for property in &mut element.properties {
+                if property.is_list() {
+                    let length = next()? as u32;
+                    property.lengths.push(length);
+                    for _ in 0..length {
+                        property.values.push(next()?);
+                    }
+                } else {
+                    property.values.push(next()?);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_binary<R: Read>(
+    reader: &mut R,
+    elements: &mut [Element],
+    big_endian: bool,
+) -> Result<(), LoadError> {
+    for element in elements {
+        for _ in 0..element.count {
+            for property in &mut element.properties {
+                if let Some(count_type) = property.count_type {
+                    let length = read_value(reader, count_type, big_endian)? as u32;
+                    property.lengths.push(length);
+                    for _ in 0..length {
+                        property
+                            .values
+                            .push(read_value(reader, property.ty, big_endian)?);
+                    }
+                } else {
+                    property
+                        .values
+                        .push(read_value(reader, property.ty, big_endian)?);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_value<R: Read>(reader: &mut R, ty: ScalarType, big_endian: bool) -> Result<f64, LoadError> {
+    let mut b = [0; 8];
+    reader
+        .read_exact(&mut b[..ty.size()])
+        .map_err(|error| match error.kind() {
+            io::ErrorKind::UnexpectedEof => {
+                LoadError::Data("file ends before its last element".to_string())
+            }
+            _ => LoadError::Io(error),
+        })?;
+    if big_endian {
+        b[..ty.size()].reverse();
+    }
+
+    Ok(match ty {
+        ScalarType::I8 => i8::from_le_bytes([b[0]]) as f64,
+        ScalarType::U8 => b[0] as f64,
+        ScalarType::I16 => i16::from_le_bytes([b[0], b[1]]) as f64,
+        ScalarType::U16 => u16::from_le_bytes([b[0], b[1]]) as f64,
+        ScalarType::I32 => i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+        ScalarType::U32 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+        ScalarType::F32 => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+        ScalarType::F64 => f64::from_le_bytes(b),
+    })
+}
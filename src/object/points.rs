@@ -0,0 +1,309 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use glam::Vec3;
+
+use crate::rendering::ray::Color;
+
+use super::{aabb::Aabb, load::LoadError, ply::Ply};
+
+/// Spheres stored as flat arrays rather than one object each: a position
+/// and radius per point, and optionally a color and a material index.
+#[derive(Clone, Default)]
+pub struct PointCloud {
+    pub positions: Vec<Vec3>,
+    pub radii: Vec<f32>,
+    pub colors: Option<Vec<Color>>,
+    pub materials: Option<Vec<u32>>,
+}
+
+/// Columns a point file can have, in the order assumed when a CSV file
+/// has no header.
+const COLUMNS: [&str; 8] = ["x", "y", "z", "radius", "red", "green", "blue", "material"];
+
+impl PointCloud {
+    /// Points all of the same `radius`.
+    pub fn new(positions: Vec<Vec3>, radius: f32) -> Self {
+        Self {
+            radii: vec![radius; positions.len()],
+            positions,
+            colors: None,
+            materials: None,
+        }
+    }
+
+    pub fn with_radii(mut self, radii: Vec<f32>) -> Self {
+        assert_eq!(radii.len(), self.len());
+        self.radii = radii;
+        self
+    }
+
+    pub fn with_colors(mut self, colors: Vec<Color>) -> Self {
+        assert_eq!(colors.len(), self.len());
+        self.colors = Some(colors);
+        self
+    }
+
+    /// Gives each point an index into the materials of the cloud it is
+    /// rendered with.
+    pub fn with_materials(mut self, materials: Vec<u32>) -> Self {
+        assert_eq!(materials.len(), self.len());
+        self.materials = Some(materials);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn point_bounds(&self, index: usize) -> Aabb {
+        let offset = Vec3::splat(self.radii[index]);
+        let position = self.positions[index];
+        Aabb::from_points(position - offset, position + offset)
+    }
+
+    /// Reads a `.ply` or `.csv` file by its extension, giving points without
+    /// a radius of their own `radius`.
+    pub fn load<P: AsRef<Path>>(path: P, radius: f32) -> Result<Self, LoadError> {
+        let path = path.as_ref();
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("ply") => {
+                Self::from_ply(path, radius)
+            }
+            Some(extension) if extension.eq_ignore_ascii_case("csv") => {
+                Self::from_csv(path, radius)
+            }
+            _ => Err(LoadError::Unsupported(format!(
+                "{}: not a .ply or .csv file",
+                path.display()
+            ))),
+        }
+    }
+
+    pub fn from_ply<P: AsRef<Path>>(path: P, radius: f32) -> Result<Self, LoadError> {
+        Self::read_ply(BufReader::new(File::open(path)?), radius)
+    }
+
+    pub fn from_csv<P: AsRef<Path>>(path: P, radius: f32) -> Result<Self, LoadError> {
+        Self::read_csv(BufReader::new(File::open(path)?), radius)
+    }
+
+    /// Reads the `vertex` element of a PLY file, using its `x`, `y` and `z`
+    /// properties and, where present, `radius`, `red`, `green`, `blue` and
+    /// `material`. Integer colors are taken to be sRGB and floating point
+    /// ones linear Rec. 709.
+    pub fn read_ply(reader: impl BufRead, radius: f32) -> Result<Self, LoadError> {
+        let ply = Ply::read(reader)?;
+        let vertices = ply
+            .element("vertex")
+            .ok_or_else(|| LoadError::Header("no `vertex` element".to_string()))?;
+        let positions = vertices
+            .vectors(["x", "y", "z"])
+            .ok_or_else(|| LoadError::Header("vertices have no position".to_string()))?;
+        let mut points = Self::new(positions, radius);
+
+        if let Some(radii) = vertices.scalar("radius") {
+            points = points.with_radii(radii.iter().map(|&r| r as f32).collect());
+        }
+        if let Some(colors) = vertices.colors() {
+            points = points.with_colors(colors);
+        }
+        if let Some(materials) = vertices.scalar("material") {
+            points = points.with_materials(material_indices(materials.iter().copied())?);
+        }
+
+        Ok(points)
+    }
+
+    /// Reads comma separated points, one per line. A first line that isn't
+    /// numbers names the columns, which can be `x`, `y`, `z`, `radius`,
+    /// `red`, `green`, `blue` and `material`, and others are ignored.
+    /// Without it the columns are taken in that order, and any after `z`
+    /// may be left out. Every line needs as many values as the first.
+    /// Colors are linear, in the working space. Lines starting with `#` are
+    /// comments.
+    pub fn read_csv(reader: impl BufRead, radius: f32) -> Result<Self, LoadError> {
+        // Which of `COLUMNS` each field of a line is.
+        let mut columns: Option<Vec<Option<usize>>> = None;
+        let mut rows: Vec<[Option<f32>; 8]> = Vec::new();
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+
+            let values: Result<Vec<f32>, _> = fields.iter().map(|field| field.parse()).collect();
+            let Ok(values) = values else {
+                if columns.is_none() && rows.is_empty() {
+                    columns = Some(
+                        fields
+                            .iter()
+                            .map(|field| {
+                                let field = field.to_ascii_lowercase();
+                                COLUMNS.iter().position(|&column| column == field)
+                            })
+                            .collect(),
+                    );
+                    continue;
+                }
+                return Err(LoadError::Data(format!(
+                    "line {}: expected numbers",
+                    number + 1
+                )));
+            };
+
+            let columns = columns
+                .get_or_insert_with(|| (0..values.len()).map(|i| (i < 8).then_some(i)).collect());
+            if values.len() != columns.len() {
+                return Err(LoadError::Data(format!(
+                    "line {}: expected {} values, found {}",
+                    number + 1,
+                    columns.len(),
+                    values.len()
+                )));
+            }
+            let mut row = [None; 8];
+            for (column, value) in columns.iter().zip(values) {
+                if let Some(column) = column {
+                    row[*column] = Some(value);
+                }
+            }
+            if row[..3].iter().any(Option::is_none) {
+                return Err(LoadError::Data(format!(
+                    "line {}: expected `x,y,z`",
+                    number + 1
+                )));
+            }
+            rows.push(row);
+        }
+
+        let column = |index: usize| {
+            rows.iter()
+                .map(|row| row[index])
+                .collect::<Option<Vec<f32>>>()
+        };
+        let positions = rows
+            .iter()
+            .map(|row| Vec3::new(row[0].unwrap(), row[1].unwrap(), row[2].unwrap()))
+            .collect();
+        let mut points = Self::new(positions, radius);
+
+        if let Some(radii) = column(3) {
+            points = points.with_radii(radii);
+        }
+        if let (Some(red), Some(green), Some(blue)) = (column(4), column(5), column(6)) {
+            let colors = (0..red.len())
+                .map(|i| Color::new(red[i], green[i], blue[i]))
+                .collect();
+            points = points.with_colors(colors);
+        }
+        if let Some(materials) = column(7) {
+            points = points.with_materials(material_indices(materials.into_iter().map(f64::from))?);
+        }
+
+        Ok(points)
+    }
+}
+
+/// Material indices read as numbers, which have to be whole and not
+/// negative.
+fn material_indices(values: impl Iterator<Item = f64>) -> Result<Vec<u32>, LoadError> {
+    values
+        .enumerate()
+        .map(|(point, value)| {
+            if value.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(&value) {
+                Ok(value as u32)
+            } else {
+                Err(LoadError::Data(format!(
+                    "point {point} has material index {value}"
+                )))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn csv(text: &str) -> Result<PointCloud, LoadError> {
+        PointCloud::read_csv(text.as_bytes(), 0.5)
+    }
+
+    #[test]
+    fn reads_csv_columns_in_order() {
+        let points = csv("# two points\n1,2,3\n4,5,6\n").unwrap();
+        assert_eq!(
+            points.positions,
+            vec![Vec3::new(1.0, 2.0, 3.0), Vec3::new(4.0, 5.0, 6.0)]
+        );
+        assert_eq!(points.radii, vec![0.5, 0.5]);
+        assert!(points.colors.is_none());
+    }
+
+    #[test]
+    fn rejects_csv_lines_of_other_widths() {
+        // The first line has no radius for the second's to go in.
+        let error = csv("1,2,3\n4,5,6,0.25\n").err().unwrap();
+        assert!(matches!(error, LoadError::Data(_)), "{error}");
+        let error = csv("x,y,z,radius\n1,2,3\n").err().unwrap();
+        assert!(matches!(error, LoadError::Data(_)), "{error}");
+    }
+
+    #[test]
+    fn reads_csv_columns_by_name() {
+        let text = "material, z, y, x, other, radius\n2, 3, 2, 1, 9, 0.1\n";
+        let points = csv(text).unwrap();
+        assert_eq!(points.positions, vec![Vec3::new(1.0, 2.0, 3.0)]);
+        assert_eq!(points.radii, vec![0.1]);
+        assert_eq!(points.materials, Some(vec![2]));
+    }
+
+    #[test]
+    fn rejects_csv_without_positions() {
+        let error = csv("1,2,3\n1,2\n").err().unwrap();
+        assert!(matches!(error, LoadError::Data(_)), "{error}");
+        let error = csv("1,2,3\na,b,c\n").err().unwrap();
+        assert!(matches!(error, LoadError::Data(_)), "{error}");
+    }
+
+    #[test]
+    fn rejects_bad_material_indices() {
+        for index in ["-1", "1.5", "1e12"] {
+            let text = format!("x,y,z,material\n0,0,0,0\n1,1,1,{index}\n");
+            let error = csv(&text).err().unwrap();
+            assert!(matches!(error, LoadError::Data(_)), "{error}");
+        }
+    }
+
+    #[test]
+    fn reads_ply_vertices() {
+        let ply = "ply
+format ascii 1.0
+element vertex 2
+property float x
+property float y
+property float z
+property float radius
+property uchar red
+property uchar green
+property uchar blue
+end_header
+0 0 0 1 255 0 0
+1 2 3 2 0 0 255
+";
+        let points = PointCloud::read_ply(ply.as_bytes(), 0.5).unwrap();
+        assert_eq!(points.positions[1], Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(points.radii, vec![1.0, 2.0]);
+        assert_eq!(points.colors.map(|colors| colors.len()), Some(2));
+    }
+}
@@ -0,0 +1,123 @@
+//! Shapes described by signed distance functions, for rendering with
+//! `SdfObject`. Shapes are built from the primitives in `shapes` and
+//! combined with the operators in `operators`, usually through `SdfExt`.
+
+pub mod operators;
+pub mod shapes;
+
+use glam::Vec3;
+
+use super::aabb::Aabb;
+
+use self::operators::{
+    Intersection, Repeat, Rounded, SmoothUnion, Subtraction, Translate, Twist, Union,
+};
+
+pub use self::shapes::{Capsule, Cuboid, Mandelbulb, Sphere, Torus};
+
+/// Signed distance to a surface: negative inside, positive outside.
+///
+/// The distance may be underestimated, which only costs steps, but never
+/// overestimated, or sphere tracing steps through the surface.
+pub trait Sdf: Send + Sync {
+    fn distance(&self, point: Vec3) -> f32;
+
+    /// Bounds of everywhere the distance is negative.
+    fn bounds(&self) -> Aabb;
+}
+
+/// Builder methods for combining shapes.
+pub trait SdfExt: Sdf + Sized {
+    fn translate(self, offset: Vec3) -> Translate<Self> {
+        Translate {
+            inner: self,
+            offset,
+        }
+    }
+
+    /// Grows the surface outwards by `radius`, rounding off edges.
+    fn rounded(self, radius: f32) -> Rounded<Self> {
+        Rounded {
+            inner: self,
+            radius,
+        }
+    }
+
+    fn union<B: Sdf>(self, other: B) -> Union<Self, B> {
+        Union { a: self, b: other }
+    }
+
+    fn intersection<B: Sdf>(self, other: B) -> Intersection<Self, B> {
+        Intersection { a: self, b: other }
+    }
+
+    /// This shape with `other` carved out of it.
+    fn subtract<B: Sdf>(self, other: B) -> Subtraction<Self, B> {
+        Subtraction { a: self, b: other }
+    }
+
+    /// Union that blends the two shapes together within `smoothness` of
+    /// where they meet.
+    fn smooth_union<B: Sdf>(self, other: B, smoothness: f32) -> SmoothUnion<Self, B> {
+        SmoothUnion {
+            a: self,
+            b: other,
+            smoothness,
+        }
+    }
+
+    /// Turns the shape around the y axis by `rate` radians per unit of height.
+    fn twist(self, rate: f32) -> Twist<Self> {
+        Twist::new(self, rate)
+    }
+
+    /// Copies of the shape `spacing` apart, `count` more of them on either
+    /// side along each axis. The shape has to fit within one spacing cell.
+    fn repeat(self, spacing: Vec3, count: [u32; 3]) -> Repeat<Self> {
+        Repeat {
+            inner: self,
+            spacing,
+            count: Vec3::new(count[0] as f32, count[1] as f32, count[2] as f32),
+        }
+    }
+}
+
+impl<T: Sdf> SdfExt for T {}
+
+#[cfg(test)]
+mod tests {
+    use crate::rendering::ray::Ray;
+
+    use super::*;
+
+    #[test]
+    fn mandelbulb_distance_is_finite_at_the_origin() {
+        let bulb = Mandelbulb::default();
+        for point in [Vec3::ZERO, Vec3::splat(1e-20), Vec3::new(0.0, 0.0, 1e-3)] {
+            let distance = bulb.distance(point);
+            assert!(
+                distance.is_finite() && distance <= 0.0,
+                "{distance} at {point}"
+            );
+        }
+        assert!(bulb.distance(Vec3::splat(2.0)) > 0.0);
+    }
+
+    #[test]
+    fn intersection_bounds_are_the_overlap() {
+        let shape = Sphere { radius: 1.0 }.intersection(Sphere { radius: 1.0 }.translate(Vec3::X));
+        let bounds = shape.bounds();
+        assert_eq!(bounds.min(), Vec3::new(0.0, -1.0, -1.0));
+        assert_eq!(bounds.max(), Vec3::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn disjoint_intersection_has_empty_bounds() {
+        let shape =
+            Sphere { radius: 1.0 }.intersection(Sphere { radius: 1.0 }.translate(Vec3::X * 5.0));
+        let bounds = shape.bounds();
+        assert!(bounds.min().cmpgt(bounds.max()).all());
+        let ray = Ray::new(Vec3::new(2.5, 0.0, -10.0), Vec3::Z, 0.0);
+        assert!(bounds.hit_range(ray, &(0.0..f32::MAX)).is_none());
+    }
+}
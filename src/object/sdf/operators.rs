@@ -0,0 +1,173 @@
+use glam::{vec2, Vec3};
+
+use crate::object::aabb::Aabb;
+
+use super::Sdf;
+
+pub struct Translate<S> {
+    pub(super) inner: S,
+    pub(super) offset: Vec3,
+}
+
+impl<S: Sdf> Sdf for Translate<S> {
+    fn distance(&self, point: Vec3) -> f32 {
+        self.inner.distance(point - self.offset)
+    }
+
+    fn bounds(&self) -> Aabb {
+        let bounds = self.inner.bounds();
+        Aabb::from_points(bounds.min() + self.offset, bounds.max() + self.offset)
+    }
+}
+
+pub struct Rounded<S> {
+    pub(super) inner: S,
+    pub(super) radius: f32,
+}
+
+impl<S: Sdf> Sdf for Rounded<S> {
+    fn distance(&self, point: Vec3) -> f32 {
+        self.inner.distance(point) - self.radius
+    }
+
+    fn bounds(&self) -> Aabb {
+        self.inner.bounds().grow(self.radius)
+    }
+}
+
+pub struct Union<A, B> {
+    pub(super) a: A,
+    pub(super) b: B,
+}
+
+impl<A: Sdf, B: Sdf> Sdf for Union<A, B> {
+    fn distance(&self, point: Vec3) -> f32 {
+        self.a.distance(point).min(self.b.distance(point))
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::from_boxes(&self.a.bounds(), &self.b.bounds())
+    }
+}
+
+pub struct Intersection<A, B> {
+    pub(super) a: A,
+    pub(super) b: B,
+}
+
+impl<A: Sdf, B: Sdf> Sdf for Intersection<A, B> {
+    fn distance(&self, point: Vec3) -> f32 {
+        self.a.distance(point).max(self.b.distance(point))
+    }
+
+    fn bounds(&self) -> Aabb {
+        let (a, b) = (self.a.bounds(), self.b.bounds());
+        let (min, max) = (a.min().max(b.min()), a.max().min(b.max()));
+        if min.cmpgt(max).any() {
+            // Operands whose bounds don't overlap have nothing in common.
+            return Aabb::empty();
+        }
+        Aabb::from_points(min, max)
+    }
+}
+
+pub struct Subtraction<A, B> {
+    pub(super) a: A,
+    pub(super) b: B,
+}
+
+impl<A: Sdf, B: Sdf> Sdf for Subtraction<A, B> {
+    fn distance(&self, point: Vec3) -> f32 {
+        self.a.distance(point).max(-self.b.distance(point))
+    }
+
+    fn bounds(&self) -> Aabb {
+        self.a.bounds()
+    }
+}
+
+/// Polynomial smooth minimum of the two distances.
+pub struct SmoothUnion<A, B> {
+    pub(super) a: A,
+    pub(super) b: B,
+    pub(super) smoothness: f32,
+}
+
+impl<A: Sdf, B: Sdf> Sdf for SmoothUnion<A, B> {
+    fn distance(&self, point: Vec3) -> f32 {
+        let (a, b) = (self.a.distance(point), self.b.distance(point));
+        let k = self.smoothness.max(1e-6);
+        let h = (0.5 + 0.5 * (b - a) / k).clamp(0.0, 1.0);
+        b + (a - b) * h - k * h * (1.0 - h)
+    }
+
+    fn bounds(&self) -> Aabb {
+        // The blend bulges out by at most a quarter of the smoothness.
+        Aabb::from_boxes(&self.a.bounds(), &self.b.bounds()).grow(0.25 * self.smoothness)
+    }
+}
+
+pub struct Twist<S> {
+    inner: S,
+    rate: f32,
+    /// Furthest the untwisted shape gets from the y axis.
+    reach: f32,
+}
+
+impl<S: Sdf> Twist<S> {
+    pub(super) fn new(inner: S, rate: f32) -> Self {
+        let bounds = inner.bounds();
+        let reach = vec2(
+            bounds.min().x.abs().max(bounds.max().x.abs()),
+            bounds.min().z.abs().max(bounds.max().z.abs()),
+        )
+        .length();
+        Self { inner, rate, reach }
+    }
+}
+
+impl<S: Sdf> Sdf for Twist<S> {
+    fn distance(&self, point: Vec3) -> f32 {
+        let (sin, cos) = (self.rate * point.y).sin_cos();
+        let twisted = Vec3::new(
+            cos * point.x - sin * point.z,
+            point.y,
+            sin * point.x + cos * point.z,
+        );
+
+        // Twisting stretches space most far from the axis; dividing by that
+        // stretch keeps the distance from being overestimated.
+        self.inner.distance(twisted) / (1.0 + (self.rate * self.reach).powi(2)).sqrt()
+    }
+
+    fn bounds(&self) -> Aabb {
+        let bounds = self.inner.bounds();
+        let reach = self.reach;
+        Aabb::from_points(
+            Vec3::new(-reach, bounds.axis(1).start, -reach),
+            Vec3::new(reach, bounds.axis(1).end, reach),
+        )
+    }
+}
+
+/// Finitely many copies laid out on a grid.
+pub struct Repeat<S> {
+    pub(super) inner: S,
+    pub(super) spacing: Vec3,
+    pub(super) count: Vec3,
+}
+
+impl<S: Sdf> Sdf for Repeat<S> {
+    fn distance(&self, point: Vec3) -> f32 {
+        let cell = (point / self.spacing)
+            .round()
+            .clamp(-self.count, self.count);
+        self.inner.distance(point - self.spacing * cell)
+    }
+
+    fn bounds(&self) -> Aabb {
+        let bounds = self.inner.bounds();
+        let extent = self.spacing * self.count;
+        Aabb::from_points(bounds.min() - extent, bounds.max() + extent)
+    }
+}
@@ -0,0 +1,128 @@
+use glam::{vec2, Vec3};
+
+use crate::object::aabb::Aabb;
+
+use super::Sdf;
+
+/// Sphere around the origin.
+pub struct Sphere {
+    pub radius: f32,
+}
+
+impl Sdf for Sphere {
+    fn distance(&self, point: Vec3) -> f32 {
+        point.length() - self.radius
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::from_points(Vec3::splat(-self.radius), Vec3::splat(self.radius))
+    }
+}
+
+/// Box around the origin with sharp edges; `rounded` softens them.
+pub struct Cuboid {
+    pub half_size: Vec3,
+}
+
+impl Sdf for Cuboid {
+    fn distance(&self, point: Vec3) -> f32 {
+        let q = point.abs() - self.half_size;
+        q.max(Vec3::ZERO).length() + q.max_element().min(0.0)
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::from_points(-self.half_size, self.half_size)
+    }
+}
+
+/// Ring around the y axis.
+pub struct Torus {
+    pub major_radius: f32,
+    pub minor_radius: f32,
+}
+
+impl Sdf for Torus {
+    fn distance(&self, point: Vec3) -> f32 {
+        let ring = vec2(point.x, point.z).length() - self.major_radius;
+        vec2(ring, point.y).length() - self.minor_radius
+    }
+
+    fn bounds(&self) -> Aabb {
+        let outer = self.major_radius + self.minor_radius;
+        Aabb::from_points(
+            Vec3::new(-outer, -self.minor_radius, -outer),
+            Vec3::new(outer, self.minor_radius, outer),
+        )
+    }
+}
+
+/// Segment from `a` to `b` thickened by `radius`.
+pub struct Capsule {
+    pub a: Vec3,
+    pub b: Vec3,
+    pub radius: f32,
+}
+
+impl Sdf for Capsule {
+    fn distance(&self, point: Vec3) -> f32 {
+        let pa = point - self.a;
+        let ba = self.b - self.a;
+        let h = (pa.dot(ba) / ba.dot(ba)).clamp(0.0, 1.0);
+        (pa - ba * h).length() - self.radius
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::from_points(self.a.min(self.b), self.a.max(self.b)).grow(self.radius)
+    }
+}
+
+/// The Mandelbulb fractal of the given `power`, its distance estimated from
+/// the running derivative of the iteration.
+pub struct Mandelbulb {
+    pub power: f32,
+    pub iterations: u32,
+}
+
+impl Default for Mandelbulb {
+    fn default() -> Self {
+        Self {
+            power: 8.0,
+            iterations: 12,
+        }
+    }
+}
+
+impl Sdf for Mandelbulb {
+    fn distance(&self, point: Vec3) -> f32 {
+        let mut z = point;
+        let mut derivative = 1.0;
+        let mut r = z.length();
+
+        for _ in 0..self.iterations {
+            if r > 2.0 {
+                break;
+            }
+            if r < f32::EPSILON {
+                // The origin maps to itself, so the orbit never escapes and
+                // its angles are undefined.
+                return 0.0;
+            }
+
+            let theta = (z.z / r).clamp(-1.0, 1.0).acos() * self.power;
+            let phi = z.y.atan2(z.x) * self.power;
+            derivative = r.powf(self.power - 1.0) * self.power * derivative + 1.0;
+
+            let (sin_theta, cos_theta) = theta.sin_cos();
+            let (sin_phi, cos_phi) = phi.sin_cos();
+            z = r.powf(self.power) * Vec3::new(sin_theta * cos_phi, sin_theta * sin_phi, cos_theta)
+                + point;
+            r = z.length();
+        }
+
+        0.5 * r.ln() * r / derivative
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::from_points(Vec3::splat(-1.2), Vec3::splat(1.2))
+    }
+}
@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use glam::Vec3;
+
+use crate::{
+    animation::TransformTrack,
+    math::{Interval, IntervalExt},
+    object::{aabb::Aabb, Object},
+    rendering::{
+        material::Material,
+        ray::{HitRecord, Ray},
+    },
+};
+
+/// Wraps an object with a keyframed transform evaluated at each ray's time.
+pub struct AnimatedObject {
+    object: Arc<dyn Object>,
+    track: TransformTrack,
+    bbox: Aabb,
+}
+
+impl AnimatedObject {
+    pub fn new<T: Object + 'static>(object: T, track: TransformTrack) -> Self {
+        let mut animated = Self {
+            object: Arc::new(object),
+            track,
+            bbox: Aabb::empty(),
+        };
+        let times = animated.motion_range().unwrap_or(0.0..0.0);
+        animated.bbox = animated.bounding_box_over(&times);
+        animated
+    }
+}
+
+impl Object for AnimatedObject {
+    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord<'_>> {
+        let transform = self.track.matrix(ray.time);
+        let inverse = transform.inverse();
+        let local_ray = Ray {
+            origin: inverse.transform_point3(ray.origin),
+            direction: inverse.transform_vector3(ray.direction),
+            ..ray
+        };
+
+        self.object
+            .hit(local_ray, range)
+            .map(|hit| hit.transform(self, ray, &transform))
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+
+    fn position(&self) -> Vec3 {
+        self.track.translation.sample(0.0).unwrap_or(Vec3::ZERO)
+    }
+
+    fn material(&self) -> Option<&dyn Material> {
+        self.object.material()
+    }
+
+    fn motion_range(&self) -> Option<Interval> {
+        let own = self.track.key_range();
+        match (own, self.object.motion_range()) {
+            (Some(a), Some(b)) => Some(Interval::from_intervals(&a, &b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    fn bounding_box_over(&self, times: &Interval) -> Aabb {
+        let local = self.object.bounding_box_over(times);
+        let bbox = self
+            .track
+            .sample_times(times)
+            .into_iter()
+            .map(|time| local.transformed(&self.track.matrix(time)))
+            .fold(Aabb::empty(), |a, b| Aabb::from_boxes(&a, &b));
+
+        // Rotations and curves bulge slightly between the sampled times.
+        bbox.grow(0.01 * (bbox.max() - bbox.min()).length()).pad()
+    }
+}
@@ -0,0 +1,225 @@
+use std::f32::consts::PI;
+
+use glam::{vec2, vec3, Vec3};
+
+use crate::{
+    math::{random, solve_quadratic, Interval},
+    object::{
+        aabb::Aabb,
+        frame::{sorted_hits, Frame, LocalHit},
+        Object, ShapeVisitor, SurfaceSample,
+    },
+    rendering::{
+        material::Material,
+        ray::{HitRecord, Ray},
+    },
+};
+
+/// Cone from a round base to an apex, closed with a flat cap. Along the
+/// side U runs around the axis and V from base to apex; the cap is mapped
+/// to the 0..1 UV square.
+pub struct Cone<M: Material> {
+    frame: Frame,
+    radius: f32,
+    height: f32,
+    material: M,
+    bbox: Aabb,
+}
+
+impl<M: Material> Cone<M> {
+    pub fn new(base: Vec3, apex: Vec3, radius: f32, material: M) -> Self {
+        let frame = Frame::with_z_axis(base, apex - base);
+        let height = (apex - base).length();
+        Self {
+            bbox: frame.bounds(&Aabb::from_points(
+                vec3(-radius, -radius, 0.0),
+                vec3(radius, radius, height),
+            )),
+            frame,
+            radius,
+            height,
+            material,
+        }
+    }
+
+    fn local_hits(&self, ray: Ray) -> Vec<LocalHit> {
+        let Ray {
+            origin: o,
+            direction: d,
+            ..
+        } = self.frame.ray_to_local(ray);
+        let mut hits = Vec::new();
+
+        // x² + y² = (k (h - z))², with k the radius shrinking per unit height.
+        let k2 = (self.radius / self.height).powi(2);
+        let w = self.height - o.z;
+        let a = d.x * d.x + d.y * d.y - k2 * d.z * d.z;
+        let b = 2.0 * (o.x * d.x + o.y * d.y + k2 * w * d.z);
+        let c = o.x * o.x + o.y * o.y - k2 * w * w;
+        if let Some((near, far)) = solve_quadratic(a, b, c).filter(|(near, far)| near < far) {
+            for t in [near, far] {
+                let point = o + t * d;
+                // The equation also describes the mirrored cone above the apex.
+                if (0.0..=self.height).contains(&point.z) {
+                    hits.push(LocalHit {
+                        t,
+                        outward_normal: vec3(point.x, point.y, k2 * (self.height - point.z))
+                            .normalize_or_zero(),
+                        uv: vec2(
+                            (point.y.atan2(point.x) + PI) / (2.0 * PI),
+                            point.z / self.height,
+                        ),
+                    });
+                }
+            }
+        }
+
+        if d.z != 0.0 {
+            let t = -o.z / d.z;
+            let point = o + t * d;
+            if point.x * point.x + point.y * point.y <= self.radius * self.radius {
+                hits.push(LocalHit {
+                    t,
+                    outward_normal: -Vec3::Z,
+                    uv: vec2(point.x, point.y) / (2.0 * self.radius) + 0.5,
+                });
+            }
+        }
+
+        hits
+    }
+
+    fn side_area(&self) -> f32 {
+        PI * self.radius * self.radius.hypot(self.height)
+    }
+}
+
+impl<M: Material> Object for Cone<M> {
+    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord<'_>> {
+        let hit = sorted_hits(self.local_hits(ray), range)
+            .into_iter()
+            .next()?;
+        Some(self.frame.hit_record(self, ray, &hit, &self.material))
+    }
+
+    fn hit_all(&self, ray: Ray, range: &Interval) -> Vec<HitRecord<'_>> {
+        sorted_hits(self.local_hits(ray), range)
+            .iter()
+            .map(|hit| self.frame.hit_record(self, ray, hit, &self.material))
+            .collect()
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+
+    fn position(&self) -> Vec3 {
+        self.frame
+            .point_to_world(vec3(0.0, 0.0, 0.25 * self.height))
+    }
+
+    fn material(&self) -> Option<&dyn Material> {
+        Some(&self.material)
+    }
+
+    fn area(&self) -> f32 {
+        self.side_area() + PI * self.radius * self.radius
+    }
+
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        let phi = 2.0 * PI * random();
+        let (sin, cos) = phi.sin_cos();
+
+        let (point, normal) = if random() * self.area() < self.side_area() {
+            // The side's area grows with the square of the distance from the apex.
+            let fraction = random().sqrt();
+            let r = self.radius * fraction;
+            let normal = vec3(self.height * cos, self.height * sin, self.radius).normalize();
+            (
+                vec3(r * cos, r * sin, self.height * (1.0 - fraction)),
+                normal,
+            )
+        } else {
+            let r = self.radius * random().sqrt();
+            (vec3(r * cos, r * sin, 0.0), -Vec3::Z)
+        };
+
+        Some(SurfaceSample {
+            point: self.frame.point_to_world(point),
+            normal: self.frame.vector_to_world(normal),
+        })
+    }
+
+    fn visit_shape(&self, visitor: &mut dyn ShapeVisitor) {
+        visitor.cone(
+            self.frame.origin(),
+            self.frame.point_to_world(Vec3::Z * self.height),
+            self.radius,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rendering::{material::Lambertian, ray::Color, texture::SolidColor};
+
+    use super::*;
+
+    /// Cone on the xy plane with its apex at z = 2 and a base of radius 1.
+    fn cone() -> Cone<Lambertian<SolidColor>> {
+        let material = Lambertian::solid_color(Color::new(0.5, 0.5, 0.5));
+        Cone::new(Vec3::ZERO, vec3(0.0, 0.0, 2.0), 1.0, material)
+    }
+
+    /// Where a ray from `origin` along `direction` crosses the cone.
+    fn crossings(origin: Vec3, direction: Vec3) -> Vec<Vec3> {
+        let cone = cone();
+        cone.hit_all(Ray::new(origin, direction, 0.0), &(0.0..f32::MAX))
+            .iter()
+            .map(|hit| (hit.point * 1e3).round() / 1e3)
+            .collect()
+    }
+
+    #[test]
+    fn ray_crosses_the_side_where_it_narrows() {
+        assert_eq!(
+            crossings(vec3(-3.0, 0.0, 1.0), Vec3::X),
+            [vec3(-0.5, 0.0, 1.0), vec3(0.5, 0.0, 1.0)]
+        );
+    }
+
+    #[test]
+    fn side_normal_leans_toward_the_apex() {
+        let cone = cone();
+        let ray = Ray::new(vec3(-3.0, 0.0, 1.0), Vec3::X, 0.0);
+        let hit = cone.hit(ray, &(0.0..f32::MAX)).unwrap();
+        // The side rises 2 over a run of 1, so the normal rises 1 over 2.
+        let expected = vec3(-2.0, 0.0, 1.0).normalize();
+        assert!(hit.normal.abs_diff_eq(expected, 1e-5), "{}", hit.normal);
+        assert!(hit.front_face);
+        assert!((hit.v - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn ray_down_the_side_leaves_through_the_cap() {
+        let cone = cone();
+        let ray = Ray::new(vec3(0.5, 0.0, 5.0), -Vec3::Z, 0.0);
+        let hits = cone.hit_all(ray, &(0.0..f32::MAX));
+        // The mirrored cone above the apex at z = 3 is not part of the shape.
+        let ts: Vec<f32> = hits.iter().map(|hit| (hit.t * 1e3).round() / 1e3).collect();
+        assert_eq!(ts, [4.0, 5.0]);
+        assert!(!hits[1].front_face);
+        assert!(
+            hits[1].normal.abs_diff_eq(Vec3::Z, 1e-6),
+            "{}",
+            hits[1].normal
+        );
+    }
+
+    #[test]
+    fn rays_outside_the_cone_miss() {
+        assert!(crossings(vec3(-3.0, 0.51, 1.0), Vec3::X).is_empty());
+        assert!(crossings(vec3(-3.0, 0.0, 3.0), Vec3::X).is_empty());
+        assert!(crossings(vec3(1.01, 0.0, 5.0), -Vec3::Z).is_empty());
+    }
+}
@@ -0,0 +1,214 @@
+use std::sync::Arc;
+
+use glam::Vec3;
+
+use crate::{
+    math::{Interval, IntervalExt},
+    object::{aabb::Aabb, Object},
+    rendering::ray::{HitRecord, Ray},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    /// Everything in the first object that is not in the second.
+    Difference,
+}
+
+impl CsgOperation {
+    fn inside(self, left: bool, right: bool) -> bool {
+        match self {
+            Self::Union => left || right,
+            Self::Intersection => left && right,
+            Self::Difference => left && !right,
+        }
+    }
+}
+
+/// Combination of two closed objects. Every surface keeps the material of
+/// the child it belongs to; surfaces of the second object that carve into
+/// the first in a difference face the other way.
+pub struct Csg {
+    operation: CsgOperation,
+    left: Arc<dyn Object>,
+    right: Arc<dyn Object>,
+    bbox: Aabb,
+}
+
+impl Csg {
+    pub fn new<L: Object + 'static, R: Object + 'static>(
+        operation: CsgOperation,
+        left: L,
+        right: R,
+    ) -> Self {
+        let bbox = match operation {
+            CsgOperation::Union => Aabb::from_boxes(left.bounding_box(), right.bounding_box()),
+            // Never larger than the first object.
+            CsgOperation::Intersection | CsgOperation::Difference => left.bounding_box().clone(),
+        };
+        Self {
+            operation,
+            left: Arc::new(left),
+            right: Arc::new(right),
+            bbox,
+        }
+    }
+
+    pub fn union<L: Object + 'static, R: Object + 'static>(left: L, right: R) -> Self {
+        Self::new(CsgOperation::Union, left, right)
+    }
+
+    pub fn intersection<L: Object + 'static, R: Object + 'static>(left: L, right: R) -> Self {
+        Self::new(CsgOperation::Intersection, left, right)
+    }
+
+    pub fn difference<L: Object + 'static, R: Object + 'static>(left: L, right: R) -> Self {
+        Self::new(CsgOperation::Difference, left, right)
+    }
+
+    /// Crossings of the combined surface within `range`, nearest first.
+    ///
+    /// Both children are intersected along the whole ray up to the end of
+    /// `range`, so that whether the ray starts inside either of them is
+    /// known, and their entries and exits are then walked in order.
+    fn crossings(&self, ray: Ray, range: &Interval) -> Vec<HitRecord<'_>> {
+        let whole_ray = f32::NEG_INFINITY..range.end;
+        let mut left = self.left.hit_all(ray, &whole_ray).into_iter().peekable();
+        let mut right = self.right.hit_all(ray, &whole_ray).into_iter().peekable();
+
+        let (mut in_left, mut in_right) = (false, false);
+        let mut crossings = Vec::new();
+        loop {
+            let from_left = match (left.peek(), right.peek()) {
+                (Some(l), Some(r)) => l.t <= r.t,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => break,
+            };
+            let was_inside = self.operation.inside(in_left, in_right);
+
+            let mut hit = if from_left {
+                let hit = left.next().unwrap();
+                in_left = hit.front_face;
+                hit
+            } else {
+                let hit = right.next().unwrap();
+                in_right = hit.front_face;
+                hit
+            };
+
+            if self.operation.inside(in_left, in_right) != was_inside && range.contains(&hit.t) {
+                if !from_left && self.operation == CsgOperation::Difference {
+                    hit.front_face = !hit.front_face;
+                }
+                hit.object = self;
+                crossings.push(hit);
+            }
+        }
+        crossings
+    }
+}
+
+impl Object for Csg {
+    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord<'_>> {
+        if !self.bbox.hit(ray, range) {
+            return None;
+        }
+        self.crossings(ray, range).into_iter().next()
+    }
+
+    fn hit_all(&self, ray: Ray, range: &Interval) -> Vec<HitRecord<'_>> {
+        if !self.bbox.hit(ray, range) {
+            return Vec::new();
+        }
+        self.crossings(ray, range)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+
+    fn position(&self) -> Vec3 {
+        self.left.position()
+    }
+
+    fn motion_range(&self) -> Option<Interval> {
+        match (self.left.motion_range(), self.right.motion_range()) {
+            (Some(a), Some(b)) => Some(Interval::from_intervals(&a, &b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    fn bounding_box_over(&self, times: &Interval) -> Aabb {
+        let left = self.left.bounding_box_over(times);
+        match self.operation {
+            CsgOperation::Union => Aabb::from_boxes(&left, &self.right.bounding_box_over(times)),
+            CsgOperation::Intersection | CsgOperation::Difference => left,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        object::types::Sphere,
+        rendering::{material::Lambertian, ray::Color},
+    };
+
+    use super::*;
+
+    /// Unit spheres around x = 0 and x = 1 combined by `operation`.
+    fn spheres(operation: CsgOperation) -> Csg {
+        let material = || Lambertian::solid_color(Color::new(0.5, 0.5, 0.5));
+        Csg::new(
+            operation,
+            Sphere::new(Vec3::ZERO, 1.0, material()),
+            Sphere::new(Vec3::X, 1.0, material()),
+        )
+    }
+
+    /// Where along the x axis a ray from x = `start` going `direction`
+    /// crosses `csg`, and whether it enters there.
+    fn crossings(csg: &Csg, start: f32, direction: f32) -> Vec<(f32, bool)> {
+        let ray = Ray::new(Vec3::new(start, 0.0, 0.0), Vec3::X * direction, 0.0);
+        csg.hit_all(ray, &(0.0..f32::MAX))
+            .iter()
+            .map(|hit| {
+                (
+                    ((start + hit.t * direction) * 1e3).round() / 1e3,
+                    hit.front_face,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn union_spans_both_spheres() {
+        let csg = spheres(CsgOperation::Union);
+        assert_eq!(crossings(&csg, -5.0, 1.0), [(-1.0, true), (2.0, false)]);
+    }
+
+    #[test]
+    fn intersection_is_the_overlap() {
+        let csg = spheres(CsgOperation::Intersection);
+        assert_eq!(crossings(&csg, -5.0, 1.0), [(0.0, true), (1.0, false)]);
+    }
+
+    #[test]
+    fn difference_exits_where_the_second_sphere_starts() {
+        let csg = spheres(CsgOperation::Difference);
+        assert_eq!(crossings(&csg, -5.0, 1.0), [(-1.0, true), (0.0, false)]);
+        // Coming from the other side, the ray enters where the carved out
+        // sphere ends.
+        assert_eq!(crossings(&csg, 5.0, -1.0), [(0.0, true), (-1.0, false)]);
+    }
+
+    #[test]
+    fn ray_starting_inside_only_exits() {
+        let csg = spheres(CsgOperation::Union);
+        assert_eq!(crossings(&csg, 0.5, 1.0), [(2.0, false)]);
+        let csg = spheres(CsgOperation::Difference);
+        assert_eq!(crossings(&csg, -0.5, 1.0), [(0.0, false)]);
+    }
+}
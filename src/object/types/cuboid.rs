@@ -0,0 +1,158 @@
+use glam::{vec2, Quat, Vec3};
+
+use crate::{
+    math::{random, Interval},
+    object::{
+        aabb::Aabb,
+        frame::{sorted_hits, Frame, LocalHit},
+        Object, ShapeVisitor, SurfaceSample,
+    },
+    rendering::{
+        material::Material,
+        ray::{HitRecord, Ray},
+    },
+};
+
+/// Solid box, either axis-aligned or rotated about its center. Each face is
+/// mapped to the whole 0..1 UV square.
+pub struct Cuboid<M: Material> {
+    frame: Frame,
+    half_size: Vec3,
+    material: M,
+    bbox: Aabb,
+}
+
+impl<M: Material> Cuboid<M> {
+    /// Axis-aligned box with opposite corners `a` and `b`.
+    pub fn new(a: Vec3, b: Vec3, material: M) -> Self {
+        Self::oriented((a + b) * 0.5, (b - a).abs(), Quat::IDENTITY, material)
+    }
+
+    /// Box of the given `size` centered on `center` and turned by `rotation`.
+    pub fn oriented(center: Vec3, size: Vec3, rotation: Quat, material: M) -> Self {
+        let frame = Frame::new(center, rotation);
+        let half_size = size * 0.5;
+        Self {
+            bbox: frame.bounds(&Aabb::from_points(-half_size, half_size)),
+            frame,
+            half_size,
+            material,
+        }
+    }
+
+    fn local_hit(&self, origin: Vec3, direction: Vec3, t: f32, axis: usize, sign: f32) -> LocalHit {
+        let point = origin + t * direction;
+        let mut outward_normal = Vec3::ZERO;
+        outward_normal[axis] = sign;
+
+        let (b, c) = ((axis + 1) % 3, (axis + 2) % 3);
+        LocalHit {
+            t,
+            outward_normal,
+            uv: vec2(
+                0.5 * (point[b] / self.half_size[b] + 1.0),
+                0.5 * (point[c] / self.half_size[c] + 1.0),
+            ),
+        }
+    }
+
+    /// Where a ray enters and leaves the box, found with the slab test.
+    fn local_hits(&self, ray: Ray) -> Vec<LocalHit> {
+        let ray = self.frame.ray_to_local(ray);
+        let inverse = ray.direction.recip();
+        let t0 = (-self.half_size - ray.origin) * inverse;
+        let t1 = (self.half_size - ray.origin) * inverse;
+        let near = t0.min(t1);
+        let far = t0.max(t1);
+
+        let entry_axis = (0..3).max_by(|&a, &b| near[a].total_cmp(&near[b])).unwrap();
+        let exit_axis = (0..3).min_by(|&a, &b| far[a].total_cmp(&far[b])).unwrap();
+        let (entry, exit) = (near[entry_axis], far[exit_axis]);
+        // Touching an edge or corner doesn't enter the box.
+        if entry >= exit {
+            return Vec::new();
+        }
+
+        vec![
+            self.local_hit(
+                ray.origin,
+                ray.direction,
+                entry,
+                entry_axis,
+                -ray.direction[entry_axis].signum(),
+            ),
+            self.local_hit(
+                ray.origin,
+                ray.direction,
+                exit,
+                exit_axis,
+                ray.direction[exit_axis].signum(),
+            ),
+        ]
+    }
+}
+
+impl<M: Material> Object for Cuboid<M> {
+    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord<'_>> {
+        let hit = sorted_hits(self.local_hits(ray), range)
+            .into_iter()
+            .next()?;
+        Some(self.frame.hit_record(self, ray, &hit, &self.material))
+    }
+
+    fn hit_all(&self, ray: Ray, range: &Interval) -> Vec<HitRecord<'_>> {
+        sorted_hits(self.local_hits(ray), range)
+            .iter()
+            .map(|hit| self.frame.hit_record(self, ray, hit, &self.material))
+            .collect()
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+
+    fn position(&self) -> Vec3 {
+        self.frame.origin()
+    }
+
+    fn material(&self) -> Option<&dyn Material> {
+        Some(&self.material)
+    }
+
+    fn area(&self) -> f32 {
+        let h = self.half_size;
+        8.0 * (h.x * h.y + h.y * h.z + h.z * h.x)
+    }
+
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        let h = self.half_size;
+        let face_areas = [h.y * h.z, h.z * h.x, h.x * h.y];
+        let mut target = random() * face_areas.iter().sum::<f32>();
+        let axis = face_areas
+            .iter()
+            .position(|&area| {
+                target -= area;
+                target < 0.0
+            })
+            .unwrap_or(2);
+        let sign = if random() < 0.5 { -1.0 } else { 1.0 };
+
+        let mut point = (Vec3::new(random(), random(), random()) * 2.0 - 1.0) * h;
+        point[axis] = sign * h[axis];
+        let mut normal = Vec3::ZERO;
+        normal[axis] = sign;
+
+        Some(SurfaceSample {
+            point: self.frame.point_to_world(point),
+            normal: self.frame.vector_to_world(normal),
+        })
+    }
+
+    fn visit_shape(&self, visitor: &mut dyn ShapeVisitor) {
+        visitor.cuboid(
+            self.frame.origin(),
+            self.half_size * 2.0,
+            self.frame.rotation(),
+        );
+    }
+}
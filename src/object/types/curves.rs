@@ -0,0 +1,386 @@
+use glam::{Quat, Vec3};
+use smallvec::{smallvec, SmallVec};
+
+use crate::{
+    math::{Interval, IntervalExt},
+    object::{
+        aabb::Aabb,
+        bvh::count_traversal,
+        curves::{evaluate_bezier, Curve},
+        Object,
+    },
+    rendering::{
+        material::Material,
+        ray::{HitRecord, Ray},
+    },
+};
+
+const LEAF_SIZE: usize = 4;
+/// Nodes split at the median, so a traversal stack this deep holds any
+/// tree of up to 2^32 segments without going to the heap.
+const STACK_SIZE: usize = 64;
+
+/// How the width of a curve is shown.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CurveShape {
+    /// Ribbon that always faces the ray, shaded as flat.
+    Flat,
+    /// Ribbon that always faces the ray, shaded as a round tube, which
+    /// looks the same for curves thinner than a pixel.
+    Round,
+}
+
+/// Piece of one of the curves, with the range of the curve's `u` it covers.
+struct Segment {
+    curve: Curve,
+    u: [f32; 2],
+}
+
+/// Node of the curves' own BVH, stored flat like a mesh's.
+struct CurveNode {
+    bounds: Aabb,
+    first: u32,
+    count: u32,
+}
+
+/// Closest crossing found so far, in ray space.
+struct CurveHit {
+    z: f32,
+    segment: usize,
+    /// Position along the segment, from 0 to 1.
+    u: f32,
+    v: f32,
+}
+
+/// Many curves sharing one material, such as the strands of hair or fur.
+/// U runs along each curve and V across its width.
+///
+/// Hits are found the way pbrt does: in a space where the ray runs along
+/// the z axis, curves are split until they are nearly straight, and the
+/// straight piece is tested for passing within half its width of the ray.
+pub struct Curves<M: Material> {
+    segments: Vec<Segment>,
+    shape: CurveShape,
+    material: M,
+    order: Vec<u32>,
+    nodes: Vec<CurveNode>,
+    bbox: Aabb,
+}
+
+impl<M: Material> Curves<M> {
+    pub fn new(curves: Vec<Curve>, shape: CurveShape, material: M) -> Self {
+        Self::with_splits(curves, shape, 2, material)
+    }
+
+    /// Cuts every curve into `2^split_depth` pieces up front, so long
+    /// bending curves get tight bounds in the BVH.
+    pub fn with_splits(
+        curves: Vec<Curve>,
+        shape: CurveShape,
+        split_depth: u32,
+        material: M,
+    ) -> Self {
+        let pieces = 1 << split_depth;
+        let segments: Vec<_> = curves
+            .iter()
+            .flat_map(|curve| {
+                (0..pieces).map(move |i| {
+                    let u = [i as f32 / pieces as f32, (i + 1) as f32 / pieces as f32];
+                    Segment {
+                        curve: curve.segment(u[0], u[1]),
+                        u,
+                    }
+                })
+            })
+            .collect();
+
+        let mut order: Vec<u32> = (0..segments.len() as u32).collect();
+        let mut nodes = Vec::new();
+        Self::build(&segments, &mut order, 0, &mut nodes);
+
+        Self {
+            bbox: nodes
+                .first()
+                .map_or(Aabb::empty(), |node| node.bounds.clone())
+                .pad(),
+            segments,
+            shape,
+            material,
+            order,
+            nodes,
+        }
+    }
+
+    fn build(segments: &[Segment], order: &mut [u32], offset: usize, nodes: &mut Vec<CurveNode>) {
+        let bounds = order.iter().fold(Aabb::empty(), |bbox, &segment| {
+            Aabb::from_boxes(&bbox, &segments[segment as usize].curve.bounds())
+        });
+
+        let index = nodes.len();
+        nodes.push(CurveNode {
+            bounds: bounds.clone(),
+            first: offset as u32,
+            count: order.len() as u32,
+        });
+        if order.len() <= LEAF_SIZE {
+            return;
+        }
+
+        let centroid = |segment: u32| {
+            let bounds = segments[segment as usize].curve.bounds();
+            (bounds.min() + bounds.max()) * 0.5
+        };
+        let axis = (0..3)
+            .max_by(|&a, &b| bounds.axis(a).size().total_cmp(&bounds.axis(b).size()))
+            .unwrap();
+        order.sort_by(|&a, &b| centroid(a)[axis].total_cmp(&centroid(b)[axis]));
+
+        let mid = order.len() / 2;
+        let (left, right) = order.split_at_mut(mid);
+        Self::build(segments, left, offset, nodes);
+        let right_index = nodes.len() as u32;
+        Self::build(segments, right, offset + mid, nodes);
+
+        nodes[index].first = right_index;
+        nodes[index].count = 0;
+    }
+
+    /// Tests one segment against the ray, given its control points in ray
+    /// space, keeping the hit if it is the closest so far.
+    fn intersect(
+        &self,
+        index: usize,
+        points: &[Vec3; 4],
+        z_range: &Interval,
+        best: &mut Option<CurveHit>,
+    ) {
+        let segment = &self.segments[index];
+
+        // Splitting in half quarters the curve's deviation from a straight
+        // line; stop once it is a small fraction of the width.
+        let deviation = (0..2)
+            .map(|i| {
+                (points[i] - 2.0 * points[i + 1] + points[i + 2])
+                    .abs()
+                    .max_element()
+            })
+            .fold(0.0, f32::max);
+        let epsilon = 0.05 * segment.curve.widths[0].max(segment.curve.widths[1]);
+        let depth = if deviation > 0.0 && epsilon > 0.0 {
+            ((std::f32::consts::SQRT_2 * 6.0 * deviation / (8.0 * epsilon)).log2() / 2.0)
+                .clamp(0.0, 10.0) as u32
+        } else {
+            0
+        };
+
+        self.intersect_recursive(
+            index,
+            points,
+            segment.curve.widths,
+            [0.0, 1.0],
+            z_range,
+            depth,
+            best,
+        );
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn intersect_recursive(
+        &self,
+        index: usize,
+        points: &[Vec3; 4],
+        widths: [f32; 2],
+        u: [f32; 2],
+        z_range: &Interval,
+        depth: u32,
+        best: &mut Option<CurveHit>,
+    ) {
+        let z_end = best.as_ref().map_or(z_range.end, |hit| hit.z);
+
+        // The ray is the z axis from `z_range.start` to `z_end`.
+        let half_width = 0.5 * widths[0].max(widths[1]);
+        let min = points.iter().fold(Vec3::MAX, |a, &b| a.min(b)) - half_width;
+        let max = points.iter().fold(Vec3::MIN, |a, &b| a.max(b)) + half_width;
+        if min.x > 0.0 || max.x < 0.0 || min.y > 0.0 || max.y < 0.0 {
+            return;
+        }
+        if max.z < z_range.start || min.z > z_end {
+            return;
+        }
+
+        if depth > 0 {
+            let [p0, p1, p2, p3] = *points;
+            let middle_width = 0.5 * (widths[0] + widths[1]);
+            let middle_u = 0.5 * (u[0] + u[1]);
+            let halves = [
+                [
+                    p0,
+                    (p0 + p1) * 0.5,
+                    (p0 + 2.0 * p1 + p2) * 0.25,
+                    (p0 + 3.0 * p1 + 3.0 * p2 + p3) * 0.125,
+                ],
+                [
+                    (p0 + 3.0 * p1 + 3.0 * p2 + p3) * 0.125,
+                    (p1 + 2.0 * p2 + p3) * 0.25,
+                    (p2 + p3) * 0.5,
+                    p3,
+                ],
+            ];
+            self.intersect_recursive(
+                index,
+                &halves[0],
+                [widths[0], middle_width],
+                [u[0], middle_u],
+                z_range,
+                depth - 1,
+                best,
+            );
+            self.intersect_recursive(
+                index,
+                &halves[1],
+                [middle_width, widths[1]],
+                [middle_u, u[1]],
+                z_range,
+                depth - 1,
+                best,
+            );
+            return;
+        }
+
+        // The ray has to pass between the lines through both ends that are
+        // perpendicular to the curve there.
+        let [p0, p1, p2, p3] = *points;
+        if (p1.y - p0.y) * -p0.y + p0.x * (p0.x - p1.x) < 0.0 {
+            return;
+        }
+        if (p2.y - p3.y) * -p3.y + p3.x * (p3.x - p2.x) < 0.0 {
+            return;
+        }
+
+        // Closest point to the ray along the straightened piece.
+        let direction = (p3 - p0).truncate();
+        let length_squared = direction.length_squared();
+        if length_squared == 0.0 {
+            return;
+        }
+        let w = (-p0.truncate()).dot(direction) / length_squared;
+        let local_u = w.clamp(0.0, 1.0);
+        let hit_width = widths[0] + (widths[1] - widths[0]) * local_u;
+
+        let (point, tangent) = evaluate_bezier(points, local_u);
+        let distance_squared = point.truncate().length_squared();
+        if distance_squared > hit_width * hit_width * 0.25 {
+            return;
+        }
+        if point.z < z_range.start || point.z > z_end {
+            return;
+        }
+        // A ray leaving the curve would find the ribbon again, turned to
+        // face it, right next to where it starts.
+        if point.length_squared() < hit_width * hit_width {
+            return;
+        }
+
+        // V is 0.5 on the ray's side of the middle of the curve and goes to
+        // 0 and 1 at its edges.
+        let offset = distance_squared.sqrt() / hit_width;
+        let side = tangent.x * -point.y + point.x * tangent.y;
+        let v = if side > 0.0 {
+            0.5 + offset
+        } else {
+            0.5 - offset
+        };
+
+        *best = Some(CurveHit {
+            z: point.z,
+            segment: index,
+            u: u[0] + (u[1] - u[0]) * local_u,
+            v,
+        });
+    }
+}
+
+impl<M: Material> Object for Curves<M> {
+    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord<'_>> {
+        let length = ray.direction.length();
+        let to_ray_space = Quat::from_rotation_arc(ray.direction / length, Vec3::Z);
+        let z_range = range.start * length..range.end * length;
+
+        let mut best = None;
+        let mut stack: SmallVec<[usize; STACK_SIZE]> = smallvec![0];
+        let (mut visits, mut tests) = (0, 0);
+        while let Some(index) = stack.pop() {
+            visits += 1;
+            let node = &self.nodes[index];
+            let closest = best
+                .as_ref()
+                .map_or(range.end, |hit: &CurveHit| hit.z / length);
+            if !node.bounds.hit(ray, &(range.start..closest)) {
+                continue;
+            }
+
+            if node.count == 0 {
+                stack.push(node.first as usize);
+                stack.push(index + 1);
+                continue;
+            }
+
+            tests += node.count;
+            let first = node.first as usize;
+            for &segment in &self.order[first..first + node.count as usize] {
+                let points = self.segments[segment as usize]
+                    .curve
+                    .points
+                    .map(|p| to_ray_space * (p - ray.origin));
+                self.intersect(segment as usize, &points, &z_range, &mut best);
+            }
+        }
+
+        count_traversal(visits, tests);
+
+        let hit = best?;
+        let segment = &self.segments[hit.segment];
+        let (_, tangent) = segment.curve.evaluate(hit.u);
+        let tangent = tangent.normalize();
+        let facing = -ray.direction / length;
+
+        let normal = match self.shape {
+            CurveShape::Flat => facing,
+            CurveShape::Round => {
+                // Turn the ribbon's normal around the curve as if it were a
+                // tube seen from across the width.
+                let across = facing.cross(tangent).normalize();
+                let angle = (hit.v - 0.5) * std::f32::consts::PI;
+                let across = Quat::from_axis_angle(tangent, angle) * across;
+                tangent.cross(across).normalize()
+            }
+        };
+
+        let t = hit.z / length;
+        Some(
+            HitRecord::new(
+                self,
+                ray,
+                ray.at(t),
+                normal,
+                t,
+                &self.material,
+                segment.u[0] + (segment.u[1] - segment.u[0]) * hit.u,
+                hit.v,
+            )
+            .with_tangent(tangent),
+        )
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+
+    fn position(&self) -> Vec3 {
+        (self.bbox.min() + self.bbox.max()) * 0.5
+    }
+
+    fn material(&self) -> Option<&dyn Material> {
+        Some(&self.material)
+    }
+}
@@ -0,0 +1,170 @@
+use std::f32::consts::PI;
+
+use glam::{vec2, vec3, Vec3};
+
+use crate::{
+    math::{random, solve_quadratic, Interval},
+    object::{
+        aabb::Aabb,
+        frame::{sorted_hits, Frame, LocalHit},
+        Object, ShapeVisitor, SurfaceSample,
+    },
+    rendering::{
+        material::Material,
+        ray::{HitRecord, Ray},
+    },
+};
+
+/// Cylinder between two points, closed with flat caps unless made `open`.
+/// Along the side U runs around the axis and V from base to top; each cap
+/// is mapped to the 0..1 UV square.
+pub struct Cylinder<M: Material> {
+    frame: Frame,
+    radius: f32,
+    height: f32,
+    capped: bool,
+    material: M,
+    bbox: Aabb,
+}
+
+impl<M: Material> Cylinder<M> {
+    pub fn new(base: Vec3, top: Vec3, radius: f32, material: M) -> Self {
+        let frame = Frame::with_z_axis(base, top - base);
+        let height = (top - base).length();
+        Self {
+            bbox: frame.bounds(&Aabb::from_points(
+                vec3(-radius, -radius, 0.0),
+                vec3(radius, radius, height),
+            )),
+            frame,
+            radius,
+            height,
+            capped: true,
+            material,
+        }
+    }
+
+    /// Leaves out the caps, making a tube.
+    pub fn open(mut self) -> Self {
+        self.capped = false;
+        self
+    }
+
+    fn local_hits(&self, ray: Ray) -> Vec<LocalHit> {
+        let Ray {
+            origin: o,
+            direction: d,
+            ..
+        } = self.frame.ray_to_local(ray);
+        let mut hits = Vec::new();
+
+        let a = d.x * d.x + d.y * d.y;
+        let b = 2.0 * (o.x * d.x + o.y * d.y);
+        let c = o.x * o.x + o.y * o.y - self.radius * self.radius;
+        // A ray running along the side touches without crossing it.
+        if let Some((near, far)) = solve_quadratic(a, b, c).filter(|(near, far)| near < far) {
+            for t in [near, far] {
+                let point = o + t * d;
+                if (0.0..=self.height).contains(&point.z) {
+                    hits.push(LocalHit {
+                        t,
+                        outward_normal: vec3(point.x, point.y, 0.0) / self.radius,
+                        uv: vec2(
+                            (point.y.atan2(point.x) + PI) / (2.0 * PI),
+                            point.z / self.height,
+                        ),
+                    });
+                }
+            }
+        }
+
+        if self.capped && d.z != 0.0 {
+            for (z, normal) in [(0.0, -Vec3::Z), (self.height, Vec3::Z)] {
+                let t = (z - o.z) / d.z;
+                let point = o + t * d;
+                if point.x * point.x + point.y * point.y <= self.radius * self.radius {
+                    hits.push(LocalHit {
+                        t,
+                        outward_normal: normal,
+                        uv: vec2(point.x, point.y) / (2.0 * self.radius) + 0.5,
+                    });
+                }
+            }
+        }
+
+        hits
+    }
+}
+
+impl<M: Material> Object for Cylinder<M> {
+    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord<'_>> {
+        let hit = sorted_hits(self.local_hits(ray), range)
+            .into_iter()
+            .next()?;
+        Some(self.frame.hit_record(self, ray, &hit, &self.material))
+    }
+
+    fn hit_all(&self, ray: Ray, range: &Interval) -> Vec<HitRecord<'_>> {
+        sorted_hits(self.local_hits(ray), range)
+            .iter()
+            .map(|hit| self.frame.hit_record(self, ray, hit, &self.material))
+            .collect()
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+
+    fn position(&self) -> Vec3 {
+        self.frame.point_to_world(vec3(0.0, 0.0, 0.5 * self.height))
+    }
+
+    fn material(&self) -> Option<&dyn Material> {
+        Some(&self.material)
+    }
+
+    fn area(&self) -> f32 {
+        let side = 2.0 * PI * self.radius * self.height;
+        if self.capped {
+            side + 2.0 * PI * self.radius * self.radius
+        } else {
+            side
+        }
+    }
+
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        let side = 2.0 * PI * self.radius * self.height;
+        let phi = 2.0 * PI * random();
+        let (sin, cos) = phi.sin_cos();
+
+        let (point, normal) = if random() * self.area() < side {
+            let normal = vec3(cos, sin, 0.0);
+            (
+                self.radius * normal + random() * self.height * Vec3::Z,
+                normal,
+            )
+        } else {
+            let r = self.radius * random().sqrt();
+            let (z, normal) = if random() < 0.5 {
+                (0.0, -Vec3::Z)
+            } else {
+                (self.height, Vec3::Z)
+            };
+            (vec3(r * cos, r * sin, z), normal)
+        };
+
+        Some(SurfaceSample {
+            point: self.frame.point_to_world(point),
+            normal: self.frame.vector_to_world(normal),
+        })
+    }
+
+    fn visit_shape(&self, visitor: &mut dyn ShapeVisitor) {
+        visitor.cylinder(
+            self.frame.origin(),
+            self.frame.point_to_world(Vec3::Z * self.height),
+            self.radius,
+            self.capped,
+        );
+    }
+}
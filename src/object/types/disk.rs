@@ -0,0 +1,115 @@
+use std::f32::consts::PI;
+
+use glam::{Quat, Vec3};
+
+use crate::{
+    math::{Interval, VecExt},
+    object::{aabb::Aabb, Object, ShapeVisitor, SurfaceSample},
+    rendering::{
+        material::Material,
+        ray::{HitRecord, Ray},
+    },
+};
+
+/// Flat disk, or an ellipse when stretched along `u` and `v`. U runs around
+/// the rim and V outwards from the center.
+pub struct Disk<M: Material> {
+    center: Vec3,
+    u: Vec3,
+    v: Vec3,
+    material: M,
+    bbox: Aabb,
+    normal: Vec3,
+    w_component: Vec3,
+}
+
+impl<M: Material> Disk<M> {
+    /// Round disk facing `normal`.
+    pub fn new(center: Vec3, normal: Vec3, radius: f32, material: M) -> Self {
+        let rotation = Quat::from_rotation_arc(Vec3::Z, normal.normalize());
+        Self::ellipse(
+            center,
+            rotation * Vec3::X * radius,
+            rotation * Vec3::Y * radius,
+            material,
+        )
+    }
+
+    /// Ellipse spanned by the semi-axes `u` and `v`, facing `u × v`.
+    pub fn ellipse(center: Vec3, u: Vec3, v: Vec3, material: M) -> Self {
+        let n = u.cross(v);
+        let extent = (u * u + v * v).powf(0.5);
+        Self {
+            center,
+            u,
+            v,
+            material,
+            bbox: Aabb::from_points(center - extent, center + extent).pad(),
+            normal: n.normalize(),
+            w_component: n / n.dot(n),
+        }
+    }
+}
+
+impl<M: Material> Object for Disk<M> {
+    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord<'_>> {
+        let denominator = self.normal.dot(ray.direction);
+        if denominator.abs() < 1e-8_f32 {
+            return None;
+        }
+
+        let t = self.normal.dot(self.center - ray.origin) / denominator;
+        if !range.contains(&t) {
+            return None;
+        }
+
+        let intersection = ray.at(t);
+        let hit_vector = intersection - self.center;
+        let alpha = self.w_component.dot(hit_vector.cross(self.v));
+        let beta = self.w_component.dot(self.u.cross(hit_vector));
+
+        let radius = alpha.hypot(beta);
+        if radius > 1.0 {
+            return None;
+        }
+
+        Some(HitRecord::new(
+            self,
+            ray,
+            intersection,
+            self.normal,
+            t,
+            &self.material,
+            (beta.atan2(alpha) + PI) / (2.0 * PI),
+            radius,
+        ))
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+
+    fn position(&self) -> Vec3 {
+        self.center
+    }
+
+    fn material(&self) -> Option<&dyn Material> {
+        Some(&self.material)
+    }
+
+    fn area(&self) -> f32 {
+        PI * self.u.cross(self.v).length()
+    }
+
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        let offset = Vec3::random_in_unit_disk();
+        Some(SurfaceSample {
+            point: self.center + offset.x * self.u + offset.y * self.v,
+            normal: self.normal,
+        })
+    }
+
+    fn visit_shape(&self, visitor: &mut dyn ShapeVisitor) {
+        visitor.disk(self.center, self.u, self.v);
+    }
+}
@@ -0,0 +1,316 @@
+use glam::{vec2, Vec3};
+use smallvec::{smallvec, SmallVec};
+
+use crate::{
+    math::{random, Interval, IntervalExt},
+    object::{
+        aabb::Aabb, bvh::count_traversal, mesh::MeshData, Object, ShapeVisitor, SurfaceSample,
+    },
+    rendering::{
+        material::Material,
+        ray::{Color, HitRecord, Ray},
+    },
+};
+
+const LEAF_SIZE: usize = 4;
+/// Nodes split at the median, so a traversal stack this deep holds any
+/// tree of up to 2^32 triangles without going to the heap.
+const STACK_SIZE: usize = 64;
+
+/// Node of the mesh's own BVH, stored flat. Leaves cover `count` triangles
+/// starting at `first` in the mesh's triangle order; inner nodes have a
+/// `count` of zero, their left child right after them and the right child
+/// at `first`.
+struct MeshNode {
+    /// Bounds for every segment between two motion keys (one if static).
+    bounds: Vec<Aabb>,
+    first: u32,
+    count: u32,
+}
+
+/// Triangle mesh, optionally deforming: its motion keys are spread evenly
+/// over `times` and vertices move linearly between them.
+pub struct TriangleMesh<M: Material> {
+    data: MeshData,
+    times: Interval,
+    material: M,
+    order: Vec<u32>,
+    nodes: Vec<MeshNode>,
+    /// Running total of triangle areas at the first motion key, for picking
+    /// triangles in proportion to their size.
+    cumulative_areas: Vec<f32>,
+    bbox: Aabb,
+}
+
+impl<M: Material> TriangleMesh<M> {
+    pub fn new(data: MeshData, material: M) -> Self {
+        let mut order: Vec<u32> = (0..data.triangle_count() as u32).collect();
+        let mut nodes = Vec::new();
+        Self::build(&data, &mut order, 0, &mut nodes);
+
+        let cumulative_areas = (0..data.triangle_count())
+            .scan(0.0, |total, triangle| {
+                let [p0, p1, p2] = data.triangles[triangle].map(|i| data.positions[0][i as usize]);
+                *total += 0.5 * (p1 - p0).cross(p2 - p0).length();
+                Some(*total)
+            })
+            .collect();
+
+        Self {
+            cumulative_areas,
+            bbox: data.bounds().pad(),
+            data,
+            times: 0.0..1.0,
+            material,
+            order,
+            nodes,
+        }
+    }
+
+    fn build(data: &MeshData, order: &mut [u32], offset: usize, nodes: &mut Vec<MeshNode>) {
+        let segments = (data.motion_keys() - 1).max(1);
+        let keys = |segment: usize| [segment, (segment + 1).min(data.motion_keys() - 1)];
+        let bounds = (0..segments)
+            .map(|segment| {
+                order.iter().fold(Aabb::empty(), |bbox, &triangle| {
+                    Aabb::from_boxes(
+                        &bbox,
+                        &data.triangle_bounds(triangle as usize, keys(segment)),
+                    )
+                })
+            })
+            .collect();
+
+        let index = nodes.len();
+        nodes.push(MeshNode {
+            bounds,
+            first: offset as u32,
+            count: order.len() as u32,
+        });
+        if order.len() <= LEAF_SIZE {
+            return;
+        }
+
+        let centroid = |triangle: u32| {
+            data.triangles[triangle as usize]
+                .iter()
+                .map(|&i| data.positions[0][i as usize])
+                .sum::<Vec3>()
+                / 3.0
+        };
+        let centroids = order.iter().fold(Aabb::empty(), |bbox, &triangle| {
+            let c = centroid(triangle);
+            Aabb::from_boxes(&bbox, &Aabb::from_points(c, c))
+        });
+        let axis = (0..3)
+            .max_by(|&a, &b| {
+                centroids
+                    .axis(a)
+                    .size()
+                    .total_cmp(&centroids.axis(b).size())
+            })
+            .unwrap();
+        order.sort_by(|&a, &b| centroid(a)[axis].total_cmp(&centroid(b)[axis]));
+
+        let mid = order.len() / 2;
+        let (left, right) = order.split_at_mut(mid);
+        Self::build(data, left, offset, nodes);
+        let right_index = nodes.len() as u32;
+        Self::build(data, right, offset + mid, nodes);
+
+        nodes[index].first = right_index;
+        nodes[index].count = 0;
+    }
+
+    /// Motion segment and position within it at `time`.
+    fn key_position(&self, time: f32) -> (usize, f32) {
+        let keys = self.data.motion_keys();
+        if keys == 1 {
+            return (0, 0.0);
+        }
+
+        let position =
+            ((time - self.times.start) / self.times.size()).clamp(0.0, 1.0) * (keys - 1) as f32;
+        let segment = (position as usize).min(keys - 2);
+        (segment, position - segment as f32)
+    }
+
+    fn vertex(&self, index: u32, segment: usize, fraction: f32) -> Vec3 {
+        let a = self.data.positions[segment][index as usize];
+        if self.data.motion_keys() == 1 {
+            a
+        } else {
+            a.lerp(self.data.positions[segment + 1][index as usize], fraction)
+        }
+    }
+
+    /// Möller-Trumbore intersection, returning `t` and the barycentric
+    /// coordinates of the second and third vertex.
+    fn intersect(
+        &self,
+        triangle: u32,
+        ray: Ray,
+        range: &Interval,
+        segment: usize,
+        fraction: f32,
+    ) -> Option<(f32, f32, f32)> {
+        let [i0, i1, i2] = self.data.triangles[triangle as usize];
+        let p0 = self.vertex(i0, segment, fraction);
+        let e1 = self.vertex(i1, segment, fraction) - p0;
+        let e2 = self.vertex(i2, segment, fraction) - p0;
+
+        let p = ray.direction.cross(e2);
+        let determinant = e1.dot(p);
+        if determinant.abs() < 1e-12 {
+            return None;
+        }
+        let inverse_determinant = 1.0 / determinant;
+
+        let s = ray.origin - p0;
+        let b1 = s.dot(p) * inverse_determinant;
+        if !(0.0..=1.0).contains(&b1) {
+            return None;
+        }
+
+        let q = s.cross(e1);
+        let b2 = ray.direction.dot(q) * inverse_determinant;
+        if b2 < 0.0 || b1 + b2 > 1.0 {
+            return None;
+        }
+
+        let t = e2.dot(q) * inverse_determinant;
+        range.contains(&t).then_some((t, b1, b2))
+    }
+}
+
+impl<M: Material> Object for TriangleMesh<M> {
+    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord<'_>> {
+        let (segment, fraction) = self.key_position(ray.time);
+
+        let mut closest = range.end;
+        let mut best = None;
+        let mut stack: SmallVec<[usize; STACK_SIZE]> = smallvec![0];
+
+        let (mut visits, mut tests) = (0, 0);
+        while let Some(index) = stack.pop() {
+            visits += 1;
+            let node = &self.nodes[index];
+            let bounds = &node.bounds[segment.min(node.bounds.len() - 1)];
+            if !bounds.hit(ray, &(range.start..closest)) {
+                continue;
+            }
+
+            if node.count == 0 {
+                stack.push(node.first as usize);
+                stack.push(index + 1);
+                continue;
+            }
+
+            tests += node.count;
+            let first = node.first as usize;
+            for &triangle in &self.order[first..first + node.count as usize] {
+                if let Some((t, b1, b2)) =
+                    self.intersect(triangle, ray, &(range.start..closest), segment, fraction)
+                {
+                    closest = t;
+                    best = Some((triangle, t, b1, b2));
+                }
+            }
+        }
+
+        count_traversal(visits, tests);
+
+        let (triangle, t, b1, b2) = best?;
+        let [i0, i1, i2] = self.data.triangles[triangle as usize];
+        let b0 = 1.0 - b1 - b2;
+        let p0 = self.vertex(i0, segment, fraction);
+        let p1 = self.vertex(i1, segment, fraction);
+        let p2 = self.vertex(i2, segment, fraction);
+        let face_normal = (p1 - p0).cross(p2 - p0);
+
+        // Shading normals come from the first motion key.
+        let normal = match &self.data.normals {
+            Some(normals) => {
+                b0 * normals[i0 as usize] + b1 * normals[i1 as usize] + b2 * normals[i2 as usize]
+            }
+            None => face_normal,
+        }
+        .normalize();
+
+        // Each barycentric coordinate times the height over the opposite
+        // edge is the distance to that edge.
+        let twice_area = face_normal.length();
+        let edge_distance = (b0 * twice_area / (p2 - p1).length())
+            .min(b1 * twice_area / (p0 - p2).length())
+            .min(b2 * twice_area / (p1 - p0).length());
+
+        let uv = match &self.data.uvs {
+            Some(uvs) => b0 * uvs[i0 as usize] + b1 * uvs[i1 as usize] + b2 * uvs[i2 as usize],
+            None => vec2(b1, b2),
+        };
+
+        let record = HitRecord::new(self, ray, ray.at(t), normal, t, &self.material, uv.x, uv.y)
+            .with_edge_distance(edge_distance);
+        Some(match &self.data.colors {
+            Some(colors) => {
+                let [c0, c1, c2] = [i0, i1, i2].map(|i| colors[i as usize].0);
+                record.with_color(Color(b0 * c0 + b1 * c1 + b2 * c2))
+            }
+            None => record,
+        })
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+
+    fn position(&self) -> Vec3 {
+        self.data.positions[0].iter().sum::<Vec3>() / self.data.vertex_count() as f32
+    }
+
+    fn material(&self) -> Option<&dyn Material> {
+        Some(&self.material)
+    }
+
+    fn area(&self) -> f32 {
+        self.cumulative_areas.last().copied().unwrap_or(0.0)
+    }
+
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        let target = random() * self.area();
+        let triangle = self
+            .cumulative_areas
+            .partition_point(|&total| total < target)
+            .min(self.cumulative_areas.len().checked_sub(1)?);
+        let [p0, p1, p2] =
+            self.data.triangles[triangle].map(|i| self.data.positions[0][i as usize]);
+
+        // Folding the unit square onto the triangle keeps points uniform.
+        let (mut b1, mut b2) = (random(), random());
+        if b1 + b2 > 1.0 {
+            (b1, b2) = (1.0 - b1, 1.0 - b2);
+        }
+        Some(SurfaceSample {
+            point: p0 + b1 * (p1 - p0) + b2 * (p2 - p0),
+            normal: (p1 - p0).cross(p2 - p0).normalize(),
+        })
+    }
+
+    fn motion_range(&self) -> Option<Interval> {
+        (self.data.motion_keys() > 1).then(|| self.times.clone())
+    }
+
+    fn bounding_box_over(&self, times: &Interval) -> Aabb {
+        let root = &self.nodes[0].bounds;
+        let (first, _) = self.key_position(times.start);
+        let (last, _) = self.key_position(times.end);
+        root[first.min(root.len() - 1)..=last.min(root.len() - 1)]
+            .iter()
+            .fold(Aabb::empty(), |a, b| Aabb::from_boxes(&a, b))
+            .pad()
+    }
+
+    fn visit_shape(&self, visitor: &mut dyn ShapeVisitor) {
+        visitor.mesh(&self.data);
+    }
+}
@@ -1,5 +1,27 @@
+pub mod animated;
+pub mod cone;
+pub mod csg;
+pub mod cuboid;
+pub mod curves;
+pub mod cylinder;
+pub mod disk;
+pub mod mesh;
 pub mod quad;
+pub mod sdf;
 pub mod sphere;
+pub mod sphere_cloud;
+pub mod torus;
 
+pub use animated::AnimatedObject;
+pub use cone::Cone;
+pub use csg::Csg;
+pub use cuboid::Cuboid;
+pub use curves::{CurveShape, Curves};
+pub use cylinder::Cylinder;
+pub use disk::Disk;
+pub use mesh::TriangleMesh;
 pub use quad::Quad;
+pub use sdf::SdfObject;
 pub use sphere::Sphere;
+pub use sphere_cloud::SphereCloud;
+pub use torus::Torus;
@@ -1,8 +1,8 @@
 use glam::Vec3;
 
 use crate::{
-    math::Interval,
-    object::{aabb::Aabb, Object},
+    math::{random, Interval},
+    object::{aabb::Aabb, Object, ShapeVisitor, SurfaceSample},
     rendering::{
         material::Material,
         ray::{HitRecord, Ray},
@@ -38,7 +38,7 @@ impl<M: Material> Quad<M> {
 }
 
 impl<M: Material> Object for Quad<M> {
-    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord> {
+    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord<'_>> {
         let denominator = self.normal.dot(ray.direction);
 
         if denominator.abs() < 1e-8_f32 {
@@ -79,4 +79,23 @@ impl<M: Material> Object for Quad<M> {
     fn position(&self) -> glam::Vec3 {
         self.origin + (self.u * 0.5) + (self.v * 0.5)
     }
+
+    fn material(&self) -> Option<&dyn Material> {
+        Some(&self.material)
+    }
+
+    fn area(&self) -> f32 {
+        self.u.cross(self.v).length()
+    }
+
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        Some(SurfaceSample {
+            point: self.origin + random() * self.u + random() * self.v,
+            normal: self.normal,
+        })
+    }
+
+    fn visit_shape(&self, visitor: &mut dyn ShapeVisitor) {
+        visitor.quad(self.origin, self.u, self.v);
+    }
 }
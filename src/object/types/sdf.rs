@@ -0,0 +1,130 @@
+use std::f32::consts::PI;
+
+use glam::{vec3, Vec3};
+
+use crate::{
+    math::Interval,
+    object::{aabb::Aabb, sdf::Sdf, Object},
+    rendering::{
+        material::Material,
+        ray::{HitRecord, Ray},
+    },
+};
+
+/// Surface of a signed distance function, found by sphere tracing: stepping
+/// along the ray by the distance to the surface until it is close enough.
+/// UVs come from the direction of the normal, as on a sphere.
+pub struct SdfObject<S: Sdf, M: Material> {
+    sdf: S,
+    material: M,
+    bbox: Aabb,
+    /// Distance to the surface that counts as a hit.
+    epsilon: f32,
+    max_steps: u32,
+}
+
+impl<S: Sdf, M: Material> SdfObject<S, M> {
+    pub fn new(sdf: S, material: M) -> Self {
+        let bounds = sdf.bounds();
+        let size = (bounds.max() - bounds.min()).max_element();
+        // Empty bounds, as of an intersection of shapes apart, stay empty.
+        let bbox = if size >= 0.0 {
+            bounds.grow(1e-3 * size).pad()
+        } else {
+            bounds
+        };
+        Self {
+            bbox,
+            sdf,
+            material,
+            epsilon: 1e-5 * size.max(1e-3),
+            max_steps: 512,
+        }
+    }
+
+    /// Sets the hit distance, in scene units. Smaller values resolve finer
+    /// detail at the cost of more steps.
+    pub fn with_epsilon(mut self, epsilon: f32) -> Self {
+        self.epsilon = epsilon;
+        self
+    }
+
+    pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Gradient of the distance by central differences on a tetrahedron.
+    fn normal(&self, point: Vec3) -> Vec3 {
+        let h = self.epsilon;
+        [
+            vec3(1.0, -1.0, -1.0),
+            vec3(-1.0, -1.0, 1.0),
+            vec3(-1.0, 1.0, -1.0),
+            vec3(1.0, 1.0, 1.0),
+        ]
+        .into_iter()
+        .map(|k| k * self.sdf.distance(point + k * h))
+        .sum::<Vec3>()
+        .normalize_or_zero()
+    }
+}
+
+impl<S: Sdf, M: Material> Object for SdfObject<S, M> {
+    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord<'_>> {
+        let range = self.bbox.hit_range(ray, range)?;
+        let speed = ray.direction.length();
+        let distance = |t: f32| self.sdf.distance(ray.at(t));
+
+        // Step off the surface first, where a ray leaving it starts.
+        let mut t = range.start;
+        let mut d = distance(t);
+        let mut step = 2.0 * self.epsilon / speed;
+        while d.abs() < self.epsilon && t < range.end {
+            t += step;
+            step *= 2.0;
+            d = distance(t);
+        }
+        // Rays starting inside march towards the surface from within.
+        let side = d.signum();
+
+        for _ in 0..self.max_steps {
+            if t > range.end {
+                return None;
+            }
+
+            let d = side * distance(t);
+            if d < self.epsilon {
+                let point = ray.at(t);
+                let normal = self.normal(point);
+                let u = ((-normal.z).atan2(normal.x) + PI) / (2.0 * PI);
+                let v = (-normal.y).clamp(-1.0, 1.0).acos() / PI;
+                return Some(HitRecord::new(
+                    self,
+                    ray,
+                    point,
+                    normal,
+                    t,
+                    &self.material,
+                    u,
+                    v,
+                ));
+            }
+            t += d / speed;
+        }
+
+        None
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+
+    fn position(&self) -> Vec3 {
+        (self.bbox.min() + self.bbox.max()) * 0.5
+    }
+
+    fn material(&self) -> Option<&dyn Material> {
+        Some(&self.material)
+    }
+}
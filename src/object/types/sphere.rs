@@ -3,8 +3,8 @@ use std::f32::consts::PI;
 use glam::{vec3, Vec3};
 
 use crate::{
-    math::Interval,
-    object::{aabb::Aabb, Object},
+    math::{Interval, VecExt},
+    object::{aabb::Aabb, Object, ShapeVisitor, SurfaceSample},
     rendering::{
         material::Material,
         ray::{HitRecord, Ray},
@@ -45,21 +45,17 @@ impl<M: Material> Sphere<M> {
     }
 
     fn center(&self, time: f32) -> Vec3 {
-        self.start_center + time * self.movement_vector
+        self.start_center + time.clamp(0.0, 1.0) * self.movement_vector
     }
 
-    fn get_uv(&self, point: Vec3) -> (f32, f32) {
-        let theta = (-point.y).acos();
-        let phi = (-point.z).atan2(point.x) + PI;
-        let u = phi / (2.0 * PI);
-        let v = theta / PI;
-
-        (u, v)
+    fn bounding_box_at(&self, time: f32) -> Aabb {
+        let rbox = vec3(self.radius, self.radius, self.radius);
+        let center = self.center(time);
+        Aabb::from_points(center - rbox, center + rbox)
     }
-}
 
-impl<M: Material> Object for Sphere<M> {
-    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord> {
+    /// Distances along `ray` at which it enters and leaves the sphere.
+    fn roots(&self, ray: Ray) -> Option<(f32, f32)> {
         let oc = ray.origin - self.center(ray.time);
         let a = ray.direction.length_squared();
         let half_b = oc.dot(ray.direction);
@@ -72,29 +68,44 @@ impl<M: Material> Object for Sphere<M> {
         }
 
         let sqrtd = discriminant.sqrt();
+        Some(((-half_b - sqrtd) / a, (-half_b + sqrtd) / a))
+    }
 
-        let mut root = (-half_b - sqrtd) / a;
-        if !range.contains(&root) {
-            root = (-half_b + sqrtd) / a;
-            if !range.contains(&root) {
-                return None;
-            }
-        }
-
+    fn record(&self, ray: Ray, root: f32) -> HitRecord<'_> {
         let point = ray.at(root);
         let normal = (point - self.center(ray.time)) / self.radius;
         let (u, v) = self.get_uv(normal);
 
-        Some(HitRecord::new(
-            self,
-            ray,
-            point,
-            normal,
-            root,
-            &self.material,
-            u,
-            v,
-        ))
+        HitRecord::new(self, ray, point, normal, root, &self.material, u, v)
+    }
+
+    fn get_uv(&self, point: Vec3) -> (f32, f32) {
+        let theta = (-point.y).acos();
+        let phi = (-point.z).atan2(point.x) + PI;
+        let u = phi / (2.0 * PI);
+        let v = theta / PI;
+
+        (u, v)
+    }
+}
+
+impl<M: Material> Object for Sphere<M> {
+    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord<'_>> {
+        let (near, far) = self.roots(ray)?;
+        let root = [near, far].into_iter().find(|root| range.contains(root))?;
+        Some(self.record(ray, root))
+    }
+
+    fn hit_all(&self, ray: Ray, range: &Interval) -> Vec<HitRecord<'_>> {
+        // A grazing ray touches without entering.
+        let Some((near, far)) = self.roots(ray).filter(|(near, far)| near < far) else {
+            return Vec::new();
+        };
+        [near, far]
+            .into_iter()
+            .filter(|root| range.contains(root))
+            .map(|root| self.record(ray, root))
+            .collect()
     }
 
     fn bounding_box(&self) -> &Aabb {
@@ -104,4 +115,39 @@ impl<M: Material> Object for Sphere<M> {
     fn position(&self) -> glam::Vec3 {
         self.start_center
     }
+
+    fn material(&self) -> Option<&dyn Material> {
+        Some(&self.material)
+    }
+
+    fn area(&self) -> f32 {
+        4.0 * PI * self.radius * self.radius
+    }
+
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        let normal = Vec3::random_unit();
+        Some(SurfaceSample {
+            point: self.center(0.0) + self.radius * normal,
+            normal,
+        })
+    }
+
+    fn motion_range(&self) -> Option<Interval> {
+        if self.movement_vector == Vec3::ZERO {
+            None
+        } else {
+            Some(0.0..1.0)
+        }
+    }
+
+    fn bounding_box_over(&self, times: &Interval) -> Aabb {
+        Aabb::from_boxes(
+            &self.bounding_box_at(times.start),
+            &self.bounding_box_at(times.end),
+        )
+    }
+
+    fn visit_shape(&self, visitor: &mut dyn ShapeVisitor) {
+        visitor.sphere(self.start_center, self.radius, self.movement_vector);
+    }
 }
@@ -0,0 +1,284 @@
+use std::f32::consts::PI;
+
+use glam::Vec3;
+use smallvec::{smallvec, SmallVec};
+
+use crate::{
+    math::{random, Interval, IntervalExt, VecExt},
+    object::{
+        aabb::Aabb, bvh::count_traversal, load::LoadError, points::PointCloud, Object,
+        SurfaceSample,
+    },
+    rendering::{
+        material::Material,
+        ray::{HitRecord, Ray},
+    },
+};
+
+const LEAF_SIZE: usize = 4;
+/// Nodes split at the median, so a traversal stack this deep holds any
+/// tree of up to 2^32 points without going to the heap.
+const STACK_SIZE: usize = 64;
+
+/// Node of the cloud's own BVH, laid out like `TriangleMesh`'s: leaves cover
+/// `count` points from `first` in the cloud's point order, and inner nodes
+/// have a `count` of zero, their left child next and the right at `first`.
+struct CloudNode {
+    bounds: Aabb,
+    first: u32,
+    count: u32,
+}
+
+/// Many spheres as a single object, for particle systems and point clouds
+/// far too large to add one `Sphere` at a time. Each point picks its
+/// material from the cloud's list by index, and a point color reaches
+/// textures that read it such as `VertexColor`.
+pub struct SphereCloud<M: Material> {
+    points: PointCloud,
+    materials: Vec<M>,
+    order: Vec<u32>,
+    nodes: Vec<CloudNode>,
+    /// Running total of sphere areas, for picking points in proportion to
+    /// their size.
+    cumulative_areas: Vec<f32>,
+    bbox: Aabb,
+}
+
+impl<M: Material> SphereCloud<M> {
+    /// Fails if any point has a material index other than zero.
+    pub fn new(points: PointCloud, material: M) -> Result<Self, LoadError> {
+        Self::with_materials(points, vec![material])
+    }
+
+    /// Cloud whose points pick one of `materials` by their material index,
+    /// or the first if they have none. Fails if an index is past the end of
+    /// `materials`.
+    pub fn with_materials(points: PointCloud, materials: Vec<M>) -> Result<Self, LoadError> {
+        assert!(!materials.is_empty());
+        if let Some(indices) = &points.materials {
+            if let Some((point, index)) = indices
+                .iter()
+                .enumerate()
+                .find(|(_, &i)| i as usize >= materials.len())
+            {
+                return Err(LoadError::Data(format!(
+                    "point {point} uses material {index} of {}",
+                    materials.len()
+                )));
+            }
+        }
+
+        let mut order: Vec<u32> = (0..points.len() as u32).collect();
+        let mut nodes = Vec::new();
+        if !points.is_empty() {
+            Self::build(&points, &mut order, 0, &mut nodes);
+        }
+
+        let cumulative_areas = points
+            .radii
+            .iter()
+            .scan(0.0, |total, radius| {
+                *total += 4.0 * PI * radius * radius;
+                Some(*total)
+            })
+            .collect();
+
+        Ok(Self {
+            bbox: nodes
+                .first()
+                .map_or_else(Aabb::empty, |root: &CloudNode| root.bounds.pad()),
+            points,
+            materials,
+            order,
+            nodes,
+            cumulative_areas,
+        })
+    }
+
+    fn build(points: &PointCloud, order: &mut [u32], offset: usize, nodes: &mut Vec<CloudNode>) {
+        let bounds = order.iter().fold(Aabb::empty(), |bbox, &point| {
+            Aabb::from_boxes(&bbox, &points.point_bounds(point as usize))
+        });
+
+        let index = nodes.len();
+        nodes.push(CloudNode {
+            bounds,
+            first: offset as u32,
+            count: order.len() as u32,
+        });
+        if order.len() <= LEAF_SIZE {
+            return;
+        }
+
+        let position = |point: u32| points.positions[point as usize];
+        let centers = order.iter().fold(Aabb::empty(), |bbox, &point| {
+            Aabb::from_boxes(&bbox, &Aabb::from_points(position(point), position(point)))
+        });
+        let axis = (0..3)
+            .max_by(|&a, &b| centers.axis(a).size().total_cmp(&centers.axis(b).size()))
+            .unwrap();
+
+        // Only the split matters, so a partial sort around the middle will do.
+        let mid = order.len() / 2;
+        order.select_nth_unstable_by(mid, |&a, &b| {
+            position(a)[axis].total_cmp(&position(b)[axis])
+        });
+
+        let (left, right) = order.split_at_mut(mid);
+        Self::build(points, left, offset, nodes);
+        let right_index = nodes.len() as u32;
+        Self::build(points, right, offset + mid, nodes);
+
+        nodes[index].first = right_index;
+        nodes[index].count = 0;
+    }
+
+    /// Nearest distance along `ray` within `range` at which it meets the
+    /// sphere of `point`.
+    fn intersect(&self, point: u32, ray: Ray, range: &Interval) -> Option<f32> {
+        let center = self.points.positions[point as usize];
+        let radius = self.points.radii[point as usize];
+
+        let oc = ray.origin - center;
+        let a = ray.direction.length_squared();
+        let half_b = oc.dot(ray.direction);
+        let c = oc.length_squared() - radius * radius;
+        let discriminant = half_b * half_b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let sqrtd = discriminant.sqrt();
+        [(-half_b - sqrtd) / a, (-half_b + sqrtd) / a]
+            .into_iter()
+            .find(|root| range.contains(root))
+    }
+
+    fn material_of(&self, point: usize) -> &M {
+        let index = self
+            .points
+            .materials
+            .as_ref()
+            .map_or(0, |materials| materials[point]);
+        &self.materials[index as usize]
+    }
+}
+
+impl<M: Material> Object for SphereCloud<M> {
+    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord<'_>> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let mut closest = range.end;
+        let mut best = None;
+        let mut stack: SmallVec<[usize; STACK_SIZE]> = smallvec![0];
+
+        let (mut visits, mut tests) = (0, 0);
+        while let Some(index) = stack.pop() {
+            visits += 1;
+            let node = &self.nodes[index];
+            if !node.bounds.hit(ray, &(range.start..closest)) {
+                continue;
+            }
+
+            if node.count == 0 {
+                stack.push(node.first as usize);
+                stack.push(index + 1);
+                continue;
+            }
+
+            tests += node.count;
+            let first = node.first as usize;
+            for &point in &self.order[first..first + node.count as usize] {
+                if let Some(t) = self.intersect(point, ray, &(range.start..closest)) {
+                    closest = t;
+                    best = Some((point as usize, t));
+                }
+            }
+        }
+
+        count_traversal(visits, tests);
+
+        let (point, t) = best?;
+        let hit_point = ray.at(t);
+        let normal = (hit_point - self.points.positions[point]) / self.points.radii[point];
+
+        // Same mapping as `Sphere`.
+        let u = ((-normal.z).atan2(normal.x) + PI) / (2.0 * PI);
+        let v = (-normal.y).acos() / PI;
+
+        let record = HitRecord::new(
+            self,
+            ray,
+            hit_point,
+            normal,
+            t,
+            self.material_of(point),
+            u,
+            v,
+        );
+        Some(match &self.points.colors {
+            Some(colors) => record.with_color(colors[point]),
+            None => record,
+        })
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+
+    fn position(&self) -> Vec3 {
+        self.points.positions.iter().sum::<Vec3>() / self.points.len().max(1) as f32
+    }
+
+    fn material(&self) -> Option<&dyn Material> {
+        match self.materials.as_slice() {
+            [material] => Some(material),
+            _ => None,
+        }
+    }
+
+    fn area(&self) -> f32 {
+        self.cumulative_areas.last().copied().unwrap_or(0.0)
+    }
+
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        let target = random() * self.area();
+        let point = self
+            .cumulative_areas
+            .partition_point(|&total| total < target)
+            .min(self.cumulative_areas.len().checked_sub(1)?);
+
+        let normal = Vec3::random_unit();
+        Some(SurfaceSample {
+            point: self.points.positions[point] + self.points.radii[point] * normal,
+            normal,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rendering::{material::Lambertian, ray::Color};
+
+    use super::*;
+
+    #[test]
+    fn refuses_material_indices_past_the_list() {
+        let points = PointCloud::new(vec![Vec3::ZERO, Vec3::X], 0.5).with_materials(vec![0, 1]);
+        let gray = || Lambertian::solid_color(Color::new(0.5, 0.5, 0.5));
+        assert!(SphereCloud::new(points.clone(), gray()).is_err());
+        assert!(SphereCloud::with_materials(points, vec![gray(), gray()]).is_ok());
+    }
+
+    #[test]
+    fn has_a_single_material_only_with_one() {
+        let points = PointCloud::new(vec![Vec3::ZERO], 0.5);
+        let gray = || Lambertian::solid_color(Color::new(0.5, 0.5, 0.5));
+        let single = SphereCloud::new(points.clone(), gray()).unwrap();
+        assert!(single.material().is_some());
+        let several = SphereCloud::with_materials(points, vec![gray(), gray()]).unwrap();
+        assert!(several.material().is_none());
+    }
+}
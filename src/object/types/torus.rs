@@ -0,0 +1,215 @@
+use std::f32::consts::PI;
+
+use glam::{vec2, vec3, Vec3};
+
+use crate::{
+    math::{polynomial_roots, random, Interval},
+    object::{
+        aabb::Aabb,
+        frame::{sorted_hits, Frame, LocalHit},
+        Object, ShapeVisitor, SurfaceSample,
+    },
+    rendering::{
+        material::Material,
+        ray::{HitRecord, Ray},
+    },
+};
+
+/// Ring around `axis`: a tube of `minor_radius` swept along a circle of
+/// `major_radius`. U runs around the axis and V around the tube.
+pub struct Torus<M: Material> {
+    frame: Frame,
+    major_radius: f32,
+    minor_radius: f32,
+    material: M,
+    bbox: Aabb,
+}
+
+impl<M: Material> Torus<M> {
+    pub fn new(
+        center: Vec3,
+        axis: Vec3,
+        major_radius: f32,
+        minor_radius: f32,
+        material: M,
+    ) -> Self {
+        let frame = Frame::with_z_axis(center, axis);
+        Self {
+            bbox: frame.bounds(&Self::local_bounds(major_radius, minor_radius)),
+            frame,
+            major_radius,
+            minor_radius,
+            material,
+        }
+    }
+
+    fn local_bounds(major_radius: f32, minor_radius: f32) -> Aabb {
+        let outer = major_radius + minor_radius;
+        Aabb::from_points(
+            vec3(-outer, -outer, -minor_radius),
+            vec3(outer, outer, minor_radius),
+        )
+    }
+
+    /// Crossings of (|p|² + R² - r²)² = 4R² (x² + y²), found numerically on
+    /// the stretch of the ray inside the bounds.
+    fn local_hits(&self, ray: Ray) -> Vec<LocalHit> {
+        let ray = self.frame.ray_to_local(ray);
+        let Some(inside) = Self::local_bounds(self.major_radius, self.minor_radius)
+            .hit_range(ray, &(f32::MIN..f32::MAX))
+        else {
+            return Vec::new();
+        };
+
+        let o = ray.origin.as_dvec3();
+        let d = ray.direction.as_dvec3();
+        let major2 = (self.major_radius as f64).powi(2);
+        let minor2 = (self.minor_radius as f64).powi(2);
+        let dd = d.dot(d);
+        let od = o.dot(d);
+        let e = o.dot(o) - major2 - minor2;
+        let coefficients = [
+            dd * dd,
+            4.0 * dd * od,
+            2.0 * dd * e + 4.0 * od * od + 4.0 * major2 * d.z * d.z,
+            4.0 * od * e + 8.0 * major2 * o.z * d.z,
+            e * e - 4.0 * major2 * (minor2 - o.z * o.z),
+        ];
+
+        polynomial_roots(&coefficients, &inside)
+            .into_iter()
+            .map(|t| {
+                let point = ray.at(t);
+                let ring = vec3(point.x, point.y, 0.0).normalize_or_zero() * self.major_radius;
+                let tube = point - ring;
+                let around_tube = point.z.atan2(ring.dot(tube) / self.major_radius);
+                LocalHit {
+                    t,
+                    outward_normal: tube.normalize(),
+                    uv: vec2(
+                        (point.y.atan2(point.x) + PI) / (2.0 * PI),
+                        (around_tube + PI) / (2.0 * PI),
+                    ),
+                }
+            })
+            .collect()
+    }
+}
+
+impl<M: Material> Object for Torus<M> {
+    fn hit(&self, ray: Ray, range: &Interval) -> Option<HitRecord<'_>> {
+        let hit = sorted_hits(self.local_hits(ray), range)
+            .into_iter()
+            .next()?;
+        Some(self.frame.hit_record(self, ray, &hit, &self.material))
+    }
+
+    fn hit_all(&self, ray: Ray, range: &Interval) -> Vec<HitRecord<'_>> {
+        sorted_hits(self.local_hits(ray), range)
+            .iter()
+            .map(|hit| self.frame.hit_record(self, ray, hit, &self.material))
+            .collect()
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bbox
+    }
+
+    fn position(&self) -> Vec3 {
+        self.frame.origin()
+    }
+
+    fn material(&self) -> Option<&dyn Material> {
+        Some(&self.material)
+    }
+
+    fn area(&self) -> f32 {
+        4.0 * PI * PI * self.major_radius * self.minor_radius
+    }
+
+    fn sample_surface(&self) -> Option<SurfaceSample> {
+        // The outside of the ring has more area than the inside, so angles
+        // around the tube are drawn uniformly and thinned in proportion.
+        let outer = self.major_radius + self.minor_radius;
+        let theta = loop {
+            let theta = 2.0 * PI * random();
+            if random() * outer <= self.major_radius + self.minor_radius * theta.cos() {
+                break theta;
+            }
+        };
+        let phi = 2.0 * PI * random();
+
+        let (sin_phi, cos_phi) = phi.sin_cos();
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        let normal = vec3(cos_theta * cos_phi, cos_theta * sin_phi, sin_theta);
+        let point = self.major_radius * vec3(cos_phi, sin_phi, 0.0) + self.minor_radius * normal;
+
+        Some(SurfaceSample {
+            point: self.frame.point_to_world(point),
+            normal: self.frame.vector_to_world(normal),
+        })
+    }
+
+    fn visit_shape(&self, visitor: &mut dyn ShapeVisitor) {
+        visitor.torus(
+            self.frame.origin(),
+            self.frame.vector_to_world(Vec3::Z),
+            self.major_radius,
+            self.minor_radius,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rendering::{material::Lambertian, ray::Color, texture::SolidColor};
+
+    use super::*;
+
+    /// Ring of radius 1 around the z axis, with a tube of radius 0.25.
+    fn torus() -> Torus<Lambertian<SolidColor>> {
+        let material = Lambertian::solid_color(Color::new(0.5, 0.5, 0.5));
+        Torus::new(Vec3::ZERO, Vec3::Z, 1.0, 0.25, material)
+    }
+
+    /// Where a ray from `origin` along x crosses the torus.
+    fn crossings(origin: Vec3) -> Vec<f32> {
+        let ray = Ray::new(origin, Vec3::X, 0.0);
+        torus()
+            .hit_all(ray, &(0.0..f32::MAX))
+            .iter()
+            .map(|hit| ((origin.x + hit.t) * 1e3).round() / 1e3)
+            .collect()
+    }
+
+    #[test]
+    fn ray_through_the_middle_crosses_the_tube_twice_on_each_side() {
+        assert_eq!(
+            crossings(Vec3::new(-3.0, 0.0, 0.0)),
+            [-1.25, -0.75, 0.75, 1.25]
+        );
+    }
+
+    #[test]
+    fn tangent_ray_touches_the_top_of_the_tube() {
+        assert_eq!(crossings(Vec3::new(-3.0, 0.0, 0.25)), [-1.0, 1.0]);
+        let torus = torus();
+        let ray = Ray::new(Vec3::new(-3.0, 0.0, 0.25), Vec3::X, 0.0);
+        let hit = torus.hit(ray, &(0.0..f32::MAX)).unwrap();
+        // The ray runs along the surface, so either side can face it.
+        assert!(hit.normal.z.abs() > 0.999, "{}", hit.normal);
+    }
+
+    #[test]
+    fn grazing_ray_crosses_the_tube_close_together() {
+        let crossings = crossings(Vec3::new(-3.0, 1.249, 0.0));
+        assert_eq!(crossings.len(), 2, "{crossings:?}");
+        assert!(crossings[1] - crossings[0] < 0.2);
+    }
+
+    #[test]
+    fn ray_past_the_tube_misses() {
+        assert!(crossings(Vec3::new(-3.0, 0.0, 0.2501)).is_empty());
+        assert!(crossings(Vec3::new(-3.0, 1.2501, 0.0)).is_empty());
+    }
+}
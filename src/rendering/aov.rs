@@ -0,0 +1,230 @@
+use std::{io, path::Path};
+
+use glam::{Vec2, Vec3};
+use image::{ImageBuffer, Rgb, Rgb32FImage};
+
+use super::colorspace::OutputTransform;
+
+/// Marks a pixel whose samples never hit anything with an ID.
+pub const NO_ID: u32 = u32::MAX;
+
+/// What a camera path found at its first hit.
+pub struct FirstHit {
+    pub albedo: Vec3,
+    pub normal: Vec3,
+    pub position: Vec3,
+    pub depth: f32,
+    pub uv: Vec2,
+    pub object_id: u32,
+    pub material_id: u32,
+}
+
+/// Running record of one camera sample, filled in by the integrator as it
+/// follows the path. Every contribution to the sample's radiance is
+/// sorted by the bounce it arrived at and, if it came from a light in the
+/// world's light list, by that light.
+pub struct PathRecord {
+    /// Product of the attenuations so far.
+    pub throughput: Vec3,
+    pub first_hit: Option<FirstHit>,
+    /// Light seen directly by the camera, the sky included.
+    pub emission: Vec3,
+    /// Light reaching the first hit straight from an emitter or the sky.
+    pub direct: Vec3,
+    pub indirect: Vec3,
+    pub light_groups: Vec<Vec3>,
+}
+
+impl PathRecord {
+    pub fn new(light_groups: usize) -> Self {
+        Self {
+            throughput: Vec3::ONE,
+            first_hit: None,
+            emission: Vec3::ZERO,
+            direct: Vec3::ZERO,
+            indirect: Vec3::ZERO,
+            light_groups: vec![Vec3::ZERO; light_groups],
+        }
+    }
+
+    /// Adds light `radiance` found at `bounce` (zero for the first hit),
+    /// weighted by the current throughput.
+    pub fn add_light(&mut self, bounce: u32, radiance: Vec3, light_group: Option<usize>) {
+        self.add_contribution(bounce, self.throughput * radiance, light_group);
+    }
+
+    /// Adds light that is already weighted by the path's throughput, for
+    /// integrators that keep track of it themselves.
+    pub fn add_contribution(
+        &mut self,
+        bounce: u32,
+        contribution: Vec3,
+        light_group: Option<usize>,
+    ) {
+        match bounce {
+            0 => self.emission += contribution,
+            1 => self.direct += contribution,
+            _ => self.indirect += contribution,
+        }
+        if let Some(group) = light_group {
+            self.light_groups[group] += contribution;
+        }
+    }
+}
+
+/// Sums of the passes over all samples of a pixel. The geometric passes
+/// are averaged over the samples that hit something; the IDs are those of
+/// the first such sample.
+#[derive(Clone, Copy)]
+pub struct AovPixel {
+    pub albedo: Vec3,
+    pub normal: Vec3,
+    pub position: Vec3,
+    pub depth: f32,
+    pub uv: Vec2,
+    pub hits: u32,
+    pub object_id: u32,
+    pub material_id: u32,
+    pub emission: Vec3,
+    pub direct: Vec3,
+    pub indirect: Vec3,
+}
+
+impl Default for AovPixel {
+    fn default() -> Self {
+        Self {
+            albedo: Vec3::ZERO,
+            normal: Vec3::ZERO,
+            position: Vec3::ZERO,
+            depth: 0.0,
+            uv: Vec2::ZERO,
+            hits: 0,
+            object_id: NO_ID,
+            material_id: NO_ID,
+            emission: Vec3::ZERO,
+            direct: Vec3::ZERO,
+            indirect: Vec3::ZERO,
+        }
+    }
+}
+
+impl AovPixel {
+    /// Adds one finished sample; its light groups go in `light_groups`.
+    pub fn add(&mut self, path: &PathRecord, light_groups: &mut [Vec3]) {
+        if let Some(hit) = &path.first_hit {
+            self.albedo += hit.albedo;
+            self.normal += hit.normal;
+            self.position += hit.position;
+            self.depth += hit.depth;
+            self.uv += hit.uv;
+            self.hits += 1;
+            if self.object_id == NO_ID {
+                self.object_id = hit.object_id;
+                self.material_id = hit.material_id;
+            }
+        }
+        self.emission += path.emission;
+        self.direct += path.direct;
+        self.indirect += path.indirect;
+
+        for (sum, &contribution) in light_groups.iter_mut().zip(&path.light_groups) {
+            *sum += contribution;
+        }
+    }
+
+    fn surface_average<T: std::ops::Div<f32, Output = T> + Default>(&self, sum: T) -> T {
+        if self.hits == 0 {
+            T::default()
+        } else {
+            sum / self.hits as f32
+        }
+    }
+
+    pub fn average_albedo(&self) -> Vec3 {
+        self.surface_average(self.albedo)
+    }
+
+    pub fn average_normal(&self) -> Vec3 {
+        self.surface_average(self.normal)
+    }
+
+    /// Average distance to the first hit, infinite if nothing was hit.
+    pub fn average_depth(&self) -> f32 {
+        if self.hits == 0 {
+            f32::INFINITY
+        } else {
+            self.depth / self.hits as f32
+        }
+    }
+}
+
+/// Render passes of a whole film, stored next to its beauty pixels.
+#[derive(Clone)]
+pub struct AovBuffer {
+    pub pixels: Vec<AovPixel>,
+    /// Number of light groups, one per light in the world.
+    pub light_group_count: usize,
+    /// Light group sums, `light_group_count` per pixel.
+    pub light_groups: Vec<Vec3>,
+}
+
+impl AovBuffer {
+    pub fn new(pixel_count: usize, light_group_count: usize) -> Self {
+        Self {
+            pixels: vec![AovPixel::default(); pixel_count],
+            light_group_count,
+            light_groups: vec![Vec3::ZERO; pixel_count * light_group_count],
+        }
+    }
+
+    /// Writes every pass as a separate OpenEXR file named after `base`,
+    /// e.g. `image.albedo.exr`. `samples` gives each pixel's sample count.
+    /// Color passes are converted to the output primaries of `transform`.
+    pub fn save(
+        &self,
+        base: &Path,
+        width: u32,
+        height: u32,
+        transform: &OutputTransform,
+        samples: impl Fn(usize) -> u32,
+    ) -> io::Result<()> {
+        let save = |name: &str, value: &dyn Fn(usize, &AovPixel) -> Vec3| {
+            let image: Rgb32FImage = ImageBuffer::from_fn(width, height, |x, y| {
+                let index = (y * width + x) as usize;
+                Rgb(value(index, &self.pixels[index]).to_array())
+            });
+            let path = base.with_extension(format!("{name}.exr"));
+            image.save(&path).map_err(io::Error::other)
+        };
+        let per_sample = |index: usize, sum: Vec3| match samples(index) {
+            0 => Vec3::ZERO,
+            n => transform.linear(sum / n as f32),
+        };
+        let id = |id: u32| {
+            if id == NO_ID {
+                Vec3::splat(-1.0)
+            } else {
+                Vec3::splat(id as f32)
+            }
+        };
+
+        save("albedo", &|_, p| transform.linear(p.average_albedo()))?;
+        save("normal", &|_, p| p.average_normal())?;
+        save("position", &|_, p| p.surface_average(p.position))?;
+        save("depth", &|_, p| Vec3::splat(p.average_depth()))?;
+        save("uv", &|_, p| p.surface_average(p.uv).extend(0.0))?;
+        save("object_id", &|_, p| id(p.object_id))?;
+        save("material_id", &|_, p| id(p.material_id))?;
+        save("emission", &|i, p| per_sample(i, p.emission))?;
+        save("direct", &|i, p| per_sample(i, p.direct))?;
+        save("indirect", &|i, p| per_sample(i, p.indirect))?;
+
+        for group in 0..self.light_group_count {
+            save(&format!("light{group}"), &|i, _| {
+                per_sample(i, self.light_groups[i * self.light_group_count + group])
+            })?;
+        }
+
+        Ok(())
+    }
+}
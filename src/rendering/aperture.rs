@@ -0,0 +1,158 @@
+use std::{
+    f32::consts::TAU,
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use glam::{vec2, Vec2, Vec3};
+use image::{io::Reader as ImageReader, GrayImage};
+
+use crate::math::{random_range, VecExt};
+
+/// Shape of the lens opening, which is also the shape out-of-focus
+/// highlights take on.
+#[derive(Clone, Default)]
+pub enum Aperture {
+    #[default]
+    Disk,
+    /// Regular polygon formed by `blades` diaphragm blades, turned by
+    /// `rotation` degrees.
+    Polygon { blades: u32, rotation: f32 },
+    /// Custom opening, brighter pixels letting through more light.
+    Image(Arc<ApertureImage>),
+}
+
+impl Aperture {
+    /// Random point on the opening, scaled so the lens radius is one.
+    pub fn sample(&self) -> Vec2 {
+        match self {
+            Self::Disk => Vec3::random_in_unit_disk().truncate(),
+            Self::Polygon { blades, rotation } => {
+                let blades = (*blades).max(3);
+                let step = TAU / blades as f32;
+                let blade = (random_range(0.0..blades as f32) as u32).min(blades - 1);
+                let angle = rotation.to_radians() + step * blade as f32;
+                let a = vec2(angle.cos(), angle.sin());
+                let b = vec2((angle + step).cos(), (angle + step).sin());
+
+                // Uniform point in the triangle between the center and one edge.
+                let (mut s, mut t) = (random_range(0.0..1.0), random_range(0.0..1.0));
+                if s + t > 1.0 {
+                    (s, t) = (1.0 - s, 1.0 - t);
+                }
+                s * a + t * b
+            }
+            Self::Image(image) => image.sample(),
+        }
+    }
+}
+
+/// Grayscale aperture mask with a table for picking pixels in proportion
+/// to their brightness.
+pub struct ApertureImage {
+    /// File the image was read from.
+    path: PathBuf,
+    width: u32,
+    height: u32,
+    /// Running sum of pixel brightness, row by row.
+    cdf: Vec<f32>,
+}
+
+impl ApertureImage {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let img = ImageReader::open(path)?
+            .decode()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        Self::new(&GrayImage::from(img), path.to_path_buf())
+    }
+
+    /// Fails if the image is completely dark, letting no light through.
+    fn new(image: &GrayImage, path: PathBuf) -> io::Result<Self> {
+        let cdf = image
+            .pixels()
+            .scan(0.0, |sum, pixel| {
+                *sum += pixel[0] as f32;
+                Some(*sum)
+            })
+            .collect::<Vec<_>>();
+        if !cdf.last().is_some_and(|&total| total > 0.0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "aperture image is completely dark",
+            ));
+        }
+
+        Ok(Self {
+            path,
+            width: image.width(),
+            height: image.height(),
+            cdf,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn sample(&self) -> Vec2 {
+        let target = random_range(0.0..*self.cdf.last().unwrap());
+        let index = self
+            .cdf
+            .partition_point(|&sum| sum <= target)
+            .min(self.cdf.len() - 1) as u32;
+
+        let x = (index % self.width) as f32 + random_range(0.0..1.0);
+        let y = (index / self.width) as f32 + random_range(0.0..1.0);
+
+        // The image spans the square around the unit disk, top row up.
+        let size = self.width.max(self.height) as f32;
+        vec2(
+            (2.0 * x - self.width as f32) / size,
+            (self.height as f32 - 2.0 * y) / size,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Luma;
+
+    use super::*;
+
+    #[test]
+    fn refuses_dark_images() {
+        let error = ApertureImage::new(&GrayImage::new(4, 4), PathBuf::new())
+            .err()
+            .unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn image_samples_fall_on_lit_pixels() {
+        // Only the right half lets light through.
+        let image = GrayImage::from_fn(4, 4, |x, _| Luma([if x >= 2 { 255 } else { 0 }]));
+        let aperture = Aperture::Image(Arc::new(
+            ApertureImage::new(&image, PathBuf::new()).unwrap(),
+        ));
+        for _ in 0..100 {
+            let point = aperture.sample();
+            assert!(
+                (0.0..=1.0).contains(&point.x) && point.y.abs() <= 1.0,
+                "{point}"
+            );
+        }
+    }
+
+    #[test]
+    fn polygon_samples_stay_inside_the_unit_disk() {
+        let aperture = Aperture::Polygon {
+            blades: 5,
+            rotation: 18.0,
+        };
+        for _ in 0..100 {
+            assert!(aperture.sample().length() <= 1.0 + 1e-6);
+        }
+    }
+}
@@ -1,29 +1,115 @@
-use std::sync::Arc;
+use std::{ops::Range, sync::Arc};
 
-use glam::{vec3, Vec3};
-use image::{ImageBuffer, Rgb};
-use rand::distributions::{Distribution, Uniform};
-use rayon::iter::{ParallelBridge, ParallelIterator};
+use glam::{vec2, vec3, Vec2, Vec3, Vec4};
+use rayon::prelude::*;
 
 use crate::{
-    math::{random, VecExt},
+    animation::CameraAnimation,
+    math::{hash_seed, random_range, reseed},
     object::{bvh::BVHCollection, Object},
 };
 
-use super::ray::{Color, Ray};
+use super::{
+    aov::{AovPixel, PathRecord},
+    aperture::Aperture,
+    debug::{self, DebugView},
+    film::{Film, FilmPixel},
+    filter::Filter,
+    integrator::{
+        continue_path, scatter_path, Bounce, BounceCounts, BounceLimits, Integrator, IntegratorKind,
+    },
+    projection::{CameraModel, Projection, View},
+    ray::{Color, Ray},
+    spectrum::SampledWavelengths,
+};
+
+/// Rows rendered together into one splatting buffer.
+const BAND_ROWS: u32 = 4;
+
+/// How the distance to the plane of sharpest focus is chosen.
+#[derive(Clone, Copy, Default)]
+pub enum Focus {
+    /// Use `focus_distance` as given.
+    #[default]
+    Manual,
+    /// Keep `look_at` sharp, following it if the camera is animated.
+    LookAt,
+    /// Focus on the first surface seen through the center of pixel (x, y)
+    /// when the shutter opens.
+    Pixel(u32, u32),
+}
+
+/// Depth of field described like a real camera. Lengths are in millimetres,
+/// and the scene is taken to be measured in metres.
+#[derive(Clone, Copy)]
+pub struct PhysicalLens {
+    pub focal_length: f32,
+    pub f_stop: f32,
+    pub sensor_width: f32,
+}
 
+impl Default for PhysicalLens {
+    /// A 50 mm lens at f/2.8 on a full frame sensor.
+    fn default() -> Self {
+        Self {
+            focal_length: 50.0,
+            f_stop: 2.8,
+            sensor_width: Self::FULL_FRAME_WIDTH,
+        }
+    }
+}
+
+impl PhysicalLens {
+    /// Width of a 35 mm film frame, in millimetres.
+    pub const FULL_FRAME_WIDTH: f32 = 36.0;
+
+    /// Vertical field of view in degrees for an image with `aspect`.
+    pub fn vfov(&self, aspect: f32) -> f32 {
+        let sensor_height = self.sensor_width / aspect;
+        2.0 * (sensor_height / (2.0 * self.focal_length))
+            .atan()
+            .to_degrees()
+    }
+
+    /// Radius of the entrance pupil in scene units.
+    pub fn aperture_radius(&self) -> f32 {
+        self.focal_length / self.f_stop / 2.0 * 0.001
+    }
+}
+
+#[derive(Clone)]
 pub struct CameraConfig {
     pub aspect_ratio: f32,
     pub image_width: u32,
     pub samples_per_pixel: u32,
     pub max_bounces: u32,
+    pub bounce_limits: BounceLimits,
     pub vfov: f32,
     pub look_from: Vec3,
     pub look_at: Vec3,
     pub vector_up: Vec3,
     pub focus_distance: f32,
+    pub focus: Focus,
     pub defocus_angle: f32,
+    /// Overrides `vfov` and `defocus_angle` when set.
+    pub lens: Option<PhysicalLens>,
+    pub aperture: Aperture,
     pub skybox: Color,
+    /// Scene time at which the shutter opens; rays are spread over
+    /// `shutter_open..shutter_close`.
+    pub shutter_open: f32,
+    pub shutter_close: f32,
+    pub projection: Projection,
+    pub filter: Filter,
+    /// Trace every path at a set of wavelengths instead of in RGB, which
+    /// lets dispersive glass split light into its colors.
+    pub spectral: bool,
+    /// Shows the scene in a debug view instead of path tracing it, which
+    /// leaves the render passes empty.
+    pub debug: Option<DebugView>,
+    /// Light transport algorithm for RGB renders; spectral renders always
+    /// trace naive paths.
+    pub integrator: IntegratorKind,
 }
 
 impl Default for CameraConfig {
@@ -33,181 +119,504 @@ impl Default for CameraConfig {
             image_width: 400,
             samples_per_pixel: 10,
             max_bounces: 50,
+            bounce_limits: BounceLimits::default(),
             vfov: 90.0,
             look_from: vec3(0.0, 0.0, 0.0),
             look_at: vec3(0.0, 0.0, 2.0),
             vector_up: vec3(0.0, 1.0, 0.0),
             focus_distance: 10.0,
+            focus: Focus::Manual,
             defocus_angle: 0.0,
+            lens: None,
+            aperture: Aperture::Disk,
             skybox: Color::new(0xAD as f32 / 255., 0xD8 as f32 / 255., 0xE6 as f32 / 255.),
+            shutter_open: 0.0,
+            shutter_close: 1.0,
+            projection: Projection::Perspective,
+            filter: Filter::default(),
+            spectral: false,
+            debug: None,
+            integrator: IntegratorKind::Naive,
         }
     }
 }
 
+impl CameraConfig {
+    /// Resolves `Focus::Pixel` into a manual focus distance by sending a
+    /// probe ray into `world`. The distance is left alone if the ray
+    /// escapes.
+    pub fn autofocus(&mut self, world: &BVHCollection, animation: Option<&CameraAnimation>) {
+        let Focus::Pixel(x, y) = self.focus else {
+            return;
+        };
+
+        let config = match animation {
+            Some(animation) => animation.config_at(self, self.shutter_open),
+            None => self.clone(),
+        };
+        let Some(ray) = config.pixel_ray(x, y) else {
+            return;
+        };
+
+        if let Some(hit) = world.hit(ray, &(0.001..f32::MAX)) {
+            let forward = (config.look_at - config.look_from).normalize();
+            self.focus_distance = (hit.point - config.look_from).dot(forward);
+            self.focus = Focus::Manual;
+        }
+    }
+
+    /// Ray through the center of pixel (x, y) from the middle of the lens
+    /// as the shutter opens, or `None` if the projection sees nothing there.
+    pub fn pixel_ray(&self, x: u32, y: u32) -> Option<Ray> {
+        let image_height = (self.image_width as f32 / self.aspect_ratio) as u32;
+        let mut view = View::new(self, self.image_width as f32 / image_height as f32);
+        view.lens_radius = 0.0;
+
+        let film = vec2(
+            (x as f32 + 0.5) / self.image_width as f32,
+            (y as f32 + 0.5) / image_height as f32,
+        );
+        let model = self.projection.build(self, &view);
+        let probe = model.generate_ray(&view, film)?;
+
+        Some(Ray::new(probe.origin, probe.direction, self.shutter_open))
+    }
+}
+
 pub struct Camera {
     image_width: u32,
     image_height: u32,
     samples_per_pixel: u32,
     max_bounces: u32,
+    bounce_limits: BounceLimits,
 
-    center: Vec3,
-    pixel00_loc: Vec3,
+    view: View,
+    model: Arc<dyn CameraModel>,
 
-    pixel_delta_u: Vec3,
-    pixel_delta_v: Vec3,
+    skybox: Color,
 
-    defocus_angle: f32,
-    defocus_disk_u: Vec3,
-    defocus_disk_v: Vec3,
+    shutter_open: f32,
+    shutter_close: f32,
 
-    skybox: Color,
-}
+    filter: Filter,
+    spectral: bool,
+    integrator: Arc<dyn Integrator>,
+
+    debug: Option<DebugView>,
+    /// Distance from the camera to `look_at`, where the depth view is down
+    /// to a tenth of white.
+    depth_scale: f32,
 
-fn linear_to_gamma(linear: f32) -> f32 {
-    linear.sqrt()
+    /// Keyframes evaluated at every ray's time for camera motion blur, along
+    /// with the configuration they animate.
+    motion: Option<(CameraConfig, CameraAnimation)>,
 }
 
 impl Camera {
     pub fn new(config: CameraConfig) -> Self {
-        let CameraConfig {
-            aspect_ratio,
-            image_width,
-            samples_per_pixel,
-            max_bounces,
-            vfov,
-            look_from,
-            look_at,
-            vector_up,
-            focus_distance,
-            defocus_angle,
-            skybox,
-        } = config;
-
-        let image_height = (image_width as f32 / aspect_ratio) as u32;
-
-        let center = look_from;
-
-        let theta = vfov.to_radians();
-        let h = (theta / 2.0).tan();
-        let viewport_height = 2.0 * h * focus_distance;
-        let viewport_width = viewport_height * (image_width as f32 / image_height as f32);
-
-        let w = (look_from - look_at).normalize();
-        let u = vector_up.cross(w).normalize();
-        let v = w.cross(u);
-
-        let viewport_u = viewport_width * u;
-        let viewport_v = viewport_height * -v;
-
-        let pixel_delta_u = viewport_u / image_width as f32;
-        let pixel_delta_v = viewport_v / image_height as f32;
-
-        let viewport_upper_left =
-            center - (focus_distance * w) - viewport_u / 2.0 - viewport_v / 2.0;
-        let pixel00_loc = viewport_upper_left + 0.5 * (pixel_delta_u + pixel_delta_v);
-
-        let defocus_radius = focus_distance * ((defocus_angle / 2.0).to_radians().tan());
-        let defocus_disk_u = u * defocus_radius;
-        let defocus_disk_v = v * defocus_radius;
+        let image_height = (config.image_width as f32 / config.aspect_ratio) as u32;
+        let view = View::new(&config, config.image_width as f32 / image_height as f32);
 
         Self {
-            image_width,
+            image_width: config.image_width,
             image_height,
-            samples_per_pixel,
-            max_bounces,
-            center,
-            pixel00_loc,
-            pixel_delta_u,
-            pixel_delta_v,
-            defocus_angle,
-            defocus_disk_u,
-            defocus_disk_v,
-            skybox,
+            samples_per_pixel: config.samples_per_pixel,
+            max_bounces: config.max_bounces,
+            bounce_limits: config.bounce_limits,
+            model: config.projection.build(&config, &view),
+            view,
+            skybox: config.skybox,
+            shutter_open: config.shutter_open,
+            shutter_close: config.shutter_close,
+            filter: config.filter,
+            spectral: config.spectral,
+            integrator: config.integrator.build(&config),
+            debug: config.debug,
+            depth_scale: (config.look_at - config.look_from).length(),
+            motion: None,
         }
     }
 
-    fn create_ray(&self, center_x: f32, center_y: f32) -> Ray {
-        let pixel_center =
-            self.pixel00_loc + (center_x * self.pixel_delta_u) + (center_y * self.pixel_delta_v);
-        let pixel_sample = pixel_center + self.pixel_sample_square();
+    /// Camera following `animation` while the shutter is open, blurring
+    /// whatever moves relative to it.
+    pub fn animated(config: CameraConfig, animation: CameraAnimation) -> Self {
+        let mut camera = Self::new(config.clone());
+        camera.motion = Some((config, animation));
+        camera
+    }
 
-        let ray_origin = if self.defocus_angle <= 0.0 {
-            self.center
+    /// Ray through `position` on the image, measured in pixels, with the
+    /// weight its radiance counts with, or `None` if the projection sees
+    /// nothing there.
+    fn create_ray(&self, position: Vec2) -> Option<(Ray, f32)> {
+        let ray_time = if self.shutter_close > self.shutter_open {
+            random_range(self.shutter_open..self.shutter_close)
         } else {
-            self.defocus_disk_sample()
+            self.shutter_open
         };
-        let ray_direction = pixel_sample - ray_origin;
-        let ray_time = random();
 
-        Ray::new(ray_origin, ray_direction, ray_time)
-    }
+        let moved_view = self.motion.as_ref().map(|(config, animation)| {
+            View::new(&animation.config_at(config, ray_time), self.view.aspect)
+        });
+        let view = moved_view.as_ref().unwrap_or(&self.view);
 
-    fn defocus_disk_sample(&self) -> Vec3 {
-        let point = Vec3::random_in_unit_disk();
-        self.center + point.x * self.defocus_disk_u + point.y * self.defocus_disk_v
+        let film = position / vec2(self.image_width as f32, self.image_height as f32);
+        let sample = self.model.generate_ray(view, film)?;
+
+        Some((
+            Ray::new(sample.origin, sample.direction, ray_time),
+            sample.weight,
+        ))
     }
 
-    fn pixel_sample_square(&self) -> Vec3 {
-        // Returns a random point in the square surrounding a pixel at the origin.
-        let mut rng = rand::thread_rng();
-        let uniform = Uniform::from(-0.5..0.5);
-        let px = uniform.sample(&mut rng);
-        let py = uniform.sample(&mut rng);
-        (px * self.pixel_delta_u) + (py * self.pixel_delta_v)
+    /// Radiance at `wavelengths` arriving along `ray`, whose own wavelength
+    /// is the hero, following the materials as the naive path tracer does.
+    /// `weight` is the camera ray's, which the lights found are recorded
+    /// into the render passes with.
+    fn ray_spectrum(
+        &self,
+        mut ray: Ray,
+        world: &BVHCollection,
+        mut wavelengths: SampledWavelengths,
+        weight: f32,
+        mut path: Option<&mut PathRecord>,
+    ) -> Vec4 {
+        let mut radiance = Vec4::ZERO;
+        let mut throughput = Vec4::ONE;
+        let mut bounces = BounceCounts::new(self.bounce_limits);
+        let mut record = |path: &mut Option<&mut PathRecord>,
+                          wavelengths: &SampledWavelengths,
+                          bounce: u32,
+                          light: Vec4,
+                          light_group: Option<usize>| {
+            radiance += light;
+            if let Some(path) = path.as_deref_mut() {
+                path.add_contribution(bounce, wavelengths.to_rgb(weight * light), light_group);
+            }
+        };
+
+        for bounce in 0..self.max_bounces {
+            let Some(hit) = world.hit(ray, &(0.001..f32::MAX)) else {
+                let sky = wavelengths.illuminant(self.skybox.0);
+                record(&mut path, &wavelengths, bounce, throughput * sky, None);
+                break;
+            };
+
+            let emission = wavelengths.illuminant(hit.material.emit(hit.u, hit.v, hit.point).0);
+            let light_group = world.light_group(hit.object);
+            record(
+                &mut path,
+                &wavelengths,
+                bounce,
+                throughput * emission,
+                light_group,
+            );
+
+            let record_path = path.as_deref_mut();
+            let (new_ray, attenuation) =
+                match scatter_path(ray, &hit, world, bounce, &mut bounces, record_path) {
+                    Bounce::End => break,
+                    Bounce::Lit(color) => {
+                        let light = wavelengths.illuminant(color.0);
+                        record(&mut path, &wavelengths, bounce, throughput * light, None);
+                        break;
+                    }
+                    Bounce::Scattered { ray, attenuation } => (ray, attenuation),
+                };
+
+            let mut attenuation = wavelengths.reflectance(attenuation.0);
+            if hit.material.disperses() {
+                attenuation *= wavelengths.terminate_secondary();
+            }
+            if !continue_path(bounce, &mut throughput, attenuation) {
+                break;
+            }
+            ray = new_ray;
+        }
+
+        radiance
     }
 
-    fn ray_color(&self, ray: Ray, bounces_left: u32, world: &BVHCollection) -> Color {
-        if bounces_left == 0 {
-            return Color::new(0.0, 0.0, 0.0);
+    /// Color of `ray` in a debug view. Only the wireframe view path traces.
+    fn debug_color(&self, view: DebugView, ray: Ray, world: &BVHCollection) -> Vec3 {
+        match view {
+            DebugView::Wireframe => {
+                let pixel_size =
+                    2.0 * (self.view.vfov.to_radians() / 2.0).tan() / self.image_height as f32;
+                debug::wire_color(ray, world, pixel_size)
+                    .unwrap_or_else(|| self.integrator.radiance(ray, world, None))
+            }
+            DebugView::WhiteFurnace => debug::white_furnace(ray, world, self.max_bounces),
+            _ => view.first_hit_color(ray, world, self.depth_scale),
         }
+    }
 
-        if let Some(hit) = world.hit(ray, &(0.001..f32::MAX)) {
-            let emission_color = hit.material.emit(hit.u, hit.v, hit.point);
-            let scatter_color = if let Some(scatter) = hit.material.scatter(ray, &hit) {
-                if let Some(new_ray) = scatter.new_ray {
-                    scatter.attenuation * self.ray_color(new_ray, bounces_left - 1, world)
+    pub fn image_width(&self) -> u32 {
+        self.image_width
+    }
+
+    pub fn image_height(&self) -> u32 {
+        self.image_height
+    }
+
+    pub fn samples_per_pixel(&self) -> u32 {
+        self.samples_per_pixel
+    }
+
+    pub fn new_film(&self, seed: u64) -> Film {
+        Film::new(self.image_width, self.image_height, seed)
+    }
+
+    /// Takes the samples with indices in `samples` for one pixel, handing
+    /// each one's position and radiance to `splat` and adding its render
+    /// passes to `aov` if given. The pixel's random sequence depends only on
+    /// the seed, its coordinates and the first sample index, which keeps
+    /// renders resumable.
+    #[allow(clippy::too_many_arguments)]
+    fn sample_pixel(
+        &self,
+        world: &BVHCollection,
+        seed: u64,
+        x: u32,
+        y: u32,
+        samples: Range<u32>,
+        mut aov: Option<(&mut AovPixel, &mut [Vec3])>,
+        mut splat: impl FnMut(Vec2, Vec3),
+    ) {
+        reseed(hash_seed(&[seed, x as u64, y as u64, samples.start as u64]));
+
+        for _ in samples {
+            let position = vec2(
+                x as f32 + random_range(0.0..1.0),
+                y as f32 + random_range(0.0..1.0),
+            );
+            let mut path = aov.is_some().then(|| PathRecord::new(world.lights().len()));
+
+            // Samples the projection has no ray for still count, as black.
+            let mut radiance = Vec3::ZERO;
+            if let Some((mut ray, weight)) = self.create_ray(position) {
+                if let Some(view) = self.debug {
+                    radiance = weight * self.debug_color(view, ray, world);
+                } else if self.spectral {
+                    let wavelengths = SampledWavelengths::sample(random_range(0.0..1.0));
+                    ray.wavelength = Some(wavelengths.hero());
+                    let spectrum =
+                        self.ray_spectrum(ray, world, wavelengths, weight, path.as_mut());
+                    radiance = weight * wavelengths.to_rgb(spectrum);
                 } else {
-                    scatter.attenuation
+                    if let Some(path) = path.as_mut() {
+                        path.throughput = Vec3::splat(weight);
+                    }
+                    radiance = weight * self.integrator.radiance(ray, world, path.as_mut());
                 }
-            } else {
-                Color::new(0., 0., 0.)
-            };
+            }
 
-            return scatter_color + emission_color;
+            if let (Some((aov_pixel, light_groups)), Some(path)) = (aov.as_mut(), &path) {
+                aov_pixel.add(path, light_groups);
+            }
+            splat(position, radiance);
         }
+    }
 
-        self.skybox
+    /// Adds a sample at `position` to every pixel of `buffer` its filter
+    /// reaches. The buffer holds full rows starting at `first_row`.
+    fn splat(&self, buffer: &mut [FilmPixel], first_row: u32, position: Vec2, radiance: Vec3) {
+        let width = self.image_width as usize;
+        let rows = (buffer.len() / width) as f32;
+        let radius = self.filter.radius;
+
+        let x0 = (position.x - 0.5 - radius).ceil().max(0.0) as usize;
+        let x1 = (position.x - 0.5 + radius).floor().min(width as f32 - 1.0);
+        let y0 = (position.y - 0.5 - radius - first_row as f32)
+            .ceil()
+            .max(0.0) as usize;
+        let y1 = (position.y - 0.5 + radius - first_row as f32)
+            .floor()
+            .min(rows - 1.0);
+        if x1 < 0.0 || y1 < 0.0 {
+            return;
+        }
+
+        for y in y0..=y1 as usize {
+            let dy = (first_row as usize + y) as f32 + 0.5 - position.y;
+            for x in x0..=x1 as usize {
+                let weight = self.filter.weight(x as f32 + 0.5 - position.x, dy);
+                if weight != 0.0 {
+                    let pixel = &mut buffer[y * width + x];
+                    pixel.sum += weight * radiance;
+                    pixel.weight += weight;
+                }
+            }
+        }
     }
 
-    pub fn render(&self, world: &BVHCollection) -> image::RgbImage {
-        let mut buffer: image::RgbImage = ImageBuffer::new(self.image_width, self.image_height);
+    /// Samples every pixel in `rows`, taking sample indices
+    /// `sample_range(x, y)`, and returns the splatted result as full rows
+    /// starting at the returned row. Because of the filter these reach a few
+    /// rows past `rows` on both sides. `aovs` covers exactly `rows`.
+    ///
+    /// Bands of rows are rendered in parallel into buffers of their own,
+    /// which are then added up in order so the result doesn't depend on
+    /// thread scheduling.
+    fn render_rows(
+        &self,
+        world: &BVHCollection,
+        seed: u64,
+        rows: Range<u32>,
+        sample_range: impl Fn(u32, u32) -> Range<u32> + Sync,
+        aovs: Option<(&mut [AovPixel], &mut [Vec3])>,
+    ) -> (u32, Vec<FilmPixel>) {
+        let width = self.image_width as usize;
+
+        let bands: Vec<Range<u32>> = rows
+            .clone()
+            .step_by(BAND_ROWS as usize)
+            .map(|start| start..(start + BAND_ROWS).min(rows.end))
+            .collect();
+        let band_aovs: Vec<Option<(&mut [AovPixel], &mut [Vec3])>> = match aovs {
+            Some((pixels, light_groups)) => {
+                let groups = light_groups.len() / pixels.len().max(1);
+                let mut light_groups = light_groups;
+                pixels
+                    .chunks_mut(width * BAND_ROWS as usize)
+                    .map(|pixels| {
+                        let (band, rest) =
+                            std::mem::take(&mut light_groups).split_at_mut(pixels.len() * groups);
+                        light_groups = rest;
+                        Some((pixels, band))
+                    })
+                    .collect()
+            }
+            None => bands.iter().map(|_| None).collect(),
+        };
 
-        buffer
-            .enumerate_rows_mut()
-            .par_bridge()
-            .for_each(|(row_index, row)| {
-                row.for_each(|(x, y, pixel)| {
-                    let mut c = Vec3::ZERO;
-                    for _ in 0..self.samples_per_pixel {
-                        let ray = self.create_ray(x as f32, y as f32);
-                        c += self.ray_color(ray, self.max_bounces, world).0;
+        let rendered: Vec<(u32, Vec<FilmPixel>)> = bands
+            .into_par_iter()
+            .zip(band_aovs)
+            .map(|(band, mut aovs)| {
+                let buffer_rows = self.tile_rows(&band);
+                let mut buffer = vec![FilmPixel::default(); buffer_rows.len() * width];
+
+                for y in band.clone() {
+                    for x in 0..self.image_width {
+                        let aov = aovs.as_mut().map(|(pixels, light_groups)| {
+                            let index = (y - band.start) as usize * width + x as usize;
+                            let groups = light_groups.len() / pixels.len();
+                            (
+                                &mut pixels[index],
+                                &mut light_groups[index * groups..(index + 1) * groups],
+                            )
+                        });
+
+                        let samples = sample_range(x, y);
+                        let count = samples.len() as u32;
+                        self.sample_pixel(world, seed, x, y, samples, aov, |position, radiance| {
+                            self.splat(&mut buffer, buffer_rows.start, position, radiance)
+                        });
+                        let own = (y - buffer_rows.start) as usize * width + x as usize;
+                        buffer[own].samples += count;
                     }
+                }
+
+                (buffer_rows.start, buffer)
+            })
+            .collect();
+
+        let all_rows = self.tile_rows(&rows);
+        let mut pixels = vec![FilmPixel::default(); all_rows.len() * width];
+        for (first_row, buffer) in rendered {
+            let start = (first_row - all_rows.start) as usize * width;
+            for (pixel, band_pixel) in pixels[start..].iter_mut().zip(&buffer) {
+                pixel.add(band_pixel);
+            }
+        }
+
+        (all_rows.start, pixels)
+    }
 
-                    let c = c / self.samples_per_pixel as f32;
+    /// Adds `samples` samples to every pixel of the film, and to its render
+    /// passes if it has them.
+    pub fn render_pass(&self, world: &BVHCollection, film: &mut Film, samples: u32) {
+        let width = film.width();
+        let seed = film.seed();
+
+        let (pixels, aovs) = film.pixels_mut();
+        let pixels = &*pixels;
+        let (first_row, rendered) = self.render_rows(
+            world,
+            seed,
+            0..self.image_height,
+            |x, y| {
+                let start = pixels[(y * width + x) as usize].samples;
+                start..start + samples
+            },
+            aovs.map(|aovs| (&mut aovs.pixels[..], &mut aovs.light_groups[..])),
+        );
+
+        film.merge_rows(first_row, &rendered);
+    }
 
-                    *pixel = Rgb([
-                        (linear_to_gamma(c.x) * 255.0) as u8,
-                        (linear_to_gamma(c.y) * 255.0) as u8,
-                        (linear_to_gamma(c.z) * 255.0) as u8,
-                    ]);
-                });
+    /// Rows a tile rendered for `rows` covers, which reach past them by the
+    /// reconstruction filter's margin.
+    pub fn tile_rows(&self, rows: &Range<u32>) -> Range<u32> {
+        let margin = self.filter.margin();
+        rows.start.saturating_sub(margin)..(rows.end + margin).min(self.image_height)
+    }
 
-                print!(
-                    "\rRendering... {:.0}%",
-                    (row_index as f32 / self.image_height as f32) * 100.0
-                );
-            });
+    /// Renders the samples with indices in `samples` for a band of rows, to be
+    /// merged into a film elsewhere starting at the returned row.
+    pub fn render_tile(
+        &self,
+        world: &BVHCollection,
+        seed: u64,
+        rows: Range<u32>,
+        samples: Range<u32>,
+    ) -> (u32, Vec<FilmPixel>) {
+        self.render_rows(world, seed, rows, |_, _| samples.clone(), None)
+    }
 
-        buffer
+    /// Renders in passes until every pixel has `target_samples` samples,
+    /// calling `on_pass` after each pass (e.g. to write a checkpoint).
+    pub fn render_progressive(
+        &self,
+        world: &BVHCollection,
+        film: &mut Film,
+        target_samples: u32,
+        mut on_pass: impl FnMut(&Film),
+    ) {
+        let pass_samples = (target_samples / 100).max(1);
+
+        loop {
+            let taken = film.samples_taken();
+            if taken >= target_samples {
+                break;
+            }
+
+            self.render_pass(world, film, pass_samples.min(target_samples - taken));
+            on_pass(film);
+
+            print!(
+                "\rRendering... {:.0}%",
+                (film.samples_taken() as f32 / target_samples as f32) * 100.0
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn physical_lens_frames_its_sensor() {
+        let lens = PhysicalLens::default();
+        // 24 mm of a 3:2 full frame sensor seen through 50 mm.
+        assert!((lens.vfov(1.5) - 26.991).abs() < 1e-3);
+        let small = PhysicalLens {
+            sensor_width: 18.0,
+            ..lens
+        };
+        assert!(small.vfov(1.5) < lens.vfov(1.5));
+        assert!((lens.aperture_radius() - 0.05 / 2.8 / 2.0).abs() < 1e-7);
     }
 }
@@ -0,0 +1,369 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use glam::{Mat3, Vec3};
+
+/// RGB primaries and white point of a color space.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Primaries {
+    /// Rec. 709, shared with sRGB; D65 white.
+    Rec709,
+    /// DCI-P3 primaries with a D65 white, as used by Display P3.
+    P3,
+    /// ACES AP1, the primaries of ACEScg; ACES (roughly D60) white.
+    Ap1,
+}
+
+impl Primaries {
+    const ALL: [Self; 3] = [Self::Rec709, Self::P3, Self::Ap1];
+
+    /// Matrix taking linear Rec. 709 values to these primaries. White points
+    /// are matched with a Bradford adaptation.
+    fn matrix_from_rec709(self) -> Mat3 {
+        match self {
+            Self::Rec709 => Mat3::IDENTITY,
+            Self::P3 => from_rows([
+                [0.822_462, 0.177_538, 0.0],
+                [0.033_194, 0.966_806, 0.0],
+                [0.017_083, 0.072_397, 0.910_520],
+            ]),
+            Self::Ap1 => from_rows([
+                [0.613_097, 0.339_523, 0.047_379],
+                [0.070_194, 0.916_354, 0.013_452],
+                [0.020_616, 0.109_570, 0.869_815],
+            ]),
+        }
+    }
+
+    fn matrix_to_rec709(self) -> Mat3 {
+        match self {
+            Self::Rec709 => Mat3::IDENTITY,
+            Self::P3 => from_rows([
+                [1.224_94, -0.224_94, 0.0],
+                [-0.042_057, 1.042_057, 0.0],
+                [-0.019_638, -0.078_636, 1.098_274],
+            ]),
+            Self::Ap1 => from_rows([
+                [1.704_859, -0.621_716, -0.083_143],
+                [-0.130_077, 1.140_736, -0.010_659],
+                [-0.023_964, -0.128_975, 1.152_94],
+            ]),
+        }
+    }
+
+    /// Matrix converting linear values in these primaries to `target`.
+    pub fn conversion(self, target: Primaries) -> Mat3 {
+        if self == target {
+            Mat3::IDENTITY
+        } else {
+            target.matrix_from_rec709() * self.matrix_to_rec709()
+        }
+    }
+}
+
+fn from_rows(rows: [[f32; 3]; 3]) -> Mat3 {
+    Mat3::from_cols_array_2d(&rows).transpose()
+}
+
+/// A color space images and textures can be tagged with.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColorSpace {
+    /// Rec. 709 primaries with the sRGB transfer curve, what 8-bit images
+    /// usually hold.
+    Srgb,
+    LinearRec709,
+    /// Linear AP1 primaries, the ACES working space for rendering.
+    AcesCg,
+    /// P3 primaries with the sRGB transfer curve.
+    DisplayP3,
+}
+
+impl ColorSpace {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "srgb" => Some(Self::Srgb),
+            "rec709" | "linear" => Some(Self::LinearRec709),
+            "acescg" => Some(Self::AcesCg),
+            "p3" | "display-p3" => Some(Self::DisplayP3),
+            _ => None,
+        }
+    }
+
+    /// Name `from_name` reads back.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Srgb => "srgb",
+            Self::LinearRec709 => "rec709",
+            Self::AcesCg => "acescg",
+            Self::DisplayP3 => "p3",
+        }
+    }
+
+    pub fn primaries(self) -> Primaries {
+        match self {
+            Self::Srgb | Self::LinearRec709 => Primaries::Rec709,
+            Self::AcesCg => Primaries::Ap1,
+            Self::DisplayP3 => Primaries::P3,
+        }
+    }
+
+    /// Whether values are stored with the sRGB transfer curve rather than
+    /// linearly.
+    pub fn is_encoded(self) -> bool {
+        matches!(self, Self::Srgb | Self::DisplayP3)
+    }
+
+    /// Linear value of an `encoded` one, still in this space's primaries.
+    pub fn decode(self, encoded: Vec3) -> Vec3 {
+        if self.is_encoded() {
+            encoded.to_array().map(srgb_to_linear).into()
+        } else {
+            encoded
+        }
+    }
+
+    pub fn encode(self, linear: Vec3) -> Vec3 {
+        if self.is_encoded() {
+            linear.to_array().map(linear_to_srgb).into()
+        } else {
+            linear
+        }
+    }
+
+    /// A value in this space as linear RGB in the working space.
+    pub fn to_working(self, value: Vec3) -> Vec3 {
+        self.primaries().conversion(working_space()) * self.decode(value)
+    }
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Primaries of the linear RGB the renderer multiplies colors in. Colors
+/// written in scene descriptions are taken to be in this space.
+static WORKING_SPACE: AtomicU8 = AtomicU8::new(0);
+
+pub fn working_space() -> Primaries {
+    Primaries::ALL[WORKING_SPACE.load(Ordering::Relaxed) as usize]
+}
+
+/// Changes the working space for the whole process. Meant to be called once,
+/// before any scene is built or rendered.
+pub fn set_working_space(primaries: Primaries) {
+    let index = Primaries::ALL.iter().position(|&p| p == primaries).unwrap();
+    WORKING_SPACE.store(index as u8, Ordering::Relaxed);
+}
+
+/// How scene-linear light is squeezed into the displayable range.
+#[derive(Clone, Copy, Default)]
+pub enum ViewTransform {
+    /// Clips everything above one.
+    #[default]
+    Standard,
+    /// x / (1 + x), bringing any brightness below one.
+    Reinhard,
+    /// Narkowicz's fit of the ACES reference rendering transform, with a
+    /// filmic toe and shoulder.
+    Filmic,
+}
+
+impl ViewTransform {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "standard" => Some(Self::Standard),
+            "reinhard" => Some(Self::Reinhard),
+            "filmic" => Some(Self::Filmic),
+            _ => None,
+        }
+    }
+
+    fn apply(self, linear: Vec3) -> Vec3 {
+        let linear = linear.max(Vec3::ZERO);
+        match self {
+            Self::Standard => linear,
+            Self::Reinhard => linear / (Vec3::ONE + linear),
+            Self::Filmic => {
+                let x = linear * 0.6;
+                (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+            }
+        }
+    }
+}
+
+/// Turns working space radiance into output values, like an OCIO display
+/// and view pair.
+#[derive(Clone, Copy)]
+pub struct OutputTransform {
+    pub space: ColorSpace,
+    pub view: ViewTransform,
+    /// In stops, applied before the view transform.
+    pub exposure: f32,
+}
+
+impl Default for OutputTransform {
+    fn default() -> Self {
+        Self {
+            space: ColorSpace::Srgb,
+            view: ViewTransform::Standard,
+            exposure: 0.0,
+        }
+    }
+}
+
+impl OutputTransform {
+    /// Scene-linear value in the output primaries, for float formats.
+    pub fn linear(&self, working: Vec3) -> Vec3 {
+        working_space().conversion(self.space.primaries()) * working
+    }
+
+    /// Display-ready value in 0..1, for 8-bit formats.
+    pub fn display(&self, working: Vec3) -> Vec3 {
+        let exposed = self.linear(working) * self.exposure.exp2();
+        self.space
+            .encode(self.view.apply(exposed))
+            .clamp(Vec3::ZERO, Vec3::ONE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const D65: [f32; 2] = [0.3127, 0.3290];
+    const ACES_WHITE: [f32; 2] = [0.32168, 0.33767];
+
+    fn chromaticity_to_xyz([x, y]: [f32; 2]) -> Vec3 {
+        Vec3::new(x / y, 1.0, (1.0 - x - y) / y)
+    }
+
+    /// Matrix from RGB with primaries at the chromaticities `rgb` and
+    /// `white` as white to CIE XYZ.
+    fn rgb_to_xyz(rgb: [[f32; 2]; 3], white: [f32; 2]) -> Mat3 {
+        let primaries = Mat3::from_cols(
+            chromaticity_to_xyz(rgb[0]),
+            chromaticity_to_xyz(rgb[1]),
+            chromaticity_to_xyz(rgb[2]),
+        );
+        let scale = primaries.inverse() * chromaticity_to_xyz(white);
+        primaries * Mat3::from_diagonal(scale)
+    }
+
+    /// Bradford chromatic adaptation of XYZ from white `from` to `to`.
+    fn bradford(from: [f32; 2], to: [f32; 2]) -> Mat3 {
+        let cone = from_rows([
+            [0.8951, 0.2664, -0.1614],
+            [-0.7502, 1.7135, 0.0367],
+            [0.0389, -0.0685, 1.0296],
+        ]);
+        let gain = (cone * chromaticity_to_xyz(to)) / (cone * chromaticity_to_xyz(from));
+        cone.inverse() * Mat3::from_diagonal(gain) * cone
+    }
+
+    fn rec709_to_xyz() -> Mat3 {
+        rgb_to_xyz([[0.64, 0.33], [0.30, 0.60], [0.15, 0.06]], D65)
+    }
+
+    fn assert_matrix(found: Mat3, expected: Mat3) {
+        assert!(found.abs_diff_eq(expected, 2e-3), "{found} for {expected}");
+    }
+
+    #[test]
+    fn p3_matrix_follows_from_its_primaries() {
+        let p3_to_xyz = rgb_to_xyz([[0.680, 0.320], [0.265, 0.690], [0.150, 0.060]], D65);
+        let expected = p3_to_xyz.inverse() * rec709_to_xyz();
+        assert_matrix(Primaries::Rec709.conversion(Primaries::P3), expected);
+        assert_matrix(
+            Primaries::P3.conversion(Primaries::Rec709),
+            expected.inverse(),
+        );
+    }
+
+    #[test]
+    fn ap1_matrix_follows_from_its_primaries_and_white() {
+        let ap1_to_xyz = rgb_to_xyz([[0.713, 0.293], [0.165, 0.830], [0.128, 0.044]], ACES_WHITE);
+        let expected = ap1_to_xyz.inverse() * bradford(D65, ACES_WHITE) * rec709_to_xyz();
+        assert_matrix(Primaries::Rec709.conversion(Primaries::Ap1), expected);
+        assert_matrix(
+            Primaries::Ap1.conversion(Primaries::Rec709),
+            expected.inverse(),
+        );
+    }
+
+    #[test]
+    fn conversions_keep_white_and_undo_each_other() {
+        for from in Primaries::ALL {
+            for to in Primaries::ALL {
+                let conversion = from.conversion(to);
+                assert!((conversion * Vec3::ONE).abs_diff_eq(Vec3::ONE, 1e-4));
+                let round_trip = to.conversion(from) * conversion;
+                assert_matrix(round_trip, Mat3::IDENTITY);
+            }
+        }
+    }
+
+    #[test]
+    fn srgb_curve_matches_reference_values() {
+        let decoded = ColorSpace::Srgb.decode(Vec3::new(0.0, 0.5, 1.0));
+        assert!(
+            decoded.abs_diff_eq(Vec3::new(0.0, 0.214_041, 1.0), 1e-5),
+            "{decoded}"
+        );
+        for value in [0.001, 0.003_130_8, 0.18, 0.9] {
+            let linear = Vec3::splat(value);
+            let round_trip = ColorSpace::DisplayP3.decode(ColorSpace::DisplayP3.encode(linear));
+            assert!(round_trip.abs_diff_eq(linear, 1e-5), "{round_trip}");
+        }
+        assert_eq!(
+            ColorSpace::AcesCg.encode(Vec3::splat(0.18)),
+            Vec3::splat(0.18)
+        );
+    }
+
+    #[test]
+    fn output_transform_exposes_and_compresses() {
+        let reinhard = OutputTransform {
+            space: ColorSpace::LinearRec709,
+            view: ViewTransform::Reinhard,
+            exposure: 1.0,
+        };
+        assert!(reinhard
+            .display(Vec3::splat(0.5))
+            .abs_diff_eq(Vec3::splat(0.5), 1e-6));
+
+        let filmic = OutputTransform {
+            view: ViewTransform::Filmic,
+            ..OutputTransform::default()
+        };
+        let mut previous = 0.0;
+        for stop in -8..8 {
+            let value = filmic.display(Vec3::splat(2f32.powi(stop))).x;
+            assert!(value >= previous && value <= 1.0);
+            previous = value;
+        }
+        assert!(filmic.display(Vec3::splat(1e3)).x > 0.99);
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for space in [
+            ColorSpace::Srgb,
+            ColorSpace::LinearRec709,
+            ColorSpace::AcesCg,
+            ColorSpace::DisplayP3,
+        ] {
+            assert_eq!(ColorSpace::from_name(space.name()), Some(space));
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Views for finding out what is going on in a scene rather than rendering
+//! it: surface attributes shown as colors, heatmaps of the work the BVH
+//! does, mesh edges, and a white furnace test of the materials. A view
+//! takes the place of the path tracer for every camera sample.
+
+use glam::{vec3, Vec3};
+
+use crate::{
+    math::hash_seed,
+    object::{
+        bvh::{BVHCollection, TraversalCounts},
+        Object,
+    },
+    rendering::ray::Ray,
+};
+
+use super::aov::NO_ID;
+
+/// Node visits at the hot end of the heatmap.
+const MAX_NODE_VISITS: u32 = 256;
+/// Primitive tests at the hot end of the heatmap.
+const MAX_PRIMITIVE_TESTS: u32 = 128;
+/// How far wireframe lines reach to either side of an edge, in pixels.
+const WIRE_WIDTH: f32 = 0.75;
+const WIRE_COLOR: Vec3 = vec3(1.0, 0.6, 0.0);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DebugView {
+    /// Shading normals, each axis mapped from -1..1 to 0..1.
+    Normals,
+    /// Texture coordinates in red and green, repeating every unit.
+    Uv,
+    /// Distance to the first hit, white up close and a tenth as bright at
+    /// the distance of `look_at`.
+    Depth,
+    /// A color of its own for every material.
+    MaterialId,
+    /// A color of its own for every object.
+    ObjectId,
+    /// The color surfaces reflect, without any lighting.
+    Albedo,
+    /// Heatmap of the BVH nodes each camera ray visits.
+    NodeVisits,
+    /// Heatmap of the primitives each camera ray is tested against.
+    PrimitiveTests,
+    /// The path traced image with the edges of mesh triangles drawn over it.
+    Wireframe,
+    /// Every surface white under a uniformly white sky, with the lights
+    /// taken out. Materials that keep all the light they scatter disappear
+    /// into the background; wherever light gets lost shows up darker.
+    WhiteFurnace,
+}
+
+impl DebugView {
+    pub const ALL: [Self; 10] = [
+        Self::Normals,
+        Self::Uv,
+        Self::Depth,
+        Self::MaterialId,
+        Self::ObjectId,
+        Self::Albedo,
+        Self::NodeVisits,
+        Self::PrimitiveTests,
+        Self::Wireframe,
+        Self::WhiteFurnace,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|view| view.name() == name)
+    }
+
+    /// Name `from_name` reads back.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Normals => "normals",
+            Self::Uv => "uv",
+            Self::Depth => "depth",
+            Self::MaterialId => "material-id",
+            Self::ObjectId => "object-id",
+            Self::Albedo => "albedo",
+            Self::NodeVisits => "node-visits",
+            Self::PrimitiveTests => "primitive-tests",
+            Self::Wireframe => "wireframe",
+            Self::WhiteFurnace => "furnace",
+        }
+    }
+
+    /// Color of what `ray` sees in `world` for the views that only look at
+    /// the first hit; anything that isn't hit is black. `depth_scale` is
+    /// the distance the depth view shows at a tenth of full brightness.
+    pub fn first_hit_color(self, ray: Ray, world: &BVHCollection, depth_scale: f32) -> Vec3 {
+        TraversalCounts::take();
+        let hit = world.hit(ray, &(0.001..f32::MAX));
+        let counts = TraversalCounts::take();
+
+        match self {
+            Self::NodeVisits => return heat(counts.node_visits, MAX_NODE_VISITS),
+            Self::PrimitiveTests => return heat(counts.primitive_tests, MAX_PRIMITIVE_TESTS),
+            _ => {}
+        }
+        let Some(hit) = hit else {
+            return Vec3::ZERO;
+        };
+
+        match self {
+            Self::Normals => 0.5 * (hit.normal + 1.0),
+            Self::Uv => vec3(hit.u.rem_euclid(1.0), hit.v.rem_euclid(1.0), 0.0),
+            Self::Depth => {
+                let distance = (hit.point - ray.origin).length();
+                Vec3::splat(0.1_f32.powf(distance / depth_scale))
+            }
+            Self::MaterialId => false_color(
+                hit.material_id()
+                    .or_else(|| world.material_id(hit.material))
+                    .unwrap_or(NO_ID),
+            ),
+            Self::ObjectId => false_color(world.object_id(hit.object).unwrap_or(NO_ID)),
+            Self::Albedo => match hit.material.scatter(ray, &hit) {
+                Some(scatter) => scatter.attenuation.0,
+                // Lights show the color they give off instead.
+                None => {
+                    let emission = hit.material.emit(hit.u, hit.v, hit.point).0;
+                    emission / emission.max_element().max(1.0)
+                }
+            },
+            _ => unreachable!("{} looks further than the first hit", self.name()),
+        }
+    }
+}
+
+/// Color of a wireframe line if `ray` first hits a mesh close enough to
+/// one of its triangles' edges, `None` otherwise. `pixel_size` is how wide
+/// a pixel is at a distance of one from the camera.
+pub fn wire_color(ray: Ray, world: &BVHCollection, pixel_size: f32) -> Option<Vec3> {
+    let hit = world.hit(ray, &(0.001..f32::MAX))?;
+    let distance = hit.t * ray.direction.length();
+    (hit.edge_distance? < WIRE_WIDTH * pixel_size * distance).then_some(WIRE_COLOR)
+}
+
+/// How much of a white sky reaches the camera along `ray`, bouncing off
+/// surfaces that scatter like their materials but absorb nothing, and
+/// passing straight through lights. Paths that are still going after
+/// `max_bounces` count as lost.
+pub fn white_furnace(mut ray: Ray, world: &BVHCollection, max_bounces: u32) -> Vec3 {
+    for _ in 0..max_bounces {
+        let Some(hit) = world.hit(ray, &(0.001..f32::MAX)) else {
+            return Vec3::ONE;
+        };
+        match hit.material.scatter(ray, &hit) {
+            Some(scatter) => match scatter.new_ray {
+                Some(new_ray) => ray = new_ray,
+                // The material lit the surface itself.
+                None => return Vec3::ONE,
+            },
+            None => ray = Ray::new(hit.point, ray.direction, ray.time),
+        }
+    }
+    Vec3::ZERO
+}
+
+/// Bright color picked at random for `id`, the same every time. Gray for
+/// things without an ID.
+fn false_color(id: u32) -> Vec3 {
+    if id == NO_ID {
+        return Vec3::splat(0.5);
+    }
+    let hash = hash_seed(&[id as u64]);
+    let channel = |shift: u32| 0.2 + 0.8 * ((hash >> shift) & 0xFF) as f32 / 255.0;
+    vec3(channel(0), channel(8), channel(16))
+}
+
+/// Color of `count` on a scale from blue through green and yellow to red,
+/// logarithmic to show small counts apart and red from `max` on.
+fn heat(count: u32, max: u32) -> Vec3 {
+    const RAMP: [Vec3; 5] = [
+        vec3(0.0, 0.0, 1.0),
+        vec3(0.0, 1.0, 1.0),
+        vec3(0.0, 1.0, 0.0),
+        vec3(1.0, 1.0, 0.0),
+        vec3(1.0, 0.0, 0.0),
+    ];
+
+    let level = ((1.0 + count as f32).ln() / (1.0 + max as f32).ln()).min(1.0);
+    let position = level * (RAMP.len() - 1) as f32;
+    let index = (position as usize).min(RAMP.len() - 2);
+    RAMP[index].lerp(RAMP[index + 1], position - index as f32)
+}
@@ -0,0 +1,237 @@
+use glam::Vec3;
+use rayon::prelude::*;
+
+/// Per-pixel features of the first hit the filter uses to find edges.
+pub struct Guides<'a> {
+    pub albedo: &'a [Vec3],
+    pub normal: &'a [Vec3],
+    /// Infinite where nothing was hit.
+    pub depth: &'a [f32],
+}
+
+pub struct DenoiseSettings {
+    /// Number of filter passes; each one doubles the footprint.
+    pub iterations: u32,
+    /// How far apart tone mapped illumination may be to still be averaged,
+    /// halved every pass.
+    pub color_sigma: f32,
+    /// Sharpness of the cutoff between differently oriented surfaces.
+    pub normal_power: f32,
+    /// Allowed relative depth change per pixel of distance.
+    pub depth_sigma: f32,
+    pub albedo_sigma: f32,
+}
+
+impl Default for DenoiseSettings {
+    fn default() -> Self {
+        Self {
+            iterations: 5,
+            color_sigma: 0.6,
+            normal_power: 64.0,
+            depth_sigma: 0.05,
+            albedo_sigma: 0.1,
+        }
+    }
+}
+
+/// B3 spline weights of the 5x5 à-trous kernel along one axis.
+const KERNEL: [f32; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+/// Edge-avoiding à-trous wavelet filter (Dammertz et al. 2010).
+///
+/// The color is divided by the albedo first so that only the lighting is
+/// blurred and textures stay sharp, then multiplied back at the end.
+pub fn denoise(
+    width: usize,
+    height: usize,
+    color: &[Vec3],
+    guides: &Guides,
+    settings: &DenoiseSettings,
+) -> Vec<Vec3> {
+    let albedo = |i: usize| {
+        let albedo = guides.albedo[i];
+        // Lights and the sky have no albedo; filter their color directly.
+        if albedo.max_element() <= 1e-3 {
+            Vec3::ONE
+        } else {
+            albedo.max(Vec3::splat(1e-3))
+        }
+    };
+
+    let mut illumination: Vec<Vec3> = (0..color.len()).map(|i| color[i] / albedo(i)).collect();
+    let mut filtered = vec![Vec3::ZERO; color.len()];
+
+    for iteration in 0..settings.iterations {
+        let step = 1isize << iteration;
+        let color_sigma = settings.color_sigma / (1 << iteration) as f32;
+
+        filtered
+            .par_chunks_mut(width)
+            .enumerate()
+            .for_each(|(y, row)| {
+                for (x, out) in row.iter_mut().enumerate() {
+                    let p = y * width + x;
+                    let center = tone_map(illumination[p]);
+
+                    let mut sum = Vec3::ZERO;
+                    let mut weights = 0.0;
+                    for (j, ky) in KERNEL.iter().enumerate() {
+                        let qy = y as isize + (j as isize - 2) * step;
+                        if qy < 0 || qy >= height as isize {
+                            continue;
+                        }
+                        for (i, kx) in KERNEL.iter().enumerate() {
+                            let qx = x as isize + (i as isize - 2) * step;
+                            if qx < 0 || qx >= width as isize {
+                                continue;
+                            }
+                            let q = qy as usize * width + qx as usize;
+                            let distance =
+                                ((i as isize - 2).abs().max((j as isize - 2).abs()) * step) as f32;
+
+                            let color_distance = (tone_map(illumination[q]) - center).length();
+                            let weight = kx
+                                * ky
+                                * (-color_distance * color_distance
+                                    / (2.0 * color_sigma * color_sigma))
+                                    .exp()
+                                * normal_weight(guides, p, q, settings.normal_power)
+                                * depth_weight(guides, p, q, distance, settings.depth_sigma)
+                                * albedo_weight(guides, p, q, settings.albedo_sigma);
+
+                            sum += weight * illumination[q];
+                            weights += weight;
+                        }
+                    }
+
+                    *out = if weights > 0.0 {
+                        sum / weights
+                    } else {
+                        illumination[p]
+                    };
+                }
+            });
+
+        std::mem::swap(&mut illumination, &mut filtered);
+    }
+
+    (0..color.len())
+        .map(|i| illumination[i] * albedo(i))
+        .collect()
+}
+
+/// Compresses bright values so one firefly doesn't dominate the distances.
+fn tone_map(color: Vec3) -> Vec3 {
+    color / (Vec3::ONE + color)
+}
+
+fn normal_weight(guides: &Guides, p: usize, q: usize, power: f32) -> f32 {
+    let (a, b) = (guides.normal[p], guides.normal[q]);
+    if a == Vec3::ZERO || b == Vec3::ZERO {
+        return if a == b { 1.0 } else { 0.0 };
+    }
+    a.normalize().dot(b.normalize()).max(0.0).powf(power)
+}
+
+fn depth_weight(guides: &Guides, p: usize, q: usize, distance: f32, sigma: f32) -> f32 {
+    let (a, b) = (guides.depth[p], guides.depth[q]);
+    if a.is_infinite() || b.is_infinite() {
+        return if a == b { 1.0 } else { 0.0 };
+    }
+    let relative = (a - b).abs() / a.max(1e-3);
+    (-relative / (sigma * distance.max(1.0))).exp()
+}
+
+fn albedo_weight(guides: &Guides, p: usize, q: usize, sigma: f32) -> f32 {
+    let difference = (guides.albedo[p] - guides.albedo[q]).length_squared();
+    (-difference / (sigma * sigma)).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: usize = 32;
+
+    /// Guides of a flat grey wall facing the camera, one unit away.
+    fn wall() -> (Vec<Vec3>, Vec<Vec3>, Vec<f32>) {
+        let count = SIZE * SIZE;
+        (
+            vec![Vec3::splat(0.5); count],
+            vec![Vec3::Z; count],
+            vec![1.0; count],
+        )
+    }
+
+    fn filter(color: &[Vec3], albedo: &[Vec3], normal: &[Vec3], depth: &[f32]) -> Vec<Vec3> {
+        let guides = Guides {
+            albedo,
+            normal,
+            depth,
+        };
+        denoise(SIZE, SIZE, color, &guides, &DenoiseSettings::default())
+    }
+
+    /// Noise around 0.25 that repeats only every 13 pixels.
+    fn noisy(i: usize) -> Vec3 {
+        Vec3::splat(0.25 + 0.1 * ((i * 7919 % 13) as f32 / 6.0 - 1.0))
+    }
+
+    fn variance(colors: &[Vec3]) -> f32 {
+        let mean = colors.iter().map(|c| c.x).sum::<f32>() / colors.len() as f32;
+        colors.iter().map(|c| (c.x - mean).powi(2)).sum::<f32>() / colors.len() as f32
+    }
+
+    #[test]
+    fn leaves_a_clean_image_alone() {
+        let (albedo, normal, depth) = wall();
+        let color = vec![Vec3::splat(0.25); SIZE * SIZE];
+        for pixel in filter(&color, &albedo, &normal, &depth) {
+            assert!(pixel.abs_diff_eq(Vec3::splat(0.25), 1e-5), "{pixel}");
+        }
+    }
+
+    #[test]
+    fn smooths_noise_on_a_flat_surface() {
+        let (albedo, normal, depth) = wall();
+        let color: Vec<Vec3> = (0..SIZE * SIZE).map(noisy).collect();
+        let denoised = filter(&color, &albedo, &normal, &depth);
+        assert!(variance(&denoised) < 0.1 * variance(&color));
+    }
+
+    #[test]
+    fn keeps_edges_between_surfaces() {
+        // The left half faces the camera and the right half sideways, lit
+        // much more brightly.
+        let (albedo, mut normal, depth) = wall();
+        let mut color: Vec<Vec3> = (0..SIZE * SIZE).map(noisy).collect();
+        for i in 0..SIZE * SIZE {
+            if i % SIZE >= SIZE / 2 {
+                normal[i] = Vec3::X;
+                color[i] += Vec3::ONE;
+            }
+        }
+        let denoised = filter(&color, &albedo, &normal, &depth);
+        for y in 0..SIZE {
+            let left = denoised[y * SIZE + SIZE / 2 - 1].x;
+            let right = denoised[y * SIZE + SIZE / 2].x;
+            assert!(left < 0.4 && right > 1.1, "{left} {right}");
+        }
+    }
+
+    #[test]
+    fn keeps_texture_detail() {
+        // A checkerboard under even light.
+        let (mut albedo, normal, depth) = wall();
+        for (i, albedo) in albedo.iter_mut().enumerate() {
+            if (i + i / SIZE).is_multiple_of(2) {
+                *albedo = Vec3::splat(0.9);
+            }
+        }
+        let color: Vec<Vec3> = albedo.iter().map(|&albedo| albedo * 0.5).collect();
+        let denoised = filter(&color, &albedo, &normal, &depth);
+        for (found, expected) in denoised.iter().zip(&color) {
+            assert!(found.abs_diff_eq(*expected, 1e-5), "{found} for {expected}");
+        }
+    }
+}
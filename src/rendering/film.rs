@@ -0,0 +1,472 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use glam::{Vec2, Vec3};
+use image::{ImageBuffer, Rgb, Rgb32FImage};
+
+use super::{
+    aov::{AovBuffer, AovPixel},
+    colorspace::OutputTransform,
+    denoise::{denoise, DenoiseSettings, Guides},
+};
+
+const CHECKPOINT_MAGIC: &[u8; 8] = b"RTXCKPT\0";
+/// Follows the magic, and goes up whenever the layout changes. Checkpoints of
+/// any other version are refused rather than converted.
+const CHECKPOINT_VERSION: u32 = 3;
+/// Bytes a pixel takes in a checkpoint, and the render passes of a pixel and
+/// one of its light groups when they are kept.
+const PIXEL_BYTES: u64 = 20;
+const AOV_PIXEL_BYTES: u64 = 96;
+const LIGHT_GROUP_BYTES: u64 = 12;
+
+/// Filter weighted sum of the samples splatted into a pixel. `samples`
+/// counts only the samples taken within the pixel itself.
+#[derive(Clone, Copy, Default)]
+pub struct FilmPixel {
+    pub sum: Vec3,
+    pub weight: f32,
+    pub samples: u32,
+}
+
+impl FilmPixel {
+    pub fn color(&self) -> Vec3 {
+        if self.weight <= 0.0 {
+            Vec3::ZERO
+        } else {
+            self.sum / self.weight
+        }
+    }
+
+    pub fn add(&mut self, other: &FilmPixel) {
+        self.sum += other.sum;
+        self.weight += other.weight;
+        self.samples += other.samples;
+    }
+}
+
+/// Accumulation buffer holding the running filter weighted sum of radiance
+/// and the number of samples taken for every pixel.
+///
+/// The random sequence of a pixel is derived from `seed`, its coordinates and
+/// its sample count, so a film restored from a checkpoint continues exactly
+/// where the interrupted render stopped.
+pub struct Film {
+    width: u32,
+    height: u32,
+    seed: u64,
+    /// Identifies the scene rendered into the film, so a checkpoint isn't
+    /// resumed with another one.
+    scene: u64,
+    /// Identifies the camera settings, which can be overridden when the
+    /// render is started, so a checkpoint isn't resumed with others.
+    camera: u64,
+    pixels: Vec<FilmPixel>,
+    aovs: Option<AovBuffer>,
+}
+
+impl Film {
+    pub fn new(width: u32, height: u32, seed: u64) -> Self {
+        Self {
+            width,
+            height,
+            seed,
+            scene: 0,
+            camera: 0,
+            pixels: vec![FilmPixel::default(); width as usize * height as usize],
+            aovs: None,
+        }
+    }
+
+    pub fn with_scene(mut self, scene: u64) -> Self {
+        self.scene = scene;
+        self
+    }
+
+    pub fn with_camera(mut self, camera: u64) -> Self {
+        self.camera = camera;
+        self
+    }
+
+    /// Starts accumulating render passes, with `light_groups` per-light passes.
+    pub fn enable_aovs(&mut self, light_groups: usize) {
+        self.aovs = Some(AovBuffer::new(self.pixels.len(), light_groups));
+    }
+
+    pub fn aovs(&self) -> Option<&AovBuffer> {
+        self.aovs.as_ref()
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn scene(&self) -> u64 {
+        self.scene
+    }
+
+    pub fn camera(&self) -> u64 {
+        self.camera
+    }
+
+    pub fn pixels_mut(&mut self) -> (&mut [FilmPixel], Option<&mut AovBuffer>) {
+        (&mut self.pixels, self.aovs.as_mut())
+    }
+
+    /// Adds a band of rendered pixels starting at `first_row` to the film.
+    pub fn merge_rows(&mut self, first_row: u32, pixels: &[FilmPixel]) {
+        let start = (first_row * self.width) as usize;
+        for (pixel, tile_pixel) in self.pixels[start..].iter_mut().zip(pixels) {
+            pixel.add(tile_pixel);
+        }
+    }
+
+    /// Number of samples every pixel has received so far.
+    pub fn samples_taken(&self) -> u32 {
+        self.pixels.iter().map(|p| p.samples).min().unwrap_or(0)
+    }
+
+    /// Average radiance of every pixel, row by row.
+    pub fn colors(&self) -> Vec<Vec3> {
+        self.pixels.iter().map(FilmPixel::color).collect()
+    }
+
+    /// Colors with the noise filtered out, using the albedo, normal and
+    /// depth passes as guides. `None` if the film has no render passes.
+    pub fn denoised(&self, settings: &DenoiseSettings) -> Option<Vec<Vec3>> {
+        let aovs = self.aovs.as_ref()?;
+        let albedo: Vec<_> = aovs.pixels.iter().map(AovPixel::average_albedo).collect();
+        let normal: Vec<_> = aovs.pixels.iter().map(AovPixel::average_normal).collect();
+        let depth: Vec<_> = aovs.pixels.iter().map(AovPixel::average_depth).collect();
+
+        Some(denoise(
+            self.width as usize,
+            self.height as usize,
+            &self.colors(),
+            &Guides {
+                albedo: &albedo,
+                normal: &normal,
+                depth: &depth,
+            },
+            settings,
+        ))
+    }
+
+    pub fn to_image(&self, transform: &OutputTransform) -> image::RgbImage {
+        colors_to_image(self.width, self.height, &self.colors(), transform)
+    }
+
+    /// Linear radiance, for formats such as OpenEXR.
+    pub fn to_float_image(&self, transform: &OutputTransform) -> Rgb32FImage {
+        colors_to_float_image(self.width, self.height, &self.colors(), transform)
+    }
+
+    /// Writes the film to `path`, going through a temporary file so an
+    /// interrupted write never destroys the previous checkpoint.
+    pub fn save_checkpoint<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let temp_path = path.with_extension("tmp");
+
+        let mut writer = BufWriter::new(File::create(&temp_path)?);
+        self.write_checkpoint(&mut writer)?;
+        writer.into_inner()?.sync_all()?;
+
+        fs::rename(temp_path, path)
+    }
+
+    pub fn load_checkpoint<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read_checkpoint(BufReader::new(File::open(path)?))
+    }
+
+    pub fn write_checkpoint(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_all(CHECKPOINT_MAGIC)?;
+        writer.write_all(&CHECKPOINT_VERSION.to_le_bytes())?;
+        writer.write_all(&self.width.to_le_bytes())?;
+        writer.write_all(&self.height.to_le_bytes())?;
+        writer.write_all(&self.seed.to_le_bytes())?;
+        writer.write_all(&self.scene.to_le_bytes())?;
+        writer.write_all(&self.camera.to_le_bytes())?;
+        // Light group count plus one, or zero without render passes.
+        let aov_flag = self
+            .aovs
+            .as_ref()
+            .map_or(0, |a| a.light_group_count as u32 + 1);
+        writer.write_all(&aov_flag.to_le_bytes())?;
+        for pixel in &self.pixels {
+            write_vec3(&mut writer, pixel.sum)?;
+            writer.write_all(&pixel.weight.to_le_bytes())?;
+            writer.write_all(&pixel.samples.to_le_bytes())?;
+        }
+        if let Some(aovs) = &self.aovs {
+            for pixel in &aovs.pixels {
+                write_vec3(&mut writer, pixel.albedo)?;
+                write_vec3(&mut writer, pixel.normal)?;
+                write_vec3(&mut writer, pixel.position)?;
+                writer.write_all(&pixel.depth.to_le_bytes())?;
+                writer.write_all(&pixel.uv.x.to_le_bytes())?;
+                writer.write_all(&pixel.uv.y.to_le_bytes())?;
+                writer.write_all(&pixel.hits.to_le_bytes())?;
+                writer.write_all(&pixel.object_id.to_le_bytes())?;
+                writer.write_all(&pixel.material_id.to_le_bytes())?;
+                write_vec3(&mut writer, pixel.emission)?;
+                write_vec3(&mut writer, pixel.direct)?;
+                write_vec3(&mut writer, pixel.indirect)?;
+            }
+            for &sum in &aovs.light_groups {
+                write_vec3(&mut writer, sum)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read_checkpoint(mut reader: impl Read) -> io::Result<Self> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != CHECKPOINT_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a render checkpoint",
+            ));
+        }
+        let version = read_u32(&mut reader)?;
+        if version != CHECKPOINT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("checkpoint version {version}, expected {CHECKPOINT_VERSION}"),
+            ));
+        }
+
+        let width = read_u32(&mut reader)?;
+        let height = read_u32(&mut reader)?;
+        let seed = read_u64(&mut reader)?;
+        let scene = read_u64(&mut reader)?;
+        let camera = read_u64(&mut reader)?;
+        let aov_flag = read_u32(&mut reader)?;
+
+        // The size in the header isn't trusted with an allocation until the
+        // pixels it promises have been read.
+        let size = checkpoint_size(width, height, aov_flag).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("checkpoint of {width}x{height} pixels is too large"),
+            )
+        })?;
+        let mut body = Vec::new();
+        (&mut reader).take(size).read_to_end(&mut body)?;
+        if body.len() as u64 != size || reader.read(&mut [0])? != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("checkpoint of {width}x{height} pixels should hold {size} bytes of them"),
+            ));
+        }
+        let mut reader = &body[..];
+
+        let mut film = Self::new(width, height, seed)
+            .with_scene(scene)
+            .with_camera(camera);
+        for pixel in film.pixels.iter_mut() {
+            pixel.sum = read_vec3(&mut reader)?;
+            pixel.weight = read_f32(&mut reader)?;
+            pixel.samples = read_u32(&mut reader)?;
+        }
+
+        if aov_flag > 0 {
+            film.enable_aovs(aov_flag as usize - 1);
+            let aovs = film.aovs.as_mut().unwrap();
+            for pixel in aovs.pixels.iter_mut() {
+                *pixel = AovPixel {
+                    albedo: read_vec3(&mut reader)?,
+                    normal: read_vec3(&mut reader)?,
+                    position: read_vec3(&mut reader)?,
+                    depth: read_f32(&mut reader)?,
+                    uv: Vec2::new(read_f32(&mut reader)?, read_f32(&mut reader)?),
+                    hits: read_u32(&mut reader)?,
+                    object_id: read_u32(&mut reader)?,
+                    material_id: read_u32(&mut reader)?,
+                    emission: read_vec3(&mut reader)?,
+                    direct: read_vec3(&mut reader)?,
+                    indirect: read_vec3(&mut reader)?,
+                };
+            }
+            for sum in aovs.light_groups.iter_mut() {
+                *sum = read_vec3(&mut reader)?;
+            }
+        }
+
+        Ok(film)
+    }
+
+    /// Writes the render passes next to the beauty image at `path`, with
+    /// the color passes in the output primaries of `transform`.
+    pub fn save_aovs<P: AsRef<Path>>(
+        &self,
+        path: P,
+        transform: &OutputTransform,
+    ) -> io::Result<()> {
+        match &self.aovs {
+            Some(aovs) => aovs.save(path.as_ref(), self.width, self.height, transform, |index| {
+                self.pixels[index].samples
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// 8-bit image of working space `colors`, given row by row, as shown by
+/// `transform`.
+pub fn colors_to_image(
+    width: u32,
+    height: u32,
+    colors: &[Vec3],
+    transform: &OutputTransform,
+) -> image::RgbImage {
+    ImageBuffer::from_fn(width, height, |x, y| {
+        let c = transform.display(colors[(y * width + x) as usize]);
+        Rgb((c * 255.0).round().to_array().map(|channel| channel as u8))
+    })
+}
+
+pub fn colors_to_float_image(
+    width: u32,
+    height: u32,
+    colors: &[Vec3],
+    transform: &OutputTransform,
+) -> Rgb32FImage {
+    ImageBuffer::from_fn(width, height, |x, y| {
+        Rgb(transform
+            .linear(colors[(y * width + x) as usize])
+            .to_array())
+    })
+}
+
+/// Bytes of pixels following the header of a checkpoint, or `None` if it
+/// doesn't fit in memory.
+fn checkpoint_size(width: u32, height: u32, aov_flag: u32) -> Option<u64> {
+    let pixels = u64::from(width).checked_mul(u64::from(height))?;
+    let mut pixel_bytes = PIXEL_BYTES;
+    if aov_flag > 0 {
+        let light_groups = u64::from(aov_flag - 1) * LIGHT_GROUP_BYTES;
+        pixel_bytes += AOV_PIXEL_BYTES + light_groups;
+    }
+    let size = pixels.checked_mul(pixel_bytes)?;
+    usize::try_from(size).ok()?;
+    Some(size)
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn write_vec3(writer: &mut impl Write, value: Vec3) -> io::Result<()> {
+    for channel in value.to_array() {
+        writer.write_all(&channel.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn read_vec3(reader: &mut impl Read) -> io::Result<Vec3> {
+    let x = read_f32(reader)?;
+    let y = read_f32(reader)?;
+    let z = read_f32(reader)?;
+    Ok(Vec3::new(x, y, z))
+}
+
+fn read_f32(reader: &mut impl Read) -> io::Result<f32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(f32::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkpoint(film: &Film) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        film.write_checkpoint(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn checkpoint_round_trip() {
+        let mut film = Film::new(3, 2, 99).with_scene(1234).with_camera(5678);
+        film.enable_aovs(2);
+        let (pixels, aovs) = film.pixels_mut();
+        for (index, pixel) in pixels.iter_mut().enumerate() {
+            *pixel = FilmPixel {
+                sum: Vec3::splat(index as f32 * 0.5),
+                weight: index as f32 + 0.25,
+                samples: index as u32 * 3,
+            };
+        }
+        let aovs = aovs.unwrap();
+        aovs.pixels[4].albedo = Vec3::new(0.1, 0.2, 0.3);
+        aovs.pixels[4].uv = Vec2::new(0.5, 0.75);
+        aovs.pixels[4].object_id = 12;
+        aovs.light_groups[11] = Vec3::new(4.0, 5.0, 6.0);
+
+        let loaded = Film::read_checkpoint(&checkpoint(&film)[..]).unwrap();
+
+        assert_eq!((loaded.width(), loaded.height()), (3, 2));
+        assert_eq!(loaded.seed(), 99);
+        assert_eq!(loaded.scene(), 1234);
+        assert_eq!(loaded.camera(), 5678);
+        for (read, written) in loaded.pixels.iter().zip(&film.pixels) {
+            assert_eq!(read.sum, written.sum);
+            assert_eq!(read.weight, written.weight);
+            assert_eq!(read.samples, written.samples);
+        }
+        let aovs = loaded.aovs().unwrap();
+        assert_eq!(aovs.light_group_count, 2);
+        assert_eq!(aovs.pixels[4].albedo, Vec3::new(0.1, 0.2, 0.3));
+        assert_eq!(aovs.pixels[4].uv, Vec2::new(0.5, 0.75));
+        assert_eq!(aovs.pixels[4].object_id, 12);
+        assert_eq!(aovs.light_groups[11], Vec3::new(4.0, 5.0, 6.0));
+    }
+
+    #[test]
+    fn refuses_other_checkpoint_versions() {
+        let mut bytes = checkpoint(&Film::new(1, 1, 0));
+        bytes[8..12].copy_from_slice(&(CHECKPOINT_VERSION + 1).to_le_bytes());
+        let error = Film::read_checkpoint(&bytes[..]).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn refuses_checkpoints_of_the_wrong_size() {
+        let bytes = checkpoint(&Film::new(2, 2, 0));
+        let truncated = Film::read_checkpoint(&bytes[..bytes.len() - 1])
+            .err()
+            .unwrap();
+        let trailing = Film::read_checkpoint(&[&bytes[..], &[0]].concat()[..])
+            .err()
+            .unwrap();
+        // A header claiming a huge film in a few bytes.
+        let mut huge = bytes.clone();
+        huge[12..20].copy_from_slice(&[0xff; 8]);
+        let huge = Film::read_checkpoint(&huge[..]).err().unwrap();
+
+        assert_eq!(truncated.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(trailing.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(huge.kind(), io::ErrorKind::InvalidData);
+    }
+}
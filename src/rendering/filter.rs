@@ -0,0 +1,189 @@
+use std::f32::consts::PI;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FilterKind {
+    Box,
+    Tent,
+    Gaussian,
+    /// Mitchell-Netravali with B = C = 1/3.
+    Mitchell,
+    /// Sinc windowed by a wider sinc, with as many lobes as the radius.
+    Lanczos,
+}
+
+/// Reconstruction filter every sample is splatted through onto the pixels
+/// whose centers lie within `radius` of it, measured in pixels.
+#[derive(Clone, Copy)]
+pub struct Filter {
+    pub kind: FilterKind,
+    pub radius: f32,
+}
+
+impl Default for Filter {
+    /// A box over the pixel the sample falls in, i.e. plain averaging.
+    fn default() -> Self {
+        Self::new(FilterKind::Box)
+    }
+}
+
+impl FilterKind {
+    pub const ALL: [Self; 5] = [
+        Self::Box,
+        Self::Tent,
+        Self::Gaussian,
+        Self::Mitchell,
+        Self::Lanczos,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Name `from_name` reads back.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Box => "box",
+            Self::Tent => "tent",
+            Self::Gaussian => "gaussian",
+            Self::Mitchell => "mitchell",
+            Self::Lanczos => "lanczos",
+        }
+    }
+}
+
+impl Filter {
+    /// Filter with the radius commonly used for `kind`.
+    pub fn new(kind: FilterKind) -> Self {
+        let radius = match kind {
+            FilterKind::Box => 0.5,
+            FilterKind::Tent => 1.0,
+            FilterKind::Gaussian => 1.5,
+            FilterKind::Mitchell => 2.0,
+            FilterKind::Lanczos => 3.0,
+        };
+        Self { kind, radius }
+    }
+
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    /// Weight of a sample `dx`, `dy` pixels away from a pixel center.
+    pub fn weight(&self, dx: f32, dy: f32) -> f32 {
+        self.evaluate(dx) * self.evaluate(dy)
+    }
+
+    /// How many pixels beyond its own one a sample can reach.
+    pub fn margin(&self) -> u32 {
+        (self.radius + 0.5).ceil() as u32
+    }
+
+    fn evaluate(&self, x: f32) -> f32 {
+        let x = x.abs();
+        if x > self.radius {
+            return 0.0;
+        }
+
+        match self.kind {
+            FilterKind::Box => 1.0,
+            FilterKind::Tent => self.radius - x,
+            FilterKind::Gaussian => {
+                const ALPHA: f32 = 2.0;
+                ((-ALPHA * x * x).exp() - (-ALPHA * self.radius * self.radius).exp()).max(0.0)
+            }
+            FilterKind::Mitchell => mitchell(2.0 * x / self.radius),
+            FilterKind::Lanczos => sinc(x) * sinc(x / self.radius),
+        }
+    }
+}
+
+fn mitchell(x: f32) -> f32 {
+    const B: f32 = 1.0 / 3.0;
+    const C: f32 = 1.0 / 3.0;
+
+    if x > 1.0 {
+        ((-B - 6.0 * C) * x * x * x
+            + (6.0 * B + 30.0 * C) * x * x
+            + (-12.0 * B - 48.0 * C) * x
+            + (8.0 * B + 24.0 * C))
+            / 6.0
+    } else {
+        ((12.0 - 9.0 * B - 6.0 * C) * x * x * x
+            + (-18.0 + 12.0 * B + 6.0 * C) * x * x
+            + (6.0 - 2.0 * B))
+            / 6.0
+    }
+}
+
+fn sinc(x: f32) -> f32 {
+    if x < 1e-5 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_peak_in_the_middle_and_end_at_their_radius() {
+        for kind in FilterKind::ALL {
+            let filter = Filter::new(kind);
+            let center = filter.weight(0.0, 0.0);
+            assert!(center > 0.0, "{}", kind.name());
+            for step in 1..=20 {
+                let x = filter.radius * step as f32 / 20.0;
+                assert!(filter.weight(x, 0.0) <= center, "{} at {x}", kind.name());
+                assert_eq!(filter.weight(x, 0.3), filter.weight(-x, -0.3));
+            }
+            assert_eq!(filter.weight(filter.radius + 0.01, 0.0), 0.0);
+            assert_eq!(filter.weight(0.0, -filter.radius - 0.01), 0.0);
+        }
+    }
+
+    #[test]
+    fn sharpening_filters_have_negative_lobes() {
+        let mitchell = Filter::new(FilterKind::Mitchell);
+        assert!(mitchell.weight(1.5, 0.0) < 0.0);
+        let lanczos = Filter::new(FilterKind::Lanczos);
+        assert!(lanczos.weight(1.5, 0.0) < 0.0);
+        assert!(lanczos.weight(2.5, 0.0) > 0.0);
+    }
+
+    #[test]
+    fn samples_a_pixel_apart_add_up_evenly() {
+        // These filters reproduce a flat image without ripples when every
+        // pixel gets the same samples.
+        for kind in [FilterKind::Box, FilterKind::Tent, FilterKind::Mitchell] {
+            let filter = Filter::new(kind);
+            let sum = |offset: f32| -> f32 {
+                (-3..=3)
+                    .map(|pixel| filter.weight(offset + pixel as f32, 0.0))
+                    .sum()
+            };
+            for step in 0..10 {
+                let offset = step as f32 / 10.0 - 0.45;
+                assert!((sum(offset) - sum(0.0)).abs() < 1e-5, "{}", kind.name());
+            }
+        }
+    }
+
+    #[test]
+    fn margin_covers_the_radius_from_anywhere_in_the_pixel() {
+        for kind in FilterKind::ALL {
+            let filter = Filter::new(kind).with_radius(1.2);
+            // A sample at the edge of its pixel reaches half a pixel further.
+            assert!(filter.margin() as f32 >= filter.radius + 0.5);
+        }
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for kind in FilterKind::ALL {
+            assert!(FilterKind::from_name(kind.name()) == Some(kind));
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use glam::Vec3;
+
+use crate::{
+    math::VecExt,
+    object::{bvh::BVHCollection, Object},
+    rendering::{aov::PathRecord, ray::Ray},
+};
+
+use super::{first_hit, Integrator};
+
+/// Ambient occlusion: one ray from the first hit in a random direction,
+/// spread like light on a matte surface, shows white unless something
+/// blocks it within `radius`. The sky shows as it is.
+pub struct AmbientOcclusion {
+    pub radius: f32,
+    pub sky: Vec3,
+}
+
+impl AmbientOcclusion {
+    /// Part of the distance from the camera to `look_at` that occluders
+    /// are looked for within.
+    pub const RADIUS_SCALE: f32 = 0.25;
+}
+
+impl Integrator for AmbientOcclusion {
+    fn radiance(&self, ray: Ray, world: &BVHCollection, mut path: Option<&mut PathRecord>) -> Vec3 {
+        let Some(hit) = world.hit(ray, &(0.001..f32::MAX)) else {
+            if let Some(path) = path {
+                path.add_light(0, self.sky, None);
+            }
+            return self.sky;
+        };
+        // Scattered either way, so that the passes don't change the noise.
+        let scatter = hit.material.scatter(ray, &hit);
+        if let Some(path) = path.as_deref_mut() {
+            path.first_hit = Some(first_hit(world, &hit, scatter.as_ref()));
+        }
+
+        let mut direction = hit.normal + Vec3::random_unit();
+        if direction.near_zero() {
+            direction = hit.normal;
+        }
+        let direction = direction.normalize();
+        let probe = Ray::new(hit.point, direction, ray.time);
+        let open = world.hit(probe, &(0.001..self.radius)).is_none();
+
+        let value = if open { Vec3::ONE } else { Vec3::ZERO };
+        if let Some(path) = path {
+            path.add_light(1, value, None);
+        }
+        value
+    }
+}
@@ -0,0 +1,430 @@
+use std::f32::consts::PI;
+
+use glam::Vec3;
+
+use crate::{
+    math::{random, random_int, VecExt},
+    object::{bvh::BVHCollection, Object},
+    rendering::{
+        aov::PathRecord,
+        ray::{HitRecord, Ray},
+    },
+};
+
+use super::{first_hit, roulette, BounceCounts, BounceLimits, Integrator};
+
+/// How far outside a point sampled on a light the ray that finds its
+/// surface details starts.
+const PROBE_OFFSET: f32 = 1e-3;
+
+/// Bidirectional path tracing after Veach, much as in pbrt: a path from the
+/// camera and one from a light picked at random are joined at every pair
+/// of their vertices, and each way of building a path is weighted by the
+/// balance heuristic. Joins to the camera itself, which would land on
+/// other pixels, are left out. Lights give off light from both sides.
+///
+/// Every sample joins up to `max_bounces` squared pairs of vertices, so it
+/// is best kept to a few bounces. The limits on each kind of bounce and
+/// Russian roulette apply to the camera and light paths separately.
+pub struct BidirectionalPathTracer {
+    pub max_bounces: u32,
+    pub bounce_limits: BounceLimits,
+    pub sky: Vec3,
+}
+
+/// Corner of a camera or light path.
+struct Vertex<'a> {
+    point: Vec3,
+    /// Surface normal, zero at the camera.
+    normal: Vec3,
+    /// `None` at the camera.
+    hit: Option<HitRecord<'a>>,
+    /// Ray that arrived at the vertex.
+    incoming: Ray,
+    /// Product of the attenuations between the start of the path and the
+    /// vertex, each divided by the density it was sampled with.
+    throughput: Vec3,
+    /// Density per unit area of reaching the vertex from the previous one.
+    pdf_forward: f32,
+    /// The same from the next vertex, as a path built the other way would.
+    pdf_reverse: f32,
+    /// Whether the material can be evaluated, which it takes to join paths
+    /// at the vertex. Mirrors, glass and lights can't be.
+    connectible: bool,
+}
+
+impl Vertex<'_> {
+    /// Turns a density per solid angle of leaving this vertex toward `next`
+    /// into one per unit area at `next`.
+    fn to_area(&self, pdf: f32, next: &Vertex) -> f32 {
+        let offset = next.point - self.point;
+        let distance_squared = offset.length_squared();
+        if distance_squared == 0.0 {
+            return 0.0;
+        }
+        let cosine = next.normal.dot(offset).abs() / distance_squared.sqrt();
+        pdf * cosine / distance_squared
+    }
+
+    /// Density per solid angle with which the material at the vertex picks
+    /// the direction to `next` for a path that arrived from `previous`.
+    fn pdf(&self, previous: Vec3, next: Vec3) -> f32 {
+        let Some(hit) = &self.hit else {
+            return 0.0;
+        };
+        let incoming = Ray::new(previous, self.point - previous, self.incoming.time);
+        hit.material
+            .evaluate(incoming, hit, next - self.point)
+            .map_or(0.0, |(_, pdf)| pdf)
+    }
+
+    /// Scattering function times the cosine toward `next`, for light
+    /// between `next` and the way the path arrived.
+    fn value(&self, next: Vec3) -> Vec3 {
+        let Some(hit) = &self.hit else {
+            return Vec3::ZERO;
+        };
+        hit.material
+            .evaluate(self.incoming, hit, next - self.point)
+            .map_or(Vec3::ZERO, |(value, _)| value.0)
+    }
+}
+
+/// Extends `vertices` by following `ray`, which leaves the last vertex with
+/// density `pdf` per solid angle, through the materials it hits until there
+/// are `max_vertices`, the bounces run past `limits` or Russian roulette
+/// ends the walk. Returns the throughput of the path if it ends up in the
+/// sky.
+fn random_walk<'a>(
+    world: &'a BVHCollection,
+    mut ray: Ray,
+    mut throughput: Vec3,
+    mut pdf: f32,
+    max_vertices: usize,
+    limits: &BounceLimits,
+    vertices: &mut Vec<Vertex<'a>>,
+) -> Option<Vec3> {
+    let mut bounces = BounceCounts::new(*limits);
+    // Product of the attenuations along the walk alone, which roulette
+    // goes by since a light path's throughput starts out at its emission.
+    let mut attenuation_product = Vec3::ONE;
+    while vertices.len() < max_vertices {
+        let Some(hit) = world.hit(ray, &(0.001..f32::MAX)) else {
+            return Some(throughput);
+        };
+
+        let scatter = hit.material.scatter(ray, &hit);
+        let connectible = hit.material.evaluate(ray, &hit, -ray.direction).is_some();
+        let mut vertex = Vertex {
+            point: hit.point,
+            normal: hit.normal,
+            hit: None,
+            incoming: ray,
+            throughput,
+            pdf_forward: 0.0,
+            pdf_reverse: 0.0,
+            connectible,
+        };
+        vertex.pdf_forward = vertices.last().unwrap().to_area(pdf, &vertex);
+
+        let next = scatter
+            .and_then(|scatter| Some((scatter.new_ray?, scatter.attenuation, scatter.kind)))
+            .filter(|&(_, _, kind)| bounces.take(kind));
+        let (forward, reverse) = match &next {
+            Some((new_ray, _, _)) if connectible => {
+                let back = Ray::new(hit.point, -new_ray.direction, ray.time);
+                let pdf = |incoming, direction| {
+                    hit.material
+                        .evaluate(incoming, &hit, direction)
+                        .map_or(0.0, |(_, pdf)| pdf)
+                };
+                (pdf(ray, new_ray.direction), pdf(back, -ray.direction))
+            }
+            _ => (0.0, 0.0),
+        };
+
+        vertex.hit = Some(hit);
+        let previous = vertices.len() - 1;
+        vertices[previous].pdf_reverse = vertex.to_area(reverse, &vertices[previous]);
+        vertices.push(vertex);
+
+        let (new_ray, attenuation, _) = next?;
+        attenuation_product *= attenuation.0;
+        // The walk started at vertex 1, so this is its bounce off vertex
+        // `len - 1`.
+        let compensation = roulette(vertices.len() as u32 - 2, attenuation_product.max_element())?;
+        attenuation_product *= compensation;
+        throughput *= attenuation.0 * compensation;
+        pdf = forward;
+        ray = new_ray;
+    }
+    None
+}
+
+impl BidirectionalPathTracer {
+    /// Path of up to `max_vertices` starting on a random point of a light
+    /// picked at random, with the position of the light in the light list.
+    fn light_path<'a>(
+        &self,
+        world: &'a BVHCollection,
+        time: f32,
+        max_vertices: usize,
+    ) -> Option<(usize, Vec<Vertex<'a>>)> {
+        let lights = world.lights();
+        if lights.is_empty() || max_vertices == 0 {
+            return None;
+        }
+        let index = random_int(0..lights.len());
+        let light = &lights[index];
+        let sample = light.sample_surface()?;
+
+        // Found again from just outside for the light's texture coordinates.
+        let normal = sample.normal;
+        let probe = Ray::new(sample.point + PROBE_OFFSET * normal, -normal, time);
+        let hit = light.hit(probe, &(0.0..2.0 * PROBE_OFFSET))?;
+        let emission = hit.material.emit(hit.u, hit.v, hit.point).0;
+        let pdf_position = 1.0 / (lights.len() as f32 * light.area());
+
+        let side = if random() < 0.5 { normal } else { -normal };
+        let mut direction = side + Vec3::random_unit();
+        if direction.near_zero() {
+            direction = side;
+        }
+        let cosine = direction.normalize().dot(normal).abs();
+        let pdf_direction = cosine / (2.0 * PI);
+
+        let mut vertices = vec![Vertex {
+            point: sample.point,
+            normal,
+            hit: Some(hit),
+            incoming: probe,
+            throughput: emission / pdf_position,
+            pdf_forward: pdf_position,
+            pdf_reverse: 0.0,
+            connectible: true,
+        }];
+        random_walk(
+            world,
+            Ray::new(sample.point, direction, time),
+            emission * cosine / (pdf_position * pdf_direction),
+            pdf_direction,
+            max_vertices,
+            &self.bounce_limits,
+            &mut vertices,
+        );
+        Some((index, vertices))
+    }
+
+    /// Light the camera path `camera` finds by joining its vertex `t - 1`
+    /// to vertex `s - 1` of the light path `light`, weighted.
+    fn connect(
+        &self,
+        world: &BVHCollection,
+        light: &[Vertex],
+        camera: &[Vertex],
+        s: usize,
+        t: usize,
+    ) -> Vec3 {
+        let (q, p) = (&light[s - 1], &camera[t - 1]);
+        if !q.connectible || !p.connectible {
+            return Vec3::ZERO;
+        }
+
+        let offset = q.point - p.point;
+        let distance = offset.length();
+        if distance == 0.0 {
+            return Vec3::ZERO;
+        }
+        let cosine_q = q.normal.dot(offset).abs() / distance;
+        let light_value = if s == 1 {
+            Vec3::splat(cosine_q)
+        } else {
+            q.value(p.point)
+        };
+        let contribution =
+            q.throughput * light_value * p.value(q.point) * p.throughput / (distance * distance);
+        if contribution == Vec3::ZERO {
+            return Vec3::ZERO;
+        }
+
+        let shadow_ray = Ray::new(p.point, offset / distance, p.incoming.time);
+        if world.hit(shadow_ray, &(0.001..distance - 0.001)).is_some() {
+            return Vec3::ZERO;
+        }
+
+        // Densities of building the joined path the other way around.
+        let p_reverse = if s == 1 {
+            q.to_area(cosine_q / (2.0 * PI), p)
+        } else {
+            q.to_area(q.pdf(light[s - 2].point, p.point), p)
+        };
+        let p_before_reverse = p.to_area(p.pdf(q.point, camera[t - 2].point), &camera[t - 2]);
+        let q_reverse = p.to_area(p.pdf(camera[t - 2].point, q.point), q);
+        let q_before_reverse = if s >= 2 {
+            q.to_area(q.pdf(p.point, light[s - 2].point), &light[s - 2])
+        } else {
+            0.0
+        };
+
+        let camera_reverse = |i: usize| match i {
+            _ if i == t - 1 => p_reverse,
+            _ if i == t - 2 => p_before_reverse,
+            _ => camera[i].pdf_reverse,
+        };
+        let light_reverse = |i: usize| match i {
+            _ if i == s - 1 => q_reverse,
+            _ if s >= 2 && i == s - 2 => q_before_reverse,
+            _ => light[i].pdf_reverse,
+        };
+        contribution
+            * balance_weight(camera, t, camera_reverse)
+            * weight_from_ratios(light_ratios(light, s, light_reverse))
+    }
+
+    /// Weight of the light the camera path `camera` finds by running into
+    /// light `light` at its vertex `t - 1`.
+    fn emitted_weight(
+        &self,
+        world: &BVHCollection,
+        camera: &[Vertex],
+        t: usize,
+        light: usize,
+    ) -> f32 {
+        let lights = world.lights();
+        let area = lights[light].area();
+        if area <= 0.0 {
+            return 1.0;
+        }
+
+        let (p, before) = (&camera[t - 1], &camera[t - 2]);
+        let offset = before.point - p.point;
+        let cosine = p.normal.dot(offset.normalize_or_zero()).abs();
+        let p_reverse = 1.0 / (lights.len() as f32 * area);
+        let p_before_reverse = p.to_area(cosine / (2.0 * PI), before);
+
+        let camera_reverse = |i: usize| match i {
+            _ if i == t - 1 => p_reverse,
+            _ if i == t - 2 => p_before_reverse,
+            _ => camera[i].pdf_reverse,
+        };
+        balance_weight(camera, t, camera_reverse)
+    }
+}
+
+/// A density of zero stands for a choice no other strategy could make.
+fn remap_zero(pdf: f32) -> f32 {
+    if pdf == 0.0 {
+        1.0
+    } else {
+        pdf
+    }
+}
+
+/// Balance heuristic weight of a path ending in camera vertex `t - 1`
+/// against the strategies that would have used fewer camera vertices, but
+/// at least two. `reverse` gives the reverse density of every vertex in
+/// the joined path.
+fn balance_weight(camera: &[Vertex], t: usize, reverse: impl Fn(usize) -> f32) -> f32 {
+    // The joined vertex can be reached either way.
+    let delta = |i: usize| i != t - 1 && !camera[i].connectible;
+    let mut ratio = 1.0;
+    let mut ratios = Vec::new();
+    for i in (2..t).rev() {
+        ratio *= remap_zero(reverse(i)) / remap_zero(camera[i].pdf_forward);
+        if !delta(i) && !delta(i - 1) {
+            ratios.push(ratio);
+        }
+    }
+    weight_from_ratios(ratios)
+}
+
+/// Density ratios of the strategies that would have used fewer light
+/// vertices than `s`.
+fn light_ratios(light: &[Vertex], s: usize, reverse: impl Fn(usize) -> f32) -> Vec<f32> {
+    let delta = |i: usize| i != s - 1 && !light[i].connectible;
+    let mut ratio = 1.0;
+    let mut ratios = Vec::new();
+    for i in (0..s).rev() {
+        ratio *= remap_zero(reverse(i)) / remap_zero(light[i].pdf_forward);
+        let delta_before = i > 0 && delta(i - 1);
+        if !delta(i) && !delta_before {
+            ratios.push(ratio);
+        }
+    }
+    ratios
+}
+
+fn weight_from_ratios(ratios: Vec<f32>) -> f32 {
+    1.0 / (1.0 + ratios.iter().sum::<f32>())
+}
+
+impl Integrator for BidirectionalPathTracer {
+    fn radiance(&self, ray: Ray, world: &BVHCollection, mut path: Option<&mut PathRecord>) -> Vec3 {
+        let max_bounces = self.max_bounces as usize;
+        let mut camera = vec![Vertex {
+            point: ray.origin,
+            normal: Vec3::ZERO,
+            hit: None,
+            incoming: ray,
+            throughput: Vec3::ONE,
+            pdf_forward: 1.0,
+            pdf_reverse: 0.0,
+            connectible: false,
+        }];
+        let escaped = random_walk(
+            world,
+            ray,
+            Vec3::ONE,
+            1.0,
+            max_bounces + 1,
+            &self.bounce_limits,
+            &mut camera,
+        );
+        let light = self.light_path(world, ray.time, max_bounces);
+
+        if let (Some(path), Some(first)) = (path.as_deref_mut(), camera.get(1)) {
+            let hit = first.hit.as_ref().unwrap();
+            let scatter = hit.material.scatter(first.incoming, hit);
+            path.first_hit = Some(first_hit(world, hit, scatter.as_ref()));
+        }
+
+        let mut radiance = Vec3::ZERO;
+        let mut record = |bounce: usize, light: Vec3, light_group: Option<usize>| {
+            radiance += light;
+            if let Some(path) = path.as_deref_mut() {
+                path.add_light(bounce as u32, light, light_group);
+            }
+        };
+
+        if let Some(throughput) = escaped {
+            record(camera.len() - 1, throughput * self.sky, None);
+        }
+        for t in 2..=camera.len() {
+            let p = &camera[t - 1];
+            let hit = p.hit.as_ref().unwrap();
+            let emission = hit.material.emit(hit.u, hit.v, hit.point).0;
+            if emission != Vec3::ZERO {
+                let light_group = world.light_group(hit.object);
+                let weight =
+                    light_group.map_or(1.0, |light| self.emitted_weight(world, &camera, t, light));
+                record(t - 2, p.throughput * emission * weight, light_group);
+            }
+
+            let Some((light_index, light)) = &light else {
+                continue;
+            };
+            for s in 1..=light.len() {
+                let bounce = s + t - 2;
+                if bounce >= max_bounces {
+                    break;
+                }
+                let contribution = self.connect(world, light, &camera, s, t);
+                if contribution != Vec3::ZERO {
+                    record(bounce, contribution, Some(*light_index));
+                }
+            }
+        }
+
+        radiance
+    }
+}
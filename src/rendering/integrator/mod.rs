@@ -0,0 +1,450 @@
+//! Light transport algorithms, which work out how much light arrives along
+//! a camera ray. They range from quick approximations for previews to the
+//! full path tracers for final renders.
+
+mod ambient_occlusion;
+mod bidirectional;
+mod naive;
+mod path;
+mod whitted;
+
+use std::{ops::MulAssign, sync::Arc};
+
+use glam::{vec2, Vec3, Vec4};
+
+use crate::{
+    math::{random, random_int},
+    object::{bvh::BVHCollection, Object},
+};
+
+use self::{
+    ambient_occlusion::AmbientOcclusion, bidirectional::BidirectionalPathTracer,
+    naive::NaivePathTracer, path::PathTracer, whitted::Whitted,
+};
+
+use super::{
+    aov::{FirstHit, PathRecord, NO_ID},
+    camera::CameraConfig,
+    material::{BounceKind, ScatterResult},
+    ray::{Color, HitRecord, Ray},
+};
+
+pub trait Integrator: Send + Sync {
+    /// Radiance arriving along the camera ray `ray`. When `path` is given,
+    /// the first hit's surface and every light found along the way are
+    /// recorded into it for the render passes, weighted by its throughput,
+    /// which starts out as the weight of the camera ray.
+    ///
+    /// Integrators that follow paths past the first bounce go from surface
+    /// to surface with `scatter_path` and `continue_path`, which limit each
+    /// kind of bounce by their `BounceLimits` and end dim paths.
+    fn radiance(&self, ray: Ray, world: &BVHCollection, path: Option<&mut PathRecord>) -> Vec3;
+}
+
+/// Which integrator a camera renders with.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum IntegratorKind {
+    /// Follows one random scattering per bounce until the path finds a
+    /// light by chance.
+    #[default]
+    Naive,
+    /// Lights every surface straight from the lights and the sky, without
+    /// shadows from the sky, and follows only mirrors and glass further.
+    Whitted,
+    /// How much of the surroundings each surface sees unblocked, in white.
+    AmbientOcclusion,
+    /// Light reaching each surface straight from a light or the sky, seen
+    /// directly or through mirrors and glass.
+    Direct,
+    /// Path tracing that also aims a ray at a light at every bounce, with
+    /// both ways of finding light weighted by multiple importance sampling.
+    Path,
+    /// Paths traced from the camera and from a light, joined at every pair
+    /// of their vertices and weighted by multiple importance sampling.
+    Bidirectional,
+}
+
+impl IntegratorKind {
+    pub const ALL: [Self; 6] = [
+        Self::Naive,
+        Self::Whitted,
+        Self::AmbientOcclusion,
+        Self::Direct,
+        Self::Path,
+        Self::Bidirectional,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Name `from_name` reads back.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Naive => "naive",
+            Self::Whitted => "whitted",
+            Self::AmbientOcclusion => "ao",
+            Self::Direct => "direct",
+            Self::Path => "path",
+            Self::Bidirectional => "bdpt",
+        }
+    }
+
+    /// The integrator with the bounce limits and sky of `config`.
+    pub fn build(self, config: &CameraConfig) -> Arc<dyn Integrator> {
+        let sky = config.skybox.0;
+        let max_bounces = config.max_bounces;
+        let bounce_limits = config.bounce_limits;
+        match self {
+            Self::Naive => Arc::new(NaivePathTracer {
+                max_bounces,
+                bounce_limits,
+                sky,
+            }),
+            Self::Whitted => Arc::new(Whitted {
+                max_bounces,
+                bounce_limits,
+                sky,
+            }),
+            Self::AmbientOcclusion => Arc::new(AmbientOcclusion {
+                radius: AmbientOcclusion::RADIUS_SCALE
+                    * (config.look_at - config.look_from).length(),
+                sky,
+            }),
+            Self::Direct => Arc::new(PathTracer {
+                max_bounces,
+                bounce_limits,
+                sky,
+                direct_only: true,
+            }),
+            Self::Path => Arc::new(PathTracer {
+                max_bounces,
+                bounce_limits,
+                sky,
+                direct_only: false,
+            }),
+            Self::Bidirectional => Arc::new(BidirectionalPathTracer {
+                max_bounces,
+                bounce_limits,
+                sky,
+            }),
+        }
+    }
+}
+
+/// Most bounces of each kind a path may take, on top of the camera's
+/// overall `max_bounces`. A limit of one on diffuse bounces, for example,
+/// still lights diffuse surfaces directly. Unlimited by default.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct BounceLimits([u32; BounceKind::ALL.len()]);
+
+impl Default for BounceLimits {
+    fn default() -> Self {
+        Self([u32::MAX; BounceKind::ALL.len()])
+    }
+}
+
+impl BounceLimits {
+    pub fn get(&self, kind: BounceKind) -> u32 {
+        self.0[kind as usize]
+    }
+
+    pub fn set(&mut self, kind: BounceKind, limit: u32) {
+        self.0[kind as usize] = limit;
+    }
+}
+
+/// Bounces of each kind a path has taken so far, and how many it may take.
+#[derive(Clone, Copy)]
+pub struct BounceCounts {
+    taken: [u32; BounceKind::ALL.len()],
+    limits: BounceLimits,
+}
+
+impl BounceCounts {
+    pub fn new(limits: BounceLimits) -> Self {
+        Self {
+            taken: [0; BounceKind::ALL.len()],
+            limits,
+        }
+    }
+
+    /// Counts a bounce of `kind`, returning whether it is within the limits.
+    pub fn take(&mut self, kind: BounceKind) -> bool {
+        let count = &mut self.taken[kind as usize];
+        *count += 1;
+        *count <= self.limits.get(kind)
+    }
+}
+
+/// Bounces a path takes before Russian roulette may end it.
+pub const ROULETTE_DEPTH: u32 = 3;
+
+/// Russian roulette: once a path is `ROULETTE_DEPTH` bounces deep, ends it
+/// with a chance that grows as `throughput`, the largest component of its
+/// throughput, drops. Returns `None` if the path ends, or else the factor
+/// that makes up for the paths that did.
+pub fn roulette(bounce: u32, throughput: f32) -> Option<f32> {
+    if bounce < ROULETTE_DEPTH {
+        return Some(1.0);
+    }
+    let survival = throughput.min(1.0);
+    (random() < survival).then(|| 1.0 / survival)
+}
+
+/// Where a path goes from a surface, as decided by `scatter_path`.
+pub enum Bounce {
+    /// The material absorbs the path, or it has run out of bounces of the
+    /// kind the material picked.
+    End,
+    /// The material lit the surface itself with this light instead of
+    /// scattering, which also ends the path.
+    Lit(Color),
+    /// The path goes on along `ray`, attenuated by `attenuation`.
+    Scattered { ray: Ray, attenuation: Color },
+}
+
+/// Scatters a path off `hit`, its `bounce`th surface, counting the bounce
+/// in `bounces`. The first hit is recorded into `path` for the render
+/// passes.
+pub fn scatter_path(
+    ray: Ray,
+    hit: &HitRecord,
+    world: &BVHCollection,
+    bounce: u32,
+    bounces: &mut BounceCounts,
+    path: Option<&mut PathRecord>,
+) -> Bounce {
+    let scatter = hit.material.scatter(ray, hit);
+    if bounce == 0 {
+        if let Some(path) = path {
+            path.first_hit = Some(first_hit(world, hit, scatter.as_ref()));
+        }
+    }
+    let Some(scatter) = scatter else {
+        return Bounce::End;
+    };
+    let Some(ray) = scatter.new_ray else {
+        return Bounce::Lit(scatter.attenuation);
+    };
+    if !bounces.take(scatter.kind) {
+        return Bounce::End;
+    }
+    Bounce::Scattered {
+        ray,
+        attenuation: scatter.attenuation,
+    }
+}
+
+/// Throughput of a path, per color channel or per wavelength.
+pub trait Throughput: Copy + MulAssign + MulAssign<f32> {
+    fn max_element(self) -> f32;
+}
+
+impl Throughput for Vec3 {
+    fn max_element(self) -> f32 {
+        Vec3::max_element(self)
+    }
+}
+
+impl Throughput for Vec4 {
+    fn max_element(self) -> f32 {
+        Vec4::max_element(self)
+    }
+}
+
+/// Attenuates the throughput of a path leaving its `bounce`th surface by
+/// `attenuation` and plays Russian roulette with it, returning whether the
+/// path goes on.
+pub fn continue_path<T: Throughput>(bounce: u32, throughput: &mut T, attenuation: T) -> bool {
+    *throughput *= attenuation;
+    match roulette(bounce, throughput.max_element()) {
+        Some(compensation) => {
+            *throughput *= compensation;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Light a camera path has found, also added to its record for the render
+/// passes when there is one.
+struct PathLight<'a> {
+    radiance: Vec3,
+    path: Option<&'a mut PathRecord>,
+}
+
+impl<'a> PathLight<'a> {
+    fn new(path: Option<&'a mut PathRecord>) -> Self {
+        Self {
+            radiance: Vec3::ZERO,
+            path,
+        }
+    }
+
+    /// Adds `light` found at `bounce`, already weighted by the throughput.
+    fn add(&mut self, bounce: u32, light: Vec3, light_group: Option<usize>) {
+        self.radiance += light;
+        if let Some(path) = self.path.as_deref_mut() {
+            path.add_light(bounce, light, light_group);
+        }
+    }
+
+    fn record(&mut self) -> Option<&mut PathRecord> {
+        self.path.as_deref_mut()
+    }
+}
+
+/// Surface details of a camera path's first hit for the render passes.
+pub fn first_hit(
+    world: &BVHCollection,
+    hit: &HitRecord,
+    scatter: Option<&ScatterResult>,
+) -> FirstHit {
+    FirstHit {
+        albedo: scatter.map_or(Vec3::ZERO, |s| s.attenuation.0),
+        normal: hit.normal,
+        position: hit.point,
+        depth: hit.t,
+        uv: vec2(hit.u, hit.v),
+        object_id: world.object_id(hit.object).unwrap_or(NO_ID),
+        material_id: hit
+            .material_id()
+            .or_else(|| world.material_id(hit.material))
+            .unwrap_or(NO_ID),
+    }
+}
+
+/// Light found by aiming a ray at one of the world's lights.
+struct LightSample {
+    /// Light given off toward the point the ray left from.
+    radiance: Vec3,
+    /// Direction of the ray, not normalized.
+    direction: Vec3,
+    /// Density per solid angle of picking the direction, choice of light
+    /// included.
+    pdf: f32,
+    /// Position of the light in the world's light list.
+    light: usize,
+}
+
+/// Aims a ray from `point` at a random point on a light picked at random,
+/// or returns `None` if there are no lights or something else is in the
+/// way.
+fn sample_light(world: &BVHCollection, point: Vec3, time: f32) -> Option<LightSample> {
+    let lights = world.lights();
+    if lights.is_empty() {
+        return None;
+    }
+    let light = random_int(0..lights.len());
+    let direction = lights[light].random_direction(point)?;
+
+    let hit = world.hit(Ray::new(point, direction, time), &(0.001..f32::MAX))?;
+    if world.light_group(hit.object) != Some(light) {
+        return None;
+    }
+    let pdf = light_pdf(world, light, point, direction);
+    (pdf > 0.0).then(|| LightSample {
+        radiance: hit.material.emit(hit.u, hit.v, hit.point).0,
+        direction,
+        pdf,
+        light,
+    })
+}
+
+/// Density per solid angle with which `sample_light` picks `direction`
+/// from `origin` when it goes to `light`.
+fn light_pdf(world: &BVHCollection, light: usize, origin: Vec3, direction: Vec3) -> f32 {
+    let lights = world.lights();
+    lights[light].pdf_value(origin, direction) / lights.len() as f32
+}
+
+/// Weight of a sample taken with density `pdf` that another strategy could
+/// have taken with density `other_pdf`.
+fn power_heuristic(pdf: f32, other_pdf: f32) -> f32 {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b == 0.0 {
+        0.0
+    } else {
+        a / (a + b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec3;
+
+    use crate::{
+        math::{random_range, reseed},
+        object::{
+            collection::ObjectCollection,
+            types::{Quad, Sphere},
+        },
+        rendering::{
+            material::{Lambertian, Light},
+            ray::Color,
+        },
+    };
+
+    use super::*;
+
+    /// A diffuse sphere on a diffuse floor under a square light, in the dark.
+    fn diffuse_world() -> BVHCollection {
+        let mut world = ObjectCollection::new();
+        world.add(Quad::new(
+            vec3(-3.0, 0.0, -3.0),
+            vec3(0.0, 0.0, 6.0),
+            vec3(6.0, 0.0, 0.0),
+            Lambertian::solid_color(Color::new(0.7, 0.7, 0.7)),
+        ));
+        world.add(Sphere::new(
+            vec3(0.0, 0.5, 0.0),
+            0.5,
+            Lambertian::solid_color(Color::new(0.8, 0.3, 0.2)),
+        ));
+        world.add_light(Quad::new(
+            vec3(-0.5, 2.0, -0.5),
+            vec3(1.0, 0.0, 0.0),
+            vec3(0.0, 0.0, 1.0),
+            Light::solid_color(Color::new(4.0, 4.0, 4.0)),
+        ));
+        world.as_bvh()
+    }
+
+    /// Mean radiance along rays from the same point at random spots on the
+    /// floor around the sphere.
+    fn mean_radiance(integrator: &dyn Integrator, world: &BVHCollection, samples: u32) -> Vec3 {
+        reseed(7);
+        let origin = vec3(0.0, 1.5, 3.0);
+        let total: Vec3 = (0..samples)
+            .map(|_| {
+                let target = vec3(random_range(-1.5..1.5), 0.0, random_range(-1.5..1.5));
+                integrator.radiance(Ray::new(origin, target - origin, 0.0), world, None)
+            })
+            .sum();
+        total / samples as f32
+    }
+
+    #[test]
+    fn bidirectional_agrees_with_path_tracing() {
+        let world = diffuse_world();
+        let path = PathTracer {
+            max_bounces: 8,
+            bounce_limits: BounceLimits::default(),
+            sky: Vec3::ZERO,
+            direct_only: false,
+        };
+        let bidirectional = BidirectionalPathTracer {
+            max_bounces: 8,
+            bounce_limits: BounceLimits::default(),
+            sky: Vec3::ZERO,
+        };
+
+        let samples = 20_000;
+        let expected = mean_radiance(&path, &world, samples);
+        let found = mean_radiance(&bidirectional, &world, samples);
+        assert!(expected.min_element() > 0.0, "{expected}");
+        let error = (found - expected).abs() / expected;
+        assert!(error.max_element() < 0.03, "{found} vs {expected}");
+    }
+}
@@ -0,0 +1,54 @@
+use glam::Vec3;
+
+use crate::{
+    object::{bvh::BVHCollection, Object},
+    rendering::{aov::PathRecord, ray::Ray},
+};
+
+use super::{
+    continue_path, scatter_path, Bounce, BounceCounts, BounceLimits, Integrator, PathLight,
+};
+
+/// Path tracing by following whichever way each material scatters, which
+/// only finds light when a path happens to run into it. Paths that have
+/// grown dim are ended early by Russian roulette.
+pub struct NaivePathTracer {
+    pub max_bounces: u32,
+    pub bounce_limits: BounceLimits,
+    pub sky: Vec3,
+}
+
+impl Integrator for NaivePathTracer {
+    fn radiance(&self, mut ray: Ray, world: &BVHCollection, path: Option<&mut PathRecord>) -> Vec3 {
+        let mut light = PathLight::new(path);
+        let mut throughput = Vec3::ONE;
+        let mut bounces = BounceCounts::new(self.bounce_limits);
+
+        for bounce in 0..self.max_bounces {
+            let Some(hit) = world.hit(ray, &(0.001..f32::MAX)) else {
+                light.add(bounce, throughput * self.sky, None);
+                break;
+            };
+
+            let emission = hit.material.emit(hit.u, hit.v, hit.point).0;
+            light.add(bounce, throughput * emission, world.light_group(hit.object));
+
+            let (new_ray, attenuation) =
+                match scatter_path(ray, &hit, world, bounce, &mut bounces, light.record()) {
+                    Bounce::End => break,
+                    Bounce::Lit(color) => {
+                        light.add(bounce, throughput * color.0, None);
+                        break;
+                    }
+                    Bounce::Scattered { ray, attenuation } => (ray, attenuation),
+                };
+
+            if !continue_path(bounce, &mut throughput, attenuation.0) {
+                break;
+            }
+            ray = new_ray;
+        }
+
+        light.radiance
+    }
+}
@@ -0,0 +1,97 @@
+use glam::Vec3;
+
+use crate::{
+    object::{bvh::BVHCollection, Object},
+    rendering::{aov::PathRecord, ray::Ray},
+};
+
+use super::{
+    continue_path, light_pdf, power_heuristic, sample_light, scatter_path, Bounce, BounceCounts,
+    BounceLimits, Integrator, PathLight,
+};
+
+/// Path tracing with next event estimation: at every surface the material
+/// can be evaluated for, a ray is also aimed at a light, and light found
+/// either way is weighted by the power heuristic. Mirrors, glass and the
+/// sky are only found by following the material. Paths that have grown dim
+/// are ended early by Russian roulette.
+pub struct PathTracer {
+    pub max_bounces: u32,
+    pub bounce_limits: BounceLimits,
+    pub sky: Vec3,
+    /// Stops after the light reaching the first surface that isn't a mirror
+    /// or glass.
+    pub direct_only: bool,
+}
+
+impl Integrator for PathTracer {
+    fn radiance(&self, mut ray: Ray, world: &BVHCollection, path: Option<&mut PathRecord>) -> Vec3 {
+        let mut light = PathLight::new(path);
+        let mut throughput = Vec3::ONE;
+        let mut bounces = BounceCounts::new(self.bounce_limits);
+
+        // Density with which the last surface picked `ray`, or `None` if it
+        // couldn't have been picked by aiming at a light.
+        let mut scatter_pdf: Option<f32> = None;
+        let mut last_point = ray.origin;
+        let mut lit = false;
+
+        for bounce in 0..self.max_bounces {
+            let Some(hit) = world.hit(ray, &(0.001..f32::MAX)) else {
+                light.add(bounce, throughput * self.sky, None);
+                break;
+            };
+
+            let emission = hit.material.emit(hit.u, hit.v, hit.point).0;
+            if emission != Vec3::ZERO {
+                let light_group = world.light_group(hit.object);
+                let weight = match (scatter_pdf, light_group) {
+                    (Some(pdf), Some(light)) => {
+                        power_heuristic(pdf, light_pdf(world, light, last_point, ray.direction))
+                    }
+                    _ => 1.0,
+                };
+                light.add(bounce, throughput * emission * weight, light_group);
+            }
+            if lit {
+                break;
+            }
+
+            let (new_ray, attenuation) =
+                match scatter_path(ray, &hit, world, bounce, &mut bounces, light.record()) {
+                    Bounce::End => break,
+                    Bounce::Lit(color) => {
+                        light.add(bounce, throughput * color.0, None);
+                        break;
+                    }
+                    Bounce::Scattered { ray, attenuation } => (ray, attenuation),
+                };
+
+            scatter_pdf = hit
+                .material
+                .evaluate(ray, &hit, new_ray.direction)
+                .map(|(_, pdf)| pdf);
+            if scatter_pdf.is_some() && bounce + 1 < self.max_bounces {
+                if let Some(sample) = sample_light(world, hit.point, ray.time) {
+                    if let Some((value, pdf)) = hit.material.evaluate(ray, &hit, sample.direction) {
+                        let weight = power_heuristic(sample.pdf, pdf);
+                        light.add(
+                            bounce + 1,
+                            throughput * value.0 * sample.radiance * weight / sample.pdf,
+                            Some(sample.light),
+                        );
+                    }
+                }
+                lit = self.direct_only;
+            }
+
+            if !continue_path(bounce, &mut throughput, attenuation.0) {
+                break;
+            }
+            last_point = hit.point;
+            ray = new_ray;
+        }
+
+        light.radiance
+    }
+}
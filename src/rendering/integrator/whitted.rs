@@ -0,0 +1,109 @@
+use glam::Vec3;
+
+use crate::{
+    object::{bvh::BVHCollection, Object},
+    rendering::{
+        aov::PathRecord,
+        ray::{HitRecord, Ray},
+    },
+};
+
+use super::{
+    continue_path, scatter_path, Bounce, BounceCounts, BounceLimits, Integrator, PathLight,
+};
+
+/// Whitted style ray tracing: surfaces are lit by a shadow ray to every
+/// light plus the sky as unshadowed ambient light, and only mirrors and
+/// glass send rays on. Long chains of them that have grown dim are ended
+/// early by Russian roulette.
+pub struct Whitted {
+    pub max_bounces: u32,
+    pub bounce_limits: BounceLimits,
+    pub sky: Vec3,
+}
+
+impl Whitted {
+    /// Light reaching the surface at `hit` from one random point on each
+    /// light, for light leaving back along `ray`, handed to `found` with
+    /// the light's position in the light list.
+    fn light_surface(
+        &self,
+        ray: Ray,
+        hit: &HitRecord,
+        world: &BVHCollection,
+        mut found: impl FnMut(usize, Vec3),
+    ) {
+        for (index, light) in world.lights().iter().enumerate() {
+            let Some(direction) = light.random_direction(hit.point) else {
+                continue;
+            };
+            let shadow_ray = Ray::new(hit.point, direction, ray.time);
+            let Some(light_hit) = world.hit(shadow_ray, &(0.001..f32::MAX)) else {
+                continue;
+            };
+            if world.light_group(light_hit.object) != Some(index) {
+                continue;
+            }
+
+            let pdf = light.pdf_value(hit.point, direction);
+            if let Some((value, _)) = hit.material.evaluate(ray, hit, direction) {
+                if pdf > 0.0 {
+                    let emission =
+                        light_hit
+                            .material
+                            .emit(light_hit.u, light_hit.v, light_hit.point);
+                    found(index, value.0 * emission.0 / pdf);
+                }
+            }
+        }
+    }
+}
+
+impl Integrator for Whitted {
+    fn radiance(&self, mut ray: Ray, world: &BVHCollection, path: Option<&mut PathRecord>) -> Vec3 {
+        let mut light = PathLight::new(path);
+        let mut throughput = Vec3::ONE;
+        let mut bounces = BounceCounts::new(self.bounce_limits);
+
+        for bounce in 0..self.max_bounces {
+            let Some(hit) = world.hit(ray, &(0.001..f32::MAX)) else {
+                light.add(bounce, throughput * self.sky, None);
+                break;
+            };
+
+            let emission = hit.material.emit(hit.u, hit.v, hit.point).0;
+            let light_group = world.light_group(hit.object);
+            light.add(bounce, throughput * emission, light_group);
+
+            let (new_ray, attenuation) =
+                match scatter_path(ray, &hit, world, bounce, &mut bounces, light.record()) {
+                    Bounce::End => break,
+                    Bounce::Lit(color) => {
+                        light.add(bounce, throughput * color.0, None);
+                        break;
+                    }
+                    Bounce::Scattered { ray, attenuation } => (ray, attenuation),
+                };
+
+            let diffuse = hit
+                .material
+                .evaluate(ray, &hit, new_ray.direction)
+                .is_some();
+            if diffuse {
+                self.light_surface(ray, &hit, world, |index, value| {
+                    light.add(bounce + 1, throughput * value, Some(index))
+                });
+                let ambient = throughput * attenuation.0 * self.sky;
+                light.add(bounce + 1, ambient, None);
+                break;
+            }
+
+            if !continue_path(bounce, &mut throughput, attenuation.0) {
+                break;
+            }
+            ray = new_ray;
+        }
+
+        light.radiance
+    }
+}
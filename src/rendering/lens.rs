@@ -0,0 +1,506 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use glam::{Vec2, Vec3};
+
+use crate::math::random_range;
+
+use super::projection::{CameraModel, CameraRay, View};
+
+/// One surface of a lens system, with lengths in metres.
+#[derive(Clone)]
+struct LensInterface {
+    /// Zero for the aperture stop.
+    curvature_radius: f32,
+    /// Distance along the axis to the next interface towards the film.
+    thickness: f32,
+    /// Index of refraction of the medium on the film side; zero means air.
+    ior: f32,
+    aperture_radius: f32,
+}
+
+/// Ray in lens space, where the film lies in the z = 0 plane and the lens
+/// extends towards negative z.
+#[derive(Clone, Copy)]
+struct LensRay {
+    origin: Vec3,
+    direction: Vec3,
+}
+
+impl LensRay {
+    fn at(&self, t: f32) -> Vec3 {
+        self.origin + t * self.direction
+    }
+}
+
+/// Height above the axis of the rays that find a lens's thick lens
+/// approximation, close enough to the axis for it to hold.
+const PARAXIAL_HEIGHT: f32 = 4e-5;
+
+/// Lens system read from a prescription, checked to bring light parallel to
+/// its axis to a focus.
+///
+/// Prescriptions use the common text format with one interface per line,
+/// front to back: curvature radius, thickness, index of refraction and
+/// aperture diameter, all lengths in millimetres, with a radius of zero
+/// marking the aperture stop. Lines starting with `#` are comments. The
+/// scene is assumed to be measured in metres.
+#[derive(Clone)]
+pub struct LensPrescription {
+    /// File the prescription was read from.
+    path: PathBuf,
+    interfaces: Vec<LensInterface>,
+    /// Principal plane and focal point on the scene side and principal
+    /// plane on the film side, along the lens space z axis.
+    thick_lens: (f32, f32, f32),
+}
+
+/// Camera tracing rays from the film through a lens prescription, which
+/// gives it the lens's real distortion, vignetting and depth of field.
+pub struct RealisticCamera {
+    /// The prescription stopped down and focused.
+    lens: LensPrescription,
+    film_size: Vec2,
+    /// Fraction of rays passing the lens from the film center, which the
+    /// weights are divided by so exposure matches the other cameras.
+    axial_transmission: f32,
+}
+
+impl LensPrescription {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        Self::parse(&fs::read_to_string(path)?, path)
+    }
+
+    /// Reads the prescription `text`, which came from the file at `path`.
+    fn parse(text: &str, path: &Path) -> io::Result<Self> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let mut interfaces = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let values = line
+                .split_whitespace()
+                .map(str::parse::<f32>)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| invalid(format!("line {}: {e}", number + 1)))?;
+            let &[radius, thickness, ior, diameter] = values.as_slice() else {
+                return Err(invalid(format!(
+                    "line {}: expected radius, thickness, index of refraction and aperture",
+                    number + 1
+                )));
+            };
+
+            interfaces.push(LensInterface {
+                curvature_radius: radius * 0.001,
+                thickness: thickness * 0.001,
+                ior,
+                aperture_radius: diameter * 0.001 / 2.0,
+            });
+        }
+        if interfaces.is_empty() {
+            return Err(invalid("lens has no interfaces".to_string()));
+        }
+
+        let mut lens = Self {
+            path: path.to_path_buf(),
+            interfaces,
+            thick_lens: (0.0, 0.0, 0.0),
+        };
+        lens.thick_lens = lens
+            .find_thick_lens()
+            .ok_or_else(|| invalid("lens does not focus light along its axis".to_string()))?;
+        Ok(lens)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn rear_z(&self) -> f32 {
+        self.interfaces.last().unwrap().thickness
+    }
+
+    fn front_z(&self) -> f32 {
+        self.interfaces.iter().map(|i| i.thickness).sum()
+    }
+
+    fn rear_aperture_radius(&self) -> f32 {
+        self.interfaces.last().unwrap().aperture_radius
+    }
+
+    /// Intersection with a spherical interface centered on the axis at
+    /// `center_z`, returning `t` and the normal facing the ray.
+    fn intersect_spherical(radius: f32, center_z: f32, ray: &LensRay) -> Option<(f32, Vec3)> {
+        let origin = ray.origin - Vec3::new(0.0, 0.0, center_z);
+
+        let a = ray.direction.length_squared();
+        let half_b = origin.dot(ray.direction);
+        let c = origin.length_squared() - radius * radius;
+        let discriminant = half_b * half_b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let root = discriminant.sqrt();
+        let t0 = (-half_b - root) / a;
+        let t1 = (-half_b + root) / a;
+        let use_closer = (ray.direction.z > 0.0) ^ (radius < 0.0);
+        let t = if use_closer { t0.min(t1) } else { t0.max(t1) };
+        if t < 0.0 {
+            return None;
+        }
+
+        let normal = (origin + t * ray.direction).normalize();
+        let normal = if normal.dot(ray.direction) > 0.0 {
+            -normal
+        } else {
+            normal
+        };
+        Some((t, normal))
+    }
+
+    /// Refracts the unit direction `incoming` through a surface with
+    /// `normal` facing against it, or `None` on total internal reflection.
+    fn refract(incoming: Vec3, normal: Vec3, eta: f32) -> Option<Vec3> {
+        let cos_i = -incoming.dot(normal);
+        let sin2_t = eta * eta * (1.0 - cos_i * cos_i).max(0.0);
+        if sin2_t >= 1.0 {
+            return None;
+        }
+
+        let cos_t = (1.0 - sin2_t).sqrt();
+        Some(eta * incoming + (eta * cos_i - cos_t) * normal)
+    }
+
+    /// Follows a ray in lens space through one interface at `z`, or
+    /// `None` if it misses or is blocked.
+    fn pass_interface(
+        interface: &LensInterface,
+        z: f32,
+        ray: &mut LensRay,
+        eta_incident: f32,
+        eta_transmitted: f32,
+    ) -> Option<()> {
+        let (t, normal) = if interface.curvature_radius == 0.0 {
+            if ray.direction.z == 0.0 {
+                return None;
+            }
+            ((z - ray.origin.z) / ray.direction.z, Vec3::ZERO)
+        } else {
+            Self::intersect_spherical(
+                interface.curvature_radius,
+                z + interface.curvature_radius,
+                ray,
+            )?
+        };
+        if t < 0.0 {
+            return None;
+        }
+
+        let hit = ray.at(t);
+        if hit.x * hit.x + hit.y * hit.y > interface.aperture_radius * interface.aperture_radius {
+            return None;
+        }
+        ray.origin = hit;
+
+        if interface.curvature_radius != 0.0 {
+            ray.direction = Self::refract(
+                ray.direction.normalize(),
+                normal,
+                eta_incident / eta_transmitted,
+            )?;
+        }
+        Some(())
+    }
+
+    fn medium(ior: f32) -> f32 {
+        if ior == 0.0 {
+            1.0
+        } else {
+            ior
+        }
+    }
+
+    /// Traces a camera space ray (lens looking along +z) leaving the film
+    /// out through the front of the lens.
+    fn trace_from_film(&self, ray: LensRay) -> Option<LensRay> {
+        let mut ray = flip_z(ray);
+        let mut z = 0.0;
+
+        for (i, interface) in self.interfaces.iter().enumerate().rev() {
+            z -= interface.thickness;
+            let outside = if i > 0 {
+                Self::medium(self.interfaces[i - 1].ior)
+            } else {
+                1.0
+            };
+            Self::pass_interface(interface, z, &mut ray, Self::medium(interface.ior), outside)?;
+        }
+
+        Some(flip_z(ray))
+    }
+
+    /// Traces a camera space ray entering the front of the lens to the film side.
+    fn trace_from_scene(&self, ray: LensRay) -> Option<LensRay> {
+        let mut ray = flip_z(ray);
+        let mut z = -self.front_z();
+
+        for (i, interface) in self.interfaces.iter().enumerate() {
+            let outside = if i > 0 {
+                Self::medium(self.interfaces[i - 1].ior)
+            } else {
+                1.0
+            };
+            Self::pass_interface(interface, z, &mut ray, outside, Self::medium(interface.ior))?;
+            z += interface.thickness;
+        }
+
+        Some(flip_z(ray))
+    }
+
+    /// Principal plane and focal point of a ray parallel to the axis that
+    /// went in as `incoming` and came out as `outgoing`, as positions along
+    /// the lens space z axis.
+    fn cardinal_points(incoming: &LensRay, outgoing: &LensRay) -> (f32, f32) {
+        let focal = outgoing.at(-outgoing.origin.x / outgoing.direction.x).z;
+        let principal = outgoing
+            .at((incoming.origin.x - outgoing.origin.x) / outgoing.direction.x)
+            .z;
+        (-principal, -focal)
+    }
+
+    /// Cardinal points of the thick lens approximation of the system.
+    fn find_thick_lens(&self) -> Option<(f32, f32, f32)> {
+        let x = PARAXIAL_HEIGHT;
+
+        let scene_ray = LensRay {
+            origin: Vec3::new(x, 0.0, self.front_z() + 1.0),
+            direction: Vec3::NEG_Z,
+        };
+        let (principal_scene, focal_scene) =
+            Self::cardinal_points(&scene_ray, &self.trace_from_scene(scene_ray)?);
+
+        let film_ray = LensRay {
+            origin: Vec3::new(x, 0.0, self.rear_z() - 1.0),
+            direction: Vec3::Z,
+        };
+        let (principal_film, _) =
+            Self::cardinal_points(&film_ray, &self.trace_from_film(film_ray)?);
+
+        [principal_scene, focal_scene, principal_film]
+            .into_iter()
+            .all(f32::is_finite)
+            .then_some((principal_scene, focal_scene, principal_film))
+    }
+
+    /// Film distance focusing the lens at `focus_distance`, found from the
+    /// thick lens approximation. Closer than the lens can focus, it focuses
+    /// as close as it can.
+    fn focus_thick_lens(&self, focus_distance: f32) -> f32 {
+        let (principal_scene, focal_scene, principal_film) = self.thick_lens;
+        let focal_length = focal_scene - principal_scene;
+        let z = -focus_distance;
+        let c = (principal_film - z - principal_scene)
+            * (principal_film - z - 4.0 * focal_length - principal_scene);
+        let delta = 0.5 * (principal_film - z + principal_scene - c.max(0.0).sqrt());
+
+        self.rear_z() + delta
+    }
+}
+
+impl RealisticCamera {
+    /// The camera with `lens` stopped down to `aperture_diameter` and
+    /// focused at `focus_distance`, or as close as the lens can focus.
+    pub fn new(
+        lens: &LensPrescription,
+        sensor_width: f32,
+        aspect: f32,
+        aperture_diameter: f32,
+        focus_distance: f32,
+    ) -> Self {
+        let mut lens = lens.clone();
+        for interface in &mut lens.interfaces {
+            if interface.curvature_radius == 0.0 {
+                interface.aperture_radius = interface
+                    .aperture_radius
+                    .min(aperture_diameter * 0.001 / 2.0);
+            }
+        }
+        let thickness = lens.focus_thick_lens(focus_distance);
+        lens.interfaces.last_mut().unwrap().thickness = thickness;
+
+        let sensor_width = sensor_width * 0.001;
+        let mut camera = Self {
+            lens,
+            film_size: Vec2::new(sensor_width, sensor_width / aspect),
+            axial_transmission: 1.0,
+        };
+        camera.axial_transmission = camera.axial_transmission();
+        camera
+    }
+
+    /// Ray from the film point `film` towards `pupil` on the rear element,
+    /// in camera space.
+    fn film_ray(&self, film: Vec2, pupil: Vec2) -> LensRay {
+        let origin = film.extend(0.0);
+        LensRay {
+            origin,
+            direction: pupil.extend(self.lens.rear_z()) - origin,
+        }
+    }
+
+    fn axial_transmission(&self) -> f32 {
+        const STEPS: usize = 64;
+        let radius = self.lens.rear_aperture_radius();
+
+        let mut inside = 0;
+        let mut passed = 0;
+        for i in 0..STEPS {
+            for j in 0..STEPS {
+                let point = Vec2::new(
+                    (i as f32 + 0.5) / STEPS as f32 * 2.0 - 1.0,
+                    (j as f32 + 0.5) / STEPS as f32 * 2.0 - 1.0,
+                );
+                if point.length_squared() > 1.0 {
+                    continue;
+                }
+                inside += 1;
+                let ray = self.film_ray(Vec2::ZERO, point * radius);
+                if self.lens.trace_from_film(ray).is_some() {
+                    passed += 1;
+                }
+            }
+        }
+
+        (passed as f32 / inside as f32).max(1e-4)
+    }
+}
+
+fn flip_z(ray: LensRay) -> LensRay {
+    LensRay {
+        origin: ray.origin * Vec3::new(1.0, 1.0, -1.0),
+        direction: ray.direction * Vec3::new(1.0, 1.0, -1.0),
+    }
+}
+
+impl CameraModel for RealisticCamera {
+    fn generate_ray(&self, view: &View, film: Vec2) -> Option<CameraRay> {
+        // The lens flips the image, so the top left of the picture lands on
+        // the bottom right of the sensor.
+        let film_point = Vec2::new(
+            (0.5 - film.x) * self.film_size.x,
+            (film.y - 0.5) * self.film_size.y,
+        );
+
+        let pupil = loop {
+            let point = Vec2::new(random_range(-1.0..1.0), random_range(-1.0..1.0));
+            if point.length_squared() < 1.0 {
+                break point * self.lens.rear_aperture_radius();
+            }
+        };
+
+        let ray = self.film_ray(film_point, pupil);
+        let cos_theta = ray.direction.normalize().z;
+        let out = self.lens.trace_from_film(ray)?;
+
+        Some(CameraRay {
+            origin: view.origin + view.to_world(out.origin),
+            direction: view.to_world(out.direction),
+            weight: cos_theta.powi(4) / self.axial_transmission,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A biconvex lens 5 mm thick with 100 mm radii, and a stop behind it.
+    const BICONVEX: &str = "# biconvex
+100 5 1.5 30
+-100 2 0 30
+0 100 0 30
+";
+
+    fn lens(text: &str) -> io::Result<LensPrescription> {
+        LensPrescription::parse(text, Path::new("test.lens"))
+    }
+
+    /// Where a ray in lens space crosses the axis.
+    fn axis_crossing(ray: &LensRay) -> f32 {
+        ray.at(-ray.origin.x / ray.direction.x).z
+    }
+
+    #[test]
+    fn thick_lens_matches_the_lensmaker_equation() {
+        let lens = lens(BICONVEX).unwrap();
+        let (n, radius, thickness) = (1.5, 0.1, 0.005);
+        let power = (n - 1.0) * (2.0 / radius - (n - 1.0) * thickness / (n * radius * radius));
+
+        let (principal_scene, focal_scene, _) = lens.thick_lens;
+        let focal_length = focal_scene - principal_scene;
+        assert!(
+            (focal_length.abs() - 1.0 / power).abs() < 1e-4,
+            "{focal_length}"
+        );
+    }
+
+    #[test]
+    fn parallel_light_comes_to_a_focus() {
+        let lens = lens(BICONVEX).unwrap();
+        let crossings: Vec<f32> = [1e-3, 2e-3, 4e-3]
+            .into_iter()
+            .map(|height| {
+                let ray = LensRay {
+                    origin: Vec3::new(height, 0.0, lens.front_z() + 1.0),
+                    direction: Vec3::NEG_Z,
+                };
+                axis_crossing(&lens.trace_from_scene(ray).unwrap())
+            })
+            .collect();
+        // Only spherical aberration spreads the focus, by far less than
+        // the focal length.
+        for crossing in &crossings {
+            assert!((crossing - crossings[0]).abs() < 1e-3, "{crossings:?}");
+        }
+    }
+
+    #[test]
+    fn focuses_at_the_focus_distance() {
+        let camera = RealisticCamera::new(&lens(BICONVEX).unwrap(), 36.0, 1.5, 30.0, 2.0);
+        // Rays close to the axis, which the thick lens describes exactly.
+        for height in [1e-4, 5e-4] {
+            let ray = camera.film_ray(Vec2::ZERO, Vec2::new(height, 0.0));
+            let crossing = axis_crossing(&camera.lens.trace_from_film(ray).unwrap());
+            assert!((crossing - 2.0).abs() < 1e-2, "{crossing}");
+        }
+    }
+
+    #[test]
+    fn stop_blocks_rays_outside_it() {
+        let camera = RealisticCamera::new(&lens(BICONVEX).unwrap(), 36.0, 1.5, 4.0, 2.0);
+        let passes = |pupil: Vec2| {
+            camera
+                .lens
+                .trace_from_film(camera.film_ray(Vec2::ZERO, pupil))
+                .is_some()
+        };
+        assert!(passes(Vec2::new(1e-3, 0.0)));
+        assert!(!passes(Vec2::new(0.01, 0.0)));
+    }
+
+    #[test]
+    fn rejects_bad_prescriptions() {
+        // Only a stop, which brings nothing to a focus.
+        assert!(lens("0 10 0 10\n").is_err());
+        assert!(lens("100 5 1.5\n").is_err());
+        assert!(lens("# no interfaces\n").is_err());
+    }
+}
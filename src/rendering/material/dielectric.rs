@@ -1,17 +1,63 @@
 use crate::{
     math::{random, VecExt},
     rendering::ray::{Color, HitRecord, Ray},
+    scene::description::MaterialDescription,
 };
 
-use super::{Material, ScatterResult};
+use super::{BounceKind, Material, ScatterResult};
+
+/// How a glass's index of refraction changes with the wavelength, given in
+/// nanometres.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Dispersion {
+    /// n = a + b / λ², with λ in micrometres.
+    Cauchy { a: f32, b: f32 },
+    /// n² = 1 + Σ bᵢ λ² / (λ² - cᵢ), with λ in micrometres.
+    Sellmeier { b: [f32; 3], c: [f32; 3] },
+}
+
+impl Dispersion {
+    /// Schott SF11, a dense flint glass with strong dispersion.
+    pub const SF11: Self = Self::Sellmeier {
+        b: [1.737_597, 0.313_747_35, 1.898_781],
+        c: [0.013_188_707, 0.062_306_814, 155.236_3],
+    };
+
+    pub fn refraction_index(&self, wavelength: f32) -> f32 {
+        let lambda = wavelength * 0.001;
+        match self {
+            Self::Cauchy { a, b } => a + b / (lambda * lambda),
+            Self::Sellmeier { b, c } => (1.0
+                + (0..3)
+                    .map(|i| b[i] * lambda * lambda / (lambda * lambda - c[i]))
+                    .sum::<f32>())
+            .sqrt(),
+        }
+    }
+}
 
 pub struct Dielectric {
+    /// Index used when the ray has no wavelength, or for all of them if
+    /// there is no `dispersion`.
     pub refraction_index: f32,
+    pub dispersion: Option<Dispersion>,
 }
 
 impl Dielectric {
     pub fn new(refraction_index: f32) -> Self {
-        Self { refraction_index }
+        Self {
+            refraction_index,
+            dispersion: None,
+        }
+    }
+
+    /// Glass that splits light into its colors in spectral renders, and has
+    /// its index at the sodium D line (589.3 nm) otherwise.
+    pub fn dispersive(dispersion: Dispersion) -> Self {
+        Self {
+            refraction_index: dispersion.refraction_index(589.3),
+            dispersion: Some(dispersion),
+        }
     }
 }
 
@@ -24,10 +70,14 @@ impl Dielectric {
 
 impl Material for Dielectric {
     fn scatter(&self, incoming: Ray, hit: &HitRecord) -> Option<ScatterResult> {
+        let refraction_index = match (&self.dispersion, incoming.wavelength) {
+            (Some(dispersion), Some(wavelength)) => dispersion.refraction_index(wavelength),
+            _ => self.refraction_index,
+        };
         let refraction_ratio = if hit.front_face {
-            1.0 / self.refraction_index
+            1.0 / refraction_index
         } else {
-            self.refraction_index
+            refraction_index
         };
 
         let unit_direction = incoming.direction.normalize();
@@ -35,18 +85,37 @@ impl Material for Dielectric {
         let sin_theta = (1.0 - (cos_theta * cos_theta)).sqrt();
 
         let cannot_refract = refraction_ratio * sin_theta > 1.0;
-        let direction =
+        let (direction, kind) =
             if cannot_refract || Self::reflectance(cos_theta, refraction_ratio) > random() {
-                unit_direction.reflect(hit.normal)
+                (unit_direction.reflect(hit.normal), BounceKind::Specular)
             } else {
-                unit_direction.refract(hit.normal, refraction_ratio)
+                (
+                    unit_direction.refract(hit.normal, refraction_ratio),
+                    BounceKind::Transmission,
+                )
             };
 
-        let new_ray = Ray::new(hit.point, direction, incoming.time);
+        let new_ray = Ray {
+            origin: hit.point,
+            direction,
+            ..incoming
+        };
 
         Some(ScatterResult {
             attenuation: Color::new(1.0, 1.0, 1.0),
             new_ray: Some(new_ray),
+            kind,
+        })
+    }
+
+    fn disperses(&self) -> bool {
+        self.dispersion.is_some()
+    }
+
+    fn describe(&self) -> Option<MaterialDescription> {
+        Some(MaterialDescription::Dielectric {
+            refraction_index: self.refraction_index,
+            dispersion: self.dispersion,
         })
     }
 }
@@ -0,0 +1,422 @@
+use std::f32::consts::{LN_2, PI};
+
+use glam::Vec3;
+
+use crate::{
+    math::random,
+    rendering::ray::{Color, HitRecord, Ray},
+    scene::description::MaterialDescription,
+};
+
+use super::{BounceKind, Material, ScatterResult};
+
+/// Number of scattering lobes followed explicitly: R, TT and TRT. Longer
+/// paths through the fiber are lumped into one more.
+const P_MAX: usize = 3;
+const SQRT_PI_OVER_8: f32 = 0.626_657;
+/// Refraction index of keratin.
+const ETA: f32 = 1.55;
+
+/// Where the hair's absorption comes from.
+#[derive(Clone, Copy)]
+enum Pigment {
+    Absorption(Vec3),
+    /// Color the hair should look after many bounces, turned into an
+    /// absorption that depends on the azimuthal roughness.
+    Color(Vec3),
+}
+
+/// Hair fiber scattering after d'Eon et al. and Chiang et al., as in pbrt:
+/// light reflects off the cuticle (R), passes through the fiber (TT) or
+/// reflects once inside (TRT), each with its own longitudinal lobe tilted by
+/// the cuticle scales, and a logistic spread around the fiber.
+///
+/// Meant for `Curves`, which give the fiber's direction as the hit tangent
+/// and the offset across it as V.
+pub struct Hair {
+    pigment: Pigment,
+    beta_m: f32,
+    beta_n: f32,
+    alpha: f32,
+    sigma_a: Vec3,
+    /// Longitudinal variance of every lobe.
+    v: [f32; P_MAX + 1],
+    /// Azimuthal logistic scale.
+    s: f32,
+    sin_2k_alpha: [f32; 3],
+    cos_2k_alpha: [f32; 3],
+}
+
+impl Hair {
+    /// Hair absorbing `sigma_a` per unit of fiber diameter.
+    pub fn new(sigma_a: Vec3) -> Self {
+        Self::with_pigment(Pigment::Absorption(sigma_a))
+    }
+
+    /// Hair colored by its `eumelanin` and `pheomelanin` concentrations:
+    /// about 8 eumelanin is black, 1.3 brown and 0.3 blonde, while
+    /// pheomelanin makes it red.
+    pub fn from_melanin(eumelanin: f32, pheomelanin: f32) -> Self {
+        Self::new(
+            eumelanin * Vec3::new(0.419, 0.697, 1.37) + pheomelanin * Vec3::new(0.187, 0.4, 1.05),
+        )
+    }
+
+    /// Hair that looks about `color` overall.
+    pub fn from_color(color: Color) -> Self {
+        Self::with_pigment(Pigment::Color(color.0))
+    }
+
+    /// Sets the longitudinal and azimuthal roughness, both in 0..1.
+    pub fn with_roughness(mut self, beta_m: f32, beta_n: f32) -> Self {
+        self.beta_m = beta_m;
+        self.beta_n = beta_n;
+        self.update()
+    }
+
+    /// Sets the angle the cuticle scales tilt the lobes by, in degrees.
+    pub fn with_scale_angle(mut self, degrees: f32) -> Self {
+        self.alpha = degrees.to_radians();
+        self.update()
+    }
+
+    fn with_pigment(pigment: Pigment) -> Self {
+        Self {
+            pigment,
+            beta_m: 0.3,
+            beta_n: 0.3,
+            alpha: 2.0_f32.to_radians(),
+            sigma_a: Vec3::ZERO,
+            v: [0.0; P_MAX + 1],
+            s: 0.0,
+            sin_2k_alpha: [0.0; 3],
+            cos_2k_alpha: [0.0; 3],
+        }
+        .update()
+    }
+
+    fn update(mut self) -> Self {
+        let (beta_m, beta_n) = (self.beta_m, self.beta_n);
+
+        self.sigma_a = match self.pigment {
+            Pigment::Absorption(sigma_a) => sigma_a,
+            Pigment::Color(color) => {
+                let denominator = 5.969 - 0.215 * beta_n + 2.532 * beta_n.powi(2)
+                    - 10.73 * beta_n.powi(3)
+                    + 5.574 * beta_n.powi(4)
+                    + 0.245 * beta_n.powi(5);
+                color
+                    .max(Vec3::splat(1e-4))
+                    .to_array()
+                    .map(|c| (c.ln() / denominator).powi(2))
+                    .into()
+            }
+        };
+
+        let v0 = (0.726 * beta_m + 0.812 * beta_m.powi(2) + 3.7 * beta_m.powi(20)).powi(2);
+        self.v = [v0, 0.25 * v0, 4.0 * v0, 4.0 * v0];
+        self.s =
+            SQRT_PI_OVER_8 * (0.265 * beta_n + 1.194 * beta_n.powi(2) + 5.372 * beta_n.powi(22));
+
+        self.sin_2k_alpha[0] = self.alpha.sin();
+        self.cos_2k_alpha[0] = safe_sqrt(1.0 - self.sin_2k_alpha[0].powi(2));
+        for i in 1..3 {
+            self.sin_2k_alpha[i] = 2.0 * self.cos_2k_alpha[i - 1] * self.sin_2k_alpha[i - 1];
+            self.cos_2k_alpha[i] =
+                self.cos_2k_alpha[i - 1].powi(2) - self.sin_2k_alpha[i - 1].powi(2);
+        }
+        self
+    }
+
+    /// Outgoing angle tilted for lobe `p` by the cuticle scales.
+    fn tilt(&self, p: usize, sin_theta: f32, cos_theta: f32) -> (f32, f32) {
+        let (sin_tilted, cos_tilted) = match p {
+            0 => (
+                sin_theta * self.cos_2k_alpha[1] - cos_theta * self.sin_2k_alpha[1],
+                cos_theta * self.cos_2k_alpha[1] + sin_theta * self.sin_2k_alpha[1],
+            ),
+            1 => (
+                sin_theta * self.cos_2k_alpha[0] + cos_theta * self.sin_2k_alpha[0],
+                cos_theta * self.cos_2k_alpha[0] - sin_theta * self.sin_2k_alpha[0],
+            ),
+            2 => (
+                sin_theta * self.cos_2k_alpha[2] + cos_theta * self.sin_2k_alpha[2],
+                cos_theta * self.cos_2k_alpha[2] - sin_theta * self.sin_2k_alpha[2],
+            ),
+            _ => (sin_theta, cos_theta),
+        };
+        (sin_tilted, cos_tilted.abs())
+    }
+
+    /// Attenuation of every lobe for light leaving at `wo`, with `h` the
+    /// offset across the fiber, along with the refracted angle inside.
+    fn attenuations(&self, sin_theta_o: f32, cos_theta_o: f32, h: f32) -> ([Vec3; P_MAX + 1], f32) {
+        let sin_theta_t = sin_theta_o / ETA;
+        let cos_theta_t = safe_sqrt(1.0 - sin_theta_t * sin_theta_t);
+        let eta_p = safe_sqrt(ETA * ETA - sin_theta_o * sin_theta_o) / cos_theta_o;
+        let sin_gamma_t = (h / eta_p).clamp(-1.0, 1.0);
+        let cos_gamma_t = safe_sqrt(1.0 - sin_gamma_t * sin_gamma_t);
+        let transmittance = (-self.sigma_a * (2.0 * cos_gamma_t / cos_theta_t)).exp();
+
+        let cos_gamma_o = safe_sqrt(1.0 - h * h);
+        let f = fresnel(cos_theta_o * cos_gamma_o, ETA);
+        let mut ap = [Vec3::ZERO; P_MAX + 1];
+        ap[0] = Vec3::splat(f);
+        ap[1] = (1.0 - f).powi(2) * transmittance;
+        for p in 2..P_MAX {
+            ap[p] = ap[p - 1] * transmittance * f;
+        }
+        ap[P_MAX] = ap[P_MAX - 1] * f * transmittance / (Vec3::ONE - transmittance * f);
+
+        (ap, sin_gamma_t.asin())
+    }
+
+    /// Scattering function times the cosine of `wi`, and the density with
+    /// which `sample` picks `wi`, both in the fiber's frame.
+    fn evaluate_local(&self, wo: Vec3, wi: Vec3, h: f32) -> (Vec3, f32) {
+        let (sin_theta_o, cos_theta_o) = (wo.x, safe_sqrt(1.0 - wo.x * wo.x));
+        let (sin_theta_i, cos_theta_i) = (wi.x, safe_sqrt(1.0 - wi.x * wi.x));
+        let phi = wi.z.atan2(wi.y) - wo.z.atan2(wo.y);
+        let gamma_o = h.clamp(-1.0, 1.0).asin();
+
+        let (ap, gamma_t) = self.attenuations(sin_theta_o, cos_theta_o, h);
+        let ap_pdf = lobe_weights(&ap);
+
+        let mut value = Vec3::ZERO;
+        let mut pdf = 0.0;
+        for p in 0..P_MAX {
+            let (sin_theta_op, cos_theta_op) = self.tilt(p, sin_theta_o, cos_theta_o);
+            let mn = longitudinal(
+                cos_theta_i,
+                cos_theta_op,
+                sin_theta_i,
+                sin_theta_op,
+                self.v[p],
+            ) * azimuthal(phi, p, self.s, gamma_o, gamma_t);
+            value += mn * ap[p];
+            pdf += mn * ap_pdf[p];
+        }
+        let m = longitudinal(
+            cos_theta_i,
+            cos_theta_o,
+            sin_theta_i,
+            sin_theta_o,
+            self.v[P_MAX],
+        ) / (2.0 * PI);
+        value += m * ap[P_MAX];
+        pdf += m * ap_pdf[P_MAX];
+
+        (value, pdf)
+    }
+
+    /// Picks an incoming direction for light leaving at `wo`, first choosing
+    /// a lobe by its share of the energy and then sampling its longitudinal
+    /// and azimuthal parts. Also returns the lobe, which counts the times
+    /// the light passed through the fiber's surface.
+    fn sample(&self, wo: Vec3, h: f32) -> (Vec3, usize) {
+        let (sin_theta_o, cos_theta_o) = (wo.x, safe_sqrt(1.0 - wo.x * wo.x));
+        let phi_o = wo.z.atan2(wo.y);
+        let gamma_o = h.clamp(-1.0, 1.0).asin();
+
+        let (ap, gamma_t) = self.attenuations(sin_theta_o, cos_theta_o, h);
+        let ap_pdf = lobe_weights(&ap);
+        let mut choice = random();
+        let p = (0..P_MAX)
+            .find(|&p| {
+                choice -= ap_pdf[p];
+                choice < 0.0
+            })
+            .unwrap_or(P_MAX);
+
+        let (sin_theta_op, cos_theta_op) = self.tilt(p, sin_theta_o, cos_theta_o);
+        let u = random().max(1e-5);
+        let cos_theta = 1.0 + self.v[p] * (u + (1.0 - u) * (-2.0 / self.v[p]).exp()).ln();
+        let sin_theta = safe_sqrt(1.0 - cos_theta * cos_theta);
+        let cos_phi = (2.0 * PI * random()).cos();
+        let sin_theta_i = -cos_theta * sin_theta_op + sin_theta * cos_phi * cos_theta_op;
+        let cos_theta_i = safe_sqrt(1.0 - sin_theta_i * sin_theta_i);
+
+        let delta_phi = if p < P_MAX {
+            phase(p, gamma_o, gamma_t) + sample_trimmed_logistic(random(), self.s, -PI, PI)
+        } else {
+            2.0 * PI * random()
+        };
+        let phi_i = phi_o + delta_phi;
+        let wi = Vec3::new(
+            sin_theta_i,
+            cos_theta_i * phi_i.cos(),
+            cos_theta_i * phi_i.sin(),
+        );
+        (wi, p)
+    }
+}
+
+impl Material for Hair {
+    fn scatter(&self, incoming: Ray, hit: &HitRecord) -> Option<ScatterResult> {
+        let [along, across, normal] = fiber_frame(hit);
+        let h = (2.0 * hit.v - 1.0).clamp(-1.0, 1.0);
+        let wo = to_local([along, across, normal], -incoming.direction.normalize());
+        let (wi, lobe) = self.sample(wo, h);
+        let (value, pdf) = self.evaluate_local(wo, wi, h);
+        if pdf <= 0.0 || !value.is_finite() {
+            return None;
+        }
+
+        let new_ray = Ray {
+            origin: hit.point,
+            direction: wi.x * along + wi.y * across + wi.z * normal,
+            ..incoming
+        };
+        Some(ScatterResult {
+            attenuation: Color(value / pdf),
+            new_ray: Some(new_ray),
+            // Only the first lobe reflects off the outside of the fiber.
+            kind: if lobe == 0 {
+                BounceKind::Specular
+            } else {
+                BounceKind::Transmission
+            },
+        })
+    }
+
+    fn evaluate(&self, incoming: Ray, hit: &HitRecord, direction: Vec3) -> Option<(Color, f32)> {
+        let frame = fiber_frame(hit);
+        let h = (2.0 * hit.v - 1.0).clamp(-1.0, 1.0);
+        let wo = to_local(frame, -incoming.direction.normalize());
+        let wi = to_local(frame, direction.normalize());
+        let (value, pdf) = self.evaluate_local(wo, wi, h);
+        Some((Color(value), pdf))
+    }
+
+    fn describe(&self) -> Option<MaterialDescription> {
+        Some(MaterialDescription::Hair {
+            sigma_a: self.sigma_a,
+            beta_m: self.beta_m,
+            beta_n: self.beta_n,
+            alpha: self.alpha.to_degrees(),
+        })
+    }
+}
+
+/// Frame at `hit` with x along the fiber and z along the normal.
+fn fiber_frame(hit: &HitRecord) -> [Vec3; 3] {
+    let normal = hit.normal;
+    let along = hit
+        .tangent
+        .unwrap_or_else(|| normal.any_orthonormal_vector());
+    let across = normal.cross(along).normalize();
+    [across.cross(normal), across, normal]
+}
+
+fn to_local([along, across, normal]: [Vec3; 3], w: Vec3) -> Vec3 {
+    Vec3::new(w.dot(along), w.dot(across), w.dot(normal))
+}
+
+fn safe_sqrt(x: f32) -> f32 {
+    x.max(0.0).sqrt()
+}
+
+/// Share of the energy in every lobe, by luminance.
+fn lobe_weights(ap: &[Vec3; P_MAX + 1]) -> [f32; P_MAX + 1] {
+    let luminance = |c: Vec3| c.dot(Vec3::new(0.2126, 0.7152, 0.0722));
+    let total: f32 = ap.iter().map(|&a| luminance(a)).sum();
+    ap.map(|a| {
+        if total > 0.0 {
+            luminance(a) / total
+        } else {
+            0.0
+        }
+    })
+}
+
+/// Fresnel reflectance of a dielectric with index `eta`, for unpolarized
+/// light arriving at `cos_theta_i` from outside.
+fn fresnel(cos_theta_i: f32, eta: f32) -> f32 {
+    let cos_theta_i = cos_theta_i.clamp(0.0, 1.0);
+    let sin_theta_t = safe_sqrt(1.0 - cos_theta_i * cos_theta_i) / eta;
+    if sin_theta_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_theta_t = safe_sqrt(1.0 - sin_theta_t * sin_theta_t);
+    let parallel = (eta * cos_theta_i - cos_theta_t) / (eta * cos_theta_i + cos_theta_t);
+    let perpendicular = (cos_theta_i - eta * cos_theta_t) / (cos_theta_i + eta * cos_theta_t);
+    0.5 * (parallel * parallel + perpendicular * perpendicular)
+}
+
+/// Modified Bessel function of the first kind, order zero.
+fn bessel_i0(x: f32) -> f32 {
+    let mut value = 0.0;
+    let mut x_2i = 1.0;
+    let mut i_factorial = 1.0;
+    let mut four_i = 1.0;
+    for i in 0..10 {
+        if i > 1 {
+            i_factorial *= i as f32;
+        }
+        value += x_2i / (four_i * i_factorial * i_factorial);
+        x_2i *= x * x;
+        four_i *= 4.0;
+    }
+    value
+}
+
+fn log_bessel_i0(x: f32) -> f32 {
+    if x > 12.0 {
+        x + 0.5 * (-(2.0 * PI).ln() + (1.0 / x).ln() + 1.0 / (8.0 * x))
+    } else {
+        bessel_i0(x).ln()
+    }
+}
+
+/// Longitudinal scattering with variance `v`.
+fn longitudinal(
+    cos_theta_i: f32,
+    cos_theta_o: f32,
+    sin_theta_i: f32,
+    sin_theta_o: f32,
+    v: f32,
+) -> f32 {
+    let a = cos_theta_i * cos_theta_o / v;
+    let b = sin_theta_i * sin_theta_o / v;
+    if v <= 0.1 {
+        (log_bessel_i0(a) - b - 1.0 / v + LN_2 + (1.0 / (2.0 * v)).ln()).exp()
+    } else {
+        (-b).exp() * bessel_i0(a) / ((1.0 / v).sinh() * 2.0 * v)
+    }
+}
+
+/// Azimuthal change of direction of lobe `p` without roughness.
+fn phase(p: usize, gamma_o: f32, gamma_t: f32) -> f32 {
+    2.0 * p as f32 * gamma_t - 2.0 * gamma_o + p as f32 * PI
+}
+
+/// Azimuthal scattering of lobe `p`: a logistic spread around its phase.
+fn azimuthal(phi: f32, p: usize, s: f32, gamma_o: f32, gamma_t: f32) -> f32 {
+    let mut delta = phi - phase(p, gamma_o, gamma_t);
+    while delta > PI {
+        delta -= 2.0 * PI;
+    }
+    while delta < -PI {
+        delta += 2.0 * PI;
+    }
+    trimmed_logistic(delta, s, -PI, PI)
+}
+
+fn logistic(x: f32, s: f32) -> f32 {
+    let x = x.abs();
+    (-x / s).exp() / (s * (1.0 + (-x / s).exp()).powi(2))
+}
+
+fn logistic_cdf(x: f32, s: f32) -> f32 {
+    1.0 / (1.0 + (-x / s).exp())
+}
+
+fn trimmed_logistic(x: f32, s: f32, a: f32, b: f32) -> f32 {
+    logistic(x, s) / (logistic_cdf(b, s) - logistic_cdf(a, s))
+}
+
+fn sample_trimmed_logistic(u: f32, s: f32, a: f32, b: f32) -> f32 {
+    let k = logistic_cdf(b, s) - logistic_cdf(a, s);
+    let x = -s * (1.0 / (u * k + logistic_cdf(a, s)) - 1.0).ln();
+    x.clamp(a, b)
+}
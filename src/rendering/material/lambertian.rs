@@ -1,3 +1,5 @@
+use std::f32::consts::PI;
+
 use glam::Vec3;
 
 use crate::{
@@ -6,9 +8,10 @@ use crate::{
         ray::{Color, HitRecord, Ray},
         texture::{solid::SolidColor, Texture},
     },
+    scene::description::MaterialDescription,
 };
 
-use super::{Material, ScatterResult};
+use super::{BounceKind, Material, ScatterResult};
 
 pub struct Lambertian<T: Texture> {
     pub albedo: T,
@@ -32,10 +35,33 @@ impl<T: Texture> Material for Lambertian<T> {
         if direction.near_zero() {
             direction = hit.normal;
         }
-        let scattered = Ray::new(hit.point, direction, incoming.time);
+        let scattered = Ray {
+            origin: hit.point,
+            direction,
+            ..incoming
+        };
         Some(ScatterResult {
-            attenuation: self.albedo.value(hit.u, hit.v, hit.point),
+            attenuation: self.albedo.value_at(hit),
             new_ray: Some(scattered),
+            kind: BounceKind::Diffuse,
         })
     }
+
+    fn evaluate(&self, incoming: Ray, hit: &HitRecord, direction: Vec3) -> Option<(Color, f32)> {
+        // Only light from the side `incoming` comes from is reflected.
+        let normal = if incoming.direction.dot(hit.normal) < 0.0 {
+            hit.normal
+        } else {
+            -hit.normal
+        };
+        let cosine = direction.normalize().dot(normal).max(0.0);
+        Some((
+            Color(self.albedo.value_at(hit).0 * cosine / PI),
+            cosine / PI,
+        ))
+    }
+
+    fn describe(&self) -> Option<MaterialDescription> {
+        Some(MaterialDescription::Lambertian(self.albedo.describe()?))
+    }
 }
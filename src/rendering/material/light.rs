@@ -1,6 +1,9 @@
-use crate::rendering::{
-    ray::{Color, HitRecord, Ray},
-    texture::{solid::SolidColor, Texture},
+use crate::{
+    rendering::{
+        ray::{Color, HitRecord, Ray},
+        texture::{solid::SolidColor, Texture},
+    },
+    scene::description::MaterialDescription,
 };
 
 use super::{Material, ScatterResult};
@@ -29,4 +32,8 @@ impl<T: Texture> Material for Light<T> {
     fn emit(&self, u: f32, v: f32, point: glam::Vec3) -> Color {
         self.albedo.value(u, v, point)
     }
+
+    fn describe(&self) -> Option<MaterialDescription> {
+        Some(MaterialDescription::Light(self.albedo.describe()?))
+    }
 }
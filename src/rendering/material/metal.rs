@@ -6,9 +6,10 @@ use crate::{
         ray::{Color, HitRecord, Ray},
         texture::{solid::SolidColor, Texture},
     },
+    scene::description::MaterialDescription,
 };
 
-use super::{Material, ScatterResult};
+use super::{BounceKind, Material, ScatterResult};
 
 pub struct Metal<T: Texture> {
     pub albedo: T,
@@ -33,14 +34,22 @@ impl Metal<SolidColor> {
 impl<T: Texture> Material for Metal<T> {
     fn scatter(&self, incoming: Ray, hit: &HitRecord) -> Option<ScatterResult> {
         let reflection_direction = incoming.direction.normalize().reflect(hit.normal);
-        let new_ray = Ray::new(
-            hit.point,
-            reflection_direction + self.fuzz * Vec3::random_unit(),
-            incoming.time,
-        );
+        let new_ray = Ray {
+            origin: hit.point,
+            direction: reflection_direction + self.fuzz * Vec3::random_unit(),
+            ..incoming
+        };
         Some(ScatterResult {
-            attenuation: self.albedo.value(hit.u, hit.v, hit.point),
+            attenuation: self.albedo.value_at(hit),
             new_ray: Some(new_ray),
+            kind: BounceKind::Specular,
+        })
+    }
+
+    fn describe(&self) -> Option<MaterialDescription> {
+        Some(MaterialDescription::Metal {
+            albedo: self.albedo.describe()?,
+            fuzz: self.fuzz,
         })
     }
 }
@@ -1,19 +1,58 @@
 pub mod dielectric;
+pub mod hair;
 pub mod lambertian;
 pub mod light;
 pub mod metal;
+pub mod registry;
 
-pub use dielectric::Dielectric;
+pub use dielectric::{Dielectric, Dispersion};
 use glam::Vec3;
+pub use hair::Hair;
 pub use lambertian::Lambertian;
 pub use light::Light;
 pub use metal::Metal;
+pub use registry::{MaterialHandle, MaterialRegistry};
+
+use std::sync::Arc;
+
+use crate::scene::description::MaterialDescription;
 
 use super::ray::{Color, HitRecord, Ray};
 
 pub struct ScatterResult {
     pub attenuation: Color,
     pub new_ray: Option<Ray>,
+    /// What kind of bounce `new_ray` takes, which counts against the
+    /// camera's limit for it.
+    pub kind: BounceKind,
+}
+
+/// Kinds of bounces, each of which a path may take a limited number of.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BounceKind {
+    /// Off a rough surface.
+    Diffuse,
+    /// Mirror-like, off metal, glass or the outside of a fiber.
+    Specular,
+    /// Through glass or a fiber.
+    Transmission,
+}
+
+impl BounceKind {
+    pub const ALL: [Self; 3] = [Self::Diffuse, Self::Specular, Self::Transmission];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Name `from_name` reads back.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Diffuse => "diffuse",
+            Self::Specular => "specular",
+            Self::Transmission => "transmission",
+        }
+    }
 }
 
 pub trait Material: Send + Sync {
@@ -21,4 +60,83 @@ pub trait Material: Send + Sync {
     fn emit(&self, _u: f32, _v: f32, _point: Vec3) -> Color {
         Color::new(0., 0., 0.)
     }
+
+    /// Scattering function times the cosine between `direction` and the
+    /// normal, for light arriving from `direction` and leaving back along
+    /// `incoming`, together with the density per solid angle with which
+    /// `scatter` picks `direction`. `None` for materials that only scatter
+    /// into a few exact directions, such as mirrors and glass, or that
+    /// can't tell; integrators can only follow those through `scatter`.
+    fn evaluate(&self, _incoming: Ray, _hit: &HitRecord, _direction: Vec3) -> Option<(Color, f32)> {
+        None
+    }
+
+    /// Whether scattering depends on the ray's wavelength, so that a spectral
+    /// path can only follow one of them past this material.
+    fn disperses(&self) -> bool {
+        false
+    }
+
+    /// The material as plain data, `None` if it can't be saved.
+    fn describe(&self) -> Option<MaterialDescription> {
+        None
+    }
+
+    /// Position of the material in its registry if it is a handle to one.
+    fn handle_id(&self) -> Option<u32> {
+        None
+    }
+}
+
+impl<M: Material + ?Sized> Material for Box<M> {
+    fn scatter(&self, incoming: Ray, hit: &HitRecord) -> Option<ScatterResult> {
+        (**self).scatter(incoming, hit)
+    }
+
+    fn emit(&self, u: f32, v: f32, point: Vec3) -> Color {
+        (**self).emit(u, v, point)
+    }
+
+    fn evaluate(&self, incoming: Ray, hit: &HitRecord, direction: Vec3) -> Option<(Color, f32)> {
+        (**self).evaluate(incoming, hit, direction)
+    }
+
+    fn disperses(&self) -> bool {
+        (**self).disperses()
+    }
+
+    fn describe(&self) -> Option<MaterialDescription> {
+        (**self).describe()
+    }
+
+    fn handle_id(&self) -> Option<u32> {
+        (**self).handle_id()
+    }
+}
+
+/// Lets objects share one material.
+impl<M: Material + ?Sized> Material for Arc<M> {
+    fn scatter(&self, incoming: Ray, hit: &HitRecord) -> Option<ScatterResult> {
+        (**self).scatter(incoming, hit)
+    }
+
+    fn emit(&self, u: f32, v: f32, point: Vec3) -> Color {
+        (**self).emit(u, v, point)
+    }
+
+    fn evaluate(&self, incoming: Ray, hit: &HitRecord, direction: Vec3) -> Option<(Color, f32)> {
+        (**self).evaluate(incoming, hit, direction)
+    }
+
+    fn disperses(&self) -> bool {
+        (**self).disperses()
+    }
+
+    fn describe(&self) -> Option<MaterialDescription> {
+        (**self).describe()
+    }
+
+    fn handle_id(&self) -> Option<u32> {
+        (**self).handle_id()
+    }
 }
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+use glam::Vec3;
+
+use crate::{
+    rendering::ray::{Color, HitRecord, Ray},
+    scene::description::MaterialDescription,
+};
+
+use super::{Material, ScatterResult};
+
+/// Where a registered material lives. Hits read it without locking, so
+/// swapping it doesn't hold up renders that are under way.
+type Slot = Arc<ArcSwap<Arc<dyn Material>>>;
+
+/// Named materials that objects share through handles. Every object made
+/// of a registered material sees it replaced when the registry swaps it.
+#[derive(Default)]
+pub struct MaterialRegistry {
+    slots: Vec<(String, Slot)>,
+}
+
+impl MaterialRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `material` under `name`, or replaces the material already
+    /// registered under it, and returns a handle to build objects with.
+    pub fn add<M: Material + 'static>(&mut self, name: &str, material: M) -> MaterialHandle {
+        self.add_shared(name, Arc::new(material))
+    }
+
+    /// Like `add` for a material that is already shared.
+    pub fn add_shared(&mut self, name: &str, material: Arc<dyn Material>) -> MaterialHandle {
+        if self.set(name, material.clone()) {
+            return self.get(name).unwrap();
+        }
+
+        let slot = Arc::new(ArcSwap::from_pointee(material));
+        self.slots.push((name.to_string(), slot.clone()));
+        MaterialHandle {
+            id: self.slots.len() as u32 - 1,
+            slot,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<MaterialHandle> {
+        let id = self.slots.iter().position(|(n, _)| n == name)?;
+        Some(MaterialHandle {
+            id: id as u32,
+            slot: self.slots[id].1.clone(),
+        })
+    }
+
+    /// Swaps the material registered under `name`, returning whether there
+    /// was one. Renders already under way may see either material.
+    pub fn set(&self, name: &str, material: Arc<dyn Material>) -> bool {
+        match self.slots.iter().find(|(n, _)| n == name) {
+            Some((_, slot)) => {
+                slot.store(Arc::new(material));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Name of the material whose handles have the id `id`.
+    #[cfg_attr(not(feature = "viewer"), allow(dead_code))]
+    pub fn name(&self, id: u32) -> Option<&str> {
+        self.slots.get(id as usize).map(|(name, _)| name.as_str())
+    }
+}
+
+/// Material of a registry that objects hold in place of their own. Clones
+/// refer to the same material.
+#[derive(Clone)]
+pub struct MaterialHandle {
+    id: u32,
+    slot: Slot,
+}
+
+impl Material for MaterialHandle {
+    fn scatter(&self, incoming: Ray, hit: &HitRecord) -> Option<ScatterResult> {
+        self.slot.load().scatter(incoming, hit)
+    }
+
+    fn emit(&self, u: f32, v: f32, point: Vec3) -> Color {
+        self.slot.load().emit(u, v, point)
+    }
+
+    fn evaluate(&self, incoming: Ray, hit: &HitRecord, direction: Vec3) -> Option<(Color, f32)> {
+        self.slot.load().evaluate(incoming, hit, direction)
+    }
+
+    fn disperses(&self) -> bool {
+        self.slot.load().disperses()
+    }
+
+    fn describe(&self) -> Option<MaterialDescription> {
+        self.slot.load().describe()
+    }
+
+    fn handle_id(&self) -> Option<u32> {
+        Some(self.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rendering::material::Light;
+
+    use super::*;
+
+    #[test]
+    fn handles_see_swapped_materials() {
+        let mut registry = MaterialRegistry::new();
+        let handle = registry.add("lamp", Light::solid_color(Color::new(1.0, 1.0, 1.0)));
+        assert_eq!(handle.emit(0.0, 0.0, Vec3::ZERO).0, Vec3::ONE);
+
+        let brighter = Arc::new(Light::solid_color(Color::new(2.0, 2.0, 2.0)));
+        assert!(registry.set("lamp", brighter));
+        assert_eq!(handle.emit(0.0, 0.0, Vec3::ZERO).0, Vec3::splat(2.0));
+        assert_eq!(handle.handle_id(), Some(0));
+        assert_eq!(registry.get("lamp").unwrap().handle_id(), Some(0));
+        assert!(!registry.set(
+            "other",
+            Arc::new(Light::solid_color(Color::new(0.0, 0.0, 0.0)))
+        ));
+    }
+}
@@ -1,4 +1,15 @@
+pub mod aov;
+pub mod aperture;
 pub mod camera;
+pub mod colorspace;
+pub mod debug;
+pub mod denoise;
+pub mod film;
+pub mod filter;
+pub mod integrator;
+pub mod lens;
 pub mod material;
+pub mod projection;
 pub mod ray;
+pub mod spectrum;
 pub mod texture;
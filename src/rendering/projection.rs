@@ -0,0 +1,396 @@
+use std::{
+    f32::consts::{PI, TAU},
+    sync::Arc,
+};
+
+use glam::{Vec2, Vec3};
+
+use super::{
+    aperture::Aperture,
+    camera::{CameraConfig, Focus},
+    lens::{LensPrescription, RealisticCamera},
+};
+
+/// Where the camera is and how it is oriented at one instant, together with
+/// the configuration values every projection may use.
+pub struct View {
+    pub origin: Vec3,
+    /// Right, up and backward directions.
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+    pub vfov: f32,
+    /// Image width divided by height.
+    pub aspect: f32,
+    pub focus_distance: f32,
+    /// Radius of the thin lens in scene units.
+    pub lens_radius: f32,
+}
+
+impl View {
+    pub fn new(config: &CameraConfig, aspect: f32) -> Self {
+        let w = (config.look_from - config.look_at).normalize();
+        let u = config.vector_up.cross(w).normalize();
+        let v = w.cross(u);
+
+        let focus_distance = match config.focus {
+            Focus::LookAt => (config.look_at - config.look_from).length(),
+            _ => config.focus_distance,
+        };
+        let (vfov, lens_radius) = match &config.lens {
+            Some(lens) => (lens.vfov(aspect), lens.aperture_radius()),
+            None => (
+                config.vfov,
+                focus_distance * (config.defocus_angle / 2.0).to_radians().tan(),
+            ),
+        };
+
+        Self {
+            origin: config.look_from,
+            u,
+            v,
+            w,
+            vfov,
+            aspect,
+            focus_distance,
+            lens_radius,
+        }
+    }
+
+    /// Turns a vector given as (right, up, forward) components into world space.
+    pub(super) fn to_world(&self, local: Vec3) -> Vec3 {
+        local.x * self.u + local.y * self.v - local.z * self.w
+    }
+}
+
+/// A ray leaving the camera, weighted by how much light the lens lets
+/// through along it.
+pub struct CameraRay {
+    pub origin: Vec3,
+    pub direction: Vec3,
+    pub weight: f32,
+}
+
+impl CameraRay {
+    fn new(origin: Vec3, direction: Vec3) -> Self {
+        Self {
+            origin,
+            direction,
+            weight: 1.0,
+        }
+    }
+}
+
+pub trait CameraModel: Send + Sync {
+    /// Ray through `film`, a position on the image running from (0, 0) in
+    /// the top left corner to (1, 1) in the bottom right. `None` means no
+    /// light reaches that point, for example outside a fisheye's circle.
+    fn generate_ray(&self, view: &View, film: Vec2) -> Option<CameraRay>;
+}
+
+#[derive(Clone, Copy)]
+pub enum FisheyeMapping {
+    /// Distance from the image center proportional to the angle.
+    Equidistant,
+    /// Preserves solid angle, as most real fisheye lenses roughly do.
+    Equisolid,
+}
+
+impl FisheyeMapping {
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Equidistant, Self::Equisolid]
+            .into_iter()
+            .find(|mapping| mapping.name() == name)
+    }
+
+    /// Name `from_name` reads back.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Equidistant => "equidistant",
+            Self::Equisolid => "equisolid",
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub enum Projection {
+    /// Thin lens perspective through the configured aperture.
+    #[default]
+    Perspective,
+    /// Parallel rays through a viewport `height` world units tall.
+    Orthographic { height: f32 },
+    /// Full sphere of directions, meant for images twice as wide as tall.
+    Equirectangular,
+    /// Circular image covering `fov` degrees across its shorter side.
+    Fisheye { fov: f32, mapping: FisheyeMapping },
+    /// Panorama wrapped around a cylinder: `horizontal_fov` degrees across
+    /// and `vfov` up the cylinder's height.
+    Cylindrical { horizontal_fov: f32 },
+    /// Traces through the lens of a prescription.
+    Realistic {
+        lens: Arc<LensPrescription>,
+        /// Sensor width in millimetres.
+        sensor_width: f32,
+        /// Diameter of the aperture stop in millimetres.
+        aperture_diameter: f32,
+    },
+}
+
+impl Projection {
+    pub fn build(&self, config: &CameraConfig, view: &View) -> Arc<dyn CameraModel> {
+        match self {
+            Self::Perspective => Arc::new(Perspective {
+                aperture: config.aperture.clone(),
+            }),
+            Self::Orthographic { height } => Arc::new(Orthographic { height: *height }),
+            Self::Equirectangular => Arc::new(Equirectangular),
+            Self::Fisheye { fov, mapping } => Arc::new(Fisheye {
+                half_fov: fov.to_radians() / 2.0,
+                mapping: *mapping,
+            }),
+            Self::Cylindrical { horizontal_fov } => Arc::new(Cylindrical {
+                horizontal_fov: horizontal_fov.to_radians(),
+            }),
+            Self::Realistic {
+                lens,
+                sensor_width,
+                aperture_diameter,
+            } => Arc::new(RealisticCamera::new(
+                lens,
+                *sensor_width,
+                view.aspect,
+                *aperture_diameter,
+                view.focus_distance,
+            )),
+        }
+    }
+}
+
+pub struct Perspective {
+    aperture: Aperture,
+}
+
+impl CameraModel for Perspective {
+    fn generate_ray(&self, view: &View, film: Vec2) -> Option<CameraRay> {
+        let half_height = (view.vfov.to_radians() / 2.0).tan() * view.focus_distance;
+        let half_width = half_height * view.aspect;
+
+        let focus_point = view.origin - view.focus_distance * view.w
+            + (2.0 * film.x - 1.0) * half_width * view.u
+            + (1.0 - 2.0 * film.y) * half_height * view.v;
+
+        let origin = if view.lens_radius <= 0.0 {
+            view.origin
+        } else {
+            let point = self.aperture.sample() * view.lens_radius;
+            view.origin + point.x * view.u + point.y * view.v
+        };
+
+        Some(CameraRay::new(origin, focus_point - origin))
+    }
+}
+
+pub struct Orthographic {
+    height: f32,
+}
+
+impl CameraModel for Orthographic {
+    fn generate_ray(&self, view: &View, film: Vec2) -> Option<CameraRay> {
+        let width = self.height * view.aspect;
+        let origin =
+            view.origin + (film.x - 0.5) * width * view.u + (0.5 - film.y) * self.height * view.v;
+
+        Some(CameraRay::new(origin, -view.w))
+    }
+}
+
+pub struct Equirectangular;
+
+impl CameraModel for Equirectangular {
+    fn generate_ray(&self, view: &View, film: Vec2) -> Option<CameraRay> {
+        let longitude = (film.x - 0.5) * TAU;
+        let latitude = (0.5 - film.y) * PI;
+
+        let direction = Vec3::new(
+            latitude.cos() * longitude.sin(),
+            latitude.sin(),
+            latitude.cos() * longitude.cos(),
+        );
+        Some(CameraRay::new(view.origin, view.to_world(direction)))
+    }
+}
+
+pub struct Fisheye {
+    half_fov: f32,
+    mapping: FisheyeMapping,
+}
+
+impl CameraModel for Fisheye {
+    fn generate_ray(&self, view: &View, film: Vec2) -> Option<CameraRay> {
+        // Position relative to the center, 1 at the edge of the shorter side.
+        let mut position = Vec2::new(2.0 * film.x - 1.0, 1.0 - 2.0 * film.y);
+        if view.aspect > 1.0 {
+            position.x *= view.aspect;
+        } else {
+            position.y /= view.aspect;
+        }
+
+        let radius = position.length();
+        if radius > 1.0 {
+            return None;
+        }
+
+        let theta = match self.mapping {
+            FisheyeMapping::Equidistant => radius * self.half_fov,
+            FisheyeMapping::Equisolid => 2.0 * (radius * (self.half_fov / 2.0).sin()).asin(),
+        };
+        let phi = position.y.atan2(position.x);
+
+        let direction = Vec3::new(
+            theta.sin() * phi.cos(),
+            theta.sin() * phi.sin(),
+            theta.cos(),
+        );
+        Some(CameraRay::new(view.origin, view.to_world(direction)))
+    }
+}
+
+pub struct Cylindrical {
+    horizontal_fov: f32,
+}
+
+impl CameraModel for Cylindrical {
+    fn generate_ray(&self, view: &View, film: Vec2) -> Option<CameraRay> {
+        let angle = (film.x - 0.5) * self.horizontal_fov;
+        let height = (1.0 - 2.0 * film.y) * (view.vfov.to_radians() / 2.0).tan();
+
+        let direction = Vec3::new(angle.sin(), height, angle.cos());
+        Some(CameraRay::new(view.origin, view.to_world(direction)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Camera at the origin looking down -z with a 90° vertical field of
+    /// view, twice as wide as tall and focused 2 units away.
+    fn view() -> View {
+        View {
+            origin: Vec3::ZERO,
+            u: Vec3::X,
+            v: Vec3::Y,
+            w: Vec3::Z,
+            vfov: 90.0,
+            aspect: 2.0,
+            focus_distance: 2.0,
+            lens_radius: 0.0,
+        }
+    }
+
+    fn direction(model: &dyn CameraModel, film: Vec2) -> Vec3 {
+        model
+            .generate_ray(&view(), film)
+            .unwrap()
+            .direction
+            .normalize()
+    }
+
+    fn assert_direction(model: &dyn CameraModel, film: Vec2, expected: Vec3) {
+        let found = direction(model, film);
+        assert!(
+            found.abs_diff_eq(expected.normalize(), 1e-5),
+            "{found} at {film}"
+        );
+    }
+
+    #[test]
+    fn perspective_spans_the_field_of_view() {
+        let perspective = Perspective {
+            aperture: Aperture::Disk,
+        };
+        assert_direction(&perspective, Vec2::splat(0.5), Vec3::NEG_Z);
+        assert_direction(&perspective, Vec2::new(0.5, 0.0), Vec3::new(0.0, 1.0, -1.0));
+        assert_direction(&perspective, Vec2::ZERO, Vec3::new(-2.0, 1.0, -1.0));
+    }
+
+    #[test]
+    fn perspective_rays_meet_in_the_focus_plane() {
+        let perspective = Perspective {
+            aperture: Aperture::Disk,
+        };
+        let view = View {
+            lens_radius: 0.5,
+            ..view()
+        };
+        let film = Vec2::new(0.3, 0.6);
+        for _ in 0..8 {
+            let ray = perspective.generate_ray(&view, film).unwrap();
+            assert!(ray.origin.z == 0.0 && ray.origin.length() <= 0.5);
+            let focus = ray.origin + ray.direction * (-2.0 / ray.direction.z);
+            assert!(
+                focus.abs_diff_eq(Vec3::new(-1.6, -0.4, -2.0), 1e-5),
+                "{focus}"
+            );
+        }
+    }
+
+    #[test]
+    fn orthographic_rays_are_parallel() {
+        let orthographic = Orthographic { height: 2.0 };
+        let corner = orthographic.generate_ray(&view(), Vec2::ZERO).unwrap();
+        assert_eq!(corner.origin, Vec3::new(-2.0, 1.0, 0.0));
+        assert_eq!(corner.direction, Vec3::NEG_Z);
+        let center = orthographic
+            .generate_ray(&view(), Vec2::splat(0.5))
+            .unwrap();
+        assert_eq!(center.origin, Vec3::ZERO);
+    }
+
+    #[test]
+    fn equirectangular_covers_every_direction() {
+        assert_direction(&Equirectangular, Vec2::splat(0.5), Vec3::NEG_Z);
+        assert_direction(&Equirectangular, Vec2::new(0.75, 0.5), Vec3::X);
+        assert_direction(&Equirectangular, Vec2::new(0.0, 0.5), Vec3::Z);
+        assert_direction(&Equirectangular, Vec2::new(0.3, 0.0), Vec3::Y);
+    }
+
+    #[test]
+    fn fisheye_maps_its_circle_to_the_field_of_view() {
+        for mapping in [FisheyeMapping::Equidistant, FisheyeMapping::Equisolid] {
+            let fisheye = Fisheye {
+                half_fov: 90f32.to_radians(),
+                mapping,
+            };
+            assert_direction(&fisheye, Vec2::splat(0.5), Vec3::NEG_Z);
+            assert_direction(&fisheye, Vec2::new(0.5, 0.0), Vec3::Y);
+            assert_direction(&fisheye, Vec2::new(0.25, 0.5), Vec3::NEG_X);
+            assert!(fisheye.generate_ray(&view(), Vec2::ZERO).is_none());
+        }
+
+        // Halfway to the edge, equidistant is halfway round and equisolid
+        // further.
+        let angle = |mapping| {
+            let fisheye = Fisheye {
+                half_fov: 90f32.to_radians(),
+                mapping,
+            };
+            direction(&fisheye, Vec2::new(0.5, 0.25))
+                .angle_between(Vec3::NEG_Z)
+                .to_degrees()
+        };
+        assert!((angle(FisheyeMapping::Equidistant) - 45.0).abs() < 1e-3);
+        let equisolid = 2.0 * (0.5 * 45f32.to_radians().sin()).asin().to_degrees();
+        assert!((angle(FisheyeMapping::Equisolid) - equisolid).abs() < 1e-3);
+    }
+
+    #[test]
+    fn cylindrical_wraps_around_and_keeps_verticals() {
+        let cylindrical = Cylindrical {
+            horizontal_fov: 180f32.to_radians(),
+        };
+        assert_direction(&cylindrical, Vec2::splat(0.5), Vec3::NEG_Z);
+        assert_direction(&cylindrical, Vec2::new(1.0, 0.5), Vec3::X);
+        assert_direction(&cylindrical, Vec2::new(1.0, 0.0), Vec3::new(1.0, 1.0, 0.0));
+    }
+}
@@ -1,7 +1,7 @@
-use std::{ops::Mul, sync::Arc};
+use std::ops::Mul;
 
-use derive_more::{Add, From, Mul};
-use glam::Vec3;
+use derive_more::{Add, From};
+use glam::{Affine3A, Vec3};
 
 use crate::{math::VecExt, object::Object};
 
@@ -32,6 +32,8 @@ pub struct Ray {
     pub origin: Vec3,
     pub direction: Vec3,
     pub time: f32,
+    /// Hero wavelength in nanometres when rendering spectrally.
+    pub wavelength: Option<f32>,
 }
 
 impl Ray {
@@ -40,6 +42,7 @@ impl Ray {
             origin,
             direction,
             time,
+            wavelength: None,
         }
     }
 
@@ -57,9 +60,19 @@ pub struct HitRecord<'a> {
     pub front_face: bool,
     pub u: f32,
     pub v: f32,
+    /// Direction along the surface in which `u` grows, for materials that
+    /// depend on it such as hair. `None` where the object doesn't give one.
+    pub tangent: Option<Vec3>,
+    /// Color the object stores at the hit, such as a vertex or point color,
+    /// for textures that read it.
+    pub color: Option<Color>,
+    /// Distance from the hit to the nearest edge of the triangle it is on,
+    /// for drawing wireframes. `None` for anything but triangle meshes.
+    pub edge_distance: Option<f32>,
 }
 
 impl<'a> HitRecord<'a> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         object: &'a dyn Object,
         ray: Ray,
@@ -86,6 +99,56 @@ impl<'a> HitRecord<'a> {
             front_face,
             u,
             v,
+            tangent: None,
+            color: None,
+            edge_distance: None,
         }
     }
+
+    pub fn with_tangent(mut self, tangent: Vec3) -> Self {
+        self.tangent = Some(tangent);
+        self
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn with_edge_distance(mut self, distance: f32) -> Self {
+        self.edge_distance = Some(distance);
+        self
+    }
+
+    /// Registry id of the material when the object holds a handle to it,
+    /// which stays the same however the world is rebuilt.
+    pub fn material_id(&self) -> Option<u32> {
+        self.material.handle_id()
+    }
+
+    /// Moves a hit found on an object in its local space into world space.
+    /// `ray` is the world space ray, whose `t` matches the local one as long
+    /// as the local ray was not renormalized.
+    pub fn transform(mut self, object: &'a dyn Object, ray: Ray, transform: &Affine3A) -> Self {
+        let outward_normal = if self.front_face {
+            self.normal
+        } else {
+            -self.normal
+        };
+        let normal_matrix = transform.matrix3.inverse().transpose();
+        let outward_normal = (normal_matrix * outward_normal).normalize();
+
+        self.object = object;
+        self.point = transform.transform_point3(self.point);
+        self.tangent = self
+            .tangent
+            .map(|tangent| transform.transform_vector3(tangent).normalize());
+        self.front_face = ray.direction.dot(outward_normal) < 0.0;
+        self.normal = if self.front_face {
+            outward_normal
+        } else {
+            -outward_normal
+        };
+        self
+    }
 }
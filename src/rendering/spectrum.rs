@@ -0,0 +1,292 @@
+use std::sync::OnceLock;
+
+use glam::{Mat3, Vec3, Vec4};
+
+use super::colorspace::{working_space, Primaries};
+
+/// Range of wavelengths, in nanometres, that spectral rendering samples.
+pub const MIN_WAVELENGTH: f32 = 380.0;
+pub const MAX_WAVELENGTH: f32 = 780.0;
+
+/// Number of wavelengths carried by every camera path.
+pub const WAVELENGTHS: usize = 4;
+
+/// Wavelengths one camera path is traced at: a randomly chosen hero
+/// wavelength and others evenly spaced from it across the visible range.
+/// Radiance along the path is a `Vec4` holding one value per wavelength.
+#[derive(Clone, Copy)]
+pub struct SampledWavelengths {
+    lambda: [f32; WAVELENGTHS],
+    /// Set once something along the path bent the wavelengths apart, after
+    /// which only the hero is followed.
+    secondary_terminated: bool,
+}
+
+impl SampledWavelengths {
+    /// Wavelengths for a uniform random number `u` in 0..1.
+    pub fn sample(u: f32) -> Self {
+        let range = MAX_WAVELENGTH - MIN_WAVELENGTH;
+        let lambda = std::array::from_fn(|i| {
+            let offset = (u + i as f32 / WAVELENGTHS as f32).fract();
+            MIN_WAVELENGTH + offset * range
+        });
+        Self {
+            lambda,
+            secondary_terminated: false,
+        }
+    }
+
+    pub fn hero(&self) -> f32 {
+        self.lambda[0]
+    }
+
+    /// Drops all but the hero wavelength, for scattering that depends on the
+    /// wavelength such as dispersion. Returns the factor to scale the path's
+    /// radiance from here on by; the hero takes over the others' share, which
+    /// keeps the estimate unbiased.
+    pub fn terminate_secondary(&mut self) -> Vec4 {
+        if self.secondary_terminated {
+            return Vec4::ONE;
+        }
+        self.secondary_terminated = true;
+        Vec4::new(WAVELENGTHS as f32, 0.0, 0.0, 0.0)
+    }
+
+    /// Reflectance spectrum matching the working space color `rgb`, at
+    /// these wavelengths.
+    pub fn reflectance(&self, rgb: Vec3) -> Vec4 {
+        let rgb = to_rec709(rgb);
+        Vec4::from_array(self.lambda.map(|lambda| smits(rgb, lambda).clamp(0.0, 1.0)))
+    }
+
+    /// Spectrum of a light whose working space color is `rgb`, under a D65
+    /// white point.
+    pub fn illuminant(&self, rgb: Vec3) -> Vec4 {
+        let rgb = to_rec709(rgb);
+        Vec4::from_array(
+            self.lambda
+                .map(|lambda| smits(rgb, lambda).max(0.0) * d65(lambda)),
+        )
+    }
+
+    /// Working space color of the `radiance` found at these wavelengths,
+    /// integrated against the CIE color matching functions. A D65 white
+    /// comes out as exactly (1, 1, 1) in Rec. 709.
+    pub fn to_rgb(self, radiance: Vec4) -> Vec3 {
+        let pdf = 1.0 / (MAX_WAVELENGTH - MIN_WAVELENGTH);
+        let xyz = self
+            .lambda
+            .iter()
+            .zip(radiance.to_array())
+            .map(|(&lambda, value)| value * cie_xyz(lambda))
+            .sum::<Vec3>()
+            / (pdf * WAVELENGTHS as f32);
+
+        Primaries::Rec709.conversion(working_space()) * (XYZ_TO_SRGB * xyz * white_balance())
+    }
+}
+
+/// Smits' basis spectra are fitted to Rec. 709 primaries.
+fn to_rec709(rgb: Vec3) -> Vec3 {
+    working_space().conversion(Primaries::Rec709) * rgb
+}
+
+/// Linear sRGB (Rec. 709 primaries, D65 white) from CIE XYZ.
+const XYZ_TO_SRGB: Mat3 = Mat3::from_cols_array(&[
+    3.2404542, -0.969266, 0.0556434, -1.5371385, 1.8760108, -0.2040259, -0.4985314, 0.041556,
+    1.0572252,
+]);
+
+/// Per channel scale that maps the D65 illuminant to white, which also takes
+/// care of the illuminant's overall brightness.
+fn white_balance() -> Vec3 {
+    static WHITE_BALANCE: OnceLock<Vec3> = OnceLock::new();
+    *WHITE_BALANCE.get_or_init(|| {
+        let xyz = (MIN_WAVELENGTH as u32..=MAX_WAVELENGTH as u32)
+            .map(|lambda| d65(lambda as f32) * cie_xyz(lambda as f32))
+            .sum::<Vec3>();
+        Vec3::ONE / (XYZ_TO_SRGB * xyz)
+    })
+}
+
+/// CIE 1931 2° color matching functions, using the multi-lobe Gaussian fit
+/// of Wyman, Sloan and Shirley (2013).
+fn cie_xyz(lambda: f32) -> Vec3 {
+    let lobe = |mu: f32, sigma_below: f32, sigma_above: f32| {
+        let sigma = if lambda < mu {
+            sigma_below
+        } else {
+            sigma_above
+        };
+        let t = (lambda - mu) / sigma;
+        (-0.5 * t * t).exp()
+    };
+
+    Vec3::new(
+        1.056 * lobe(599.8, 37.9, 31.0) + 0.362 * lobe(442.0, 16.0, 26.7)
+            - 0.065 * lobe(501.1, 20.4, 26.2),
+        0.821 * lobe(568.8, 46.9, 40.5) + 0.286 * lobe(530.9, 16.3, 31.1),
+        1.217 * lobe(437.0, 11.8, 36.0) + 0.681 * lobe(459.0, 26.0, 13.8),
+    )
+}
+
+/// Relative spectral power of CIE standard illuminant D65 from 380 nm to
+/// 780 nm in 10 nm steps.
+const D65: [f32; 41] = [
+    49.98, 54.65, 82.75, 91.49, 93.43, 86.68, 104.86, 117.01, 117.81, 114.86, 115.92, 108.81,
+    109.35, 107.80, 104.79, 107.69, 104.41, 104.05, 100.00, 96.33, 95.79, 88.69, 90.01, 89.60,
+    87.70, 83.29, 83.70, 80.03, 80.21, 82.28, 78.28, 69.72, 71.61, 74.35, 61.60, 69.89, 75.09,
+    63.59, 46.42, 66.81, 63.38,
+];
+
+fn d65(lambda: f32) -> f32 {
+    let position = ((lambda - 380.0) / 10.0).clamp(0.0, (D65.len() - 1) as f32);
+    let index = (position as usize).min(D65.len() - 2);
+    let t = position - index as f32;
+    D65[index] * (1.0 - t) + D65[index + 1] * t
+}
+
+/// Smits' (1999) basis spectra in ten bins covering 380 nm to 720 nm.
+const SMITS_WHITE: [f32; 10] = [
+    1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000,
+];
+const SMITS_CYAN: [f32; 10] = [
+    0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000,
+];
+const SMITS_MAGENTA: [f32; 10] = [
+    1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959,
+];
+const SMITS_YELLOW: [f32; 10] = [
+    0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840,
+];
+const SMITS_RED: [f32; 10] = [
+    0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149,
+];
+const SMITS_GREEN: [f32; 10] = [
+    0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025,
+];
+const SMITS_BLUE: [f32; 10] = [
+    1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496,
+];
+
+/// Value at `lambda` of the smooth spectrum Smits' method builds for `rgb`:
+/// as much white as the smallest channel, then as much of the secondary
+/// color spanned by the two largest, then the largest primary.
+fn smits(rgb: Vec3, lambda: f32) -> f32 {
+    let bin = (((lambda - 380.0) / 34.0) as usize).min(9);
+    let (r, g, b) = (rgb.x, rgb.y, rgb.z);
+
+    let mut value = 0.0;
+    if r <= g && r <= b {
+        value += r * SMITS_WHITE[bin];
+        if g <= b {
+            value += (g - r) * SMITS_CYAN[bin] + (b - g) * SMITS_BLUE[bin];
+        } else {
+            value += (b - r) * SMITS_CYAN[bin] + (g - b) * SMITS_GREEN[bin];
+        }
+    } else if g <= r && g <= b {
+        value += g * SMITS_WHITE[bin];
+        if r <= b {
+            value += (r - g) * SMITS_MAGENTA[bin] + (b - r) * SMITS_BLUE[bin];
+        } else {
+            value += (b - g) * SMITS_MAGENTA[bin] + (r - b) * SMITS_RED[bin];
+        }
+    } else {
+        value += b * SMITS_WHITE[bin];
+        if r <= g {
+            value += (r - b) * SMITS_YELLOW[bin] + (g - r) * SMITS_GREEN[bin];
+        } else {
+            value += (g - b) * SMITS_YELLOW[bin] + (r - g) * SMITS_RED[bin];
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Color of the spectrum `spectrum` gives for every set of wavelengths,
+    /// averaged over evenly spread hero wavelengths.
+    fn integrate(spectrum: impl Fn(&SampledWavelengths) -> Vec4) -> Vec3 {
+        const STEPS: usize = 400;
+        (0..STEPS)
+            .map(|i| {
+                let wavelengths = SampledWavelengths::sample((i as f32 + 0.5) / STEPS as f32);
+                wavelengths.to_rgb(spectrum(&wavelengths))
+            })
+            .sum::<Vec3>()
+            / STEPS as f32
+    }
+
+    fn assert_color(found: Vec3, expected: Vec3, tolerance: f32) {
+        assert!(
+            found.abs_diff_eq(expected, tolerance),
+            "{found} for {expected}"
+        );
+    }
+
+    #[test]
+    fn wavelengths_spread_across_the_visible_range() {
+        let wavelengths = SampledWavelengths::sample(0.9);
+        let range = MAX_WAVELENGTH - MIN_WAVELENGTH;
+        assert!((wavelengths.hero() - (MIN_WAVELENGTH + 0.9 * range)).abs() < 1e-3);
+        for lambda in wavelengths.lambda {
+            assert!((MIN_WAVELENGTH..MAX_WAVELENGTH).contains(&lambda));
+        }
+        let mut sorted = wavelengths.lambda;
+        sorted.sort_by(f32::total_cmp);
+        for pair in sorted.windows(2) {
+            assert!((pair[1] - pair[0] - range / WAVELENGTHS as f32).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn white_light_comes_out_white() {
+        assert_color(integrate(|w| w.illuminant(Vec3::ONE)), Vec3::ONE, 0.02);
+        assert_color(
+            integrate(|w| w.illuminant(Vec3::splat(0.25))),
+            Vec3::splat(0.25),
+            0.01,
+        );
+    }
+
+    #[test]
+    fn colors_survive_the_trip_through_a_spectrum() {
+        for rgb in [
+            Vec3::new(0.8, 0.1, 0.1),
+            Vec3::new(0.1, 0.6, 0.2),
+            Vec3::new(0.2, 0.3, 0.9),
+            Vec3::new(0.9, 0.8, 0.2),
+        ] {
+            assert_color(integrate(|w| w.illuminant(rgb)), rgb, 0.05);
+            // A surface of that color under white light.
+            let lit = integrate(|w| w.illuminant(Vec3::ONE) * w.reflectance(rgb));
+            assert_color(lit, rgb, 0.05);
+        }
+    }
+
+    #[test]
+    fn reflectances_stay_physical() {
+        let wavelengths = SampledWavelengths::sample(0.3);
+        for rgb in [
+            Vec3::ONE,
+            Vec3::ZERO,
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::splat(2.0),
+        ] {
+            let reflectance = wavelengths.reflectance(rgb);
+            assert!(reflectance.min_element() >= 0.0 && reflectance.max_element() <= 1.0);
+        }
+    }
+
+    #[test]
+    fn terminating_secondaries_hands_their_share_to_the_hero() {
+        let mut wavelengths = SampledWavelengths::sample(0.5);
+        assert_eq!(
+            wavelengths.terminate_secondary(),
+            Vec4::new(WAVELENGTHS as f32, 0.0, 0.0, 0.0)
+        );
+        assert_eq!(wavelengths.terminate_secondary(), Vec4::ONE);
+    }
+}
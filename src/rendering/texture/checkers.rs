@@ -1,6 +1,9 @@
 use glam::Vec3;
 
-use crate::rendering::ray::Color;
+use crate::{
+    rendering::ray::{Color, HitRecord},
+    scene::description::TextureDescription,
+};
 
 use super::{solid::SolidColor, Texture};
 
@@ -18,6 +21,11 @@ impl<T: Texture, U: Texture> CheckerTexture<T, U> {
             odd,
         }
     }
+
+    fn is_even(&self, point: Vec3) -> bool {
+        let floored = (self.inverse_scale * point).floor();
+        (floored.x + floored.y + floored.z) as i32 % 2 == 0
+    }
 }
 
 impl CheckerTexture<SolidColor, SolidColor> {
@@ -28,12 +36,26 @@ impl CheckerTexture<SolidColor, SolidColor> {
 
 impl<T: Texture, U: Texture> Texture for CheckerTexture<T, U> {
     fn value(&self, u: f32, v: f32, point: Vec3) -> Color {
-        let floored = (self.inverse_scale * point).floor();
-        let is_even = (floored.x + floored.y + floored.z) as i32 % 2 == 0;
-        if is_even {
+        if self.is_even(point) {
             self.even.value(u, v, point)
         } else {
             self.odd.value(u, v, point)
         }
     }
+
+    fn value_at(&self, hit: &HitRecord) -> Color {
+        if self.is_even(hit.point) {
+            self.even.value_at(hit)
+        } else {
+            self.odd.value_at(hit)
+        }
+    }
+
+    fn describe(&self) -> Option<TextureDescription> {
+        Some(TextureDescription::Checker {
+            scale: 1.0 / self.inverse_scale,
+            even: Box::new(self.even.describe()?),
+            odd: Box::new(self.odd.describe()?),
+        })
+    }
 }
@@ -1,24 +1,47 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use glam::Vec3;
 use image::io::Reader as ImageReader;
-use image::RgbImage;
+use image::{DynamicImage, Rgb32FImage};
 
-use crate::rendering::ray::Color;
+use crate::{
+    rendering::{colorspace::ColorSpace, ray::Color},
+    scene::description::TextureDescription,
+};
 
 use super::Texture;
 
 pub struct ImageTexture {
-    image: RgbImage,
+    /// Texel values as stored in the file, before any decoding.
+    image: Rgb32FImage,
+    color_space: ColorSpace,
+    path: PathBuf,
 }
 
 impl ImageTexture {
+    /// Loads a texture, taking floating point images to be linear Rec. 709
+    /// and all others to be sRGB.
     pub fn from_file<P: AsRef<Path>>(path: P) -> Self {
-        let img = ImageReader::open(path).unwrap().decode().unwrap();
+        let path = path.as_ref().to_path_buf();
+        let img = ImageReader::open(&path).unwrap().decode().unwrap();
+        let color_space = match img {
+            DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_) => {
+                ColorSpace::LinearRec709
+            }
+            _ => ColorSpace::Srgb,
+        };
         Self {
-            image: RgbImage::from(img),
+            image: img.into_rgb32f(),
+            color_space,
+            path,
         }
     }
+
+    /// Overrides the color space the file's values are in.
+    pub fn with_color_space(mut self, color_space: ColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
 }
 
 impl Texture for ImageTexture {
@@ -31,11 +54,13 @@ impl Texture for ImageTexture {
 
         let pixel = self.image.get_pixel(i as u32, j as u32);
 
-        let scale = 1. / 255.;
-        Color::new(
-            pixel[0] as f32 * scale,
-            pixel[1] as f32 * scale,
-            pixel[2] as f32 * scale,
-        )
+        Color(self.color_space.to_working(Vec3::from_array(pixel.0)))
+    }
+
+    fn describe(&self) -> Option<TextureDescription> {
+        Some(TextureDescription::Image {
+            path: self.path.clone(),
+            color_space: self.color_space,
+        })
     }
 }
@@ -1,15 +1,63 @@
 pub mod checkers;
 pub mod image;
+pub mod registry;
 pub mod solid;
+pub mod vertex;
 
 pub use checkers::CheckerTexture;
 pub use image::ImageTexture;
+pub use registry::TextureRegistry;
 pub use solid::SolidColor;
+pub use vertex::VertexColor;
+
+use std::sync::Arc;
 
 use glam::Vec3;
 
-use super::ray::Color;
+use crate::scene::description::TextureDescription;
+
+use super::ray::{Color, HitRecord};
 
 pub trait Texture: Send + Sync {
     fn value(&self, u: f32, v: f32, point: Vec3) -> Color;
+
+    /// Value at a hit, which materials look textures up with so that
+    /// textures can use more of the hit than its coordinates.
+    fn value_at(&self, hit: &HitRecord) -> Color {
+        self.value(hit.u, hit.v, hit.point)
+    }
+
+    /// The texture as plain data, `None` if it can't be saved.
+    fn describe(&self) -> Option<TextureDescription> {
+        None
+    }
+}
+
+impl<T: Texture + ?Sized> Texture for Box<T> {
+    fn value(&self, u: f32, v: f32, point: Vec3) -> Color {
+        (**self).value(u, v, point)
+    }
+
+    fn value_at(&self, hit: &HitRecord) -> Color {
+        (**self).value_at(hit)
+    }
+
+    fn describe(&self) -> Option<TextureDescription> {
+        (**self).describe()
+    }
+}
+
+/// Lets materials share one texture.
+impl<T: Texture + ?Sized> Texture for Arc<T> {
+    fn value(&self, u: f32, v: f32, point: Vec3) -> Color {
+        (**self).value(u, v, point)
+    }
+
+    fn value_at(&self, hit: &HitRecord) -> Color {
+        (**self).value_at(hit)
+    }
+
+    fn describe(&self) -> Option<TextureDescription> {
+        (**self).describe()
+    }
 }
@@ -0,0 +1,39 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::rendering::colorspace::ColorSpace;
+
+use super::ImageTexture;
+
+/// Textures shared between materials. Images are loaded only once however
+/// many materials use them.
+#[derive(Default)]
+pub struct TextureRegistry {
+    images: Vec<(PathBuf, ColorSpace, Arc<ImageTexture>)>,
+}
+
+impl TextureRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The image at `path` read in `color_space`, loaded the first time it
+    /// is asked for.
+    pub fn image<P: AsRef<Path>>(&mut self, path: P, color_space: ColorSpace) -> Arc<ImageTexture> {
+        let path = path.as_ref();
+        if let Some((_, _, image)) = self
+            .images
+            .iter()
+            .find(|(p, space, _)| p == path && *space == color_space)
+        {
+            return image.clone();
+        }
+
+        let image = Arc::new(ImageTexture::from_file(path).with_color_space(color_space));
+        self.images
+            .push((path.to_path_buf(), color_space, image.clone()));
+        image
+    }
+}
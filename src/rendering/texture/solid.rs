@@ -1,6 +1,6 @@
 use glam::Vec3;
 
-use crate::rendering::ray::Color;
+use crate::{rendering::ray::Color, scene::description::TextureDescription};
 
 use super::Texture;
 
@@ -18,4 +18,8 @@ impl Texture for SolidColor {
     fn value(&self, _u: f32, _v: f32, _point: Vec3) -> Color {
         self.color
     }
+
+    fn describe(&self) -> Option<TextureDescription> {
+        Some(TextureDescription::Solid(self.color.0))
+    }
 }
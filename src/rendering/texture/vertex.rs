@@ -0,0 +1,46 @@
+use glam::Vec3;
+
+use crate::{
+    rendering::ray::{Color, HitRecord},
+    scene::description::TextureDescription,
+};
+
+use super::Texture;
+
+/// The color an object stores at the hit, such as a mesh's vertex colors
+/// or a point cloud's point colors, or `fallback` where it has none.
+pub struct VertexColor {
+    fallback: Color,
+}
+
+impl VertexColor {
+    pub fn new() -> Self {
+        Self::with_fallback(Color::new(1.0, 1.0, 1.0))
+    }
+
+    pub fn with_fallback(fallback: Color) -> Self {
+        Self { fallback }
+    }
+}
+
+impl Default for VertexColor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Texture for VertexColor {
+    fn value(&self, _u: f32, _v: f32, _point: Vec3) -> Color {
+        self.fallback
+    }
+
+    fn value_at(&self, hit: &HitRecord) -> Color {
+        hit.color.unwrap_or(self.fallback)
+    }
+
+    fn describe(&self) -> Option<TextureDescription> {
+        Some(TextureDescription::Vertex {
+            fallback: self.fallback.0,
+        })
+    }
+}
@@ -0,0 +1,366 @@
+use std::{path::PathBuf, sync::Arc};
+
+use glam::{Quat, Vec3};
+
+use crate::{
+    object::{
+        collection::ObjectCollection,
+        mesh::MeshData,
+        types::{Cone, Cuboid, Cylinder, Disk, Quad, Sphere, Torus, TriangleMesh},
+        Object, ShapeVisitor,
+    },
+    rendering::{
+        camera::CameraConfig,
+        colorspace::ColorSpace,
+        material::{
+            Dielectric, Dispersion, Hair, Lambertian, Light, Material, MaterialHandle,
+            MaterialRegistry, Metal,
+        },
+        ray::Color,
+        texture::{CheckerTexture, SolidColor, Texture, TextureRegistry, VertexColor},
+    },
+};
+
+use super::Scene;
+
+/// A texture as plain data, for saving it and building it again.
+#[derive(Clone, PartialEq, Debug)]
+pub enum TextureDescription {
+    Solid(Vec3),
+    Checker {
+        scale: f32,
+        even: Box<TextureDescription>,
+        odd: Box<TextureDescription>,
+    },
+    Image {
+        path: PathBuf,
+        color_space: ColorSpace,
+    },
+    Vertex {
+        fallback: Vec3,
+    },
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum MaterialDescription {
+    Lambertian(TextureDescription),
+    Metal {
+        albedo: TextureDescription,
+        fuzz: f32,
+    },
+    Dielectric {
+        refraction_index: f32,
+        dispersion: Option<Dispersion>,
+    },
+    Light(TextureDescription),
+    Hair {
+        sigma_a: Vec3,
+        beta_m: f32,
+        beta_n: f32,
+        /// Cuticle scale angle in degrees.
+        alpha: f32,
+    },
+}
+
+/// The geometry of an object as plain data, in the terms of its
+/// constructor.
+#[derive(Clone)]
+pub enum ShapeDescription {
+    Sphere {
+        center: Vec3,
+        radius: f32,
+        /// How far the sphere moves over the shutter interval 0..1.
+        movement: Vec3,
+    },
+    Quad {
+        origin: Vec3,
+        u: Vec3,
+        v: Vec3,
+    },
+    Disk {
+        center: Vec3,
+        u: Vec3,
+        v: Vec3,
+    },
+    Cuboid {
+        center: Vec3,
+        size: Vec3,
+        rotation: Quat,
+    },
+    Cylinder {
+        base: Vec3,
+        top: Vec3,
+        radius: f32,
+        capped: bool,
+    },
+    Cone {
+        base: Vec3,
+        apex: Vec3,
+        radius: f32,
+    },
+    Torus {
+        center: Vec3,
+        axis: Vec3,
+        major_radius: f32,
+        minor_radius: f32,
+    },
+    Mesh(MeshData),
+}
+
+#[derive(Clone)]
+pub struct ObjectDescription {
+    pub shape: ShapeDescription,
+    pub material: MaterialDescription,
+    /// Whether the object is also in the light list.
+    pub light: bool,
+}
+
+/// Everything needed to build a scene again: its camera and the objects
+/// that could be described. Objects such as CSG, SDFs, curves, sphere
+/// clouds, animated objects and deforming meshes have no description and
+/// are counted in `skipped`, so a scene saved without them renders
+/// differently.
+pub struct SceneDescription {
+    pub camera: CameraConfig,
+    pub objects: Vec<ObjectDescription>,
+    pub skipped: usize,
+}
+
+impl TextureDescription {
+    /// Builds the texture, loading images through `textures` so that each
+    /// is read only once.
+    pub fn build(&self, textures: &mut TextureRegistry) -> Arc<dyn Texture> {
+        match self {
+            Self::Solid(color) => Arc::new(SolidColor::new(Color(*color))),
+            Self::Checker { scale, even, odd } => Arc::new(CheckerTexture::new(
+                *scale,
+                even.build(textures),
+                odd.build(textures),
+            )),
+            Self::Image { path, color_space } => textures.image(path, *color_space),
+            Self::Vertex { fallback } => Arc::new(VertexColor::with_fallback(Color(*fallback))),
+        }
+    }
+}
+
+impl MaterialDescription {
+    pub fn build(&self, textures: &mut TextureRegistry) -> Arc<dyn Material> {
+        match self {
+            Self::Lambertian(albedo) => Arc::new(Lambertian::new(albedo.build(textures))),
+            Self::Metal { albedo, fuzz } => Arc::new(Metal::new(albedo.build(textures), *fuzz)),
+            Self::Dielectric {
+                refraction_index,
+                dispersion,
+            } => Arc::new(Dielectric {
+                refraction_index: *refraction_index,
+                dispersion: *dispersion,
+            }),
+            Self::Light(albedo) => Arc::new(Light::new(albedo.build(textures))),
+            Self::Hair {
+                sigma_a,
+                beta_m,
+                beta_n,
+                alpha,
+            } => Arc::new(
+                Hair::new(*sigma_a)
+                    .with_roughness(*beta_m, *beta_n)
+                    .with_scale_angle(*alpha),
+            ),
+        }
+    }
+}
+
+impl ShapeDescription {
+    /// The geometry of `object`, `None` if it can't be saved.
+    pub fn of(object: &dyn Object) -> Option<Self> {
+        let mut describer = Describer::default();
+        object.visit_shape(&mut describer);
+        describer.0
+    }
+
+    /// Adds the shape to `world` made of `material`, to its lights too if
+    /// `light` is set.
+    pub fn add_to<M: Material + 'static>(
+        &self,
+        world: &mut ObjectCollection,
+        material: M,
+        light: bool,
+    ) {
+        fn add<T: Object + 'static>(world: &mut ObjectCollection, object: T, light: bool) {
+            if light {
+                world.add_light(object);
+            } else {
+                world.add(object);
+            }
+        }
+
+        match self.clone() {
+            Self::Sphere {
+                center,
+                radius,
+                movement,
+            } => {
+                if movement == Vec3::ZERO {
+                    add(world, Sphere::new(center, radius, material), light)
+                } else {
+                    let sphere = Sphere::moving(center, center + movement, radius, material);
+                    add(world, sphere, light)
+                }
+            }
+            Self::Quad { origin, u, v } => add(world, Quad::new(origin, u, v, material), light),
+            Self::Disk { center, u, v } => add(world, Disk::ellipse(center, u, v, material), light),
+            Self::Cuboid {
+                center,
+                size,
+                rotation,
+            } => add(
+                world,
+                Cuboid::oriented(center, size, rotation, material),
+                light,
+            ),
+            Self::Cylinder {
+                base,
+                top,
+                radius,
+                capped,
+            } => {
+                let cylinder = Cylinder::new(base, top, radius, material);
+                if capped {
+                    add(world, cylinder, light)
+                } else {
+                    add(world, cylinder.open(), light)
+                }
+            }
+            Self::Cone { base, apex, radius } => {
+                add(world, Cone::new(base, apex, radius, material), light)
+            }
+            Self::Torus {
+                center,
+                axis,
+                major_radius,
+                minor_radius,
+            } => add(
+                world,
+                Torus::new(center, axis, major_radius, minor_radius, material),
+                light,
+            ),
+            Self::Mesh(data) => add(world, TriangleMesh::new(data, material), light),
+        }
+    }
+}
+
+/// Describes the shape an object hands to it.
+#[derive(Default)]
+struct Describer(Option<ShapeDescription>);
+
+impl ShapeVisitor for Describer {
+    fn sphere(&mut self, center: Vec3, radius: f32, movement: Vec3) {
+        self.0 = Some(ShapeDescription::Sphere {
+            center,
+            radius,
+            movement,
+        });
+    }
+
+    fn quad(&mut self, origin: Vec3, u: Vec3, v: Vec3) {
+        self.0 = Some(ShapeDescription::Quad { origin, u, v });
+    }
+
+    fn disk(&mut self, center: Vec3, u: Vec3, v: Vec3) {
+        self.0 = Some(ShapeDescription::Disk { center, u, v });
+    }
+
+    fn cuboid(&mut self, center: Vec3, size: Vec3, rotation: Quat) {
+        self.0 = Some(ShapeDescription::Cuboid {
+            center,
+            size,
+            rotation,
+        });
+    }
+
+    fn cylinder(&mut self, base: Vec3, top: Vec3, radius: f32, capped: bool) {
+        self.0 = Some(ShapeDescription::Cylinder {
+            base,
+            top,
+            radius,
+            capped,
+        });
+    }
+
+    fn cone(&mut self, base: Vec3, apex: Vec3, radius: f32) {
+        self.0 = Some(ShapeDescription::Cone { base, apex, radius });
+    }
+
+    fn torus(&mut self, center: Vec3, axis: Vec3, major_radius: f32, minor_radius: f32) {
+        self.0 = Some(ShapeDescription::Torus {
+            center,
+            axis,
+            major_radius,
+            minor_radius,
+        });
+    }
+
+    fn mesh(&mut self, data: &MeshData) {
+        // Deforming meshes have no description.
+        if data.motion_keys() == 1 {
+            self.0 = Some(ShapeDescription::Mesh(data.clone()));
+        }
+    }
+}
+
+impl SceneDescription {
+    /// Describes `scene`'s camera and every object of its world that can be
+    /// described.
+    pub fn of(scene: &Scene) -> Self {
+        let lights = scene.world.lights();
+        let mut objects = Vec::new();
+        let mut skipped = 0;
+        for object in scene.world.objects() {
+            let shape = ShapeDescription::of(object.as_ref());
+            let material = object.material().and_then(|material| material.describe());
+            let (Some(shape), Some(material)) = (shape, material) else {
+                skipped += 1;
+                continue;
+            };
+            objects.push(ObjectDescription {
+                shape,
+                material,
+                light: lights.iter().any(|light| Arc::ptr_eq(light, object)),
+            });
+        }
+
+        Self {
+            camera: scene.camera.clone(),
+            objects,
+            skipped,
+        }
+    }
+
+    /// Builds the scene. Objects of the same material share it through the
+    /// scene's registry, where materials are named `m0`, `m1`, ... in order
+    /// of first use like in scene files.
+    pub fn build(&self) -> Scene {
+        let mut world = ObjectCollection::new();
+        let mut textures = TextureRegistry::new();
+        let mut registry = MaterialRegistry::new();
+        let mut materials: Vec<(&MaterialDescription, MaterialHandle)> = Vec::new();
+        for object in &self.objects {
+            let material = match materials
+                .iter()
+                .find(|(description, _)| **description == object.material)
+            {
+                Some((_, handle)) => handle.clone(),
+                None => {
+                    let name = format!("m{}", materials.len());
+                    let material = object.material.build(&mut textures);
+                    let handle = registry.add_shared(&name, material);
+                    materials.push((&object.material, handle.clone()));
+                    handle
+                }
+            };
+            object.shape.add_to(&mut world, material, object.light);
+        }
+
+        Scene::new(self.camera.clone(), world.as_bvh()).with_materials(registry)
+    }
+}
@@ -0,0 +1,858 @@
+//! Plain text scene files. Every line is one statement, and lines starting
+//! with `#` are comments:
+//!
+//! ```text
+//! camera look_from 13 2 3
+//! texture t0 solid 0.5 0.2 0.1
+//! texture t1 solid 0.9 0.9 0.9
+//! texture t2 checker 0.32 t0 t1
+//! material m0 lambertian t2
+//! texture t3 solid 4 4 4
+//! material m1 light t3
+//! sphere m0 0 1 0 1
+//! light quad m1 -1 4 -1 2 0 0 0 0 2
+//! ```
+//!
+//! Textures and materials are named before objects use them. Lights are
+//! objects prefixed with `light`. A mesh is followed by OBJ-like `v`, `vn`,
+//! `vt`, `vc` (vertex color) and `f` lines with indices counted from zero,
+//! and closed with `end`. Colors are in the working space.
+
+use std::{
+    collections::HashMap, fmt::Write as _, fs, io, path::Path, str::SplitWhitespace, sync::Arc,
+};
+
+use glam::{Quat, Vec2, Vec3};
+
+use crate::{
+    object::{load::LoadError, mesh::MeshData},
+    rendering::{
+        aperture::{Aperture, ApertureImage},
+        camera::{CameraConfig, Focus},
+        colorspace::ColorSpace,
+        debug::DebugView,
+        filter::{Filter, FilterKind},
+        integrator::{BounceLimits, IntegratorKind},
+        lens::LensPrescription,
+        material::{BounceKind, Dispersion},
+        projection::{FisheyeMapping, Projection},
+        ray::Color,
+    },
+};
+
+use super::description::{
+    MaterialDescription, ObjectDescription, SceneDescription, ShapeDescription, TextureDescription,
+};
+
+impl SceneDescription {
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = Writer::default();
+        writer.camera(&self.camera);
+        for object in &self.objects {
+            writer.object(object);
+        }
+        fs::write(path, writer.text)
+    }
+
+    /// Reads a scene from the text of a scene file.
+    pub fn parse(text: &str) -> Result<Self, LoadError> {
+        let mut reader = Reader::default();
+        let mut lines = text.lines().enumerate();
+        while let Some((number, line)) = lines.next() {
+            let mut words = Words::new(line, number + 1);
+            let Some(keyword) = words.next() else {
+                continue;
+            };
+            reader.statement(keyword, &mut words, &mut lines)?;
+        }
+
+        Ok(Self {
+            camera: reader.camera,
+            objects: reader.objects,
+            skipped: 0,
+        })
+    }
+}
+
+/// The scene file lines setting up `camera`, which tell apart cameras that
+/// render different images of the same scene.
+pub fn camera_settings(camera: &CameraConfig) -> String {
+    let mut writer = Writer::default();
+    writer.camera(camera);
+    writer.text
+}
+
+#[derive(Default)]
+struct Writer {
+    text: String,
+    textures: usize,
+    materials: Vec<MaterialDescription>,
+}
+
+impl Writer {
+    fn camera(&mut self, camera: &CameraConfig) {
+        let vector = |v: Vec3| format!("{} {} {}", v.x, v.y, v.z);
+        let focus = match camera.focus {
+            Focus::Manual => "manual".to_string(),
+            Focus::LookAt => "lookat".to_string(),
+            Focus::Pixel(x, y) => format!("pixel {x} {y}"),
+        };
+        let aperture = match &camera.aperture {
+            Aperture::Disk => "disk".to_string(),
+            Aperture::Polygon { blades, rotation } => format!("polygon {blades} {rotation}"),
+            Aperture::Image(image) => format!("image {}", image.path().display()),
+        };
+        let projection = match &camera.projection {
+            Projection::Perspective => "perspective".to_string(),
+            Projection::Orthographic { height } => format!("orthographic {height}"),
+            Projection::Equirectangular => "equirectangular".to_string(),
+            Projection::Fisheye { fov, mapping } => format!("fisheye {fov} {}", mapping.name()),
+            Projection::Cylindrical { horizontal_fov } => format!("cylindrical {horizontal_fov}"),
+            Projection::Realistic {
+                lens,
+                sensor_width,
+                aperture_diameter,
+            } => format!(
+                "realistic {sensor_width} {aperture_diameter} {}",
+                lens.path().display()
+            ),
+        };
+        let filter = format!("{} {}", camera.filter.kind.name(), camera.filter.radius);
+        let mut settings = vec![
+            ("aspect_ratio", camera.aspect_ratio.to_string()),
+            ("image_width", camera.image_width.to_string()),
+            ("samples_per_pixel", camera.samples_per_pixel.to_string()),
+            ("max_bounces", camera.max_bounces.to_string()),
+            ("vfov", camera.vfov.to_string()),
+            ("look_from", vector(camera.look_from)),
+            ("look_at", vector(camera.look_at)),
+            ("vector_up", vector(camera.vector_up)),
+            ("focus_distance", camera.focus_distance.to_string()),
+            ("focus", focus),
+            ("defocus_angle", camera.defocus_angle.to_string()),
+            ("aperture", aperture),
+            ("skybox", vector(camera.skybox.0)),
+            (
+                "shutter",
+                format!("{} {}", camera.shutter_open, camera.shutter_close),
+            ),
+            ("projection", projection),
+            ("filter", filter),
+            ("spectral", camera.spectral.to_string()),
+            ("integrator", camera.integrator.name().to_string()),
+        ];
+        if let Some(lens) = &camera.lens {
+            settings.extend([
+                ("focal_length", lens.focal_length.to_string()),
+                ("f_stop", lens.f_stop.to_string()),
+                ("sensor_width", lens.sensor_width.to_string()),
+            ]);
+        }
+        if let Some(view) = camera.debug {
+            settings.push(("debug", view.name().to_string()));
+        }
+        for (key, value) in settings {
+            writeln!(self.text, "camera {key} {value}").unwrap();
+        }
+        for kind in BounceKind::ALL {
+            let limit = camera.bounce_limits.get(kind);
+            if limit != BounceLimits::default().get(kind) {
+                writeln!(self.text, "camera bounce_limit {} {limit}", kind.name()).unwrap();
+            }
+        }
+    }
+
+    /// Writes `texture` and the textures it is made of, returning its name.
+    fn texture(&mut self, texture: &TextureDescription) -> String {
+        let definition = match texture {
+            TextureDescription::Solid(color) => {
+                format!("solid {} {} {}", color.x, color.y, color.z)
+            }
+            TextureDescription::Checker { scale, even, odd } => {
+                let (even, odd) = (self.texture(even), self.texture(odd));
+                format!("checker {scale} {even} {odd}")
+            }
+            TextureDescription::Image { path, color_space } => {
+                format!("image {} {}", color_space.name(), path.display())
+            }
+            TextureDescription::Vertex { fallback } => {
+                format!("vertex {} {} {}", fallback.x, fallback.y, fallback.z)
+            }
+        };
+
+        let name = format!("t{}", self.textures);
+        self.textures += 1;
+        writeln!(self.text, "texture {name} {definition}").unwrap();
+        name
+    }
+
+    /// Name of `material`, writing it first if it hasn't been yet.
+    fn material(&mut self, material: &MaterialDescription) -> String {
+        if let Some(index) = self.materials.iter().position(|m| m == material) {
+            return format!("m{index}");
+        }
+
+        let definition = match material {
+            MaterialDescription::Lambertian(albedo) => {
+                format!("lambertian {}", self.texture(albedo))
+            }
+            MaterialDescription::Metal { albedo, fuzz } => {
+                format!("metal {} {fuzz}", self.texture(albedo))
+            }
+            MaterialDescription::Dielectric {
+                refraction_index,
+                dispersion,
+            } => match dispersion {
+                None => format!("dielectric {refraction_index}"),
+                Some(Dispersion::Cauchy { a, b }) => {
+                    format!("dielectric {refraction_index} cauchy {a} {b}")
+                }
+                Some(Dispersion::Sellmeier { b, c }) => format!(
+                    "dielectric {refraction_index} sellmeier {} {} {} {} {} {}",
+                    b[0], b[1], b[2], c[0], c[1], c[2]
+                ),
+            },
+            MaterialDescription::Light(albedo) => format!("light {}", self.texture(albedo)),
+            MaterialDescription::Hair {
+                sigma_a,
+                beta_m,
+                beta_n,
+                alpha,
+            } => format!(
+                "hair {} {} {} {beta_m} {beta_n} {alpha}",
+                sigma_a.x, sigma_a.y, sigma_a.z
+            ),
+        };
+
+        let name = format!("m{}", self.materials.len());
+        self.materials.push(material.clone());
+        writeln!(self.text, "material {name} {definition}").unwrap();
+        name
+    }
+
+    fn object(&mut self, object: &ObjectDescription) {
+        let material = self.material(&object.material);
+        let prefix = if object.light { "light " } else { "" };
+        let v = |v: Vec3| format!("{} {} {}", v.x, v.y, v.z);
+
+        let statement = match &object.shape {
+            ShapeDescription::Sphere {
+                center,
+                radius,
+                movement,
+            } => {
+                if *movement == Vec3::ZERO {
+                    format!("sphere {material} {} {radius}", v(*center))
+                } else {
+                    format!("sphere {material} {} {radius} {}", v(*center), v(*movement))
+                }
+            }
+            ShapeDescription::Quad { origin, u, v: side } => {
+                format!("quad {material} {} {} {}", v(*origin), v(*u), v(*side))
+            }
+            ShapeDescription::Disk { center, u, v: side } => {
+                format!("disk {material} {} {} {}", v(*center), v(*u), v(*side))
+            }
+            ShapeDescription::Cuboid {
+                center,
+                size,
+                rotation,
+            } => format!(
+                "cuboid {material} {} {} {} {} {} {}",
+                v(*center),
+                v(*size),
+                rotation.x,
+                rotation.y,
+                rotation.z,
+                rotation.w
+            ),
+            ShapeDescription::Cylinder {
+                base,
+                top,
+                radius,
+                capped,
+            } => {
+                let open = if *capped { "" } else { " open" };
+                format!(
+                    "cylinder {material} {} {} {radius}{open}",
+                    v(*base),
+                    v(*top)
+                )
+            }
+            ShapeDescription::Cone { base, apex, radius } => {
+                format!("cone {material} {} {} {radius}", v(*base), v(*apex))
+            }
+            ShapeDescription::Torus {
+                center,
+                axis,
+                major_radius,
+                minor_radius,
+            } => format!(
+                "torus {material} {} {} {major_radius} {minor_radius}",
+                v(*center),
+                v(*axis)
+            ),
+            ShapeDescription::Mesh(data) => {
+                let mut text = format!("mesh {material}\n");
+                for position in &data.positions[0] {
+                    writeln!(text, "v {}", v(*position)).unwrap();
+                }
+                for normal in data.normals.iter().flatten() {
+                    writeln!(text, "vn {}", v(*normal)).unwrap();
+                }
+                for uv in data.uvs.iter().flatten() {
+                    writeln!(text, "vt {} {}", uv.x, uv.y).unwrap();
+                }
+                for color in data.colors.iter().flatten() {
+                    writeln!(text, "vc {}", v(color.0)).unwrap();
+                }
+                for [a, b, c] in &data.triangles {
+                    writeln!(text, "f {a} {b} {c}").unwrap();
+                }
+                text + "end"
+            }
+        };
+        writeln!(self.text, "{prefix}{statement}").unwrap();
+    }
+}
+
+#[derive(Default)]
+struct Reader {
+    camera: CameraConfig,
+    textures: HashMap<String, TextureDescription>,
+    materials: HashMap<String, MaterialDescription>,
+    objects: Vec<ObjectDescription>,
+}
+
+impl Reader {
+    fn statement<'a>(
+        &mut self,
+        keyword: &str,
+        words: &mut Words,
+        lines: &mut impl Iterator<Item = (usize, &'a str)>,
+    ) -> Result<(), LoadError> {
+        match keyword {
+            "camera" => self.camera_setting(words)?,
+            "texture" => {
+                let name = words.word()?.to_string();
+                let texture = self.texture(words)?;
+                self.textures.insert(name, texture);
+            }
+            "material" => {
+                let name = words.word()?.to_string();
+                let material = self.material(words)?;
+                self.materials.insert(name, material);
+            }
+            "light" => {
+                let shape = words.word()?;
+                self.object(shape, words, lines, true)?;
+            }
+            shape => self.object(shape, words, lines, false)?,
+        }
+        words.finish()
+    }
+
+    fn camera_setting(&mut self, words: &mut Words) -> Result<(), LoadError> {
+        let camera = &mut self.camera;
+        match words.word()? {
+            "aspect_ratio" => camera.aspect_ratio = words.float()?,
+            "image_width" => camera.image_width = words.parse()?,
+            "samples_per_pixel" => camera.samples_per_pixel = words.parse()?,
+            "max_bounces" => camera.max_bounces = words.parse()?,
+            "bounce_limit" => {
+                let name = words.word()?;
+                let kind = BounceKind::from_name(name)
+                    .ok_or_else(|| words.error(&format!("unknown bounce kind `{name}`")))?;
+                camera.bounce_limits.set(kind, words.parse()?);
+            }
+            "vfov" => camera.vfov = words.float()?,
+            "look_from" => camera.look_from = words.vector()?,
+            "look_at" => camera.look_at = words.vector()?,
+            "vector_up" => camera.vector_up = words.vector()?,
+            "focus_distance" => camera.focus_distance = words.float()?,
+            "focus" => {
+                camera.focus = match words.word()? {
+                    "manual" => Focus::Manual,
+                    "lookat" => Focus::LookAt,
+                    "pixel" => Focus::Pixel(words.parse()?, words.parse()?),
+                    mode => return Err(words.error(&format!("unknown focus mode `{mode}`"))),
+                }
+            }
+            "defocus_angle" => camera.defocus_angle = words.float()?,
+            "aperture" => {
+                camera.aperture = match words.word()? {
+                    "disk" => Aperture::Disk,
+                    "polygon" => Aperture::Polygon {
+                        blades: words.parse()?,
+                        rotation: words.float()?,
+                    },
+                    "image" => {
+                        let path = words.rest();
+                        let image = ApertureImage::from_file(&path).map_err(|error| {
+                            words.error(&format!("cannot load aperture image {path}: {error}"))
+                        })?;
+                        Aperture::Image(Arc::new(image))
+                    }
+                    shape => return Err(words.error(&format!("unknown aperture `{shape}`"))),
+                }
+            }
+            "projection" => camera.projection = read_projection(words)?,
+            "filter" => {
+                let name = words.word()?;
+                let kind = FilterKind::from_name(name)
+                    .ok_or_else(|| words.error(&format!("unknown filter `{name}`")))?;
+                camera.filter = Filter::new(kind).with_radius(words.float()?);
+            }
+            "debug" => {
+                let name = words.word()?;
+                let view = DebugView::from_name(name)
+                    .ok_or_else(|| words.error(&format!("unknown debug view `{name}`")))?;
+                camera.debug = Some(view);
+            }
+            "focal_length" => {
+                camera
+                    .lens
+                    .get_or_insert_with(Default::default)
+                    .focal_length = words.float()?
+            }
+            "f_stop" => camera.lens.get_or_insert_with(Default::default).f_stop = words.float()?,
+            "sensor_width" => {
+                camera
+                    .lens
+                    .get_or_insert_with(Default::default)
+                    .sensor_width = words.float()?
+            }
+            "skybox" => camera.skybox.0 = words.vector()?,
+            "shutter" => {
+                camera.shutter_open = words.float()?;
+                camera.shutter_close = words.float()?;
+            }
+            "spectral" => camera.spectral = words.parse()?,
+            "integrator" => {
+                let name = words.word()?;
+                camera.integrator = IntegratorKind::from_name(name)
+                    .ok_or_else(|| words.error(&format!("unknown integrator `{name}`")))?;
+            }
+            setting => return Err(words.error(&format!("unknown camera setting `{setting}`"))),
+        }
+        Ok(())
+    }
+
+    fn texture(&self, words: &mut Words) -> Result<TextureDescription, LoadError> {
+        Ok(match words.word()? {
+            "solid" => TextureDescription::Solid(words.vector()?),
+            "checker" => TextureDescription::Checker {
+                scale: words.float()?,
+                even: Box::new(self.named_texture(words)?),
+                odd: Box::new(self.named_texture(words)?),
+            },
+            "image" => {
+                let name = words.word()?;
+                let color_space = ColorSpace::from_name(name)
+                    .ok_or_else(|| words.error(&format!("unknown color space `{name}`")))?;
+                TextureDescription::Image {
+                    color_space,
+                    path: words.rest().into(),
+                }
+            }
+            "vertex" => TextureDescription::Vertex {
+                fallback: words.vector()?,
+            },
+            kind => return Err(words.error(&format!("unknown texture `{kind}`"))),
+        })
+    }
+
+    fn named_texture(&self, words: &mut Words) -> Result<TextureDescription, LoadError> {
+        let name = words.word()?;
+        self.textures
+            .get(name)
+            .cloned()
+            .ok_or_else(|| words.error(&format!("no texture named `{name}`")))
+    }
+
+    fn material(&self, words: &mut Words) -> Result<MaterialDescription, LoadError> {
+        Ok(match words.word()? {
+            "lambertian" => MaterialDescription::Lambertian(self.named_texture(words)?),
+            "metal" => MaterialDescription::Metal {
+                albedo: self.named_texture(words)?,
+                fuzz: words.float()?,
+            },
+            "dielectric" => MaterialDescription::Dielectric {
+                refraction_index: words.float()?,
+                dispersion: match words.next() {
+                    None => None,
+                    Some("cauchy") => Some(Dispersion::Cauchy {
+                        a: words.float()?,
+                        b: words.float()?,
+                    }),
+                    Some("sellmeier") => {
+                        let b = words.vector()?.to_array();
+                        let c = words.vector()?.to_array();
+                        Some(Dispersion::Sellmeier { b, c })
+                    }
+                    Some(kind) => return Err(words.error(&format!("unknown dispersion `{kind}`"))),
+                },
+            },
+            "light" => MaterialDescription::Light(self.named_texture(words)?),
+            "hair" => MaterialDescription::Hair {
+                sigma_a: words.vector()?,
+                beta_m: words.float()?,
+                beta_n: words.float()?,
+                alpha: words.float()?,
+            },
+            kind => return Err(words.error(&format!("unknown material `{kind}`"))),
+        })
+    }
+
+    fn object<'a>(
+        &mut self,
+        shape: &str,
+        words: &mut Words,
+        lines: &mut impl Iterator<Item = (usize, &'a str)>,
+        light: bool,
+    ) -> Result<(), LoadError> {
+        let name = words.word()?;
+        let material = self
+            .materials
+            .get(name)
+            .cloned()
+            .ok_or_else(|| words.error(&format!("no material named `{name}`")))?;
+
+        let shape = match shape {
+            "sphere" => ShapeDescription::Sphere {
+                center: words.vector()?,
+                radius: words.float()?,
+                movement: match words.peek() {
+                    Some(_) => words.vector()?,
+                    None => Vec3::ZERO,
+                },
+            },
+            "quad" => ShapeDescription::Quad {
+                origin: words.vector()?,
+                u: words.vector()?,
+                v: words.vector()?,
+            },
+            "disk" => ShapeDescription::Disk {
+                center: words.vector()?,
+                u: words.vector()?,
+                v: words.vector()?,
+            },
+            "cuboid" => ShapeDescription::Cuboid {
+                center: words.vector()?,
+                size: words.vector()?,
+                rotation: Quat::from_xyzw(
+                    words.float()?,
+                    words.float()?,
+                    words.float()?,
+                    words.float()?,
+                ),
+            },
+            "cylinder" => ShapeDescription::Cylinder {
+                base: words.vector()?,
+                top: words.vector()?,
+                radius: words.float()?,
+                capped: match words.next() {
+                    None => true,
+                    Some("open") => false,
+                    Some(word) => return Err(words.error(&format!("unexpected `{word}`"))),
+                },
+            },
+            "cone" => ShapeDescription::Cone {
+                base: words.vector()?,
+                apex: words.vector()?,
+                radius: words.float()?,
+            },
+            "torus" => ShapeDescription::Torus {
+                center: words.vector()?,
+                axis: words.vector()?,
+                major_radius: words.float()?,
+                minor_radius: words.float()?,
+            },
+            "mesh" => ShapeDescription::Mesh(read_mesh(lines)?),
+            shape => return Err(words.error(&format!("unknown statement `{shape}`"))),
+        };
+
+        self.objects.push(ObjectDescription {
+            shape,
+            material,
+            light,
+        });
+        Ok(())
+    }
+}
+
+/// Reads the projection of a `camera projection` line, loading the
+/// prescription of a realistic lens.
+fn read_projection(words: &mut Words) -> Result<Projection, LoadError> {
+    Ok(match words.word()? {
+        "perspective" => Projection::Perspective,
+        "orthographic" => Projection::Orthographic {
+            height: words.float()?,
+        },
+        "equirectangular" => Projection::Equirectangular,
+        "fisheye" => {
+            let fov = words.float()?;
+            let name = words.word()?;
+            let mapping = FisheyeMapping::from_name(name)
+                .ok_or_else(|| words.error(&format!("unknown fisheye mapping `{name}`")))?;
+            Projection::Fisheye { fov, mapping }
+        }
+        "cylindrical" => Projection::Cylindrical {
+            horizontal_fov: words.float()?,
+        },
+        "realistic" => {
+            let sensor_width = words.float()?;
+            let aperture_diameter = words.float()?;
+            let path = words.rest();
+            let lens = LensPrescription::from_file(&path)
+                .map_err(|error| words.error(&format!("cannot load lens {path}: {error}")))?;
+            Projection::Realistic {
+                lens: Arc::new(lens),
+                sensor_width,
+                aperture_diameter,
+            }
+        }
+        name => return Err(words.error(&format!("unknown projection `{name}`"))),
+    })
+}
+
+/// Reads the lines of a mesh up to its `end`.
+fn read_mesh<'a>(
+    lines: &mut impl Iterator<Item = (usize, &'a str)>,
+) -> Result<MeshData, LoadError> {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut uvs = Vec::new();
+    let mut colors = Vec::new();
+    let mut triangles = Vec::new();
+
+    let last_line = loop {
+        let Some((number, line)) = lines.next() else {
+            return Err(LoadError::Data("mesh without `end`".to_string()));
+        };
+        let mut words = Words::new(line, number + 1);
+        match words.next() {
+            None => continue,
+            Some("v") => positions.push(words.vector()?),
+            Some("vn") => normals.push(words.vector()?),
+            Some("vt") => uvs.push(Vec2::new(words.float()?, words.float()?)),
+            Some("vc") => colors.push(words.vector()?),
+            Some("f") => triangles.push([words.parse()?, words.parse()?, words.parse()?]),
+            Some("end") => break words,
+            Some(word) => return Err(words.error(&format!("unexpected `{word}` in mesh"))),
+        }
+        words.finish()?;
+    };
+
+    let count = positions.len();
+    if let Some(corner) = triangles.iter().flatten().find(|&&i| i as usize >= count) {
+        return Err(last_line.error(&format!("mesh uses vertex {corner} of {count}")));
+    }
+    let check = |name: &str, length: usize| match length {
+        0 => Ok(false),
+        _ if length == count => Ok(true),
+        _ => Err(last_line.error(&format!("mesh has {length} {name} for {count} vertices"))),
+    };
+
+    let (has_normals, has_uvs, has_colors) = (
+        check("normals", normals.len())?,
+        check("UVs", uvs.len())?,
+        check("colors", colors.len())?,
+    );
+    let mut data = MeshData::new(positions, triangles);
+    if has_normals {
+        data = data.with_normals(normals);
+    }
+    if has_uvs {
+        data = data.with_uvs(uvs);
+    }
+    if has_colors {
+        data = data.with_colors(colors.into_iter().map(Color).collect());
+    }
+    Ok(data)
+}
+
+/// The words of one line, with errors that name the line.
+struct Words<'a> {
+    words: std::iter::Peekable<SplitWhitespace<'a>>,
+    line: &'a str,
+    number: usize,
+}
+
+impl<'a> Words<'a> {
+    /// Words of `line`, which is empty if it is a comment.
+    fn new(line: &'a str, number: usize) -> Self {
+        let line = line.trim();
+        let line = if line.starts_with('#') { "" } else { line };
+        Self {
+            words: line.split_whitespace().peekable(),
+            line,
+            number,
+        }
+    }
+
+    fn error(&self, message: &str) -> LoadError {
+        LoadError::Data(format!("line {}: {message}", self.number))
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        self.words.next()
+    }
+
+    fn peek(&mut self) -> Option<&'a str> {
+        self.words.peek().copied()
+    }
+
+    fn word(&mut self) -> Result<&'a str, LoadError> {
+        self.next()
+            .ok_or_else(|| self.error(&format!("`{}` is incomplete", self.line)))
+    }
+
+    fn parse<T: std::str::FromStr>(&mut self) -> Result<T, LoadError> {
+        let word = self.word()?;
+        word.parse()
+            .map_err(|_| self.error(&format!("bad value `{word}`")))
+    }
+
+    fn float(&mut self) -> Result<f32, LoadError> {
+        self.parse()
+    }
+
+    fn vector(&mut self) -> Result<Vec3, LoadError> {
+        Ok(Vec3::new(self.float()?, self.float()?, self.float()?))
+    }
+
+    /// The remaining words joined by single spaces.
+    fn rest(&mut self) -> String {
+        self.words.by_ref().collect::<Vec<_>>().join(" ")
+    }
+
+    fn finish(&mut self) -> Result<(), LoadError> {
+        match self.next() {
+            None => Ok(()),
+            Some(word) => Err(self.error(&format!("unexpected `{word}`"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rendering::camera::PhysicalLens;
+
+    use super::*;
+
+    /// Writes `camera`, reads it back and checks that it writes the same
+    /// way again.
+    fn round_trip(camera: &CameraConfig) -> CameraConfig {
+        let mut writer = Writer::default();
+        writer.camera(camera);
+        let read = SceneDescription::parse(&writer.text).unwrap().camera;
+
+        let mut again = Writer::default();
+        again.camera(&read);
+        assert_eq!(again.text, writer.text);
+        read
+    }
+
+    #[test]
+    fn documented_example_parses() {
+        let source = include_str!("file.rs");
+        let example: String = source
+            .lines()
+            .skip_while(|line| *line != "//! ```text")
+            .skip(1)
+            .take_while(|line| *line != "//! ```")
+            .map(|line| format!("{}\n", line.trim_start_matches("//! ")))
+            .collect();
+        let scene = SceneDescription::parse(&example).unwrap();
+        assert_eq!(scene.objects.len(), 2);
+        assert_eq!(scene.camera.look_from, Vec3::new(13.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn default_camera_round_trips() {
+        round_trip(&CameraConfig::default());
+    }
+
+    #[test]
+    fn camera_settings_round_trip() {
+        let mut camera = CameraConfig {
+            aspect_ratio: 1.5,
+            image_width: 640,
+            samples_per_pixel: 32,
+            max_bounces: 12,
+            vfov: 35.5,
+            look_from: Vec3::new(1.0, 2.0, 3.0),
+            look_at: Vec3::new(0.0, 0.5, -1.0),
+            focus_distance: 4.25,
+            focus: Focus::LookAt,
+            defocus_angle: 0.6,
+            lens: Some(PhysicalLens {
+                focal_length: 85.0,
+                f_stop: 1.4,
+                sensor_width: 23.5,
+            }),
+            aperture: Aperture::Polygon {
+                blades: 7,
+                rotation: 12.5,
+            },
+            skybox: Color::new(0.1, 0.2, 0.3),
+            shutter_open: 0.25,
+            shutter_close: 0.75,
+            projection: Projection::Fisheye {
+                fov: 190.0,
+                mapping: FisheyeMapping::Equisolid,
+            },
+            filter: Filter::new(FilterKind::Lanczos).with_radius(2.0),
+            spectral: true,
+            debug: Some(DebugView::Normals),
+            integrator: IntegratorKind::Bidirectional,
+            ..Default::default()
+        };
+        camera.bounce_limits.set(BounceKind::Diffuse, 3);
+
+        let read = round_trip(&camera);
+        assert!(matches!(read.focus, Focus::LookAt));
+        let lens = read.lens.unwrap();
+        assert_eq!(
+            (lens.focal_length, lens.f_stop, lens.sensor_width),
+            (85.0, 1.4, 23.5)
+        );
+        assert!(matches!(
+            read.aperture,
+            Aperture::Polygon { blades: 7, rotation } if rotation == 12.5
+        ));
+        assert!(matches!(
+            read.projection,
+            Projection::Fisheye { fov, mapping: FisheyeMapping::Equisolid } if fov == 190.0
+        ));
+        assert!(read.filter.kind == FilterKind::Lanczos && read.filter.radius == 2.0);
+        assert!(matches!(read.debug, Some(DebugView::Normals)));
+        assert!(read.integrator == IntegratorKind::Bidirectional);
+        assert_eq!(read.bounce_limits.get(BounceKind::Diffuse), 3);
+    }
+
+    #[test]
+    fn other_projections_round_trip() {
+        for (projection, focus) in [
+            (
+                Projection::Orthographic { height: 3.5 },
+                Focus::Pixel(20, 30),
+            ),
+            (Projection::Equirectangular, Focus::Manual),
+            (
+                Projection::Cylindrical {
+                    horizontal_fov: 270.0,
+                },
+                Focus::Manual,
+            ),
+        ] {
+            let camera = CameraConfig {
+                projection,
+                focus,
+                ..Default::default()
+            };
+            round_trip(&camera);
+        }
+    }
+}
@@ -0,0 +1,115 @@
+pub mod description;
+pub mod file;
+pub mod obj;
+pub mod presets;
+
+use std::fs;
+
+use crate::{
+    animation::{Animation, CameraAnimation},
+    math::{hash_bytes, hash_seed},
+    object::bvh::BVHCollection,
+    rendering::{
+        camera::{Camera, CameraConfig},
+        material::MaterialRegistry,
+    },
+};
+
+use self::description::SceneDescription;
+
+/// What a scene is built from, in a form that can be sent to another
+/// machine. Images that textures load are still read from the disk of the
+/// machine building the scene.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SceneSource {
+    /// Name of a preset, which every build of the renderer has.
+    Preset(String),
+    /// Text of a scene file.
+    Text(String),
+}
+
+impl SceneSource {
+    /// Reads the file `name` if it ends in `.scene`, otherwise stands for
+    /// the preset called `name`.
+    pub fn open(name: &str) -> Result<Self, String> {
+        if name.ends_with(".scene") {
+            fs::read_to_string(name)
+                .map(Self::Text)
+                .map_err(|error| format!("Could not read {name}: {error}"))
+        } else {
+            Ok(Self::Preset(name.to_string()))
+        }
+    }
+
+    pub fn build(&self) -> Result<Scene, String> {
+        match self {
+            Self::Preset(name) => {
+                presets::load(name).ok_or_else(|| format!("Nonexistent scene {name}"))
+            }
+            Self::Text(text) => SceneDescription::parse(text)
+                .map(|description| description.build())
+                .map_err(|error| format!("Could not read scene: {error}")),
+        }
+    }
+
+    /// Identifies the scene without its text. Presets also depend on the
+    /// seed they are built with.
+    pub fn hash(&self) -> u64 {
+        match self {
+            Self::Preset(name) => hash_seed(&[0, hash_bytes(name.as_bytes())]),
+            Self::Text(text) => hash_seed(&[1, hash_bytes(text.as_bytes())]),
+        }
+    }
+}
+
+pub struct Scene {
+    pub camera: CameraConfig,
+    pub world: BVHCollection,
+    /// Default playback settings for animated scenes.
+    pub animation: Option<Animation>,
+    pub camera_animation: Option<CameraAnimation>,
+    /// Materials objects of the world share, which can be swapped between
+    /// renders.
+    pub materials: MaterialRegistry,
+}
+
+impl Scene {
+    pub fn new(camera: CameraConfig, world: BVHCollection) -> Self {
+        Self {
+            camera,
+            world,
+            animation: None,
+            camera_animation: None,
+            materials: MaterialRegistry::new(),
+        }
+    }
+
+    pub fn with_materials(mut self, materials: MaterialRegistry) -> Self {
+        self.materials = materials;
+        self
+    }
+
+    pub fn camera(&self) -> Camera {
+        self.build_camera(self.camera.clone())
+    }
+
+    /// Camera for one frame of `animation`, with its shutter interval set.
+    /// An animated camera keeps moving while the shutter is open.
+    pub fn frame_camera(&self, animation: &Animation, frame: u32) -> Camera {
+        let mut config = self.camera.clone();
+        let shutter = animation.shutter(frame);
+        config.shutter_open = shutter.start;
+        config.shutter_close = shutter.end;
+
+        self.build_camera(config)
+    }
+
+    fn build_camera(&self, mut config: CameraConfig) -> Camera {
+        config.autofocus(&self.world, self.camera_animation.as_ref());
+
+        match &self.camera_animation {
+            Some(camera_animation) => Camera::animated(config, camera_animation.clone()),
+            None => Camera::new(config),
+        }
+    }
+}
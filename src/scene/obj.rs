@@ -0,0 +1,205 @@
+use std::{
+    f32::consts::{PI, TAU},
+    fmt::Write as _,
+    fs, io,
+    path::Path,
+};
+
+use glam::{Quat, Vec3};
+
+use crate::object::{frame::Frame, mesh::MeshData};
+
+use super::description::{SceneDescription, ShapeDescription};
+
+/// Number of edges curved surfaces are split into around their axis.
+const SEGMENTS: u32 = 48;
+
+impl ShapeDescription {
+    /// Triangles approximating the shape, wound counterclockwise seen from
+    /// outside. Moving spheres are placed where they are at time 0.
+    pub fn to_mesh(&self) -> MeshData {
+        let mut mesh = MeshBuilder::default();
+        match self.clone() {
+            Self::Sphere { center, radius, .. } => {
+                let frame = Frame::new(center, Quat::IDENTITY);
+                mesh.grid(&frame, SEGMENTS / 2, |around, up| {
+                    let (theta, phi) = (PI * (1.0 - up), TAU * around);
+                    radius
+                        * Vec3::new(
+                            theta.sin() * phi.cos(),
+                            theta.sin() * phi.sin(),
+                            theta.cos(),
+                        )
+                });
+            }
+            Self::Quad { origin, u, v } => {
+                mesh.polygon(vec![origin, origin + u, origin + u + v, origin + v])
+            }
+            Self::Disk { center, u, v } => mesh.polygon(
+                ring(1.0, 0.0)
+                    .map(|point| center + point.x * u + point.y * v)
+                    .collect(),
+            ),
+            Self::Cuboid {
+                center,
+                size,
+                rotation,
+            } => {
+                let frame = Frame::new(center, rotation);
+                let corner = |i: usize| {
+                    let sign = Vec3::new(
+                        (i & 1) as f32 - 0.5,
+                        (i >> 1 & 1) as f32 - 0.5,
+                        (i >> 2 & 1) as f32 - 0.5,
+                    );
+                    frame.point_to_world(sign * size)
+                };
+                let faces = [
+                    [0, 4, 6, 2],
+                    [1, 3, 7, 5],
+                    [0, 1, 5, 4],
+                    [2, 6, 7, 3],
+                    [0, 2, 3, 1],
+                    [4, 5, 7, 6],
+                ];
+                for face in faces {
+                    mesh.polygon(face.map(corner).to_vec());
+                }
+            }
+            Self::Cylinder {
+                base,
+                top,
+                radius,
+                capped,
+            } => {
+                let frame = Frame::with_z_axis(base, top - base);
+                let height = (top - base).length();
+                mesh.grid(&frame, 1, |around, up| {
+                    let (sin, cos) = (TAU * around).sin_cos();
+                    Vec3::new(radius * cos, radius * sin, height * up)
+                });
+                if capped {
+                    mesh.cap(&frame, radius, 0.0, true);
+                    mesh.cap(&frame, radius, height, false);
+                }
+            }
+            Self::Cone { base, apex, radius } => {
+                let frame = Frame::with_z_axis(base, apex - base);
+                let height = (apex - base).length();
+                mesh.grid(&frame, 1, |around, up| {
+                    let (sin, cos) = (TAU * around).sin_cos();
+                    let ring = (1.0 - up) * radius;
+                    Vec3::new(ring * cos, ring * sin, height * up)
+                });
+                mesh.cap(&frame, radius, 0.0, true);
+            }
+            Self::Torus {
+                center,
+                axis,
+                major_radius,
+                minor_radius,
+            } => {
+                let frame = Frame::with_z_axis(center, axis);
+                mesh.grid(&frame, SEGMENTS / 2, |around, up| {
+                    let (sin, cos) = (TAU * around).sin_cos();
+                    let (tube_sin, tube_cos) = (TAU * up).sin_cos();
+                    let distance = major_radius + minor_radius * tube_cos;
+                    Vec3::new(distance * cos, distance * sin, minor_radius * tube_sin)
+                });
+            }
+            Self::Mesh(data) => return data,
+        }
+
+        MeshData::new(mesh.positions, mesh.triangles)
+    }
+}
+
+impl SceneDescription {
+    /// Writes the geometry of every object to a Wavefront OBJ file, one
+    /// `o` group per object. Materials are left out.
+    pub fn write_obj<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut text = String::new();
+        let mut first_vertex = 1;
+        for (index, object) in self.objects.iter().enumerate() {
+            let mesh = object.shape.to_mesh();
+            writeln!(text, "o object{index}").unwrap();
+            for p in &mesh.positions[0] {
+                writeln!(text, "v {} {} {}", p.x, p.y, p.z).unwrap();
+            }
+            for n in mesh.normals.iter().flatten() {
+                writeln!(text, "vn {} {} {}", n.x, n.y, n.z).unwrap();
+            }
+
+            for triangle in &mesh.triangles {
+                let [a, b, c] = triangle.map(|i| i as usize + first_vertex);
+                if mesh.normals.is_some() {
+                    writeln!(text, "f {a}//{a} {b}//{b} {c}//{c}").unwrap();
+                } else {
+                    writeln!(text, "f {a} {b} {c}").unwrap();
+                }
+            }
+            first_vertex += mesh.vertex_count();
+        }
+        fs::write(path, text)
+    }
+}
+
+#[derive(Default)]
+struct MeshBuilder {
+    positions: Vec<Vec3>,
+    triangles: Vec<[u32; 3]>,
+}
+
+impl MeshBuilder {
+    /// Surface swept by `point(around, up)` over 0..1 in both parameters,
+    /// split into `SEGMENTS` columns and `rows` rows. The outside is the
+    /// side the surface turns counterclockwise around as `up` grows.
+    fn grid(&mut self, frame: &Frame, rows: u32, point: impl Fn(f32, f32) -> Vec3) {
+        let first = self.positions.len() as u32;
+        for row in 0..=rows {
+            for column in 0..=SEGMENTS {
+                let local = point(column as f32 / SEGMENTS as f32, row as f32 / rows as f32);
+                self.positions.push(frame.point_to_world(local));
+            }
+        }
+
+        let width = SEGMENTS + 1;
+        for row in 0..rows {
+            for column in 0..SEGMENTS {
+                let a = first + row * width + column;
+                let b = a + width;
+                self.triangles.push([a, a + 1, b]);
+                self.triangles.push([a + 1, b + 1, b]);
+            }
+        }
+    }
+
+    /// Convex polygon, triangulated as a fan around its first point.
+    fn polygon(&mut self, points: Vec<Vec3>) {
+        let first = self.positions.len() as u32;
+        let count = points.len() as u32;
+        self.positions.extend(points);
+        self.triangles
+            .extend((1..count.saturating_sub(1)).map(|i| [first, first + i, first + i + 1]));
+    }
+
+    /// Round cap across the local z axis at `height`, facing down if
+    /// `downward` is set and up otherwise.
+    fn cap(&mut self, frame: &Frame, radius: f32, height: f32, downward: bool) {
+        let mut points: Vec<Vec3> = ring(radius, height)
+            .map(|point| frame.point_to_world(point))
+            .collect();
+        if downward {
+            points.reverse();
+        }
+        self.polygon(points);
+    }
+}
+
+/// `SEGMENTS` points counterclockwise around a circle in the local xy plane.
+fn ring(radius: f32, height: f32) -> impl Iterator<Item = Vec3> {
+    (0..SEGMENTS).map(move |i| {
+        let (sin, cos) = (TAU * i as f32 / SEGMENTS as f32).sin_cos();
+        Vec3::new(radius * cos, radius * sin, height)
+    })
+}
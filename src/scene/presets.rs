@@ -0,0 +1,1054 @@
+use std::f32::consts::FRAC_PI_2;
+
+use glam::{vec3, Quat, Vec3};
+
+use crate::{
+    animation::{Animation, CameraAnimation, Track, TransformTrack},
+    math::{random, random_range, VecExt},
+    object::{
+        collection::ObjectCollection,
+        curves::{load_strands, Curve},
+        mesh::{MeshData, PolygonMesh, Subdivision},
+        points::PointCloud,
+        sdf::{self, SdfExt},
+        types::{
+            AnimatedObject, Cone, Csg, Cuboid, CurveShape, Curves, Cylinder, Disk, Quad, SdfObject,
+            Sphere, SphereCloud, Torus, TriangleMesh,
+        },
+        Object,
+    },
+    rendering::{
+        camera::CameraConfig,
+        colorspace::ColorSpace,
+        material::{
+            Dielectric, Dispersion, Hair, Lambertian, Light, Material, MaterialRegistry, Metal,
+        },
+        ray::Color,
+        texture::{CheckerTexture, ImageTexture, VertexColor},
+    },
+};
+
+use super::Scene;
+
+/// Builds one of the built-in scenes by name. Scenes that use randomness draw
+/// from the thread's generator, so reseeding beforehand reproduces them.
+pub fn load(name: &str) -> Option<Scene> {
+    match name {
+        "balls" => Some(random_balls()),
+        "earth" => Some(earth()),
+        "quads" => Some(quads()),
+        "turntable" => Some(turntable()),
+        "motion" => Some(motion()),
+        "prism" => Some(prism()),
+        "csg" => Some(csg()),
+        "primitives" => Some(primitives()),
+        "sdf" => Some(sdf_shapes()),
+        "subdivision" => Some(subdivision()),
+        "fur" => Some(fur()),
+        "strands" => Some(strands()),
+        "particles" => Some(particles()),
+        "points" => Some(points()),
+        "model" => Some(model()),
+        _ => None,
+    }
+}
+
+fn random_balls() -> Scene {
+    let mut world = ObjectCollection::new();
+    let mut materials = MaterialRegistry::new();
+
+    let checkers =
+        CheckerTexture::with_colors(0.32, Color::new(0.2, 0.3, 0.1), Color::new(0.9, 0.9, 0.9));
+    let ground_material = materials.add("ground", Lambertian::new(checkers));
+    let glass = materials.add("glass", Dielectric::new(1.5));
+
+    world.add(Sphere::new(
+        vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        ground_material,
+    ));
+
+    for a in -11..11 {
+        for b in -11..11 {
+            let material_choice = random();
+            let center = vec3(a as f32 + 0.9 * random(), 0.2, b as f32 + 0.9 * random());
+
+            if (center - vec3(4.0, 0.2, 0.0)).length() <= 0.9 {
+                continue;
+            }
+
+            if material_choice < 0.8 {
+                let albedo = Color::random();
+                world.add(Sphere::new(center, 0.2, Lambertian::solid_color(albedo)))
+            } else if material_choice < 0.95 {
+                let albedo = Color::random();
+                let fuzz = random_range(0.0..0.5);
+                let material = Metal::solid_color(albedo, fuzz);
+                world.add(Sphere::new(center, 0.2, material))
+            } else {
+                world.add(Sphere::new(center, 0.2, glass.clone()))
+            }
+        }
+    }
+
+    world.add(Sphere::new(vec3(0.0, 1.0, 0.0), 1.0, glass));
+
+    world.add(Sphere::new(
+        vec3(-4.0, 1.0, 0.0),
+        1.0,
+        Lambertian::solid_color(Color::new(0.4, 0.2, 0.1)),
+    ));
+
+    world.add_light(Sphere::new(
+        vec3(4.0, 1.0, 0.0),
+        0.2,
+        Light::solid_color(Color::new(2.0, 2.0, 2.0)), //Metal::solid_color(Color::new(0.7, 0.6, 0.5), 0.1),
+    ));
+
+    let vfov = 20.0;
+    let look_from = vec3(13.0, 2.0, 3.0);
+    let look_at = vec3(0.0, 0.0, 0.0);
+
+    let camera = CameraConfig {
+        image_width: 800,
+        samples_per_pixel: 1000,
+        defocus_angle: 0.0,
+        vfov,
+        look_from,
+        look_at,
+        skybox: Color::new(0., 0., 0.),
+        ..Default::default()
+    };
+
+    Scene::new(camera, world.as_bvh()).with_materials(materials)
+}
+
+fn earth() -> Scene {
+    let mut world = ObjectCollection::new();
+    world.add(Sphere::new(
+        vec3(0., 1.0, 0.0),
+        1.0,
+        Lambertian::new(ImageTexture::from_file("earthmap.jpg").with_color_space(ColorSpace::Srgb)),
+    ));
+
+    let vfov = 45.0;
+    let look_from = vec3(3., 2.0, -1.0);
+    let look_at = vec3(0.0, 1.0, 0.0);
+
+    let camera = CameraConfig {
+        image_width: 5120,
+        samples_per_pixel: 8192,
+        defocus_angle: 0.0,
+        vfov,
+        look_from,
+        look_at,
+        ..Default::default()
+    };
+
+    Scene::new(camera, world.as_bvh())
+}
+
+fn quads() -> Scene {
+    let mut world = ObjectCollection::new();
+
+    let red = Lambertian::solid_color(Color::new(1.0, 0.2, 0.2));
+    let green = Lambertian::solid_color(Color::new(0.2, 1.0, 0.2));
+    let blue = Lambertian::solid_color(Color::new(0.2, 0.2, 1.0));
+    let orange = Lambertian::solid_color(Color::new(1.0, 0.5, 0.0));
+    let teal = Lambertian::solid_color(Color::new(0.2, 0.8, 0.8));
+
+    world.add(Quad::new(
+        vec3(-3., -2., 5.),
+        vec3(0., 0., -4.),
+        vec3(0., 4., 0.),
+        red,
+    ));
+
+    world.add(Quad::new(
+        vec3(-2., -2., 0.),
+        vec3(4., 0., 0.),
+        vec3(0., 4., 0.),
+        green,
+    ));
+
+    world.add(Quad::new(
+        vec3(3., -2., 1.),
+        vec3(0., 0., 4.),
+        vec3(0., 4., 0.),
+        blue,
+    ));
+
+    world.add(Quad::new(
+        vec3(-2., 3., 1.),
+        vec3(4., 0., 0.),
+        vec3(0., 0., 4.),
+        orange,
+    ));
+
+    world.add(Quad::new(
+        vec3(-2., -3., 5.),
+        vec3(4., 0., 0.),
+        vec3(0., 0., -4.),
+        teal,
+    ));
+
+    let camera = CameraConfig {
+        aspect_ratio: 1.0,
+        image_width: 400,
+        samples_per_pixel: 100,
+        max_bounces: 50,
+        vfov: 80.0,
+        look_from: vec3(0., 0., 9.),
+        look_at: vec3(0., 0., 0.),
+        defocus_angle: 0.0,
+        ..Default::default()
+    };
+
+    Scene::new(camera, world.as_bvh())
+}
+
+fn turntable() -> Scene {
+    let mut world = ObjectCollection::new();
+
+    let checkers =
+        CheckerTexture::with_colors(0.5, Color::new(0.2, 0.3, 0.1), Color::new(0.9, 0.9, 0.9));
+    world.add(Sphere::new(
+        vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(checkers),
+    ));
+
+    let mut platter = ObjectCollection::new();
+    for i in 0..6 {
+        let angle = i as f32 * std::f32::consts::TAU / 6.0;
+        let center = vec3(2.5 * angle.cos(), 0.5, 2.5 * angle.sin());
+        if i % 2 == 0 {
+            platter.add(Sphere::new(
+                center,
+                0.5,
+                Lambertian::solid_color(Color::random()),
+            ));
+        } else {
+            platter.add(Sphere::new(
+                center,
+                0.5,
+                Metal::solid_color(Color::new(0.8, 0.8, 0.8), 0.05),
+            ));
+        }
+    }
+
+    // Quarter turns per second, keyed separately so slerp never takes a shortcut.
+    let rotation = (0..=4).fold(Track::new(), |track, i| {
+        track.linear(i as f32, Quat::from_rotation_y(i as f32 * FRAC_PI_2))
+    });
+    world.add(AnimatedObject::new(
+        platter.as_bvh(),
+        TransformTrack {
+            rotation,
+            ..Default::default()
+        },
+    ));
+
+    let rest = vec3(0.0, 0.5, 0.0);
+    let apex = vec3(0.0, 2.5, 0.0);
+    let translation = (0..4)
+        .fold(Track::new(), |track, i| {
+            track.bezier(i as f32, rest, apex, apex)
+        })
+        .linear(4.0, rest);
+    world.add(AnimatedObject::new(
+        Sphere::new(Vec3::ZERO, 0.5, Dielectric::new(1.5)),
+        TransformTrack {
+            translation,
+            ..Default::default()
+        },
+    ));
+
+    let camera = CameraConfig {
+        image_width: 640,
+        samples_per_pixel: 64,
+        vfov: 40.0,
+        look_from: vec3(0.0, 3.0, 12.0),
+        look_at: vec3(0.0, 0.5, 0.0),
+        ..Default::default()
+    };
+
+    let mut scene = Scene::new(camera, world.as_bvh());
+    scene.animation = Some(Animation {
+        frames: 0..=95,
+        ..Default::default()
+    });
+    scene.camera_animation = Some(CameraAnimation {
+        look_from: Some(
+            Track::new()
+                .bezier(
+                    0.0,
+                    vec3(0.0, 3.0, 12.0),
+                    vec3(6.0, 3.0, 12.0),
+                    vec3(9.0, 2.0, 6.0),
+                )
+                .linear(4.0, vec3(7.0, 1.5, 4.0)),
+        ),
+        look_at: None,
+        vfov: Some(Track::new().linear(0.0, 40.0).linear(4.0, 30.0)),
+    });
+
+    scene
+}
+
+/// Still showing the different kinds of motion blur: a moving sphere, a
+/// spinning box, a waving sheet and a slightly panning camera.
+fn motion() -> Scene {
+    let mut world = ObjectCollection::new();
+
+    world.add(Quad::new(
+        vec3(-10.0, 0.0, -10.0),
+        vec3(20.0, 0.0, 0.0),
+        vec3(0.0, 0.0, 20.0),
+        Lambertian::new(CheckerTexture::with_colors(
+            0.5,
+            Color::new(0.2, 0.3, 0.1),
+            Color::new(0.9, 0.9, 0.9),
+        )),
+    ));
+
+    world.add(Sphere::moving(
+        vec3(-2.5, 0.5, 0.0),
+        vec3(-2.5, 1.5, 0.0),
+        0.5,
+        Lambertian::solid_color(Color::new(0.8, 0.2, 0.2)),
+    ));
+
+    let mut cube = ObjectCollection::new();
+    let faces = [
+        (vec3(-0.5, -0.5, 0.5), Vec3::X, Vec3::Y),
+        (vec3(0.5, -0.5, -0.5), -Vec3::X, Vec3::Y),
+        (vec3(0.5, -0.5, 0.5), -Vec3::Z, Vec3::Y),
+        (vec3(-0.5, -0.5, -0.5), Vec3::Z, Vec3::Y),
+        (vec3(-0.5, 0.5, 0.5), Vec3::X, -Vec3::Z),
+        (vec3(-0.5, -0.5, -0.5), Vec3::X, Vec3::Z),
+    ];
+    for (origin, u, v) in faces {
+        cube.add(Quad::new(
+            origin,
+            u,
+            v,
+            Metal::solid_color(Color::new(0.8, 0.7, 0.3), 0.2),
+        ));
+    }
+    world.add(AnimatedObject::new(
+        cube.as_bvh(),
+        TransformTrack {
+            translation: Track::constant(vec3(0.0, 0.8, 0.0)),
+            rotation: Track::new()
+                .linear(0.0, Quat::IDENTITY)
+                .linear(1.0, Quat::from_rotation_y(FRAC_PI_2)),
+            ..Default::default()
+        },
+    ));
+
+    // A sheet waving over three motion keys.
+    const CELLS: u32 = 24;
+    let wave = |phase: f32| -> Vec<Vec3> {
+        (0..=CELLS)
+            .flat_map(|j| (0..=CELLS).map(move |i| (i, j)))
+            .map(|(i, j)| {
+                let x = i as f32 / CELLS as f32;
+                let y = j as f32 / CELLS as f32;
+                vec3(
+                    1.8 + 1.5 * x,
+                    0.2 + 1.5 * y,
+                    0.3 * x * (6.0 * x + phase).sin(),
+                )
+            })
+            .collect()
+    };
+    let mut triangles = Vec::new();
+    for j in 0..CELLS {
+        for i in 0..CELLS {
+            let a = j * (CELLS + 1) + i;
+            let b = a + CELLS + 1;
+            triangles.push([a, a + 1, b + 1]);
+            triangles.push([a, b + 1, b]);
+        }
+    }
+    let sheet = MeshData::new(wave(0.0), triangles)
+        .with_motion_key(wave(1.5))
+        .with_motion_key(wave(3.0));
+    world.add(TriangleMesh::new(
+        sheet,
+        Lambertian::solid_color(Color::new(0.2, 0.4, 0.8)),
+    ));
+
+    let camera = CameraConfig {
+        image_width: 640,
+        samples_per_pixel: 128,
+        vfov: 35.0,
+        look_from: vec3(0.0, 2.0, 9.0),
+        look_at: vec3(0.0, 0.8, 0.0),
+        ..Default::default()
+    };
+
+    let mut scene = Scene::new(camera, world.as_bvh());
+    scene.camera_animation = Some(CameraAnimation {
+        look_from: Some(
+            Track::new()
+                .linear(0.0, vec3(0.0, 2.0, 9.0))
+                .linear(1.0, vec3(0.15, 2.0, 9.0)),
+        ),
+        ..Default::default()
+    });
+
+    scene
+}
+
+/// Flint glass prism and a crown glass ball in front of a checkered wall,
+/// rendered spectrally so the wall's edges seen through them break up into
+/// rainbows.
+fn prism() -> Scene {
+    let mut world = ObjectCollection::new();
+
+    world.add(Quad::new(
+        vec3(-15.0, -10.0, -7.75),
+        vec3(30.0, 0.0, 0.0),
+        vec3(0.0, 22.0, 0.0),
+        Lambertian::new(CheckerTexture::with_colors(
+            0.5,
+            Color::new(0.05, 0.05, 0.05),
+            Color::new(0.9, 0.9, 0.9),
+        )),
+    ));
+
+    // Triangular cross-section in the y-z plane, extruded along x.
+    let section = [
+        vec3(0.0, 0.7, 0.0),
+        vec3(0.0, -0.5, 0.7),
+        vec3(0.0, -0.5, -0.7),
+    ];
+    let positions = [-1.5, 1.5]
+        .iter()
+        .flat_map(|&x| section.map(|p| p + vec3(x, 0.0, 0.0)))
+        .collect();
+    let triangles = vec![
+        [0, 2, 1],
+        [3, 4, 5],
+        [0, 1, 4],
+        [0, 4, 3],
+        [1, 2, 5],
+        [1, 5, 4],
+        [2, 0, 3],
+        [2, 3, 5],
+    ];
+    world.add(TriangleMesh::new(
+        MeshData::new(positions, triangles),
+        Dielectric::dispersive(Dispersion::SF11),
+    ));
+
+    world.add(Sphere::new(
+        vec3(2.3, -0.1, 0.5),
+        0.5,
+        Dielectric::dispersive(Dispersion::Cauchy {
+            a: 1.5046,
+            b: 0.0042,
+        }),
+    ));
+
+    let camera = CameraConfig {
+        aspect_ratio: 3.0 / 2.0,
+        image_width: 600,
+        samples_per_pixel: 256,
+        vfov: 40.0,
+        look_from: vec3(0.0, 0.5, 6.0),
+        look_at: vec3(0.0, 0.0, 0.0),
+        skybox: Color::new(1.0, 1.0, 1.0),
+        spectral: true,
+        ..Default::default()
+    };
+
+    Scene::new(camera, world.as_bvh())
+}
+
+/// Axis-aligned cube with outward facing triangles.
+fn cube(center: Vec3, size: f32) -> MeshData {
+    let triangles = vec![
+        [0, 4, 6],
+        [0, 6, 2],
+        [1, 3, 7],
+        [1, 7, 5],
+        [0, 1, 5],
+        [0, 5, 4],
+        [2, 6, 7],
+        [2, 7, 3],
+        [0, 2, 3],
+        [0, 3, 1],
+        [4, 5, 7],
+        [4, 7, 6],
+    ];
+    MeshData::new(cube_corners(center, size), triangles)
+}
+
+/// The same cube made of quads, as a subdivision cage.
+fn cube_cage(center: Vec3, size: f32) -> PolygonMesh {
+    let faces = vec![
+        vec![0, 4, 6, 2],
+        vec![1, 3, 7, 5],
+        vec![0, 1, 5, 4],
+        vec![2, 6, 7, 3],
+        vec![0, 2, 3, 1],
+        vec![4, 5, 7, 6],
+    ];
+    PolygonMesh::new(cube_corners(center, size), faces).unwrap()
+}
+
+fn cube_corners(center: Vec3, size: f32) -> Vec<Vec3> {
+    // Vertex i has its x, y and z on the positive side for bits 0, 1 and 2.
+    (0..8)
+        .map(|i| {
+            let corner = vec3((i & 1) as f32, (i >> 1 & 1) as f32, (i >> 2 & 1) as f32);
+            center + (corner - 0.5) * size
+        })
+        .collect()
+}
+
+/// Solids built with constructive solid geometry: a biconvex lens, a ball
+/// on a stand with a cube bitten out of it and a hollow shell cut open.
+fn csg() -> Scene {
+    let mut world = ObjectCollection::new();
+
+    world.add(Sphere::new(
+        vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::new(CheckerTexture::with_colors(
+            0.5,
+            Color::new(0.2, 0.3, 0.1),
+            Color::new(0.9, 0.9, 0.9),
+        )),
+    ));
+
+    world.add(Csg::intersection(
+        Sphere::new(vec3(-2.5, 1.0, 1.6), 1.8, Dielectric::new(1.5)),
+        Sphere::new(vec3(-2.5, 1.0, -1.6), 1.8, Dielectric::new(1.5)),
+    ));
+
+    let ball_on_stand = Csg::union(
+        Sphere::new(
+            vec3(0.0, 1.2, 0.0),
+            1.0,
+            Lambertian::solid_color(Color::new(0.9, 0.4, 0.1)),
+        ),
+        TriangleMesh::new(
+            cube(vec3(0.0, 0.25, 0.0), 0.5),
+            Lambertian::solid_color(Color::new(0.3, 0.3, 0.3)),
+        ),
+    );
+    world.add(Csg::difference(
+        ball_on_stand,
+        TriangleMesh::new(
+            cube(vec3(0.6, 1.8, 0.6), 1.2),
+            Lambertian::solid_color(Color::new(0.9, 0.9, 0.9)),
+        ),
+    ));
+
+    let shell = Csg::difference(
+        Sphere::new(
+            vec3(2.5, 1.0, 0.0),
+            1.0,
+            Metal::solid_color(Color::new(0.8, 0.8, 0.9), 0.1),
+        ),
+        Sphere::new(
+            vec3(2.5, 1.0, 0.0),
+            0.85,
+            Lambertian::solid_color(Color::new(0.2, 0.4, 0.8)),
+        ),
+    );
+    world.add(Csg::difference(
+        shell,
+        TriangleMesh::new(
+            cube(vec3(2.5, 2.0, 1.0), 1.6),
+            Lambertian::solid_color(Color::new(0.9, 0.9, 0.9)),
+        ),
+    ));
+
+    let camera = CameraConfig {
+        image_width: 600,
+        samples_per_pixel: 128,
+        vfov: 35.0,
+        look_from: vec3(0.0, 3.0, 9.0),
+        look_at: vec3(0.0, 1.0, 0.0),
+        ..Default::default()
+    };
+
+    Scene::new(camera, world.as_bvh())
+}
+
+fn primitives() -> Scene {
+    let mut world = ObjectCollection::new();
+
+    world.add(Quad::new(
+        vec3(-20.0, 0.0, 20.0),
+        vec3(40.0, 0.0, 0.0),
+        vec3(0.0, 0.0, -40.0),
+        Lambertian::solid_color(Color::new(0.6, 0.6, 0.6)),
+    ));
+
+    world.add(Cuboid::new(
+        vec3(-4.2, 0.0, -0.6),
+        vec3(-2.8, 1.4, 0.8),
+        Lambertian::solid_color(Color::new(0.8, 0.2, 0.2)),
+    ));
+    world.add(Cuboid::oriented(
+        vec3(-3.5, 1.9, 0.1),
+        vec3(0.7, 0.7, 0.7),
+        Quat::from_euler(glam::EulerRot::YXZ, 0.6, 0.6, 0.0),
+        Metal::solid_color(Color::new(0.8, 0.7, 0.5), 0.05),
+    ));
+
+    world.add(Cylinder::new(
+        vec3(-1.5, 0.0, 0.0),
+        vec3(-1.5, 1.6, 0.0),
+        0.6,
+        Lambertian::solid_color(Color::new(0.2, 0.5, 0.8)),
+    ));
+    world.add(Cone::new(
+        vec3(0.2, 0.0, 0.3),
+        vec3(0.2, 2.0, 0.3),
+        0.7,
+        Lambertian::solid_color(Color::new(0.9, 0.7, 0.2)),
+    ));
+    world.add(Torus::new(
+        vec3(2.0, 0.8, 0.0),
+        vec3(0.0, 0.6, 1.0),
+        0.6,
+        0.2,
+        Metal::solid_color(Color::new(0.9, 0.9, 0.9), 0.0),
+    ));
+    world.add(Disk::ellipse(
+        vec3(3.6, 0.8, -0.5),
+        vec3(0.5, 0.0, 0.2),
+        vec3(0.0, 0.8, 0.0),
+        Lambertian::solid_color(Color::new(0.2, 0.8, 0.3)),
+    ));
+
+    let hexagon = (0..6)
+        .map(|i| {
+            let angle = i as f32 * std::f32::consts::FRAC_PI_3;
+            vec3(angle.cos(), 0.01, -angle.sin()) * vec3(0.8, 1.0, 0.8) + vec3(0.0, 0.0, 2.2)
+        })
+        .collect();
+    world.add(TriangleMesh::new(
+        MeshData::polygon(hexagon),
+        Lambertian::solid_color(Color::new(0.8, 0.3, 0.8)),
+    ));
+
+    world.add_light(Disk::new(
+        vec3(0.0, 6.0, 1.0),
+        vec3(0.0, -1.0, 0.0),
+        2.0,
+        Light::solid_color(Color::new(4.0, 4.0, 4.0)),
+    ));
+    world.add_light(
+        Cylinder::new(
+            vec3(-4.0, 3.2, -2.0),
+            vec3(4.0, 3.2, -2.0),
+            0.08,
+            Light::solid_color(Color::new(6.0, 3.0, 1.5)),
+        )
+        .open(),
+    );
+
+    let camera = CameraConfig {
+        image_width: 600,
+        samples_per_pixel: 128,
+        vfov: 35.0,
+        look_from: vec3(0.0, 4.0, 11.0),
+        look_at: vec3(0.0, 1.0, 0.0),
+        skybox: Color::new(0.05, 0.05, 0.08),
+        ..Default::default()
+    };
+
+    Scene::new(camera, world.as_bvh())
+}
+
+fn sdf_shapes() -> Scene {
+    let mut world = ObjectCollection::new();
+
+    world.add(Quad::new(
+        vec3(-20.0, 0.0, 20.0),
+        vec3(40.0, 0.0, 0.0),
+        vec3(0.0, 0.0, -40.0),
+        Lambertian::solid_color(Color::new(0.5, 0.5, 0.5)),
+    ));
+
+    world.add(
+        SdfObject::new(
+            sdf::Mandelbulb::default().translate(vec3(0.0, 1.2, 0.0)),
+            Lambertian::solid_color(Color::new(0.8, 0.5, 0.3)),
+        )
+        .with_epsilon(2e-4)
+        .with_max_steps(1024),
+    );
+
+    let blob = sdf::Sphere { radius: 0.6 }
+        .translate(vec3(0.0, 0.6, 0.0))
+        .smooth_union(
+            sdf::Sphere { radius: 0.45 }.translate(vec3(0.55, 1.2, 0.1)),
+            0.4,
+        )
+        .smooth_union(
+            sdf::Capsule {
+                a: vec3(-0.2, 0.4, 0.0),
+                b: vec3(-0.7, 1.4, 0.2),
+                radius: 0.2,
+            },
+            0.3,
+        );
+    world.add(SdfObject::new(
+        blob.translate(vec3(-2.8, 0.0, 0.0)),
+        Metal::solid_color(Color::new(0.4, 0.8, 0.6), 0.2),
+    ));
+
+    let twisted = sdf::Cuboid {
+        half_size: vec3(0.35, 0.9, 0.35),
+    }
+    .rounded(0.05)
+    .twist(1.2);
+    world.add(SdfObject::new(
+        twisted.translate(vec3(2.6, 0.95, 0.0)),
+        Lambertian::solid_color(Color::new(0.3, 0.4, 0.9)),
+    ));
+
+    let carved = sdf::Cuboid {
+        half_size: Vec3::splat(0.5),
+    }
+    .intersection(sdf::Sphere { radius: 0.68 })
+    .subtract(sdf::Sphere { radius: 0.58 });
+    world.add(SdfObject::new(
+        carved.translate(vec3(-1.3, 0.5, 2.2)),
+        Lambertian::solid_color(Color::new(0.9, 0.8, 0.2)),
+    ));
+
+    let rings = sdf::Torus {
+        major_radius: 0.2,
+        minor_radius: 0.06,
+    }
+    .union(sdf::Sphere { radius: 0.08 })
+    .repeat(vec3(0.55, 1.0, 0.55), [2, 0, 1]);
+    world.add(SdfObject::new(
+        rings.translate(vec3(1.4, 0.06, 2.4)),
+        Metal::solid_color(Color::new(0.9, 0.9, 0.9), 0.05),
+    ));
+
+    world.add_light(Disk::new(
+        vec3(1.0, 7.0, 3.0),
+        vec3(-0.1, -1.0, -0.4),
+        2.0,
+        Light::solid_color(Color::new(5.0, 5.0, 5.0)),
+    ));
+
+    let camera = CameraConfig {
+        image_width: 600,
+        samples_per_pixel: 128,
+        vfov: 35.0,
+        look_from: vec3(0.0, 3.5, 10.0),
+        look_at: vec3(0.0, 1.0, 0.0),
+        skybox: Color::new(0.3, 0.35, 0.45),
+        ..Default::default()
+    };
+
+    Scene::new(camera, world.as_bvh())
+}
+
+/// A cube refined with Catmull-Clark at increasing levels, with Loop, and
+/// smoothed then displaced by a checker pattern.
+fn subdivision() -> Scene {
+    let mut world = ObjectCollection::new();
+
+    world.add(Quad::new(
+        vec3(-20.0, 0.0, 20.0),
+        vec3(40.0, 0.0, 0.0),
+        vec3(0.0, 0.0, -40.0),
+        Lambertian::solid_color(Color::new(0.5, 0.5, 0.5)),
+    ));
+
+    let clay = || Lambertian::solid_color(Color::new(0.8, 0.6, 0.5));
+    for (level, x) in [(1, -4.5), (2, -2.25), (4, 0.0)] {
+        world.add(TriangleMesh::new(
+            cube_cage(vec3(x, 0.8, 0.0), 1.6)
+                .subdivided(Subdivision::CatmullClark, level)
+                .to_mesh_data(),
+            clay(),
+        ));
+    }
+    world.add(TriangleMesh::new(
+        cube(vec3(2.25, 0.8, 0.0), 1.6).subdivided(Subdivision::Loop, 3),
+        clay(),
+    ));
+
+    let height =
+        CheckerTexture::with_colors(0.25, Color::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0));
+    world.add(TriangleMesh::new(
+        cube_cage(vec3(4.5, 0.8, 0.0), 1.6)
+            .subdivided(Subdivision::CatmullClark, 6)
+            .to_mesh_data()
+            .displaced(&height, 0.06),
+        Lambertian::solid_color(Color::new(0.3, 0.5, 0.8)),
+    ));
+
+    world.add_light(Disk::new(
+        vec3(-2.0, 7.0, 4.0),
+        vec3(0.3, -1.0, -0.6),
+        2.0,
+        Light::solid_color(Color::new(5.0, 5.0, 5.0)),
+    ));
+
+    let camera = CameraConfig {
+        image_width: 600,
+        samples_per_pixel: 128,
+        vfov: 30.0,
+        look_from: vec3(0.0, 4.0, 14.0),
+        look_at: vec3(0.0, 0.8, 0.0),
+        skybox: Color::new(0.3, 0.35, 0.45),
+        ..Default::default()
+    };
+
+    Scene::new(camera, world.as_bvh())
+}
+
+/// A furry ball on a patch of grass, both grown from random curves.
+fn fur() -> Scene {
+    let mut world = ObjectCollection::new();
+
+    world.add(Quad::new(
+        vec3(-20.0, 0.0, 20.0),
+        vec3(40.0, 0.0, 0.0),
+        vec3(0.0, 0.0, -40.0),
+        Lambertian::solid_color(Color::new(0.15, 0.1, 0.05)),
+    ));
+
+    let blades = (0..6000)
+        .map(|_| {
+            let root = vec3(random_range(-4.0..4.0), 0.0, random_range(-3.0..2.0));
+            let up = vec3(0.0, random_range(0.15..0.35), 0.0);
+            let lean = vec3(random_range(-0.1..0.1), 0.0, random_range(-0.1..0.1));
+            // Outer control points start the blade upright and bend it over.
+            Curve::b_spline(
+                [root - up, root, root + up + lean, root + up + 3.0 * lean],
+                [0.02, 0.0],
+            )
+        })
+        .collect();
+    world.add(Curves::new(
+        blades,
+        CurveShape::Flat,
+        Lambertian::solid_color(Color::new(0.2, 0.5, 0.1)),
+    ));
+
+    let center = vec3(0.0, 0.9, 0.0);
+    let radius = 0.7;
+    world.add(Sphere::new(
+        center,
+        radius,
+        Lambertian::solid_color(Color::new(0.05, 0.03, 0.02)),
+    ));
+    let hairs = (0..15000)
+        .flat_map(|_| {
+            let normal = Vec3::random_unit();
+            let root = center + normal * radius;
+            let length = random_range(0.2..0.3);
+            // Hairs droop a little under their own weight.
+            let points: Vec<_> = (0..4)
+                .map(|i| {
+                    let along = i as f32 / 3.0 * length;
+                    root + normal * along - vec3(0.0, 0.3, 0.0) * along * along
+                })
+                .collect();
+            Curve::through_points(&points, &[0.008, 0.006, 0.003, 0.001])
+        })
+        .collect();
+    world.add(Curves::new(
+        hairs,
+        CurveShape::Round,
+        Hair::from_melanin(0.4, 0.3)
+            .with_roughness(0.25, 0.3)
+            .with_scale_angle(3.0),
+    ));
+
+    world.add_light(Disk::new(
+        vec3(3.0, 6.0, 5.0),
+        vec3(-0.4, -1.0, -0.6),
+        1.5,
+        Light::solid_color(Color::new(8.0, 8.0, 8.0)),
+    ));
+
+    let camera = CameraConfig {
+        image_width: 600,
+        samples_per_pixel: 128,
+        vfov: 30.0,
+        look_from: vec3(0.0, 2.0, 6.0),
+        look_at: vec3(0.0, 0.8, 0.0),
+        skybox: Color::new(0.4, 0.45, 0.55),
+        ..Default::default()
+    };
+
+    Scene::new(camera, world.as_bvh())
+}
+
+/// Hair strands read from `strands.txt` in the working directory.
+fn strands() -> Scene {
+    let mut world = ObjectCollection::new();
+
+    let curves = load_strands("strands.txt").expect("Could not read strands.txt");
+    let hair = Curves::new(
+        curves,
+        CurveShape::Round,
+        Hair::from_color(Color::new(0.3, 0.2, 0.1)),
+    );
+    let bounds = hair.bounding_box().clone();
+    let center = (bounds.min() + bounds.max()) * 0.5;
+    let size = (bounds.max() - bounds.min()).length();
+    world.add(hair);
+
+    world.add_light(Disk::new(
+        center + vec3(1.0, 2.0, 2.0) * size,
+        vec3(-1.0, -2.0, -2.0),
+        0.5 * size,
+        Light::solid_color(Color::new(8.0, 8.0, 8.0)),
+    ));
+
+    let camera = CameraConfig {
+        image_width: 600,
+        samples_per_pixel: 128,
+        vfov: 40.0,
+        look_from: center + vec3(0.0, 0.0, 1.5) * size,
+        look_at: center,
+        skybox: Color::new(0.3, 0.3, 0.3),
+        ..Default::default()
+    };
+
+    Scene::new(camera, world.as_bvh())
+}
+
+/// A spiral galaxy of small spheres in one `SphereCloud`, colored per point
+/// and with a few metal ones among them.
+fn particles() -> Scene {
+    let mut world = ObjectCollection::new();
+
+    world.add(Sphere::new(
+        vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        Lambertian::solid_color(Color::new(0.3, 0.3, 0.3)),
+    ));
+
+    let count = 300_000;
+    let core = Vec3::new(1.0, 0.75, 0.4);
+    let rim = Vec3::new(0.25, 0.45, 1.0);
+    let mut positions = Vec::with_capacity(count);
+    let mut colors = Vec::with_capacity(count);
+    let mut materials = Vec::with_capacity(count);
+    for i in 0..count {
+        // Two logarithmic arms, loosening toward the rim.
+        let distance = random().powf(0.7) * 3.0;
+        let arm = (i % 2) as f32 * std::f32::consts::PI;
+        let angle = arm + 1.8 * (1.0 + distance).ln() * 2.0 + 0.5 * random_range(-1.0..1.0);
+        let spread = 0.15 * (1.0 - distance / 3.0) + 0.02;
+        let offset = Vec3::random_unit() * spread * random();
+        positions.push(vec3(distance * angle.cos(), 1.0, distance * angle.sin()) + offset);
+        colors.push(Color(core.lerp(rim, distance / 3.0)));
+        materials.push(u32::from(random() < 0.1));
+    }
+    let points = PointCloud::new(positions, 0.012)
+        .with_colors(colors)
+        .with_materials(materials);
+
+    let materials: Vec<Box<dyn Material>> = vec![
+        Box::new(Lambertian::new(VertexColor::new())),
+        Box::new(Metal::new(VertexColor::new(), 0.2)),
+    ];
+    world.add(SphereCloud::with_materials(points, materials).unwrap());
+
+    world.add_light(Quad::new(
+        vec3(-1.5, 5.0, -1.5),
+        vec3(3.0, 0.0, 0.0),
+        vec3(0.0, 0.0, 3.0),
+        Light::solid_color(Color::new(6.0, 6.0, 6.0)),
+    ));
+
+    let camera = CameraConfig {
+        image_width: 600,
+        samples_per_pixel: 64,
+        vfov: 40.0,
+        look_from: vec3(0.0, 5.5, 7.0),
+        look_at: vec3(0.0, 0.8, 0.0),
+        skybox: Color::new(0.1, 0.1, 0.15),
+        ..Default::default()
+    };
+
+    Scene::new(camera, world.as_bvh())
+}
+
+/// Points read from `points.ply` in the working directory, shown in their
+/// own colors.
+fn points() -> Scene {
+    let mut world = ObjectCollection::new();
+
+    let points = PointCloud::load("points.ply", 0.01).expect("Could not read points.ply");
+    let cloud = SphereCloud::new(points, Lambertian::new(VertexColor::new()))
+        .expect("Could not use points.ply");
+    let bounds = cloud.bounding_box().clone();
+    let center = (bounds.min() + bounds.max()) * 0.5;
+    let size = (bounds.max() - bounds.min()).length();
+    world.add(cloud);
+
+    let camera = CameraConfig {
+        image_width: 600,
+        samples_per_pixel: 64,
+        vfov: 40.0,
+        look_from: center + vec3(0.0, 0.5, 1.5) * size,
+        look_at: center,
+        skybox: Color::new(0.7, 0.8, 1.0),
+        ..Default::default()
+    };
+
+    Scene::new(camera, world.as_bvh())
+}
+
+/// The first of `model.ply` and `model.stl` found in the working directory,
+/// in its vertex colors where it has them.
+fn model() -> Scene {
+    let mut world = ObjectCollection::new();
+
+    let path = ["model.ply", "model.stl"]
+        .into_iter()
+        .find(|path| std::path::Path::new(path).exists())
+        .expect("No model.ply or model.stl to load");
+    let data =
+        MeshData::load(path).unwrap_or_else(|error| panic!("Could not read {path}: {error}"));
+    println!("{path}: {}", data.stats());
+
+    let bounds = data.bounds();
+    let center = (bounds.min() + bounds.max()) * 0.5;
+    let size = (bounds.max() - bounds.min()).length();
+    world.add(TriangleMesh::new(
+        data,
+        Lambertian::new(VertexColor::with_fallback(Color::new(0.7, 0.7, 0.7))),
+    ));
+
+    world.add_light(Disk::new(
+        center + vec3(1.0, 2.0, 2.0) * size,
+        vec3(-1.0, -2.0, -2.0),
+        0.5 * size,
+        Light::solid_color(Color::new(4.0, 4.0, 4.0)),
+    ));
+
+    let camera = CameraConfig {
+        image_width: 600,
+        samples_per_pixel: 64,
+        vfov: 40.0,
+        look_from: center + vec3(0.0, 0.5, 1.5) * size,
+        look_at: center,
+        skybox: Color::new(0.3, 0.3, 0.35),
+        ..Default::default()
+    };
+
+    Scene::new(camera, world.as_bvh())
+}
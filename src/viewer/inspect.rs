@@ -0,0 +1,93 @@
+use std::fmt::Write as _;
+
+use glam::Vec3;
+
+use crate::{object::Object, rendering::camera::CameraConfig, scene::Scene};
+
+use super::render::Preview;
+
+/// Describes what pixel (x, y) of `camera`'s image sees in `scene`: the
+/// object, material and hit of the ray through its center, and what
+/// `preview` has gathered for it so far.
+pub fn inspect(
+    scene: &Scene,
+    camera: &CameraConfig,
+    preview: Option<&Preview>,
+    x: u32,
+    y: u32,
+) -> String {
+    let mut text = format!("Pixel {x}, {y}\n");
+    if let Some(preview) = preview.filter(|p| x < p.width && y < p.height) {
+        let radiance = preview.colors[(y * preview.width + x) as usize];
+        writeln!(text, "  {} samples", preview.samples).unwrap();
+        writeln!(text, "  radiance {}", vector(radiance)).unwrap();
+    }
+
+    let Some(ray) = camera.pixel_ray(x, y) else {
+        text += "\nOutside the projection\n";
+        return text;
+    };
+    let Some(hit) = scene.world.hit(ray, &(0.001..f32::MAX)) else {
+        text += "\nSky\n";
+        return text;
+    };
+
+    let object = hit.object;
+    let bounds = object.bounding_box();
+    text += "\nObject";
+    if let Some(id) = scene.world.object_id(object) {
+        write!(text, " #{id}").unwrap();
+    }
+    writeln!(text, "\n  {}", short_type_name(object.type_name())).unwrap();
+    writeln!(text, "  position {}", vector(object.position())).unwrap();
+    writeln!(
+        text,
+        "  bounds {} to {}",
+        vector(bounds.min()),
+        vector(bounds.max())
+    )
+    .unwrap();
+
+    text += "\nMaterial";
+    if let Some(id) = hit.material_id() {
+        let name = scene.materials.name(id).unwrap_or("?");
+        write!(text, " #{id} \"{name}\"").unwrap();
+    } else if let Some(id) = scene.world.material_id(hit.material) {
+        write!(text, " #{id}").unwrap();
+    }
+    match hit.material.describe() {
+        Some(description) => writeln!(text, "\n  {description:?}").unwrap(),
+        None => text += "\n  (can't be described)\n",
+    }
+
+    text += "\nHit\n";
+    let distance = (hit.point - ray.origin).length();
+    writeln!(text, "  t {:.4}, distance {distance:.4}", hit.t).unwrap();
+    writeln!(text, "  point {}", vector(hit.point)).unwrap();
+    writeln!(text, "  normal {}", vector(hit.normal)).unwrap();
+    writeln!(text, "  u {:.4}, v {:.4}", hit.u, hit.v).unwrap();
+    let face = if hit.front_face { "front" } else { "back" };
+    writeln!(text, "  {face} face").unwrap();
+
+    text
+}
+
+fn vector(v: Vec3) -> String {
+    format!("({:.3}, {:.3}, {:.3})", v.x, v.y, v.z)
+}
+
+/// `name` with the module paths left out, such as `Sphere<Lambertian<SolidColor>>`.
+fn short_type_name(name: &str) -> String {
+    let mut short = String::new();
+    let mut segment = String::new();
+    for c in name.chars() {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            segment.push(c);
+        } else {
+            short += segment.rsplit("::").next().unwrap();
+            segment.clear();
+            short.push(c);
+        }
+    }
+    short + segment.rsplit("::").next().unwrap()
+}
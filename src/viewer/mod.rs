@@ -0,0 +1,445 @@
+//! Interactive preview window for framing a shot before the final render.
+//! The scene renders progressively in the background and starts over
+//! whenever the camera changes:
+//!
+//! - drag to orbit around `look_at`, shift-drag or drag with the middle or
+//!   right button to pan, and scroll to zoom;
+//! - W, A, S, D fly forward, left, back and right, and Q and E down and up;
+//! - the panel sets the field of view, aperture, focus distance and bounce
+//!   count, picks the integrator or a debug view, and with "Click to focus" on the next
+//!   click focuses on what is under the mouse;
+//! - ctrl-click shows what a pixel sees: the object, its material, the hit
+//!   and the radiance gathered there so far.
+//!
+//! The framed camera can be saved with the scene to a scene file, which
+//! renders the final image. Animation is ignored while previewing.
+
+mod inspect;
+mod navigation;
+mod render;
+
+use std::{cell::RefCell, error::Error, rc::Rc, sync::mpsc, sync::Arc, thread};
+
+use fltk::{
+    app::{self, MouseButton, MouseWheel},
+    button::{Button, ToggleButton},
+    dialog,
+    enums::{ColorDepth, Event, Font},
+    frame::Frame,
+    group::Flex,
+    image::RgbImage,
+    menu::Choice,
+    prelude::*,
+    text::{TextBuffer, TextDisplay},
+    valuator::HorValueSlider,
+    window::Window,
+};
+
+use crate::{
+    rendering::{
+        camera::{CameraConfig, Focus},
+        colorspace::OutputTransform,
+        debug::DebugView,
+        integrator::IntegratorKind,
+    },
+    scene::{description::SceneDescription, Scene},
+};
+
+use self::{
+    inspect::inspect,
+    render::{render_previews, Preview},
+};
+
+/// Widest image the viewer renders, to stay responsive.
+const PREVIEW_WIDTH: u32 = 640;
+const PANEL_WIDTH: i32 = 320;
+const MIN_HEIGHT: i32 = 640;
+
+/// Opens the viewer on `scene` and returns once its window is closed.
+pub fn run(scene: Scene, transform: OutputTransform, seed: u64) -> Result<(), Box<dyn Error>> {
+    let scene = Arc::new(scene);
+    let config = preview_camera(&scene.camera);
+    let width = config.image_width as i32;
+    let height = (config.image_width as f32 / config.aspect_ratio) as i32;
+
+    let app = app::App::default();
+    let mut window = Window::default()
+        .with_size(width + PANEL_WIDTH, height.max(MIN_HEIGHT))
+        .with_label("rtx");
+    let mut image = Frame::new(0, 0, width, height, None);
+
+    let mut panel = Flex::new(width, 0, PANEL_WIDTH, height.max(MIN_HEIGHT), None).column();
+    panel.set_margin(10);
+    let distance = (config.look_from - config.look_at).length();
+    let controls = Controls {
+        vfov: slider(&mut panel, "Field of view", 1.0..170.0, 0.5, config.vfov),
+        aperture: slider(
+            &mut panel,
+            "Aperture",
+            0.0..10.0,
+            0.05,
+            config.defocus_angle,
+        ),
+        focus_distance: slider(
+            &mut panel,
+            "Focus distance",
+            0.1..(4.0 * distance).max(10.0),
+            0.01,
+            config.focus_distance,
+        ),
+        bounces: slider(
+            &mut panel,
+            "Bounces",
+            1.0..100.0,
+            1.0,
+            config.max_bounces as f32,
+        ),
+        integrator: integrator_choice(&mut panel),
+        view: view_choice(&mut panel),
+        click_to_focus: ToggleButton::default().with_label("Click to focus"),
+    };
+    panel.fixed(&controls.click_to_focus, 30);
+    let mut reset = Button::default().with_label("Reset camera");
+    panel.fixed(&reset, 30);
+    let mut save = Button::default().with_label("Save scene...");
+    panel.fixed(&save, 30);
+    let mut status = Frame::default();
+    panel.fixed(&status, 20);
+    let mut inspector = TextDisplay::default();
+    inspector.set_buffer(TextBuffer::default());
+    inspector.set_text_font(Font::Courier);
+    inspector.set_text_size(12);
+    panel.end();
+
+    window.end();
+    window.show();
+
+    let (cameras, camera_receiver) = mpsc::channel();
+    let (previews, preview_receiver) = app::channel::<Preview>();
+    thread::spawn({
+        let scene = scene.clone();
+        move || render_previews(&scene, camera_receiver, previews, transform, seed)
+    });
+
+    let viewer = Rc::new(RefCell::new(Viewer {
+        scene,
+        initial: config.clone(),
+        config,
+        cameras,
+        controls,
+        inspector,
+        preview: None,
+        drag: (0, 0),
+    }));
+    viewer.borrow_mut().update();
+    connect_controls(&viewer, &mut reset, &mut save);
+    connect_image(&viewer, &mut image);
+
+    while app.wait() {
+        if let Some(preview) = preview_receiver.recv() {
+            let pixels = RgbImage::new(
+                &preview.pixels,
+                preview.width as i32,
+                preview.height as i32,
+                ColorDepth::Rgb8,
+            )?;
+            image.set_image(Some(pixels));
+            image.redraw();
+            status.set_label(&format!("{} samples per pixel", preview.samples));
+            viewer.borrow_mut().preview = Some(preview);
+        }
+    }
+
+    Ok(())
+}
+
+/// `camera` at a resolution the viewer can keep up with, with a physical
+/// lens turned into the field of view and aperture the panel sets.
+fn preview_camera(camera: &CameraConfig) -> CameraConfig {
+    let mut config = camera.clone();
+    config.image_width = config.image_width.min(PREVIEW_WIDTH);
+    if let Some(lens) = config.lens.take() {
+        config.vfov = lens.vfov(config.aspect_ratio);
+        config.defocus_angle = 2.0
+            * (lens.aperture_radius() / config.focus_distance)
+                .atan()
+                .to_degrees();
+    }
+    config
+}
+
+/// Adds a labelled slider over `range` to the panel.
+fn slider(
+    panel: &mut Flex,
+    label: &str,
+    range: std::ops::Range<f32>,
+    step: f32,
+    value: f32,
+) -> HorValueSlider {
+    let label = Frame::default().with_label(label);
+    panel.fixed(&label, 20);
+    let mut slider = HorValueSlider::default();
+    slider.set_range(range.start as f64, range.end as f64);
+    slider.set_step(step as f64, 1);
+    slider.set_value(value as f64);
+    panel.fixed(&slider, 25);
+    slider
+}
+
+/// Adds a choice of integrator to the panel.
+fn integrator_choice(panel: &mut Flex) -> Choice {
+    let label = Frame::default().with_label("Integrator");
+    panel.fixed(&label, 20);
+    let mut choice = Choice::default();
+    for integrator in IntegratorKind::ALL {
+        choice.add_choice(integrator.name());
+    }
+    panel.fixed(&choice, 25);
+    choice
+}
+
+/// Adds a choice between path tracing and each debug view to the panel.
+fn view_choice(panel: &mut Flex) -> Choice {
+    let label = Frame::default().with_label("View");
+    panel.fixed(&label, 20);
+    let mut choice = Choice::default();
+    choice.add_choice("path tracing");
+    for view in DebugView::ALL {
+        choice.add_choice(view.name());
+    }
+    panel.fixed(&choice, 25);
+    choice
+}
+
+struct Controls {
+    vfov: HorValueSlider,
+    aperture: HorValueSlider,
+    focus_distance: HorValueSlider,
+    bounces: HorValueSlider,
+    integrator: Choice,
+    view: Choice,
+    click_to_focus: ToggleButton,
+}
+
+struct Viewer {
+    scene: Arc<Scene>,
+    initial: CameraConfig,
+    config: CameraConfig,
+    cameras: mpsc::Sender<CameraConfig>,
+    controls: Controls,
+    inspector: TextDisplay,
+    /// The latest image of the render.
+    preview: Option<Preview>,
+    /// Where the mouse was at the last drag event.
+    drag: (i32, i32),
+}
+
+impl Viewer {
+    /// Restarts the render from the current camera and shows its settings
+    /// in the panel.
+    fn update(&mut self) {
+        // The render thread only stops sending once the window is gone.
+        let _ = self.cameras.send(self.config.clone());
+
+        let config = &self.config;
+        let controls = &mut self.controls;
+        controls.vfov.set_value(config.vfov as f64);
+        controls.aperture.set_value(config.defocus_angle as f64);
+        controls
+            .focus_distance
+            .set_value(config.focus_distance as f64);
+        controls.bounces.set_value(config.max_bounces as f64);
+        let integrator = IntegratorKind::ALL
+            .iter()
+            .position(|&kind| kind == config.integrator);
+        controls
+            .integrator
+            .set_value(integrator.unwrap_or(0) as i32);
+        let view = config
+            .debug
+            .and_then(|view| DebugView::ALL.iter().position(|&v| v == view));
+        controls
+            .view
+            .set_value(view.map_or(0, |index| index as i32 + 1));
+    }
+
+    /// Focuses on the surface seen through pixel (x, y) of the preview.
+    fn focus_on(&mut self, x: i32, y: i32) {
+        if x < 0 || y < 0 {
+            return;
+        }
+        self.config.focus = Focus::Pixel(x as u32, y as u32);
+        self.config.autofocus(&self.scene.world, None);
+        // A pixel that sees nothing leaves the focus where it was.
+        self.config.focus = Focus::Manual;
+        self.controls.click_to_focus.set_value(false);
+        self.update();
+    }
+
+    /// Shows what pixel (x, y) of the preview sees.
+    fn inspect(&mut self, x: i32, y: i32) {
+        if x < 0 || y < 0 {
+            return;
+        }
+        let text = inspect(
+            &self.scene,
+            &self.config,
+            self.preview.as_ref(),
+            x as u32,
+            y as u32,
+        );
+        self.inspector.buffer().unwrap().set_text(&text);
+    }
+
+    /// Writes the scene with the framed camera, at the scene's own
+    /// resolution and sample count, to `path`.
+    fn save(&self, path: &str) {
+        let mut description = SceneDescription::of(&self.scene);
+        description.camera = CameraConfig {
+            image_width: self.scene.camera.image_width,
+            samples_per_pixel: self.scene.camera.samples_per_pixel,
+            ..self.config.clone()
+        };
+        if let Err(error) = description.save(path) {
+            dialog::alert_default(&format!("Could not save {path}: {error}"));
+        } else if description.skipped > 0 {
+            dialog::alert_default(&format!(
+                "Saved {path} without {} objects that can't be described",
+                description.skipped
+            ));
+        }
+    }
+}
+
+fn connect_controls(viewer: &Rc<RefCell<Viewer>>, reset: &mut Button, save: &mut Button) {
+    let on_change = |set: fn(&mut CameraConfig, f32)| {
+        let viewer = viewer.clone();
+        move |slider: &mut HorValueSlider| {
+            let mut viewer = viewer.borrow_mut();
+            set(&mut viewer.config, slider.value() as f32);
+            viewer.update();
+        }
+    };
+
+    let mut controls = {
+        let viewer = viewer.borrow();
+        let controls = &viewer.controls;
+        [
+            controls.vfov.clone(),
+            controls.aperture.clone(),
+            controls.focus_distance.clone(),
+            controls.bounces.clone(),
+        ]
+    };
+    controls[0].set_callback(on_change(|config, value| config.vfov = value));
+    controls[1].set_callback(on_change(|config, value| config.defocus_angle = value));
+    controls[2].set_callback(on_change(|config, value| {
+        config.focus_distance = value;
+        config.focus = Focus::Manual;
+    }));
+    controls[3].set_callback(on_change(|config, value| config.max_bounces = value as u32));
+
+    let mut integrator = viewer.borrow().controls.integrator.clone();
+    integrator.set_callback({
+        let viewer = viewer.clone();
+        move |choice| {
+            let mut viewer = viewer.borrow_mut();
+            if let Ok(index) = usize::try_from(choice.value()) {
+                viewer.config.integrator = IntegratorKind::ALL[index];
+            }
+            viewer.update();
+        }
+    });
+
+    let mut view = viewer.borrow().controls.view.clone();
+    view.set_callback({
+        let viewer = viewer.clone();
+        move |choice| {
+            let mut viewer = viewer.borrow_mut();
+            viewer.config.debug = usize::try_from(choice.value() - 1)
+                .ok()
+                .map(|index| DebugView::ALL[index]);
+            viewer.update();
+        }
+    });
+
+    reset.set_callback({
+        let viewer = viewer.clone();
+        move |_| {
+            let mut viewer = viewer.borrow_mut();
+            viewer.config = viewer.initial.clone();
+            viewer.update();
+        }
+    });
+    save.set_callback({
+        let viewer = viewer.clone();
+        move |_| {
+            // Asked first, as the dialog keeps handling events while open.
+            if let Some(path) = dialog::file_chooser("Save scene", "*.scene", ".", false) {
+                viewer.borrow().save(&path);
+            }
+        }
+    });
+}
+
+/// Hooks the mouse and keyboard navigation up to the image.
+fn connect_image(viewer: &Rc<RefCell<Viewer>>, image: &mut Frame) {
+    let viewer = viewer.clone();
+    image.handle(move |image, event| {
+        let mut viewer = viewer.borrow_mut();
+        let viewer = &mut *viewer;
+        match event {
+            Event::Push => {
+                // Taking focus is what lets the image receive key presses.
+                let _ = image.take_focus();
+                let (x, y) = app::event_coords();
+                viewer.drag = (x, y);
+                if app::is_event_ctrl() {
+                    viewer.inspect(x - image.x(), y - image.y());
+                } else if viewer.controls.click_to_focus.value() {
+                    viewer.focus_on(x - image.x(), y - image.y());
+                }
+                true
+            }
+            Event::Drag => {
+                let (x, y) = app::event_coords();
+                let dx = (x - viewer.drag.0) as f32;
+                let dy = (y - viewer.drag.1) as f32;
+                viewer.drag = (x, y);
+                if app::is_event_shift() || app::event_mouse_button() != MouseButton::Left {
+                    navigation::pan(&mut viewer.config, dx, dy);
+                } else {
+                    navigation::orbit(&mut viewer.config, dx, dy);
+                }
+                viewer.update();
+                true
+            }
+            Event::MouseWheel => {
+                let steps = match app::event_dy() {
+                    MouseWheel::Up => 1.0,
+                    MouseWheel::Down => -1.0,
+                    _ => return false,
+                };
+                navigation::zoom(&mut viewer.config, steps);
+                viewer.update();
+                true
+            }
+            Event::KeyDown => {
+                let (forward, right, up) = match app::event_key().to_char() {
+                    Some('w') => (1.0, 0.0, 0.0),
+                    Some('s') => (-1.0, 0.0, 0.0),
+                    Some('a') => (0.0, -1.0, 0.0),
+                    Some('d') => (0.0, 1.0, 0.0),
+                    Some('q') => (0.0, 0.0, -1.0),
+                    Some('e') => (0.0, 0.0, 1.0),
+                    _ => return false,
+                };
+                navigation::fly(&mut viewer.config, forward, right, up);
+                viewer.update();
+                true
+            }
+            Event::Focus | Event::Unfocus => true,
+            _ => false,
+        }
+    });
+}
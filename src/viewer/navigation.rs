@@ -0,0 +1,69 @@
+use glam::{Quat, Vec3};
+
+use crate::rendering::camera::CameraConfig;
+
+/// Radians the camera turns by per pixel dragged.
+const ORBIT_SPEED: f32 = 0.005;
+/// How much closer one step of the mouse wheel takes the camera.
+const ZOOM_STEP: f32 = 0.9;
+/// Part of the distance to `look_at` one key press flies.
+const FLY_STEP: f32 = 0.05;
+
+/// The camera's right, up and backward directions, like the view it renders
+/// with.
+fn axes(camera: &CameraConfig) -> (Vec3, Vec3, Vec3) {
+    let w = (camera.look_from - camera.look_at).normalize();
+    let u = camera.vector_up.cross(w).normalize();
+    (u, w.cross(u), w)
+}
+
+/// Turns the camera around `look_at` for a drag of `dx`, `dy` pixels, so
+/// the scene follows the mouse. Stops short of looking straight along
+/// `vector_up`, where the view would flip.
+pub fn orbit(camera: &mut CameraConfig, dx: f32, dy: f32) {
+    let up = camera.vector_up.normalize();
+    let (right, _, _) = axes(camera);
+    let offset = camera.look_from - camera.look_at;
+
+    let turned = Quat::from_axis_angle(up, -dx * ORBIT_SPEED) * offset;
+    let right = Quat::from_axis_angle(up, -dx * ORBIT_SPEED) * right;
+    let tilted = Quat::from_axis_angle(right, -dy * ORBIT_SPEED) * turned;
+
+    let offset = if tilted.normalize().dot(up).abs() < 0.99 {
+        tilted
+    } else {
+        turned
+    };
+    camera.look_from = camera.look_at + offset;
+}
+
+/// Slides the camera and `look_at` sideways for a drag of `dx`, `dy`
+/// pixels, so that what is at `look_at` stays under the mouse.
+pub fn pan(camera: &mut CameraConfig, dx: f32, dy: f32) {
+    let (right, up, _) = axes(camera);
+    let image_height = camera.image_width as f32 / camera.aspect_ratio;
+    let distance = (camera.look_from - camera.look_at).length();
+    let pixel = 2.0 * distance * (camera.vfov.to_radians() / 2.0).tan() / image_height;
+
+    let offset = pixel * (dy * up - dx * right);
+    camera.look_from += offset;
+    camera.look_at += offset;
+}
+
+/// Moves the camera toward `look_at` by `steps` wheel steps, or away from
+/// it if `steps` is negative.
+pub fn zoom(camera: &mut CameraConfig, steps: f32) {
+    let offset = (camera.look_from - camera.look_at) * ZOOM_STEP.powf(steps);
+    camera.look_from = camera.look_at + offset;
+}
+
+/// Flies the camera and `look_at` along the camera's own axes, one step
+/// per unit of `forward`, `right` and `up`.
+pub fn fly(camera: &mut CameraConfig, forward: f32, right: f32, up: f32) {
+    let (u, v, w) = axes(camera);
+    let step = FLY_STEP * (camera.look_from - camera.look_at).length();
+
+    let offset = step * (right * u + up * v - forward * w);
+    camera.look_from += offset;
+    camera.look_at += offset;
+}
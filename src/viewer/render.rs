@@ -0,0 +1,72 @@
+use std::sync::mpsc::Receiver;
+
+use fltk::app;
+use glam::Vec3;
+
+use crate::{
+    rendering::{
+        camera::{Camera, CameraConfig},
+        colorspace::OutputTransform,
+        film::colors_to_image,
+    },
+    scene::Scene,
+};
+
+/// The film of a progressive render so far, ready to display.
+pub struct Preview {
+    pub pixels: Vec<u8>,
+    /// Radiance of every pixel in the working space.
+    pub colors: Vec<Vec3>,
+    pub width: u32,
+    pub height: u32,
+    pub samples: u32,
+}
+
+/// Renders `scene` one sample per pixel at a time, sending the image to the
+/// window after every pass. Whenever a new camera arrives the render starts
+/// over from it; otherwise it stops at the camera's samples per pixel and
+/// waits. Returns once the window stops sending cameras.
+pub fn render_previews(
+    scene: &Scene,
+    cameras: Receiver<CameraConfig>,
+    previews: app::Sender<Preview>,
+    transform: OutputTransform,
+    seed: u64,
+) {
+    let Ok(mut config) = cameras.recv() else {
+        return;
+    };
+
+    loop {
+        config.autofocus(&scene.world, None);
+        let camera = Camera::new(config.clone());
+        let mut film = camera.new_film(seed);
+
+        let mut restarted = false;
+        while film.samples_taken() < camera.samples_per_pixel() {
+            camera.render_pass(&scene.world, &mut film, 1);
+            let colors = film.colors();
+            previews.send(Preview {
+                pixels: colors_to_image(film.width(), film.height(), &colors, &transform)
+                    .into_raw(),
+                colors,
+                width: film.width(),
+                height: film.height(),
+                samples: film.samples_taken(),
+            });
+
+            if let Some(newer) = cameras.try_iter().last() {
+                config = newer;
+                restarted = true;
+                break;
+            }
+        }
+
+        if !restarted {
+            match cameras.recv() {
+                Ok(newer) => config = newer,
+                Err(_) => return,
+            }
+        }
+    }
+}